@@ -0,0 +1,767 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::db::portfolio::TradeSide;
+use crate::indicators;
+use crate::models::PriceBar;
+use crate::screener::{Screen, ScreenError, ScreenInput};
+
+/// Trading days in a year, for annualising the Sharpe ratio
+const TRADING_DAYS: f64 = 252.0;
+
+/// What a strategy wants to do after seeing a bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Buy,
+    Sell,
+    Hold,
+}
+
+/// An end-of-day, long-only strategy. The engine calls `on_bar` once per bar, oldest first,
+/// with the history up to and including the bar that just closed.
+pub trait Strategy {
+    fn on_bar(&mut self, bars: &[PriceBar], in_position: bool) -> Signal;
+}
+
+/// The built-in strategies with their parameters, as chosen in the GUI or on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrategySpec {
+    /// Buy when the fast SMA crosses above the slow one, sell when it crosses back below
+    SmaCrossover { fast: usize, slow: usize },
+    /// Buy when RSI drops below `oversold`, sell when it rises above `overbought`
+    RsiReversion { period: usize, oversold: f64, overbought: f64 },
+    /// Buy when the entry screener expression matches, sell when the exit one does
+    Screen { entry: String, exit: String },
+    /// Buy on the first bar and hold to the end, as a benchmark
+    BuyAndHold,
+}
+
+impl StrategySpec {
+    /// Every strategy with sensible default parameters
+    pub fn defaults() -> [StrategySpec; 4] {
+        [
+            StrategySpec::SmaCrossover { fast: 50, slow: 200 },
+            StrategySpec::RsiReversion { period: 14, oversold: 30.0, overbought: 70.0 },
+            StrategySpec::Screen {
+                entry: "close > sma(50) and sma(50) > sma(200)".to_string(),
+                exit: "close < sma(50)".to_string(),
+            },
+            StrategySpec::BuyAndHold,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StrategySpec::SmaCrossover { .. } => "SMA crossover",
+            StrategySpec::RsiReversion { .. } => "RSI reversion",
+            StrategySpec::Screen { .. } => "Screen entry/exit",
+            StrategySpec::BuyAndHold => "Buy and hold",
+        }
+    }
+
+    /// Name including the parameters, e.g. `SMA crossover (50/200)`
+    pub fn label(&self) -> String {
+        match self {
+            StrategySpec::SmaCrossover { fast, slow } => format!("SMA crossover ({}/{})", fast, slow),
+            StrategySpec::RsiReversion { period, oversold, overbought } => {
+                format!("RSI reversion ({}, {}/{})", period, oversold, overbought)
+            }
+            StrategySpec::Screen { entry, exit } => format!("Enter: {} / Exit: {}", entry, exit),
+            StrategySpec::BuyAndHold => "Buy and hold".to_string(),
+        }
+    }
+
+    pub fn build(&self) -> Result<Box<dyn Strategy>, ScreenError> {
+        Ok(match self {
+            StrategySpec::SmaCrossover { fast, slow } => {
+                if *fast == 0 || fast >= slow {
+                    return Err(ScreenError { message: "The fast SMA must be shorter than the slow one".to_string(), column: 1 });
+                }
+                Box::new(SmaCrossover { fast: *fast, slow: *slow })
+            }
+            StrategySpec::RsiReversion { period, oversold, overbought } => {
+                if *period == 0 || oversold >= overbought {
+                    return Err(ScreenError { message: "RSI oversold must be below overbought".to_string(), column: 1 });
+                }
+                Box::new(RsiReversion { period: *period, oversold: *oversold, overbought: *overbought })
+            }
+            StrategySpec::Screen { entry, exit } => {
+                Box::new(ScreenStrategy { entry: Screen::parse(entry)?, exit: Screen::parse(exit)? })
+            }
+            StrategySpec::BuyAndHold => Box::new(BuyAndHold),
+        })
+    }
+}
+
+pub struct SmaCrossover {
+    pub fast: usize,
+    pub slow: usize,
+}
+
+impl Strategy for SmaCrossover {
+    fn on_bar(&mut self, bars: &[PriceBar], in_position: bool) -> Signal {
+        let n = bars.len();
+        if n <= self.slow {
+            return Signal::Hold;
+        }
+        let mean = |end: usize, period: usize| bars[end - period..end].iter().map(|b| b.close).sum::<f64>() / period as f64;
+        let (fast, slow) = (mean(n, self.fast), mean(n, self.slow));
+        let (prev_fast, prev_slow) = (mean(n - 1, self.fast), mean(n - 1, self.slow));
+
+        if !in_position && prev_fast <= prev_slow && fast > slow {
+            Signal::Buy
+        } else if in_position && prev_fast >= prev_slow && fast < slow {
+            Signal::Sell
+        } else {
+            Signal::Hold
+        }
+    }
+}
+
+pub struct RsiReversion {
+    pub period: usize,
+    pub oversold: f64,
+    pub overbought: f64,
+}
+
+impl Strategy for RsiReversion {
+    fn on_bar(&mut self, bars: &[PriceBar], in_position: bool) -> Signal {
+        // Wilder's smoothing has settled well within ten periods
+        let window = &bars[bars.len().saturating_sub(self.period * 10 + 1)..];
+        let closes: Vec<f64> = window.iter().map(|b| b.close).collect();
+        let Some(rsi) = indicators::rsi(&closes, self.period).last().copied().flatten() else {
+            return Signal::Hold;
+        };
+
+        if !in_position && rsi < self.oversold {
+            Signal::Buy
+        } else if in_position && rsi > self.overbought {
+            Signal::Sell
+        } else {
+            Signal::Hold
+        }
+    }
+}
+
+/// Screener expressions as entry and exit rules. Turnover, trades and delivery aren't available
+/// per bar, so conditions on them never match.
+pub struct ScreenStrategy {
+    pub entry: Screen,
+    pub exit: Screen,
+}
+
+impl Strategy for ScreenStrategy {
+    fn on_bar(&mut self, bars: &[PriceBar], in_position: bool) -> Signal {
+        let screen = if in_position { &self.exit } else { &self.entry };
+        let window = &bars[bars.len().saturating_sub(screen.lookback())..];
+        let prev_close = bars.len().checked_sub(2).map(|i| bars[i].close);
+        let input = ScreenInput { bars: window, prev_close, turnover: None, trades: None, delivery_qty: None, delivery_pct: None };
+
+        match (screen.matches(&input), in_position) {
+            (true, false) => Signal::Buy,
+            (true, true) => Signal::Sell,
+            _ => Signal::Hold,
+        }
+    }
+}
+
+pub struct BuyAndHold;
+
+impl Strategy for BuyAndHold {
+    fn on_bar(&mut self, _bars: &[PriceBar], in_position: bool) -> Signal {
+        if in_position { Signal::Hold } else { Signal::Buy }
+    }
+}
+
+/// When an order placed on a bar's signal is filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillModel {
+    /// At the next bar's open, as an order placed after the close would be
+    NextOpen,
+    /// At the signal bar's own close
+    Close,
+}
+
+impl FillModel {
+    pub const ALL: [FillModel; 2] = [FillModel::NextOpen, FillModel::Close];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FillModel::NextOpen => "Next open",
+            FillModel::Close => "Same close",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "next-open" | "open" => Some(FillModel::NextOpen),
+            "close" => Some(FillModel::Close),
+            _ => None,
+        }
+    }
+}
+
+/// How many shares each entry buys
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PositionSizing {
+    /// A fixed number of shares
+    Quantity(i64),
+    /// Up to this many rupees per entry
+    Amount(f64),
+    /// This percentage of current equity
+    PercentOfEquity(f64),
+}
+
+impl PositionSizing {
+    /// `100%` of equity, `50000` rupees or `25sh` shares
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase().replace(['₹', ','], "");
+        let sizing = if let Some(pct) = s.strip_suffix('%') {
+            PositionSizing::PercentOfEquity(pct.trim().parse().ok()?)
+        } else if let Some(qty) = s.strip_suffix("shares").or_else(|| s.strip_suffix("sh")) {
+            PositionSizing::Quantity(qty.trim().parse().ok()?)
+        } else {
+            PositionSizing::Amount(s.parse().ok()?)
+        };
+        let valid = match sizing {
+            PositionSizing::Quantity(qty) => qty > 0,
+            PositionSizing::Amount(amount) => amount > 0.0,
+            PositionSizing::PercentOfEquity(pct) => pct > 0.0 && pct <= 100.0,
+        };
+        valid.then_some(sizing)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            PositionSizing::Quantity(qty) => format!("{}sh", qty),
+            PositionSizing::Amount(amount) => format!("{}", amount),
+            PositionSizing::PercentOfEquity(pct) => format!("{}%", pct),
+        }
+    }
+
+    /// Shares to buy at `price` with `cash` available, leaving room for the buy charges
+    fn quantity(&self, price: f64, cash: f64, costs: &CostModel) -> i64 {
+        if price <= 0.0 {
+            return 0;
+        }
+        let budget = match *self {
+            PositionSizing::Quantity(qty) => qty as f64 * price,
+            PositionSizing::Amount(amount) => amount,
+            PositionSizing::PercentOfEquity(pct) => cash * pct / 100.0,
+        }.min(cash);
+        if budget <= 0.0 {
+            return 0;
+        }
+
+        let rate = costs.charges(TradeSide::Buy, budget).total() / budget;
+        let mut qty = (budget / (price * (1.0 + rate))).floor() as i64;
+        if let PositionSizing::Quantity(max) = *self {
+            qty = qty.min(max);
+        }
+        while qty > 0 {
+            let value = qty as f64 * price;
+            if value + costs.charges(TradeSide::Buy, value).total() <= cash {
+                break;
+            }
+            qty -= 1;
+        }
+        qty
+    }
+}
+
+/// Statutory and broker charges on an NSE equity delivery trade, as percentages of the trade value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    pub brokerage_pct: f64,
+    /// Cap on brokerage per order, in rupees; zero for no cap
+    pub brokerage_max: f64,
+    /// Securities transaction tax, on both buys and sells for delivery
+    pub stt_pct: f64,
+    /// NSE transaction charges
+    pub exchange_pct: f64,
+    /// SEBI turnover fee (₹10 per crore)
+    pub sebi_pct: f64,
+    /// Stamp duty, on buys only
+    pub stamp_duty_pct: f64,
+    /// GST on brokerage, exchange and SEBI charges
+    pub gst_pct: f64,
+}
+
+impl Default for CostModel {
+    /// Delivery trades through a discount broker: 0.03% brokerage capped at ₹20 an order
+    fn default() -> Self {
+        Self {
+            brokerage_pct: 0.03,
+            brokerage_max: 20.0,
+            stt_pct: 0.1,
+            exchange_pct: 0.00297,
+            sebi_pct: 0.0001,
+            stamp_duty_pct: 0.015,
+            gst_pct: 18.0,
+        }
+    }
+}
+
+impl CostModel {
+    /// No charges at all, to see a strategy's raw edge
+    pub fn zero() -> Self {
+        Self { brokerage_pct: 0.0, brokerage_max: 0.0, stt_pct: 0.0, exchange_pct: 0.0, sebi_pct: 0.0, stamp_duty_pct: 0.0, gst_pct: 0.0 }
+    }
+
+    pub fn charges(&self, side: TradeSide, value: f64) -> CostBreakdown {
+        let mut brokerage = value * self.brokerage_pct / 100.0;
+        if self.brokerage_max > 0.0 {
+            brokerage = brokerage.min(self.brokerage_max);
+        }
+        let exchange = value * self.exchange_pct / 100.0;
+        let sebi = value * self.sebi_pct / 100.0;
+        CostBreakdown {
+            brokerage,
+            stt: value * self.stt_pct / 100.0,
+            exchange,
+            sebi,
+            stamp_duty: if side == TradeSide::Buy { value * self.stamp_duty_pct / 100.0 } else { 0.0 },
+            gst: (brokerage + exchange + sebi) * self.gst_pct / 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CostBreakdown {
+    pub brokerage: f64,
+    pub stt: f64,
+    pub exchange: f64,
+    pub sebi: f64,
+    pub stamp_duty: f64,
+    pub gst: f64,
+}
+
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.brokerage + self.stt + self.exchange + self.sebi + self.stamp_duty + self.gst
+    }
+
+    fn add(&mut self, other: &CostBreakdown) {
+        self.brokerage += other.brokerage;
+        self.stt += other.stt;
+        self.exchange += other.exchange;
+        self.sebi += other.sebi;
+        self.stamp_duty += other.stamp_duty;
+        self.gst += other.gst;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BacktestConfig {
+    pub initial_capital: f64,
+    pub fill: FillModel,
+    pub sizing: PositionSizing,
+    pub costs: CostModel,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_capital: 100_000.0,
+            fill: FillModel::NextOpen,
+            sizing: PositionSizing::PercentOfEquity(100.0),
+            costs: CostModel::default(),
+        }
+    }
+}
+
+/// A completed round trip. Charges cover both legs and are included in the P&L.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestTrade {
+    pub entry_date: NaiveDate,
+    pub entry_price: f64,
+    pub exit_date: NaiveDate,
+    pub exit_price: f64,
+    pub quantity: i64,
+    pub charges: f64,
+    pub pnl: f64,
+    pub return_pct: f64,
+    pub bars_held: usize,
+    /// Still open on the last bar and closed there so the P&L is counted
+    pub closed_at_end: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EquityPoint {
+    pub date: NaiveDate,
+    pub equity: f64,
+    /// Percentage below the highest equity so far
+    pub drawdown_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestSummary {
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    pub total_return_pct: f64,
+    /// None for runs shorter than a day
+    pub cagr_pct: Option<f64>,
+    pub max_drawdown_pct: f64,
+    /// Annualised from daily returns with a zero risk-free rate; None if equity never moved
+    pub sharpe: Option<f64>,
+    pub trades: usize,
+    pub win_rate_pct: Option<f64>,
+    pub total_charges: f64,
+    /// Share of bars with a position open
+    pub exposure_pct: f64,
+    /// Close-to-close change over the same bars, before charges
+    pub buy_and_hold_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestResult {
+    pub summary: BacktestSummary,
+    pub trades: Vec<BacktestTrade>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub charges: CostBreakdown,
+}
+
+struct Position {
+    entry_index: usize,
+    entry_date: NaiveDate,
+    entry_price: f64,
+    quantity: i64,
+    entry_charges: f64,
+}
+
+/// Cash, the open position and everything traded so far
+struct Account<'a> {
+    config: &'a BacktestConfig,
+    cash: f64,
+    position: Option<Position>,
+    trades: Vec<BacktestTrade>,
+    charges: CostBreakdown,
+}
+
+impl Account<'_> {
+    /// Fill `signal` at `price` on bar `index`; signals that don't change the position are ignored
+    fn execute(&mut self, signal: Signal, index: usize, date: NaiveDate, price: f64, closed_at_end: bool) {
+        let costs = &self.config.costs;
+        match (signal, self.position.take()) {
+            (Signal::Buy, None) => {
+                let quantity = self.config.sizing.quantity(price, self.cash, costs);
+                if quantity > 0 {
+                    let cost = costs.charges(TradeSide::Buy, quantity as f64 * price);
+                    self.charges.add(&cost);
+                    self.cash -= quantity as f64 * price + cost.total();
+                    self.position = Some(Position {
+                        entry_index: index,
+                        entry_date: date,
+                        entry_price: price,
+                        quantity,
+                        entry_charges: cost.total(),
+                    });
+                }
+            }
+            (Signal::Sell, Some(open)) => {
+                let value = open.quantity as f64 * price;
+                let cost = costs.charges(TradeSide::Sell, value);
+                self.charges.add(&cost);
+                self.cash += value - cost.total();
+
+                let invested = open.quantity as f64 * open.entry_price + open.entry_charges;
+                let pnl = value - cost.total() - invested;
+                self.trades.push(BacktestTrade {
+                    entry_date: open.entry_date,
+                    entry_price: open.entry_price,
+                    exit_date: date,
+                    exit_price: price,
+                    quantity: open.quantity,
+                    charges: open.entry_charges + cost.total(),
+                    pnl,
+                    return_pct: if invested > 0.0 { pnl / invested * 100.0 } else { 0.0 },
+                    bars_held: index - open.entry_index,
+                    closed_at_end,
+                });
+            }
+            (_, open) => self.position = open,
+        }
+    }
+
+    fn equity(&self, close: f64) -> f64 {
+        self.cash + self.position.as_ref().map_or(0.0, |open| open.quantity as f64 * close)
+    }
+}
+
+/// Replay `bars` (oldest first) through `strategy`, one bar at a time
+pub fn run_backtest(bars: &[PriceBar], strategy: &mut dyn Strategy, config: &BacktestConfig) -> BacktestResult {
+    let mut account = Account {
+        config,
+        cash: config.initial_capital,
+        position: None,
+        trades: Vec::new(),
+        charges: CostBreakdown::default(),
+    };
+    let mut pending: Option<Signal> = None;
+    let mut equity = Vec::with_capacity(bars.len());
+    let mut bars_in_market = 0;
+
+    for (i, bar) in bars.iter().enumerate() {
+        if let Some(signal) = pending.take() {
+            account.execute(signal, i, bar.date, bar.open, false);
+        }
+
+        let signal = strategy.on_bar(&bars[..=i], account.position.is_some());
+        if signal != Signal::Hold {
+            match config.fill {
+                FillModel::Close => account.execute(signal, i, bar.date, bar.close, false),
+                FillModel::NextOpen => pending = Some(signal),
+            }
+        }
+
+        if account.position.is_some() {
+            bars_in_market += 1;
+            // Count whatever is still held at the end as sold at the last close
+            if i == bars.len() - 1 {
+                account.execute(Signal::Sell, i, bar.date, bar.close, true);
+            }
+        }
+        equity.push((bar.date, account.equity(bar.close)));
+    }
+
+    let equity_curve = with_drawdowns(&equity, config.initial_capital);
+    let summary = summarize(bars, &account.trades, &equity_curve, &account.charges, bars_in_market, config.initial_capital);
+    BacktestResult { summary, trades: account.trades, equity_curve, charges: account.charges }
+}
+
+fn with_drawdowns(equity: &[(NaiveDate, f64)], initial_capital: f64) -> Vec<EquityPoint> {
+    let mut peak = initial_capital;
+    equity.iter()
+        .map(|&(date, equity)| {
+            peak = peak.max(equity);
+            let drawdown_pct = if peak > 0.0 { (peak - equity) / peak * 100.0 } else { 0.0 };
+            EquityPoint { date, equity, drawdown_pct }
+        })
+        .collect()
+}
+
+fn summarize(
+    bars: &[PriceBar],
+    trades: &[BacktestTrade],
+    equity_curve: &[EquityPoint],
+    charges: &CostBreakdown,
+    bars_in_market: usize,
+    initial_capital: f64,
+) -> BacktestSummary {
+    let final_equity = equity_curve.last().map(|p| p.equity).unwrap_or(initial_capital);
+    let total_return_pct = if initial_capital > 0.0 { (final_equity / initial_capital - 1.0) * 100.0 } else { 0.0 };
+
+    let cagr_pct = match (equity_curve.first(), equity_curve.last()) {
+        (Some(first), Some(last)) if last.date > first.date && initial_capital > 0.0 && final_equity > 0.0 => {
+            let years = (last.date - first.date).num_days() as f64 / 365.25;
+            Some(((final_equity / initial_capital).powf(1.0 / years) - 1.0) * 100.0)
+        }
+        _ => None,
+    };
+
+    let mut previous = initial_capital;
+    let returns: Vec<f64> = equity_curve.iter()
+        .map(|p| {
+            let r = if previous > 0.0 { p.equity / previous - 1.0 } else { 0.0 };
+            previous = p.equity;
+            r
+        })
+        .collect();
+    let sharpe = if returns.len() > 1 {
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        let std_dev = variance.sqrt();
+        (std_dev > 1e-12).then(|| mean / std_dev * TRADING_DAYS.sqrt())
+    } else {
+        None
+    };
+
+    let wins = trades.iter().filter(|t| t.pnl > 0.0).count();
+    let buy_and_hold_pct = match (bars.first(), bars.last()) {
+        (Some(first), Some(last)) if first.close > 0.0 => (last.close / first.close - 1.0) * 100.0,
+        _ => 0.0,
+    };
+
+    BacktestSummary {
+        initial_capital,
+        final_equity,
+        total_return_pct,
+        cagr_pct,
+        max_drawdown_pct: equity_curve.iter().map(|p| p.drawdown_pct).fold(0.0, f64::max),
+        sharpe,
+        trades: trades.len(),
+        win_rate_pct: (!trades.is_empty()).then(|| wins as f64 / trades.len() as f64 * 100.0),
+        total_charges: charges.total(),
+        exposure_pct: if bars.is_empty() { 0.0 } else { bars_in_market as f64 / bars.len() as f64 * 100.0 },
+        buy_and_hold_pct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the given signal on each bar, by index
+    struct Scripted(Vec<(usize, Signal)>);
+
+    impl Strategy for Scripted {
+        fn on_bar(&mut self, bars: &[PriceBar], _in_position: bool) -> Signal {
+            self.0.iter().find(|(i, _)| *i == bars.len() - 1).map_or(Signal::Hold, |(_, signal)| *signal)
+        }
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    fn bar(date: NaiveDate, open: f64, close: f64) -> PriceBar {
+        PriceBar { date, open, high: open.max(close), low: open.min(close), close, volume: 1000 }
+    }
+
+    /// Six daily bars opening at 100, 110, ... and closing 5 above the open
+    fn rising_bars() -> Vec<PriceBar> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..6)
+            .map(|i| bar(start + chrono::Duration::days(i), 100.0 + 10.0 * i as f64, 105.0 + 10.0 * i as f64))
+            .collect()
+    }
+
+    fn run(fill: FillModel, signals: Vec<(usize, Signal)>) -> BacktestResult {
+        let config = BacktestConfig { initial_capital: 10_000.0, fill, sizing: PositionSizing::Quantity(10), costs: CostModel::zero() };
+        run_backtest(&rising_bars(), &mut Scripted(signals), &config)
+    }
+
+    #[test]
+    fn charges_follow_the_delivery_cost_model() {
+        let costs = CostModel::default();
+
+        let buy = costs.charges(TradeSide::Buy, 100_000.0);
+        assert_near(buy.brokerage, 20.0); // 0.03% is 30, capped at 20
+        assert_near(buy.stt, 100.0);
+        assert_near(buy.exchange, 2.97);
+        assert_near(buy.sebi, 0.1);
+        assert_near(buy.stamp_duty, 15.0);
+        // GST on brokerage, exchange and SEBI charges only
+        assert_near(buy.gst, (20.0 + 2.97 + 0.1) * 0.18);
+
+        // STT on both legs, stamp duty on buys only
+        let sell = costs.charges(TradeSide::Sell, 100_000.0);
+        assert_near(sell.stt, 100.0);
+        assert_near(sell.stamp_duty, 0.0);
+        assert_near(buy.total() - sell.total(), 15.0);
+
+        // Below the cap, and with no cap
+        assert_near(costs.charges(TradeSide::Buy, 10_000.0).brokerage, 3.0);
+        let uncapped = CostModel { brokerage_max: 0.0, ..costs };
+        assert_near(uncapped.charges(TradeSide::Buy, 100_000.0).brokerage, 30.0);
+
+        assert_eq!(CostModel::zero().charges(TradeSide::Buy, 100_000.0).total(), 0.0);
+    }
+
+    #[test]
+    fn position_size_is_limited_by_cash() {
+        let zero = CostModel::zero();
+        assert_eq!(PositionSizing::Quantity(100).quantity(50.0, 2_000.0, &zero), 40);
+        assert_eq!(PositionSizing::Quantity(10).quantity(50.0, 2_000.0, &zero), 10);
+        assert_eq!(PositionSizing::Amount(10_000.0).quantity(300.0, 100_000.0, &zero), 33);
+        assert_eq!(PositionSizing::Amount(10_000.0).quantity(300.0, 3_000.0, &zero), 10);
+        assert_eq!(PositionSizing::PercentOfEquity(50.0).quantity(100.0, 10_000.0, &zero), 50);
+        assert_eq!(PositionSizing::PercentOfEquity(100.0).quantity(0.0, 10_000.0, &zero), 0);
+        assert_eq!(PositionSizing::PercentOfEquity(100.0).quantity(100.0, 0.0, &zero), 0);
+
+        // All the cash can't buy 100 shares once the buy charges are added
+        let costs = CostModel::default();
+        let qty = PositionSizing::PercentOfEquity(100.0).quantity(100.0, 10_000.0, &costs);
+        assert_eq!(qty, 99);
+        assert!(9_900.0 + costs.charges(TradeSide::Buy, 9_900.0).total() <= 10_000.0);
+    }
+
+    #[test]
+    fn close_fills_on_the_signal_bar() {
+        let result = run(FillModel::Close, vec![(1, Signal::Buy), (3, Signal::Sell)]);
+        let trade = &result.trades[0];
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!((trade.entry_date.to_string(), trade.entry_price), ("2024-01-02".to_string(), 115.0));
+        assert_eq!((trade.exit_date.to_string(), trade.exit_price), ("2024-01-04".to_string(), 135.0));
+        assert_eq!((trade.quantity, trade.bars_held, trade.closed_at_end), (10, 2, false));
+        assert_near(trade.pnl, 200.0);
+    }
+
+    #[test]
+    fn next_open_fills_on_the_following_bar() {
+        let result = run(FillModel::NextOpen, vec![(1, Signal::Buy), (3, Signal::Sell)]);
+        let trade = &result.trades[0];
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!((trade.entry_date.to_string(), trade.entry_price), ("2024-01-03".to_string(), 120.0));
+        assert_eq!((trade.exit_date.to_string(), trade.exit_price), ("2024-01-05".to_string(), 140.0));
+        assert_eq!((trade.bars_held, trade.closed_at_end), (2, false));
+        assert_near(trade.pnl, 200.0);
+    }
+
+    #[test]
+    fn a_signal_on_the_last_bar() {
+        // There is no next open to fill at
+        let result = run(FillModel::NextOpen, vec![(5, Signal::Buy)]);
+        assert!(result.trades.is_empty());
+        assert_eq!(result.summary.exposure_pct, 0.0);
+
+        // Filled at the last close and closed again right there
+        let result = run(FillModel::Close, vec![(5, Signal::Buy)]);
+        let trade = &result.trades[0];
+        assert_eq!((trade.entry_price, trade.exit_price, trade.bars_held, trade.closed_at_end), (155.0, 155.0, 0, true));
+        assert_near(trade.pnl, 0.0);
+
+        // A pending sell on the last bar becomes the forced exit at its close
+        let result = run(FillModel::NextOpen, vec![(1, Signal::Buy), (5, Signal::Sell)]);
+        let trade = &result.trades[0];
+        assert_eq!((trade.exit_date.to_string(), trade.exit_price, trade.closed_at_end), ("2024-01-06".to_string(), 155.0, true));
+    }
+
+    #[test]
+    fn open_positions_are_closed_at_the_last_close() {
+        for (fill, entry_price, bars_held) in [(FillModel::Close, 115.0, 4), (FillModel::NextOpen, 120.0, 3)] {
+            let result = run(fill, vec![(1, Signal::Buy)]);
+            let trade = &result.trades[0];
+            assert_eq!(result.trades.len(), 1);
+            assert_eq!((trade.entry_price, trade.exit_price), (entry_price, 155.0));
+            assert_eq!((trade.bars_held, trade.closed_at_end), (bars_held, true));
+            assert_near(trade.pnl, (155.0 - entry_price) * 10.0);
+            assert_near(result.summary.final_equity, 10_000.0 + trade.pnl);
+        }
+    }
+
+    #[test]
+    fn summary_statistics_on_a_known_equity_curve() {
+        // Fully invested from the first close, so equity is 100 × close: 10,000, 11,000, 9,900, 12,100
+        let dates = [(2020, 1, 1), (2020, 6, 1), (2021, 1, 1), (2022, 1, 1)];
+        let closes = [100.0, 110.0, 99.0, 121.0];
+        let bars: Vec<PriceBar> = dates.iter().zip(closes)
+            .map(|(&(y, m, d), close)| bar(NaiveDate::from_ymd_opt(y, m, d).unwrap(), close, close))
+            .collect();
+        let config = BacktestConfig { initial_capital: 10_000.0, fill: FillModel::Close, sizing: PositionSizing::Quantity(100), costs: CostModel::zero() };
+        let summary = run_backtest(&bars, &mut BuyAndHold, &config).summary;
+
+        assert_near(summary.final_equity, 12_100.0);
+        assert_near(summary.total_return_pct, 21.0);
+        assert_near(summary.buy_and_hold_pct, 21.0);
+        // 731 days from the first bar to the last
+        assert_near(summary.cagr_pct.unwrap(), (1.21f64.powf(365.25 / 731.0) - 1.0) * 100.0);
+        // From the 11,000 peak down to 9,900
+        assert_near(summary.max_drawdown_pct, 10.0);
+
+        let returns = [0.0, 0.1, -0.1, 12_100.0 / 9_900.0 - 1.0];
+        let mean = returns.iter().sum::<f64>() / 4.0;
+        let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 3.0).sqrt();
+        assert_near(summary.sharpe.unwrap(), mean / std_dev * 252f64.sqrt());
+
+        assert_eq!((summary.trades, summary.win_rate_pct, summary.exposure_pct), (1, Some(100.0), 100.0));
+    }
+
+    #[test]
+    fn flat_equity_has_no_sharpe_ratio() {
+        let summary = run(FillModel::Close, Vec::new()).summary;
+        assert_eq!(summary.sharpe, None);
+        assert_eq!(summary.max_drawdown_pct, 0.0);
+        assert_eq!(summary.cagr_pct, Some(0.0));
+        assert_eq!(summary.win_rate_pct, None);
+    }
+}
@@ -0,0 +1,514 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::db::breadth::YEAR_SECS;
+use crate::db::timestamp_to_date;
+use crate::indicators::{ema, rsi, sma};
+
+/// Sessions averaged for volume alerts
+const VOLUME_AVERAGE_SESSIONS: usize = 20;
+
+/// Indicator alerts load this many times the period in closes, so EMA and RSI smoothing settles before the latest session
+const INDICATOR_LOOKBACK_PERIODS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    /// Previous close at or below the level, close above it
+    CrossesAbove,
+    /// Previous close at or above the level, close below it
+    CrossesBelow,
+    ChangeAbove,
+    ChangeBelow,
+    /// High beats every high of the previous 52 weeks
+    New52WeekHigh,
+    /// Volume is at least `value` times the average of the previous 20 sessions
+    VolumeAboveAverage,
+    /// Previous close at or below its SMA(period), close above it
+    CrossesAboveSma,
+    CrossesBelowSma,
+    /// Previous close at or below its EMA(period), close above it
+    CrossesAboveEma,
+    CrossesBelowEma,
+    /// RSI(period) was at or below `value` the session before and is above it now
+    RsiAbove,
+    RsiBelow,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 12] = [
+        AlertKind::CrossesAbove, AlertKind::CrossesBelow, AlertKind::ChangeAbove,
+        AlertKind::ChangeBelow, AlertKind::New52WeekHigh, AlertKind::VolumeAboveAverage,
+        AlertKind::CrossesAboveSma, AlertKind::CrossesBelowSma, AlertKind::CrossesAboveEma,
+        AlertKind::CrossesBelowEma, AlertKind::RsiAbove, AlertKind::RsiBelow,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            AlertKind::CrossesAbove => "crosses_above",
+            AlertKind::CrossesBelow => "crosses_below",
+            AlertKind::ChangeAbove => "change_above",
+            AlertKind::ChangeBelow => "change_below",
+            AlertKind::New52WeekHigh => "new_52w_high",
+            AlertKind::VolumeAboveAverage => "volume_above_average",
+            AlertKind::CrossesAboveSma => "crosses_above_sma",
+            AlertKind::CrossesBelowSma => "crosses_below_sma",
+            AlertKind::CrossesAboveEma => "crosses_above_ema",
+            AlertKind::CrossesBelowEma => "crosses_below_ema",
+            AlertKind::RsiAbove => "rsi_above",
+            AlertKind::RsiBelow => "rsi_below",
+        }
+    }
+
+    /// Accepts the storage key or the same with dashes, as typed on the command line
+    pub fn parse(key: &str) -> Option<Self> {
+        let key = key.trim().to_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlertKind::CrossesAbove => "Close crosses above",
+            AlertKind::CrossesBelow => "Close crosses below",
+            AlertKind::ChangeAbove => "% change above",
+            AlertKind::ChangeBelow => "% change below",
+            AlertKind::New52WeekHigh => "New 52-week high",
+            AlertKind::VolumeAboveAverage => "Volume above 20-day average ×",
+            AlertKind::CrossesAboveSma => "Close crosses above SMA",
+            AlertKind::CrossesBelowSma => "Close crosses below SMA",
+            AlertKind::CrossesAboveEma => "Close crosses above EMA",
+            AlertKind::CrossesBelowEma => "Close crosses below EMA",
+            AlertKind::RsiAbove => "RSI crosses above",
+            AlertKind::RsiBelow => "RSI crosses below",
+        }
+    }
+
+    /// Indicator period used when none is given, or None for kinds that don't use an indicator
+    pub fn default_period(&self) -> Option<usize> {
+        match self {
+            AlertKind::CrossesAboveSma | AlertKind::CrossesBelowSma
+            | AlertKind::CrossesAboveEma | AlertKind::CrossesBelowEma => Some(50),
+            AlertKind::RsiAbove | AlertKind::RsiBelow => Some(14),
+            _ => None,
+        }
+    }
+
+    /// What the rule's value means, or None when the kind takes no value
+    pub fn value_label(&self) -> Option<&'static str> {
+        match self {
+            AlertKind::CrossesAbove | AlertKind::CrossesBelow => Some("Price"),
+            AlertKind::ChangeAbove | AlertKind::ChangeBelow => Some("Percent"),
+            AlertKind::VolumeAboveAverage => Some("Multiple"),
+            AlertKind::RsiAbove | AlertKind::RsiBelow => Some("RSI level"),
+            AlertKind::New52WeekHigh | AlertKind::CrossesAboveSma | AlertKind::CrossesBelowSma
+            | AlertKind::CrossesAboveEma | AlertKind::CrossesBelowEma => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub id: i64,
+    pub symbol: String,
+    pub kind: AlertKind,
+    pub value: f64,
+    /// Indicator period, for SMA, EMA and RSI rules
+    pub period: Option<usize>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+impl AlertRule {
+    /// The condition in words, e.g. "Close crosses above 2500.00"
+    pub fn describe(&self) -> String {
+        match self.kind {
+            AlertKind::CrossesAbove | AlertKind::CrossesBelow => format!("{} {:.2}", self.kind.label(), self.value),
+            AlertKind::ChangeAbove | AlertKind::ChangeBelow => format!("{} {:+.2}%", self.kind.label(), self.value),
+            AlertKind::VolumeAboveAverage => format!("{} {}", self.kind.label(), self.value),
+            AlertKind::New52WeekHigh => self.kind.label().to_string(),
+            AlertKind::CrossesAboveSma | AlertKind::CrossesBelowSma
+            | AlertKind::CrossesAboveEma | AlertKind::CrossesBelowEma => {
+                format!("{}({})", self.kind.label(), self.period.unwrap_or_default())
+            }
+            AlertKind::RsiAbove | AlertKind::RsiBelow => {
+                let label = self.kind.label().replacen("RSI", &format!("RSI({})", self.period.unwrap_or_default()), 1);
+                format!("{} {:.2}", label, self.value)
+            }
+        }
+    }
+}
+
+/// An entry in the notification inbox
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertNotification {
+    pub id: i64,
+    /// None once the rule has been deleted
+    pub rule_id: Option<i64>,
+    pub symbol: String,
+    /// Session that triggered the alert
+    pub date: NaiveDate,
+    pub message: String,
+    pub is_read: bool,
+    pub created_at: i64,
+}
+
+/// Add a rule for `symbol`. The value is ignored for kinds that don't take one, and the period for kinds
+/// without an indicator; indicator kinds fall back to [`AlertKind::default_period`].
+pub fn create_alert_rule(conn: &Connection, symbol: &str, kind: AlertKind, value: f64, period: Option<usize>) -> Result<i64, Box<dyn std::error::Error>> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol is required".into());
+    }
+    let known: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM nse_symbols WHERE symbol = ?1)
+             OR EXISTS(SELECT 1 FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE')",
+        params![symbol],
+        |row| row.get(0),
+    )?;
+    if !known {
+        return Err(format!("Unknown symbol {}", symbol).into());
+    }
+
+    let value = match kind {
+        _ if kind.value_label().is_none() => 0.0,
+        _ if !value.is_finite() => return Err("Value must be a number".into()),
+        AlertKind::CrossesAbove | AlertKind::CrossesBelow | AlertKind::VolumeAboveAverage if value <= 0.0 => {
+            return Err(format!("{} needs a value above zero", kind.label()).into());
+        }
+        AlertKind::RsiAbove | AlertKind::RsiBelow if value <= 0.0 || value >= 100.0 => {
+            return Err(format!("{} needs a level between 0 and 100", kind.label()).into());
+        }
+        _ => value,
+    };
+
+    let period = match kind.default_period() {
+        Some(default) => match period.unwrap_or(default) {
+            0 => return Err(format!("{} needs a period above zero", kind.label()).into()),
+            period => Some(period),
+        },
+        None => None,
+    };
+
+    conn.execute(
+        "INSERT INTO alert_rules (symbol, kind, value, period, enabled, created_at) VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+        params![symbol, kind.key(), value, period.map(|p| p as i64), chrono::Utc::now().timestamp()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_alert_rules(conn: &Connection) -> rusqlite::Result<Vec<AlertRule>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, kind, value, period, enabled, created_at FROM alert_rules ORDER BY symbol, id"
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let kind: String = row.get(2)?;
+        let period: Option<i64> = row.get(4)?;
+        Ok((row.get(0)?, row.get(1)?, kind, row.get(3)?, period, row.get(5)?, row.get(6)?))
+    })?;
+    let mut rules = Vec::new();
+    for row in rows {
+        let (id, symbol, kind, value, period, enabled, created_at) = row?;
+        // Rules of a kind this build doesn't know are left alone
+        if let Some(kind) = AlertKind::parse(&kind) {
+            let period = period.map(|p| p as usize);
+            rules.push(AlertRule { id, symbol, kind, value, period, enabled, created_at });
+        }
+    }
+    Ok(rules)
+}
+
+pub fn set_alert_rule_enabled(conn: &Connection, id: i64, enabled: bool) -> rusqlite::Result<()> {
+    conn.execute("UPDATE alert_rules SET enabled = ?2 WHERE id = ?1", params![id, enabled])?;
+    Ok(())
+}
+
+/// Delete a rule; notifications it already raised stay in the inbox
+pub fn delete_alert_rule(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM alert_rules WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Check every enabled rule against the latest stored session and add an inbox entry for each one that fires.
+/// Only the newest session is checked, so backfilling older history doesn't replay old alerts,
+/// and a rule fires at most once per session however often this runs. Returns the new entries.
+pub fn evaluate_alerts(conn: &Connection) -> rusqlite::Result<Vec<AlertNotification>> {
+    let latest: Option<i64> = conn.query_row("SELECT MAX(date) FROM bhavcopy_data WHERE exchange = 'NSE'", [], |row| row.get(0))?;
+    let Some(latest) = latest else {
+        return Ok(Vec::new());
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let mut notifications = Vec::new();
+    for rule in get_alert_rules(conn)?.into_iter().filter(|rule| rule.enabled) {
+        let Some(message) = check_rule(conn, &rule, latest)? else {
+            continue;
+        };
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO alert_notifications (rule_id, symbol, date, message, is_read, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+            params![rule.id, rule.symbol, latest, message, now],
+        )?;
+        if inserted > 0 {
+            notifications.push(AlertNotification {
+                id: conn.last_insert_rowid(),
+                rule_id: Some(rule.id),
+                symbol: rule.symbol.clone(),
+                date: timestamp_to_date(latest).unwrap_or_default(),
+                message,
+                is_read: false,
+                created_at: now,
+            });
+        }
+    }
+    Ok(notifications)
+}
+
+struct SessionBar {
+    close: Option<f64>,
+    prev_close: Option<f64>,
+    high: Option<f64>,
+    volume: Option<i64>,
+}
+
+/// The notification text if `rule` fires on session `date`
+fn check_rule(conn: &Connection, rule: &AlertRule, date: i64) -> rusqlite::Result<Option<String>> {
+    let bar = conn.query_row(
+        "SELECT close, prev_close, high, volume FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE' AND date = ?2",
+        params![rule.symbol, date],
+        |row| Ok(SessionBar { close: row.get(0)?, prev_close: row.get(1)?, high: row.get(2)?, volume: row.get(3)? }),
+    ).optional()?;
+    let Some(SessionBar { close: Some(close), prev_close, high, volume }) = bar else {
+        return Ok(None);
+    };
+    let symbol = &rule.symbol;
+    let level = rule.value;
+
+    let message = match rule.kind {
+        AlertKind::CrossesAbove => prev_close
+            .filter(|prev| *prev <= level && close > level)
+            .map(|_| format!("{} closed at {:.2}, crossing above {:.2}", symbol, close, level)),
+        AlertKind::CrossesBelow => prev_close
+            .filter(|prev| *prev >= level && close < level)
+            .map(|_| format!("{} closed at {:.2}, crossing below {:.2}", symbol, close, level)),
+        AlertKind::ChangeAbove | AlertKind::ChangeBelow => prev_close
+            .filter(|prev| *prev > 0.0)
+            .map(|prev| (close - prev) / prev * 100.0)
+            .filter(|change| if rule.kind == AlertKind::ChangeAbove { *change > level } else { *change < level })
+            .map(|change| {
+                let direction = if rule.kind == AlertKind::ChangeAbove { "above" } else { "below" };
+                format!("{} changed {:+.2}% to {:.2}, {} {:+.2}%", symbol, change, close, direction, level)
+            }),
+        AlertKind::New52WeekHigh => {
+            let prior_high: Option<f64> = conn.query_row(
+                "SELECT MAX(high) FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE' AND date >= ?2 - ?3 AND date < ?2",
+                params![symbol, date, YEAR_SECS],
+                |row| row.get(0),
+            )?;
+            match (high, prior_high) {
+                (Some(high), Some(prior)) if high > prior => {
+                    Some(format!("{} made a new 52-week high of {:.2} (previous {:.2})", symbol, high, prior))
+                }
+                _ => None,
+            }
+        }
+        AlertKind::VolumeAboveAverage => {
+            let (average, sessions): (Option<f64>, i64) = conn.query_row(
+                "SELECT AVG(volume), COUNT(volume) FROM (
+                     SELECT volume FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE' AND date < ?2 ORDER BY date DESC LIMIT ?3
+                 )",
+                params![symbol, date, VOLUME_AVERAGE_SESSIONS as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            // A partial window would make newly listed stocks fire on every session
+            match (volume, average) {
+                (Some(volume), Some(average))
+                    if sessions as usize == VOLUME_AVERAGE_SESSIONS && average > 0.0 && volume as f64 >= level * average =>
+                {
+                    Some(format!("{} traded {} shares, {:.1}× its 20-day average", symbol, volume, volume as f64 / average))
+                }
+                _ => None,
+            }
+        }
+        AlertKind::CrossesAboveSma | AlertKind::CrossesBelowSma | AlertKind::CrossesAboveEma | AlertKind::CrossesBelowEma => {
+            let period = rule.period.or(rule.kind.default_period()).unwrap_or_default();
+            let closes = recent_closes(conn, symbol, date, period)?;
+            let (name, average) = match rule.kind {
+                AlertKind::CrossesAboveSma | AlertKind::CrossesBelowSma => ("SMA", sma(&closes, period)),
+                _ => ("EMA", ema(&closes, period)),
+            };
+            let above = matches!(rule.kind, AlertKind::CrossesAboveSma | AlertKind::CrossesAboveEma);
+            match last_two(&closes, &average) {
+                Some(((prev_close, prev_avg), (close, avg)))
+                    if (above && prev_close <= prev_avg && close > avg) || (!above && prev_close >= prev_avg && close < avg) =>
+                {
+                    let direction = if above { "above" } else { "below" };
+                    Some(format!("{} closed at {:.2}, crossing {} its {}({}) of {:.2}", symbol, close, direction, name, period, avg))
+                }
+                _ => None,
+            }
+        }
+        AlertKind::RsiAbove | AlertKind::RsiBelow => {
+            let period = rule.period.or(rule.kind.default_period()).unwrap_or_default();
+            let closes = recent_closes(conn, symbol, date, period)?;
+            let values = rsi(&closes, period);
+            let above = rule.kind == AlertKind::RsiAbove;
+            match values.as_slice() {
+                [.., Some(prev), Some(current)]
+                    if (above && *prev <= level && *current > level) || (!above && *prev >= level && *current < level) =>
+                {
+                    let direction = if above { "above" } else { "below" };
+                    Some(format!("{} RSI({}) moved {} {:.2} to {:.2}", symbol, period, direction, level, current))
+                }
+                _ => None,
+            }
+        }
+    };
+    Ok(message)
+}
+
+/// Closes of the sessions up to and including `date`, oldest first, enough to warm up a `period` indicator
+fn recent_closes(conn: &Connection, symbol: &str, date: i64, period: usize) -> rusqlite::Result<Vec<f64>> {
+    let limit = (period * INDICATOR_LOOKBACK_PERIODS).max(period + 2);
+    let mut stmt = conn.prepare(
+        "SELECT close FROM bhavcopy_data
+         WHERE symbol = ?1 AND exchange = 'NSE' AND series = 'EQ' AND date <= ?2 AND close IS NOT NULL
+         ORDER BY date DESC LIMIT ?3"
+    )?;
+    let mut closes = stmt.query_map(params![symbol, date, limit as i64], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<f64>>>()?;
+    closes.reverse();
+    Ok(closes)
+}
+
+/// The last two (close, indicator) pairs, when the indicator has a value for both
+fn last_two(closes: &[f64], values: &[Option<f64>]) -> Option<((f64, f64), (f64, f64))> {
+    match (closes, values) {
+        ([.., prev_close, close], [.., Some(prev), Some(current)]) => Some(((*prev_close, *prev), (*close, *current))),
+        _ => None,
+    }
+}
+
+/// Newest first
+pub fn get_alert_notifications(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<AlertNotification>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, rule_id, symbol, date, message, is_read, created_at
+         FROM alert_notifications
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?1"
+    )?;
+    let rows = stmt.query_map(params![limit as i64], |row| {
+        Ok(AlertNotification {
+            id: row.get(0)?,
+            rule_id: row.get(1)?,
+            symbol: row.get(2)?,
+            date: timestamp_to_date(row.get(3)?).unwrap_or_default(),
+            message: row.get(4)?,
+            is_read: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn count_unread_alert_notifications(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row("SELECT COUNT(*) FROM alert_notifications WHERE is_read = 0", [], |row| {
+        row.get::<_, i64>(0).map(|n| n as usize)
+    })
+}
+
+pub fn set_alert_notification_read(conn: &Connection, id: i64, is_read: bool) -> rusqlite::Result<()> {
+    conn.execute("UPDATE alert_notifications SET is_read = ?2 WHERE id = ?1", params![id, is_read])?;
+    Ok(())
+}
+
+pub fn mark_all_alert_notifications_read(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("UPDATE alert_notifications SET is_read = 1 WHERE is_read = 0", [])?;
+    Ok(())
+}
+
+pub fn delete_alert_notification(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM alert_notifications WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::date_to_timestamp;
+    use crate::db::test_support::test_db;
+
+    /// Store one NSE EQ session per day from 1 Jan 2024 with these closes
+    fn store_closes(conn: &Connection, symbol: &str, closes: &[f64]) {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        for (i, close) in closes.iter().enumerate() {
+            let prev_close = i.checked_sub(1).map(|p| closes[p]);
+            conn.execute(
+                "INSERT INTO bhavcopy_data (symbol, series, date, open, high, low, close, prev_close, volume, exchange)
+                 VALUES (?1, 'EQ', ?2, ?3, ?3, ?3, ?3, ?4, 1000, 'NSE')",
+                params![symbol, date_to_timestamp(start + chrono::Duration::days(i as i64)), close, prev_close],
+            ).unwrap();
+        }
+    }
+
+    fn fired(conn: &Connection, kind: AlertKind, value: f64, period: Option<usize>) -> Vec<String> {
+        create_alert_rule(conn, "ABC", kind, value, period).unwrap();
+        evaluate_alerts(conn).unwrap().into_iter().map(|alert| alert.message).collect()
+    }
+
+    #[test]
+    fn a_rule_fires_once_per_session() {
+        let conn = test_db();
+        store_closes(&conn, "ABC", &[100.0, 105.0]);
+
+        assert_eq!(fired(&conn, AlertKind::CrossesAbove, 102.0, None), vec!["ABC closed at 105.00, crossing above 102.00"]);
+        // UNIQUE(rule_id, date) keeps a second run from repeating it
+        assert!(evaluate_alerts(&conn).unwrap().is_empty());
+        assert_eq!(get_alert_notifications(&conn, 10).unwrap().len(), 1);
+        assert_eq!(count_unread_alert_notifications(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn only_the_latest_session_is_checked() {
+        let conn = test_db();
+        // The cross above 102 happened on the second session, not the latest one
+        store_closes(&conn, "ABC", &[100.0, 105.0, 106.0]);
+        assert!(fired(&conn, AlertKind::CrossesAbove, 102.0, None).is_empty());
+    }
+
+    #[test]
+    fn close_crossing_its_sma() {
+        let conn = test_db();
+        // SMA(3) is 9.67 with a close of 9, then 10.33 with a close of 12
+        store_closes(&conn, "ABC", &[10.0, 10.0, 10.0, 10.0, 9.0, 12.0]);
+        assert!(fired(&conn, AlertKind::CrossesBelowSma, 0.0, Some(3)).is_empty());
+        assert_eq!(fired(&conn, AlertKind::CrossesAboveSma, 0.0, Some(3)), vec!["ABC closed at 12.00, crossing above its SMA(3) of 10.33"]);
+    }
+
+    #[test]
+    fn close_crossing_its_ema() {
+        let conn = test_db();
+        // EMA(3) is 9 with a close of 8, then 10.5 with a close of 12
+        store_closes(&conn, "ABC", &[10.0, 10.0, 10.0, 10.0, 8.0, 12.0]);
+        assert_eq!(fired(&conn, AlertKind::CrossesAboveEma, 0.0, Some(3)), vec!["ABC closed at 12.00, crossing above its EMA(3) of 10.50"]);
+
+        let conn = test_db();
+        store_closes(&conn, "ABC", &[10.0, 10.0, 10.0, 10.0, 12.0, 8.0]);
+        assert_eq!(fired(&conn, AlertKind::CrossesBelowEma, 0.0, Some(3)), vec!["ABC closed at 8.00, crossing below its EMA(3) of 9.50"]);
+    }
+
+    #[test]
+    fn rsi_crossing_a_level() {
+        let conn = test_db();
+        // RSI(3) is 100 after four gains, then 66.67 after a loss
+        store_closes(&conn, "ABC", &[10.0, 11.0, 12.0, 13.0, 14.0, 13.0]);
+        assert!(fired(&conn, AlertKind::RsiAbove, 70.0, Some(3)).is_empty());
+        assert_eq!(fired(&conn, AlertKind::RsiBelow, 70.0, Some(3)), vec!["ABC RSI(3) moved below 70.00 to 66.67"]);
+    }
+
+    #[test]
+    fn indicator_rules_wait_for_enough_history() {
+        let conn = test_db();
+        store_closes(&conn, "ABC", &[10.0, 12.0]);
+        assert!(fired(&conn, AlertKind::CrossesAboveSma, 0.0, Some(3)).is_empty());
+        assert!(fired(&conn, AlertKind::RsiAbove, 50.0, Some(3)).is_empty());
+    }
+}
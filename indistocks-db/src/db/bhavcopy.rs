@@ -0,0 +1,594 @@
+use std::fmt;
+use std::io::Read;
+use chrono::NaiveDate;
+use crate::models::{BhavCopyRow, Exchange};
+
+/// The NSE and BSE equity BhavCopy layouts we know how to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BhavCopyFormat {
+    /// `cmDDMONYYYYbhav.csv` before TOTALTRADES and ISIN were added (roughly pre-2011)
+    LegacyNoIsin,
+    /// `cmDDMONYYYYbhav.csv` with TOTALTRADES and ISIN (up to mid-2024)
+    Legacy,
+    /// `BhavCopy_NSE_CM_0_0_0_YYYYMMDD_F_0000.csv` UDiFF format (2024 onwards)
+    Udiff,
+    /// `sec_bhavdata_full_DDMMYYYY.csv` security-wise full BhavCopy (turnover in lakhs)
+    SecurityWise,
+    /// BSE `EQ_ISINCODE_DDMMYY.CSV` (up to mid-2024)
+    BseLegacy,
+    /// `BhavCopy_BSE_CM_0_0_0_YYYYMMDD_F_0000.CSV`, the NSE UDiFF layout with `Src` set to BSE
+    BseUdiff,
+}
+
+/// Header names for each field of a format. `None` means the format has no such column.
+struct ColumnNames {
+    exchange: Exchange,
+    symbol: &'static str,
+    series: &'static str,
+    date: &'static str,
+    date_formats: &'static [&'static str],
+    open: &'static str,
+    high: &'static str,
+    low: &'static str,
+    close: &'static str,
+    last: &'static str,
+    prev_close: &'static str,
+    volume: &'static str,
+    turnover: &'static str,
+    turnover_multiplier: f64,
+    trades: Option<&'static str>,
+    isin: Option<&'static str>,
+    name: Option<&'static str>,
+    deliverable_qty: Option<&'static str>,
+    delivery_percent: Option<&'static str>,
+}
+
+const LEGACY_COLUMNS: ColumnNames = ColumnNames {
+    exchange: Exchange::Nse,
+    symbol: "SYMBOL",
+    series: "SERIES",
+    date: "TIMESTAMP",
+    date_formats: &["%d-%b-%Y"],
+    open: "OPEN",
+    high: "HIGH",
+    low: "LOW",
+    close: "CLOSE",
+    last: "LAST",
+    prev_close: "PREVCLOSE",
+    volume: "TOTTRDQTY",
+    turnover: "TOTTRDVAL",
+    turnover_multiplier: 1.0,
+    trades: Some("TOTALTRADES"),
+    isin: Some("ISIN"),
+    name: None,
+    deliverable_qty: None,
+    delivery_percent: None,
+};
+
+const LEGACY_NO_ISIN_COLUMNS: ColumnNames = ColumnNames {
+    trades: None,
+    isin: None,
+    ..LEGACY_COLUMNS
+};
+
+const UDIFF_COLUMNS: ColumnNames = ColumnNames {
+    exchange: Exchange::Nse,
+    symbol: "TckrSymb",
+    series: "SctySrs",
+    date: "TradDt",
+    date_formats: &["%Y-%m-%d", "%d-%b-%Y"],
+    open: "OpnPric",
+    high: "HghPric",
+    low: "LwPric",
+    close: "ClsPric",
+    last: "LastPric",
+    prev_close: "PrvsClsgPric",
+    volume: "TtlTradgVol",
+    turnover: "TtlTrfVal",
+    turnover_multiplier: 1.0,
+    trades: Some("TtlNbOfTxsExctd"),
+    isin: Some("ISIN"),
+    name: None,
+    deliverable_qty: None,
+    delivery_percent: None,
+};
+
+const SECURITY_WISE_COLUMNS: ColumnNames = ColumnNames {
+    exchange: Exchange::Nse,
+    symbol: "SYMBOL",
+    series: "SERIES",
+    date: "DATE1",
+    date_formats: &["%d-%b-%Y"],
+    open: "OPEN_PRICE",
+    high: "HIGH_PRICE",
+    low: "LOW_PRICE",
+    close: "CLOSE_PRICE",
+    last: "LAST_PRICE",
+    prev_close: "PREV_CLOSE",
+    volume: "TTL_TRD_QNTY",
+    turnover: "TURNOVER_LACS",
+    turnover_multiplier: 100_000.0,
+    trades: Some("NO_OF_TRADES"),
+    isin: None,
+    name: None,
+    deliverable_qty: Some("DELIV_QTY"),
+    delivery_percent: Some("DELIV_PER"),
+};
+
+// BSE identifies a listing by its scrip code, which both BSE layouts carry
+const BSE_LEGACY_COLUMNS: ColumnNames = ColumnNames {
+    exchange: Exchange::Bse,
+    symbol: "SC_CODE",
+    series: "SC_TYPE",
+    date: "TRADING_DATE",
+    date_formats: &["%d-%b-%y", "%d-%b-%Y", "%d/%m/%Y"],
+    open: "OPEN",
+    high: "HIGH",
+    low: "LOW",
+    close: "CLOSE",
+    last: "LAST",
+    prev_close: "PREVCLOSE",
+    volume: "NO_OF_SHRS",
+    turnover: "NET_TURNOV",
+    turnover_multiplier: 1.0,
+    trades: Some("NO_TRADES"),
+    isin: Some("ISIN_CODE"),
+    name: Some("SC_NAME"),
+    deliverable_qty: None,
+    delivery_percent: None,
+};
+
+const BSE_UDIFF_COLUMNS: ColumnNames = ColumnNames {
+    exchange: Exchange::Bse,
+    symbol: "FinInstrmId",
+    series: "FinInstrmTp",
+    name: Some("FinInstrmNm"),
+    ..UDIFF_COLUMNS
+};
+
+/// Map a BSE instrument type onto the NSE series code, so plain equity shares read as `EQ`
+fn bse_series(instrument_type: &str) -> String {
+    match instrument_type {
+        "Q" | "STK" => "EQ".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl BhavCopyFormat {
+    /// Identify the format from a header row. Header cells are compared after trimming,
+    /// since some files pad them with spaces or end with a trailing comma.
+    /// NSE and BSE UDiFF files share a header, so this reports [`BhavCopyFormat::Udiff`] for both.
+    pub fn detect(headers: &csv::StringRecord) -> Option<Self> {
+        let has = |name: &str| headers.iter().any(|h| h.trim() == name);
+        if has("SC_CODE") && has("SC_TYPE") && has("CLOSE") {
+            Some(BhavCopyFormat::BseLegacy)
+        } else if has("TckrSymb") && has("TradDt") {
+            Some(BhavCopyFormat::Udiff)
+        } else if has("SYMBOL") && has("CLOSE_PRICE") && has("DATE1") {
+            Some(BhavCopyFormat::SecurityWise)
+        } else if has("SYMBOL") && has("SERIES") && has("CLOSE") {
+            if has("ISIN") && has("TOTALTRADES") {
+                Some(BhavCopyFormat::Legacy)
+            } else {
+                Some(BhavCopyFormat::LegacyNoIsin)
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BhavCopyFormat::LegacyNoIsin => "legacy (no ISIN)",
+            BhavCopyFormat::Legacy => "legacy",
+            BhavCopyFormat::Udiff => "UDiFF",
+            BhavCopyFormat::SecurityWise => "security-wise full",
+            BhavCopyFormat::BseLegacy => "BSE legacy",
+            BhavCopyFormat::BseUdiff => "BSE UDiFF",
+        }
+    }
+
+    pub fn exchange(&self) -> Exchange {
+        self.column_names().exchange
+    }
+
+    fn column_names(&self) -> &'static ColumnNames {
+        match self {
+            BhavCopyFormat::LegacyNoIsin => &LEGACY_NO_ISIN_COLUMNS,
+            BhavCopyFormat::Legacy => &LEGACY_COLUMNS,
+            BhavCopyFormat::Udiff => &UDIFF_COLUMNS,
+            BhavCopyFormat::SecurityWise => &SECURITY_WISE_COLUMNS,
+            BhavCopyFormat::BseLegacy => &BSE_LEGACY_COLUMNS,
+            BhavCopyFormat::BseUdiff => &BSE_UDIFF_COLUMNS,
+        }
+    }
+}
+
+/// Errors that stop a whole BhavCopy file from being parsed
+#[derive(Debug)]
+pub enum BhavCopyError {
+    Csv(csv::Error),
+    UnknownFormat,
+    MissingColumn(BhavCopyFormat, &'static str),
+}
+
+impl fmt::Display for BhavCopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BhavCopyError::Csv(e) => write!(f, "CSV error: {}", e),
+            BhavCopyError::UnknownFormat => write!(f, "CSV header does not match a known BhavCopy format"),
+            BhavCopyError::MissingColumn(format, column) => {
+                write!(f, "{} BhavCopy is missing the {} column", format.label(), column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BhavCopyError {}
+
+impl From<csv::Error> for BhavCopyError {
+    fn from(e: csv::Error) -> Self {
+        BhavCopyError::Csv(e)
+    }
+}
+
+/// A single row that could not be turned into a [`BhavCopyRow`]
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// 1-based line number in the CSV, when known
+    pub line: Option<u64>,
+    pub field: &'static str,
+    pub value: String,
+    pub reason: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: ", line)?,
+            None => write!(f, "unknown line: ")?,
+        }
+        write!(f, "{} = {:?}: {}", self.field, self.value, self.reason)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+struct ColumnMap {
+    symbol: usize,
+    series: usize,
+    date: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    last: usize,
+    prev_close: usize,
+    volume: usize,
+    turnover: usize,
+    trades: Option<usize>,
+    isin: Option<usize>,
+    name: Option<usize>,
+    deliverable_qty: Option<usize>,
+    delivery_percent: Option<usize>,
+}
+
+impl ColumnMap {
+    fn build(format: BhavCopyFormat, headers: &csv::StringRecord) -> Result<Self, BhavCopyError> {
+        let names = format.column_names();
+        let find = |name: &'static str| headers.iter().position(|h| h.trim() == name);
+        let require = |name: &'static str| find(name).ok_or(BhavCopyError::MissingColumn(format, name));
+
+        Ok(ColumnMap {
+            symbol: require(names.symbol)?,
+            series: require(names.series)?,
+            date: find(names.date),
+            open: require(names.open)?,
+            high: require(names.high)?,
+            low: require(names.low)?,
+            close: require(names.close)?,
+            last: require(names.last)?,
+            prev_close: require(names.prev_close)?,
+            volume: require(names.volume)?,
+            turnover: require(names.turnover)?,
+            trades: names.trades.and_then(find),
+            isin: names.isin.and_then(find),
+            name: names.name.and_then(find),
+            deliverable_qty: names.deliverable_qty.and_then(find),
+            delivery_percent: names.delivery_percent.and_then(find),
+        })
+    }
+}
+
+/// Reads an NSE or BSE equity BhavCopy in any known format and yields typed rows.
+/// Malformed rows are reported as [`RowError`]s rather than being coerced to zero.
+pub struct BhavCopyParser<R: Read> {
+    reader: csv::Reader<R>,
+    format: BhavCopyFormat,
+    columns: ColumnMap,
+    fallback_date: Option<NaiveDate>,
+    record: csv::StringRecord,
+    /// `record` was read ahead while detecting the format and is yet to be returned
+    pending: bool,
+}
+
+impl<'a> BhavCopyParser<&'a [u8]> {
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, BhavCopyError> {
+        Self::new(data)
+    }
+}
+
+impl<R: Read> BhavCopyParser<R> {
+    pub fn new(reader: R) -> Result<Self, BhavCopyError> {
+        // Some NSE files (e.g., 2024-06-19, 2024-06-20) have trailing commas in headers
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(reader);
+
+        let headers = reader.headers()?.clone();
+        let mut format = BhavCopyFormat::detect(&headers).ok_or(BhavCopyError::UnknownFormat)?;
+
+        // Only the Src column of the rows tells a BSE UDiFF file from an NSE one
+        let mut record = csv::StringRecord::new();
+        let mut pending = false;
+        if let (BhavCopyFormat::Udiff, Some(src)) = (format, headers.iter().position(|h| h.trim() == "Src")) {
+            pending = reader.read_record(&mut record)?;
+            if pending && record.get(src).map(|s| s.trim()) == Some("BSE") {
+                format = BhavCopyFormat::BseUdiff;
+            }
+        }
+        let columns = ColumnMap::build(format, &headers)?;
+
+        Ok(Self {
+            reader,
+            format,
+            columns,
+            fallback_date: None,
+            record,
+            pending,
+        })
+    }
+
+    /// Date to use for rows when the file has no date column or a row leaves it blank
+    pub fn with_fallback_date(mut self, date: Option<NaiveDate>) -> Self {
+        self.fallback_date = date;
+        self
+    }
+
+    pub fn format(&self) -> BhavCopyFormat {
+        self.format
+    }
+
+    fn parse_record(&self) -> Result<BhavCopyRow, RowError> {
+        let record = &self.record;
+        let line = record.position().map(|p| p.line());
+        let names = self.format.column_names();
+        let cols = &self.columns;
+
+        let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+        let error = |field: &'static str, value: &str, reason: &str| RowError {
+            line,
+            field,
+            value: value.to_string(),
+            reason: reason.to_string(),
+        };
+        let real = |field: &'static str, idx: usize| -> Result<f64, RowError> {
+            let value = text(idx);
+            if value.is_empty() {
+                return Err(error(field, value, "missing value"));
+            }
+            value.parse::<f64>().map_err(|_| error(field, value, "not a number"))
+        };
+        let int = |field: &'static str, idx: usize| -> Result<i64, RowError> {
+            let value = text(idx);
+            if value.is_empty() {
+                return Err(error(field, value, "missing value"));
+            }
+            // Some files write whole quantities as "1234.00"
+            value.parse::<i64>()
+                .or_else(|_| match value.parse::<f64>() {
+                    Ok(v) if v.fract() == 0.0 => Ok(v as i64),
+                    _ => Err(()),
+                })
+                .map_err(|_| error(field, value, "not an integer"))
+        };
+
+        let symbol = text(cols.symbol).to_uppercase();
+        if symbol.is_empty() {
+            return Err(error("symbol", "", "missing value"));
+        }
+
+        let date = match cols.date.map(text).filter(|s| !s.is_empty()) {
+            Some(value) => names.date_formats.iter()
+                .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+                .ok_or_else(|| error("date", value, "unrecognised date"))?,
+            None => self.fallback_date.ok_or_else(|| error("date", "", "missing value"))?,
+        };
+
+        let last = match text(cols.last) {
+            "" | "-" => None,
+            _ => Some(real("last", cols.last)?),
+        };
+        let trades = match cols.trades {
+            Some(idx) if !text(idx).is_empty() => Some(int("trades", idx)?),
+            _ => None,
+        };
+        // Delivery is "-" for series that settle without it, such as trade-for-trade
+        let deliverable_qty = match cols.deliverable_qty {
+            Some(idx) if !matches!(text(idx), "" | "-") => Some(int("deliverable_qty", idx)?),
+            _ => None,
+        };
+        let delivery_percent = match cols.delivery_percent {
+            Some(idx) if !matches!(text(idx), "" | "-") => Some(real("delivery_percent", idx)?),
+            _ => None,
+        };
+        let isin = cols.isin.map(text).filter(|s| !s.is_empty()).map(|s| s.to_string());
+        let name = cols.name.map(text).filter(|s| !s.is_empty()).map(|s| s.to_string());
+        let series = match names.exchange {
+            Exchange::Nse => text(cols.series).to_string(),
+            Exchange::Bse => bse_series(text(cols.series)),
+        };
+
+        Ok(BhavCopyRow {
+            symbol,
+            series,
+            date,
+            open: real("open", cols.open)?,
+            high: real("high", cols.high)?,
+            low: real("low", cols.low)?,
+            close: real("close", cols.close)?,
+            last,
+            prev_close: real("prev_close", cols.prev_close)?,
+            volume: int("volume", cols.volume)?,
+            turnover: real("turnover", cols.turnover)? * names.turnover_multiplier,
+            trades,
+            isin,
+            exchange: names.exchange,
+            name,
+            deliverable_qty,
+            delivery_percent,
+        })
+    }
+}
+
+impl<R: Read> Iterator for BhavCopyParser<R> {
+    type Item = Result<BhavCopyRow, RowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let read = if self.pending {
+                self.pending = false;
+                Ok(true)
+            } else {
+                self.reader.read_record(&mut self.record)
+            };
+            match read {
+                Ok(false) => return None,
+                // Skip blank lines some files end with
+                Ok(true) if self.record.iter().all(|f| f.trim().is_empty()) => continue,
+                Ok(true) => return Some(self.parse_record()),
+                Err(e) => {
+                    return Some(Err(RowError {
+                        line: e.position().map(|p| p.line()),
+                        field: "record",
+                        value: String::new(),
+                        reason: e.to_string(),
+                    }));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(csv: &str) -> (BhavCopyFormat, Vec<Result<BhavCopyRow, RowError>>) {
+        let parser = BhavCopyParser::from_bytes(csv.as_bytes()).unwrap();
+        let format = parser.format();
+        (format, parser.collect())
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn legacy_without_isin() {
+        let (format, rows) = parse(
+            "SYMBOL,SERIES,OPEN,HIGH,LOW,CLOSE,LAST,PREVCLOSE,TOTTRDQTY,TOTTRDVAL,TIMESTAMP,\n\
+             RELIANCE,EQ,1000,1020,990,1010,1011,995,123456,124690560,04-JAN-2010,\n",
+        );
+        assert_eq!(format, BhavCopyFormat::LegacyNoIsin);
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.symbol, "RELIANCE");
+        assert_eq!(row.date, date(2010, 1, 4));
+        assert_eq!(row.close, 1010.0);
+        assert_eq!(row.volume, 123456);
+        assert_eq!(row.trades, None);
+        assert_eq!(row.isin, None);
+    }
+
+    #[test]
+    fn legacy_with_isin() {
+        let (format, rows) = parse(
+            "SYMBOL,SERIES,OPEN,HIGH,LOW,CLOSE,LAST,PREVCLOSE,TOTTRDQTY,TOTTRDVAL,TIMESTAMP,TOTALTRADES,ISIN,\n\
+             TCS,EQ,3900,3950,3880,3925.5,3926,3890,200000,785100000,03-JUN-2024,15000,INE467B01029,\n\
+             \n",
+        );
+        assert_eq!(format, BhavCopyFormat::Legacy);
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.symbol, "TCS");
+        assert_eq!(row.date, date(2024, 6, 3));
+        assert_eq!(row.close, 3925.5);
+        assert_eq!(row.trades, Some(15000));
+        assert_eq!(row.isin.as_deref(), Some("INE467B01029"));
+        assert_eq!(row.exchange, Exchange::Nse);
+    }
+
+    #[test]
+    fn udiff() {
+        let (format, rows) = parse(
+            "TradDt,BizDt,Sgmt,Src,FinInstrmTp,FinInstrmId,ISIN,TckrSymb,SctySrs,XpryDt,FininstrmActlXpryDt,StrkPric,OptnTp,FinInstrmNm,OpnPric,HghPric,LwPric,ClsPric,LastPric,PrvsClsgPric,UndrlygPric,SttlmPric,OpnIntrst,ChngInOpnIntrst,TtlTradgVol,TtlTrfVal,TtlNbOfTxsExctd,SsnId,NewBrdLotQty,Rmks,Rsvd1,Rsvd2,Rsvd3,Rsvd4\n\
+             2024-07-31,2024-07-31,CM,NSE,STK,2885,INE002A01018,RELIANCE,EQ,,,,,RELIANCE INDUSTRIES LTD,3130,3150,3120,3140,3141,3131,,3140,,,500000,1570000000,20000,F1,1,,,,,\n",
+        );
+        assert_eq!(format, BhavCopyFormat::Udiff);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.symbol, "RELIANCE");
+        assert_eq!(row.date, date(2024, 7, 31));
+        assert_eq!(row.close, 3140.0);
+        assert_eq!(row.last, Some(3141.0));
+        assert_eq!(row.trades, Some(20000));
+        assert_eq!(row.exchange, Exchange::Nse);
+    }
+
+    #[test]
+    fn security_wise_full() {
+        let (format, rows) = parse(
+            "SYMBOL, SERIES, DATE1, PREV_CLOSE, OPEN_PRICE, HIGH_PRICE, LOW_PRICE, LAST_PRICE, CLOSE_PRICE, AVG_PRICE, TTL_TRD_QNTY, TURNOVER_LACS, NO_OF_TRADES, DELIV_QTY, DELIV_PER\n\
+             INFY, EQ, 31-Jul-2024, 1850, 1855, 1870, 1845, 1862, 1860, 1858.2, 400000, 7432.8, 30000, 240000, 60.00\n\
+             SOMESME, BE, 31-Jul-2024, 10, 10, 10.5, 9.8, 10.2, 10.1, 10.1, 5000, 0.505, 40, -, -\n",
+        );
+        assert_eq!(format, BhavCopyFormat::SecurityWise);
+        assert_eq!(rows.len(), 2);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.symbol, "INFY");
+        assert_eq!(row.date, date(2024, 7, 31));
+        assert_eq!(row.close, 1860.0);
+        assert_eq!(row.turnover, 7432.8 * 100_000.0);
+        assert_eq!(row.deliverable_qty, Some(240000));
+        assert_eq!(row.delivery_percent, Some(60.0));
+        let row = rows[1].as_ref().unwrap();
+        assert_eq!(row.deliverable_qty, None);
+        assert_eq!(row.delivery_percent, None);
+    }
+
+    #[test]
+    fn malformed_fields_are_row_errors() {
+        let (_, rows) = parse(
+            "SYMBOL,SERIES,OPEN,HIGH,LOW,CLOSE,LAST,PREVCLOSE,TOTTRDQTY,TOTTRDVAL,TIMESTAMP\n\
+             GOOD,EQ,10,11,9,10.5,10.5,10,100,1050,04-JAN-2010\n\
+             BADPRICE,EQ,10,11,9,n/a,10.5,10,100,1050,04-JAN-2010\n\
+             BADVOLUME,EQ,10,11,9,10.5,10.5,10,lots,1050,04-JAN-2010\n",
+        );
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].is_ok());
+
+        let err = rows[1].as_ref().unwrap_err();
+        assert_eq!((err.line, err.field, err.value.as_str()), (Some(3), "close", "n/a"));
+        assert_eq!(err.reason, "not a number");
+
+        let err = rows[2].as_ref().unwrap_err();
+        assert_eq!((err.line, err.field, err.value.as_str()), (Some(4), "volume", "lots"));
+        assert_eq!(err.reason, "not an integer");
+    }
+
+    #[test]
+    fn unknown_header_is_rejected() {
+        let result = BhavCopyParser::from_bytes(b"Date,Ticker,Price\n2024-07-31,RELIANCE,3140\n");
+        assert!(matches!(result, Err(BhavCopyError::UnknownFormat)));
+    }
+}
@@ -0,0 +1,300 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use crate::db::timestamp_to_date;
+
+/// Seconds in the 52-week lookback used for new highs and lows
+pub(crate) const YEAR_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// A stock in one of the top-movers lists
+#[derive(Debug, Clone, Serialize)]
+pub struct Mover {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub close: f64,
+    pub change_percent: f64,
+    pub volume: i64,
+    pub turnover: f64,
+}
+
+/// Advance/decline counts and new highs/lows for one session
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BreadthPoint {
+    pub date: NaiveDate,
+    pub advances: usize,
+    pub declines: usize,
+    pub unchanged: usize,
+    /// Stocks whose high beat every high of the previous 52 weeks
+    pub new_highs: usize,
+    /// Stocks whose low undercut every low of the previous 52 weeks
+    pub new_lows: usize,
+}
+
+impl BreadthPoint {
+    /// Advances per decline; None when nothing declined
+    pub fn advance_decline_ratio(&self) -> Option<f64> {
+        (self.declines > 0).then(|| self.advances as f64 / self.declines as f64)
+    }
+}
+
+/// Market overview for the latest stored session (EQ series only)
+#[derive(Debug, Clone)]
+pub struct MarketBreadth {
+    pub summary: BreadthPoint,
+    pub total_volume: i64,
+    pub total_turnover: f64,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    pub most_active_by_volume: Vec<Mover>,
+    pub most_active_by_turnover: Vec<Mover>,
+}
+
+/// Breadth for the latest session with `top_n` stocks in each movers list, or None without data
+pub fn get_market_breadth(conn: &Connection, top_n: usize) -> rusqlite::Result<Option<MarketBreadth>> {
+    let latest: Option<i64> = conn.query_row(
+        "SELECT MAX(date) FROM bhavcopy_data WHERE series = 'EQ' AND exchange = 'NSE'",
+        [],
+        |row| row.get(0),
+    )?;
+    let Some(latest) = latest else {
+        return Ok(None);
+    };
+
+    let summary = conn.query_row(
+        "SELECT SUM(t.close > t.prev_close),
+                SUM(t.close < t.prev_close),
+                SUM(t.close = t.prev_close),
+                SUM(t.high > p.prior_high),
+                SUM(t.low < p.prior_low)
+         FROM bhavcopy_data t
+         LEFT JOIN (
+             SELECT symbol, MAX(high) AS prior_high, MIN(low) AS prior_low
+             FROM bhavcopy_data
+             WHERE series = 'EQ' AND exchange = 'NSE' AND date >= ?1 - ?2 AND date < ?1
+             GROUP BY symbol
+         ) p ON p.symbol = t.symbol
+         WHERE t.date = ?1 AND t.series = 'EQ' AND t.exchange = 'NSE' AND t.prev_close > 0",
+        params![latest, YEAR_SECS],
+        |row| {
+            let count = |idx: usize| row.get::<_, Option<i64>>(idx).map(|n| n.unwrap_or(0) as usize);
+            Ok(BreadthPoint {
+                date: timestamp_to_date(latest).unwrap_or_default(),
+                advances: count(0)?,
+                declines: count(1)?,
+                unchanged: count(2)?,
+                new_highs: count(3)?,
+                new_lows: count(4)?,
+            })
+        },
+    )?;
+
+    let (total_volume, total_turnover): (Option<i64>, Option<f64>) = conn.query_row(
+        "SELECT SUM(volume), SUM(turnover) FROM bhavcopy_data WHERE date = ?1 AND series = 'EQ' AND exchange = 'NSE'",
+        params![latest],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(Some(MarketBreadth {
+        summary,
+        total_volume: total_volume.unwrap_or(0),
+        total_turnover: total_turnover.unwrap_or(0.0),
+        gainers: get_movers(conn, latest, "change_percent DESC", top_n)?,
+        losers: get_movers(conn, latest, "change_percent ASC", top_n)?,
+        most_active_by_volume: get_movers(conn, latest, "COALESCE(b.volume, 0) DESC", top_n)?,
+        most_active_by_turnover: get_movers(conn, latest, "COALESCE(b.turnover, 0) DESC", top_n)?,
+    }))
+}
+
+fn get_movers(conn: &Connection, date: i64, order_by: &str, limit: usize) -> rusqlite::Result<Vec<Mover>> {
+    let query = format!(
+        "SELECT b.symbol, ns.name, b.close, (b.close - b.prev_close) / b.prev_close * 100.0 AS change_percent,
+                COALESCE(b.volume, 0), COALESCE(b.turnover, 0)
+         FROM bhavcopy_data b
+         LEFT JOIN nse_symbols ns ON ns.symbol = b.symbol
+         WHERE b.date = ?1 AND b.series = 'EQ' AND b.exchange = 'NSE' AND b.prev_close > 0
+         ORDER BY {}, b.symbol
+         LIMIT ?2",
+        order_by
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map(params![date, limit as i64], |row| {
+        Ok(Mover {
+            symbol: row.get(0)?,
+            name: row.get(1)?,
+            close: row.get(2)?,
+            change_percent: row.get(3)?,
+            volume: row.get(4)?,
+            turnover: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Breadth for each of the latest `sessions` stored sessions, oldest first
+pub fn get_breadth_history(conn: &Connection, sessions: usize) -> rusqlite::Result<Vec<BreadthPoint>> {
+    let range: Option<(i64, i64)> = conn.query_row(
+        "SELECT MIN(date), MAX(date) FROM (
+             SELECT DISTINCT date FROM bhavcopy_data WHERE series = 'EQ' AND exchange = 'NSE' ORDER BY date DESC LIMIT ?1
+         )",
+        params![sessions as i64],
+        |row| Ok(row.get::<_, Option<i64>>(0)?.zip(row.get::<_, Option<i64>>(1)?)),
+    )?;
+    match range {
+        Some((from, to)) => get_breadth_between(conn, from, to),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Breadth for every session from `from` to `to` (timestamps), oldest first.
+/// A new high or low needs at least one earlier session within the 52 weeks to compare against.
+/// Highs and lows are tracked per symbol with sliding-window deques; SQL window frames over
+/// a year of history are an order of magnitude slower.
+fn get_breadth_between(conn: &Connection, from: i64, to: i64) -> rusqlite::Result<Vec<BreadthPoint>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT symbol, date, high, low, close, prev_close
+         FROM bhavcopy_data
+         WHERE series = 'EQ' AND exchange = 'NSE' AND date BETWEEN ?1 AND ?2
+         ORDER BY symbol, date"
+    )?;
+    let mut rows = stmt.query(params![from - YEAR_SECS, to])?;
+
+    let mut points: BTreeMap<i64, BreadthPoint> = BTreeMap::new();
+    let mut symbol = String::new();
+    // (date, value) with values decreasing (highs) or increasing (lows) from front to back
+    let mut highs: VecDeque<(i64, f64)> = VecDeque::new();
+    let mut lows: VecDeque<(i64, f64)> = VecDeque::new();
+
+    while let Some(row) = rows.next()? {
+        let row_symbol = row.get_ref(0)?.as_str()?;
+        let date: i64 = row.get(1)?;
+        let high: Option<f64> = row.get(2)?;
+        let low: Option<f64> = row.get(3)?;
+        let close: Option<f64> = row.get(4)?;
+        let prev_close: Option<f64> = row.get(5)?;
+
+        if row_symbol != symbol {
+            symbol = row_symbol.to_string();
+            highs.clear();
+            lows.clear();
+        }
+        while highs.front().is_some_and(|(d, _)| *d < date - YEAR_SECS) {
+            highs.pop_front();
+        }
+        while lows.front().is_some_and(|(d, _)| *d < date - YEAR_SECS) {
+            lows.pop_front();
+        }
+
+        if date >= from {
+            if let (Some(close), Some(prev_close)) = (close, prev_close.filter(|p| *p > 0.0)) {
+                let point = points.entry(date).or_insert_with(|| BreadthPoint {
+                    date: timestamp_to_date(date).unwrap_or_default(),
+                    advances: 0,
+                    declines: 0,
+                    unchanged: 0,
+                    new_highs: 0,
+                    new_lows: 0,
+                });
+                match close.partial_cmp(&prev_close) {
+                    Some(std::cmp::Ordering::Greater) => point.advances += 1,
+                    Some(std::cmp::Ordering::Less) => point.declines += 1,
+                    _ => point.unchanged += 1,
+                }
+                if let (Some(high), Some((_, prior))) = (high, highs.front()) {
+                    if high > *prior {
+                        point.new_highs += 1;
+                    }
+                }
+                if let (Some(low), Some((_, prior))) = (low, lows.front()) {
+                    if low < *prior {
+                        point.new_lows += 1;
+                    }
+                }
+            }
+        }
+
+        if let Some(high) = high {
+            while highs.back().is_some_and(|(_, h)| *h <= high) {
+                highs.pop_back();
+            }
+            highs.push_back((date, high));
+        }
+        if let Some(low) = low {
+            while lows.back().is_some_and(|(_, l)| *l >= low) {
+                lows.pop_back();
+            }
+            lows.push_back((date, low));
+        }
+    }
+
+    Ok(points.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::date_to_timestamp;
+    use crate::db::test_support::test_db;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// One session as (high, low, close, prev_close)
+    fn store(conn: &Connection, symbol: &str, series: &str, date: NaiveDate, (high, low, close, prev_close): (f64, f64, f64, Option<f64>)) {
+        conn.execute(
+            "INSERT INTO bhavcopy_data (symbol, series, date, open, high, low, close, prev_close, volume, turnover, exchange)
+             VALUES (?1, ?2, ?3, ?6, ?4, ?5, ?6, ?7, 1000, ?6 * 1000, 'NSE')",
+            params![symbol, series, date_to_timestamp(date), high, low, close, prev_close],
+        ).unwrap();
+    }
+
+    /// 2 Jan 2024 after a session on 1 Jan, with one stock that also traded over a year earlier
+    fn fixture_day(conn: &Connection) {
+        let (before, day) = (date(2024, 1, 1), date(2024, 1, 2));
+        store(conn, "AAA", "EQ", before, (110.0, 90.0, 100.0, Some(95.0)));
+        store(conn, "AAA", "EQ", day, (115.0, 105.0, 112.0, Some(100.0))); // Up, new high
+        store(conn, "BBB", "EQ", before, (55.0, 45.0, 50.0, None));
+        store(conn, "BBB", "EQ", day, (49.0, 40.0, 42.0, Some(50.0))); // Down, new low
+        store(conn, "CCC", "EQ", before, (22.0, 18.0, 20.0, None));
+        store(conn, "CCC", "EQ", day, (21.0, 19.0, 20.0, Some(20.0))); // Unchanged
+        store(conn, "DDD", "EQ", day, (30.0, 25.0, 28.0, Some(26.0))); // Up; just listed, so nothing to beat
+        // Up with a new high and a new low; the range from over 52 weeks ago doesn't count
+        store(conn, "EEE", "EQ", date(2022, 11, 28), (500.0, 1.0, 250.0, None));
+        store(conn, "EEE", "EQ", before, (60.0, 50.0, 55.0, None));
+        store(conn, "EEE", "EQ", day, (70.0, 45.0, 65.0, Some(55.0)));
+        // Not counted: another series, and a row without a previous close
+        store(conn, "FFF", "BE", day, (10.0, 8.0, 9.0, Some(5.0)));
+        store(conn, "GGG", "EQ", day, (10.0, 8.0, 9.0, None));
+    }
+
+    #[test]
+    fn breadth_on_the_latest_session() {
+        let conn = test_db();
+        assert!(get_market_breadth(&conn, 5).unwrap().is_none());
+        fixture_day(&conn);
+
+        let breadth = get_market_breadth(&conn, 2).unwrap().unwrap();
+        let expected = BreadthPoint { date: date(2024, 1, 2), advances: 3, declines: 1, unchanged: 1, new_highs: 2, new_lows: 2 };
+        assert_eq!(breadth.summary, expected);
+        assert_eq!(breadth.summary.advance_decline_ratio(), Some(3.0));
+
+        let symbols = |movers: &[Mover]| movers.iter().map(|m| m.symbol.clone()).collect::<Vec<_>>();
+        assert_eq!(symbols(&breadth.gainers), vec!["EEE", "AAA"]);
+        assert_eq!(symbols(&breadth.losers), vec!["BBB", "CCC"]);
+        assert!((breadth.gainers[0].change_percent - 10.0 / 55.0 * 100.0).abs() < 1e-9);
+        assert_eq!(symbols(&breadth.most_active_by_turnover), vec!["AAA", "EEE"]);
+    }
+
+    #[test]
+    fn breadth_history_matches_the_latest_session() {
+        let conn = test_db();
+        fixture_day(&conn);
+
+        let history = get_breadth_history(&conn, 2).unwrap();
+        let first = BreadthPoint { date: date(2024, 1, 1), advances: 1, declines: 0, unchanged: 0, new_highs: 0, new_lows: 0 };
+        assert_eq!(history, vec![first, get_market_breadth(&conn, 1).unwrap().unwrap().summary]);
+        assert_eq!(get_breadth_history(&conn, 1).unwrap().len(), 1);
+        assert_eq!(first.advance_decline_ratio(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use rusqlite::{Connection, params};
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::collections::HashSet;
+use std::path::Path;
+use crate::db::{date_to_timestamp, timestamp_to_date};
+
+/// An exchange holiday on a weekday
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketHoliday {
+    pub date: NaiveDate,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HolidayImportReport {
+    pub inserted: usize,
+    /// Already in the calendar (descriptions are updated)
+    pub updated: usize,
+    /// Saturdays and Sundays, which are closed anyway
+    pub weekends: usize,
+    pub rejected: Vec<String>,
+}
+
+/// Weekends plus the imported exchange holidays
+#[derive(Debug, Clone, Default)]
+pub struct TradingCalendar {
+    holidays: HashSet<NaiveDate>,
+}
+
+impl TradingCalendar {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        let holidays = get_market_holidays(conn)?.into_iter().map(|h| h.date).collect();
+        Ok(Self { holidays })
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Trading days from `from` to `to`, inclusive, oldest first
+    pub fn trading_days(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        from.iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| self.is_trading_day(*date))
+            .collect()
+    }
+}
+
+/// Import an exchange holiday list CSV, such as the one on NSE's "Holidays" page
+/// (`Sr. No, Date, Day, Description` with dates like `26-Jan-2024`). Any column whose
+/// header contains "date" is read as the date; "description" or "holiday" as the name.
+pub fn import_holidays_csv(conn: &Connection, path: &Path) -> Result<HolidayImportReport, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    let data = data.strip_prefix(b"\xEF\xBB\xBF".as_slice()).unwrap_or(&data);
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = rdr.headers()?.clone();
+    let column = |names: &[&str]| headers.iter()
+        .position(|h| names.iter().any(|name| h.trim().to_lowercase().contains(name)));
+    let date_idx = column(&["date"]).ok_or("Missing date column")?;
+    let description_idx = column(&["description", "holiday"]);
+
+    let mut report = HolidayImportReport::default();
+
+    for (line, result) in rdr.records().enumerate() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                report.rejected.push(format!("line {}: {}", line + 2, e));
+                continue;
+            }
+        };
+        let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+
+        let date_str = text(date_idx);
+        let Some(date) = ["%d-%b-%Y", "%d-%B-%Y", "%d %b %Y", "%d %B %Y", "%Y-%m-%d", "%d-%m-%Y", "%d/%m/%Y"].iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(date_str, fmt).ok())
+        else {
+            if !record.iter().all(|field| field.trim().is_empty()) {
+                report.rejected.push(format!("line {}: invalid date {:?}", line + 2, date_str));
+            }
+            continue;
+        };
+
+        if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            report.weekends += 1;
+            continue;
+        }
+
+        let description = description_idx.map(text).unwrap_or("").to_string();
+        if save_market_holiday(conn, date, &description)? {
+            report.inserted += 1;
+        } else {
+            report.updated += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Add a holiday, or update its description. Returns true if the date was new.
+pub fn save_market_holiday(conn: &Connection, date: NaiveDate, description: &str) -> rusqlite::Result<bool> {
+    let ts = date_to_timestamp(date);
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM market_holidays WHERE date = ?1)",
+        params![ts],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT INTO market_holidays (date, description) VALUES (?1, ?2)
+         ON CONFLICT(date) DO UPDATE SET description = excluded.description",
+        params![ts, description],
+    )?;
+    Ok(!exists)
+}
+
+pub fn get_market_holidays(conn: &Connection) -> rusqlite::Result<Vec<MarketHoliday>> {
+    let mut stmt = conn.prepare("SELECT date, description FROM market_holidays ORDER BY date")?;
+    let rows = stmt.query_map([], |row| {
+        let ts: i64 = row.get(0)?;
+        let description: Option<String> = row.get(1)?;
+        Ok((ts, description.unwrap_or_default()))
+    })?;
+    let mut holidays = Vec::new();
+    for row in rows {
+        let (ts, description) = row?;
+        if let Some(date) = timestamp_to_date(ts) {
+            holidays.push(MarketHoliday { date, description });
+        }
+    }
+    Ok(holidays)
+}
+
+/// Trading days from `from` to `to` with no NSE rows in `bhavcopy_data`, oldest first.
+/// The range defaults to the span of stored data, so only gaps in the middle are reported.
+pub fn find_missing_trading_days(conn: &Connection, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<NaiveDate>, Box<dyn std::error::Error>> {
+    let stored = crate::db::downloads::get_bhavcopy_date_range(conn)?;
+    let (Some(from), Some(to)) = (from.or(stored.map(|(min, _)| min)), to.or(stored.map(|(_, max)| max))) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare("SELECT DISTINCT date FROM bhavcopy_data WHERE exchange = 'NSE' AND date BETWEEN ?1 AND ?2")?;
+    let present: HashSet<NaiveDate> = stmt
+        .query_map(params![date_to_timestamp(from), date_to_timestamp(to)], |row| row.get::<_, i64>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter_map(timestamp_to_date)
+        .collect();
+
+    let calendar = TradingCalendar::load(conn)?;
+    Ok(calendar.trading_days(from, to)
+        .into_iter()
+        .filter(|date| !present.contains(date))
+        .collect())
+}
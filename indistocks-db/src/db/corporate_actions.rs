@@ -0,0 +1,412 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use std::path::Path;
+use crate::db::{date_column, date_to_timestamp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorporateActionType {
+    Split,
+    Bonus,
+    Dividend,
+}
+
+impl CorporateActionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorporateActionType::Split => "split",
+            CorporateActionType::Bonus => "bonus",
+            CorporateActionType::Dividend => "dividend",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "split" => Some(CorporateActionType::Split),
+            "bonus" => Some(CorporateActionType::Bonus),
+            "dividend" => Some(CorporateActionType::Dividend),
+            _ => None,
+        }
+    }
+}
+
+/// A price-affecting action parsed from an NSE "PURPOSE" string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedAction {
+    pub action_type: CorporateActionType,
+    /// Multiplier for prices before the ex-date (splits and bonuses only)
+    pub factor: Option<f64>,
+    /// Dividend per share in rupees
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CorporateAction {
+    pub id: i64,
+    pub symbol: String,
+    pub ex_date: NaiveDate,
+    pub action_type: CorporateActionType,
+    pub purpose: String,
+    pub factor: Option<f64>,
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CorporateActionImportReport {
+    pub inserted: usize,
+    /// Already present in the table
+    pub skipped: usize,
+    /// Purposes that do not change the price series (AGMs, rights, interest payments, ...)
+    pub ignored: usize,
+    pub rejected: Vec<String>,
+}
+
+/// Extract splits, bonuses and dividends from a purpose such as
+/// `Bonus 1:1`, `Face Value Split (Sub-Division) - From Rs 10/- Per Share To Rs 2/- Per Share`
+/// or `Interim Dividend - Rs 2.50 Per Share`. One purpose may contain several actions joined by `/`.
+pub fn parse_corporate_action_purpose(purpose: &str, face_value: Option<f64>) -> Vec<ParsedAction> {
+    let mut actions = Vec::new();
+
+    for part in split_purpose(purpose) {
+        let lower = part.to_lowercase();
+
+        if lower.contains("bonus") {
+            if let Some((new, held)) = parse_ratio(&lower) {
+                if new > 0.0 && held > 0.0 {
+                    actions.push(ParsedAction {
+                        action_type: CorporateActionType::Bonus,
+                        factor: Some(held / (new + held)),
+                        amount: None,
+                    });
+                }
+            }
+        } else if lower.contains("split") || lower.contains("sub-division") || lower.contains("subdivision") {
+            let values = rupee_amounts(&lower);
+            if let [from, to, ..] = values[..] {
+                if from > 0.0 && to > 0.0 && from != to {
+                    actions.push(ParsedAction {
+                        action_type: CorporateActionType::Split,
+                        factor: Some(to / from),
+                        amount: None,
+                    });
+                }
+            }
+        } else if lower.contains("dividend") {
+            let amount = rupee_amounts(&lower).first().copied().or_else(|| {
+                // Some dividends are announced as a percentage of face value, e.g. "Dividend - 150%"
+                let pct = percentage(&lower)?;
+                face_value.map(|fv| fv * pct / 100.0)
+            });
+            if let Some(amount) = amount.filter(|a| *a > 0.0) {
+                actions.push(ParsedAction {
+                    action_type: CorporateActionType::Dividend,
+                    factor: None,
+                    amount: Some(amount),
+                });
+            }
+        }
+    }
+
+    actions
+}
+
+/// Split a combined purpose like `Annual General Meeting/Dividend - Rs 8 Per Share`,
+/// without breaking amounts written as `Rs 10/-`
+fn split_purpose(purpose: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in purpose.char_indices() {
+        let separator = c == ';' || (c == '/' && !purpose[i + 1..].starts_with('-'));
+        if separator {
+            parts.push(&purpose[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&purpose[start..]);
+    parts
+}
+
+/// Numbers that follow "Rs", "Rs." or "Re" in a purpose string, e.g. `rs 10/-`, `rs.2.50`
+fn rupee_amounts(text: &str) -> Vec<f64> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut values = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Some(rest) = ["rs.", "rs", "re.", "re"].iter().find_map(|prefix| token.strip_prefix(prefix)) else {
+            continue;
+        };
+        let candidate = if rest.is_empty() { tokens.get(i + 1).copied().unwrap_or("") } else { rest };
+        if let Some(value) = leading_number(candidate) {
+            values.push(value);
+        }
+    }
+
+    values
+}
+
+fn leading_number(text: &str) -> Option<f64> {
+    let end = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    text[..end].trim_end_matches('.').parse().ok()
+}
+
+fn parse_ratio(text: &str) -> Option<(f64, f64)> {
+    let colon = text.find(':')?;
+    let before = text[..colon].trim_end();
+    let start = before.rfind(|c: char| !(c.is_ascii_digit() || c == '.')).map(|i| i + 1).unwrap_or(0);
+    let new = before[start..].parse().ok()?;
+    let held = leading_number(text[colon + 1..].trim_start())?;
+    Some((new, held))
+}
+
+fn percentage(text: &str) -> Option<f64> {
+    let pct = text.find('%')?;
+    let before = text[..pct].trim_end();
+    let start = before.rfind(|c: char| !(c.is_ascii_digit() || c == '.')).map(|i| i + 1).unwrap_or(0);
+    before[start..].parse().ok()
+}
+
+/// Import NSE's corporate-actions CSV export (SYMBOL, SERIES, PURPOSE, FACE VALUE, EX-DATE, ...)
+pub fn import_corporate_actions_csv(conn: &Connection, path: &Path) -> Result<CorporateActionImportReport, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    // The export sometimes starts with a byte-order mark
+    let data = data.strip_prefix(b"\xEF\xBB\xBF".as_slice()).unwrap_or(&data);
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = rdr.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().trim_matches('"').eq_ignore_ascii_case(name));
+    let symbol_idx = column("SYMBOL").ok_or("Missing SYMBOL column")?;
+    let purpose_idx = column("PURPOSE").ok_or("Missing PURPOSE column")?;
+    let ex_date_idx = column("EX-DATE").ok_or("Missing EX-DATE column")?;
+    let series_idx = column("SERIES");
+    let face_value_idx = column("FACE VALUE");
+
+    let mut report = CorporateActionImportReport::default();
+    let now = chrono::Utc::now().timestamp();
+
+    for (line, result) in rdr.records().enumerate() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                report.rejected.push(format!("line {}: {}", line + 2, e));
+                continue;
+            }
+        };
+        let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+
+        let symbol = text(symbol_idx).to_uppercase();
+        let purpose = text(purpose_idx).to_string();
+        let ex_date_str = text(ex_date_idx);
+        let ex_date = match ["%d-%b-%Y", "%d-%m-%Y", "%Y-%m-%d"].iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(ex_date_str, fmt).ok()) {
+            Some(date) if !symbol.is_empty() => date,
+            _ => {
+                report.rejected.push(format!("line {}: invalid symbol or ex-date {:?}", line + 2, ex_date_str));
+                continue;
+            }
+        };
+        let series = series_idx.map(text).unwrap_or("").to_string();
+        let face_value = face_value_idx.and_then(|idx| text(idx).parse::<f64>().ok());
+
+        let actions = parse_corporate_action_purpose(&purpose, face_value);
+        if actions.is_empty() {
+            report.ignored += 1;
+            continue;
+        }
+
+        let ex_ts = date_to_timestamp(ex_date);
+        for action in actions {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO corporate_actions
+                 (symbol, series, ex_date, action_type, purpose, factor, amount, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![symbol, series, ex_ts, action.action_type.as_str(), purpose, action.factor, action.amount, now],
+            )?;
+            if inserted > 0 {
+                report.inserted += 1;
+            } else {
+                report.skipped += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn get_corporate_actions(conn: &Connection, symbol: &str) -> rusqlite::Result<Vec<CorporateAction>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, ex_date, action_type, purpose, factor, amount
+         FROM corporate_actions
+         WHERE symbol = ?1
+         ORDER BY ex_date"
+    )?;
+
+    let rows = stmt.query_map(params![symbol], |row| {
+        let action_type: String = row.get(3)?;
+        Ok(CorporateAction {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            ex_date: date_column(row, 2)?,
+            action_type: CorporateActionType::parse(&action_type).unwrap_or(CorporateActionType::Dividend),
+            purpose: row.get(4)?,
+            factor: row.get(5)?,
+            amount: row.get(6)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Price multipliers for a symbol as (ex_date, factor) pairs, ordered by ex-date.
+/// A price on date `d` is adjusted by multiplying it with every factor whose ex-date is after `d`.
+/// Dividend factors use the last EQ close before the ex-date; dividends without one are left out.
+pub fn get_adjustment_factors(conn: &Connection, symbol: &str) -> rusqlite::Result<Vec<(NaiveDate, f64)>> {
+    let mut factors = Vec::new();
+
+    // Actions announced for ex-dates we have no prices for yet don't affect the stored series
+    let latest_ts: Option<i64> = conn.query_row(
+        "SELECT MAX(date) FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE' AND series = 'EQ'",
+        params![symbol],
+        |row| row.get(0),
+    )?;
+    let Some(latest_ts) = latest_ts else {
+        return Ok(factors);
+    };
+
+    for action in get_corporate_actions(conn, symbol)? {
+        let ex_ts = date_to_timestamp(action.ex_date);
+        if ex_ts > latest_ts {
+            continue;
+        }
+
+        let factor = match action.action_type {
+            CorporateActionType::Split | CorporateActionType::Bonus => action.factor,
+            CorporateActionType::Dividend => {
+                let prior_close: Option<f64> = conn.query_row(
+                    "SELECT close FROM bhavcopy_data
+                     WHERE symbol = ?1 AND exchange = 'NSE' AND series = 'EQ' AND date < ?2
+                     ORDER BY date DESC LIMIT 1",
+                    params![symbol, ex_ts],
+                    |row| row.get(0),
+                ).ok();
+                match (prior_close, action.amount) {
+                    (Some(close), Some(amount)) if close > amount => Some((close - amount) / close),
+                    _ => None,
+                }
+            }
+        };
+
+        if let Some(factor) = factor.filter(|f| *f > 0.0) {
+            factors.push((action.ex_date, factor));
+        }
+    }
+
+    Ok(factors)
+}
+
+/// Cumulative adjustment for a price on `date`
+pub fn cumulative_adjustment(factors: &[(NaiveDate, f64)], date: NaiveDate) -> f64 {
+    factors.iter()
+        .filter(|(ex_date, _)| *ex_date > date)
+        .map(|(_, factor)| factor)
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{test_db, TempDir};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn parsed(action_type: CorporateActionType, factor: Option<f64>, amount: Option<f64>) -> ParsedAction {
+        ParsedAction { action_type, factor, amount }
+    }
+
+    #[test]
+    fn bonus_ratios() {
+        assert_eq!(parse_corporate_action_purpose("Bonus 1:1", None), vec![parsed(CorporateActionType::Bonus, Some(0.5), None)]);
+        // Two new shares for every three held
+        assert_eq!(parse_corporate_action_purpose("Bonus 2:3", None), vec![parsed(CorporateActionType::Bonus, Some(0.6), None)]);
+        assert!(parse_corporate_action_purpose("Bonus", None).is_empty());
+    }
+
+    #[test]
+    fn face_value_splits() {
+        let split = parse_corporate_action_purpose("Face Value Split (Sub-Division) - From Rs 10/- Per Share To Re 1/- Per Share", None);
+        assert_eq!(split, vec![parsed(CorporateActionType::Split, Some(0.1), None)]);
+
+        let split = parse_corporate_action_purpose("Face Value Split (Sub-Division) - From Rs.5/- Per Share To Rs.2/- Per Share", None);
+        assert_eq!(split, vec![parsed(CorporateActionType::Split, Some(0.4), None)]);
+    }
+
+    #[test]
+    fn dividends_in_rupees_or_as_a_percentage_of_face_value() {
+        let dividend = parse_corporate_action_purpose("Interim Dividend - Rs 2.50 Per Share", None);
+        assert_eq!(dividend, vec![parsed(CorporateActionType::Dividend, None, Some(2.5))]);
+
+        let dividend = parse_corporate_action_purpose("Dividend - 150%", Some(2.0));
+        assert_eq!(dividend, vec![parsed(CorporateActionType::Dividend, None, Some(3.0))]);
+        // Without a face value the amount is unknown
+        assert!(parse_corporate_action_purpose("Dividend - 150%", None).is_empty());
+    }
+
+    #[test]
+    fn combined_purposes() {
+        let actions = parse_corporate_action_purpose("Annual General Meeting/Dividend - Rs 8 Per Share", None);
+        assert_eq!(actions, vec![parsed(CorporateActionType::Dividend, None, Some(8.0))]);
+
+        // `Rs 10/-` is an amount, not a separator
+        let actions = parse_corporate_action_purpose("AGM/Dividend - Rs 10/- Per Share", None);
+        assert_eq!(actions, vec![parsed(CorporateActionType::Dividend, None, Some(10.0))]);
+
+        let actions = parse_corporate_action_purpose("AGM/Bonus 1:1/Final Dividend - Rs 5 Per Share", None);
+        assert_eq!(actions, vec![
+            parsed(CorporateActionType::Bonus, Some(0.5), None),
+            parsed(CorporateActionType::Dividend, None, Some(5.0)),
+        ]);
+
+        assert!(parse_corporate_action_purpose("Annual General Meeting", None).is_empty());
+        assert!(parse_corporate_action_purpose("Rights 1:5 @ Premium Rs 100/-", None).is_empty());
+    }
+
+    #[test]
+    fn adjustment_factors_from_imported_actions() {
+        let conn = test_db();
+        // A close of 100 every day from 1 to 10 Jan 2024
+        for day in 1..=10 {
+            conn.execute(
+                "INSERT INTO bhavcopy_data (symbol, series, date, close, exchange) VALUES ('ABC', 'EQ', ?1, 100.0, 'NSE')",
+                params![date_to_timestamp(date(2024, 1, day))],
+            ).unwrap();
+        }
+
+        let dir = TempDir::new("corporate-actions");
+        let path = dir.path().join("CF-CA-equities.csv");
+        std::fs::write(&path, "\u{feff}SYMBOL,SERIES,PURPOSE,FACE VALUE,EX-DATE
+ABC,EQ,Dividend - Rs 5 Per Share,10,01-Jan-2024
+ABC,EQ,AGM/Dividend - 100%,10,05-Jan-2024
+ABC,EQ,Face Value Split (Sub-Division) - From Rs 10/- Per Share To Re 1/- Per Share,10,08-Jan-2024
+ABC,EQ,Bonus 1:1,1,01-Feb-2024
+ABC,EQ,Annual General Meeting,1,15-Jan-2024
+ABC,EQ,Dividend,10,not a date
+").unwrap();
+
+        let report = import_corporate_actions_csv(&conn, &path).unwrap();
+        assert_eq!((report.inserted, report.skipped, report.ignored, report.rejected.len()), (4, 0, 1, 1));
+        assert_eq!(import_corporate_actions_csv(&conn, &path).unwrap().skipped, 4);
+
+        // No close before the first dividend, and the bonus is after the latest stored session
+        let factors = get_adjustment_factors(&conn, "ABC").unwrap();
+        assert_eq!(factors, vec![(date(2024, 1, 5), 0.9), (date(2024, 1, 8), 0.1)]);
+
+        assert!((cumulative_adjustment(&factors, date(2024, 1, 4)) - 0.09).abs() < 1e-12);
+        assert_eq!(cumulative_adjustment(&factors, date(2024, 1, 5)), 0.1);
+        assert_eq!(cumulative_adjustment(&factors, date(2024, 1, 8)), 1.0);
+
+        assert!(get_adjustment_factors(&conn, "XYZ").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use crate::db::bhavcopy::RowError;
+use crate::db::{date_to_timestamp, timestamp_to_date};
+use crate::models::DeliveryRow;
+
+/// Later files for the same day replace earlier ones, since both NSE sources report the same positions
+pub(crate) const INSERT_DELIVERY_SQL: &str =
+    "INSERT OR REPLACE INTO delivery_data (symbol, series, date, traded_qty, deliverable_qty, delivery_percent)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+/// Delivery positions read from one MTO file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedDelivery {
+    pub date: Option<NaiveDate>,
+    pub rows: Vec<DeliveryRow>,
+    /// Rows that could not be parsed
+    pub errors: Vec<RowError>,
+}
+
+/// Parse NSE's security-wise delivery position file (`MTO_DDMMYYYY.DAT`).
+/// The trading date comes from the file's `10,MTO,DDMMYYYY` header record, then its
+/// `Trade Date <DD-MON-YYYY>` line, and falls back to `fallback_date`.
+pub fn parse_mto(data: &[u8], fallback_date: Option<NaiveDate>) -> Result<ParsedDelivery, Box<dyn std::error::Error>> {
+    let text = String::from_utf8_lossy(data);
+
+    let mut date = None;
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.first() == Some(&"10") && fields.get(1) == Some(&"MTO") {
+            date = fields.get(2).and_then(|d| NaiveDate::parse_from_str(d, "%d%m%Y").ok());
+        } else if let Some(rest) = line.trim().strip_prefix("Trade Date <") {
+            date = rest.split('>').next().and_then(|d| NaiveDate::parse_from_str(d, "%d-%b-%Y").ok());
+        }
+        if date.is_some() {
+            break;
+        }
+    }
+    let date = date.or(fallback_date);
+
+    let mut parsed = ParsedDelivery { date, ..ParsedDelivery::default() };
+    let mut is_mto = false;
+    for (index, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        match fields.first() {
+            Some(&"10") if fields.get(1) == Some(&"MTO") => is_mto = true,
+            Some(&"20") => {
+                is_mto = true;
+                match parse_mto_record(&fields, date) {
+                    Ok(row) => parsed.rows.push(row),
+                    Err((field, value, reason)) => parsed.errors.push(RowError {
+                        line: Some(index as u64 + 1),
+                        field,
+                        value,
+                        reason: reason.to_string(),
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !is_mto {
+        return Err("Not an MTO delivery position file".into());
+    }
+    if date.is_none() {
+        return Err("MTO file has no trade date".into());
+    }
+    Ok(parsed)
+}
+
+/// One `20,<sr no>,<symbol>,<series>,<traded>,<deliverable>,<percent>` record
+fn parse_mto_record(fields: &[&str], date: Option<NaiveDate>) -> Result<DeliveryRow, (&'static str, String, &'static str)> {
+    let field = |idx: usize| fields.get(idx).copied().unwrap_or("");
+    let int = |name: &'static str, idx: usize| field(idx).parse::<i64>()
+        .map_err(|_| (name, field(idx).to_string(), "not an integer"));
+
+    let symbol = field(2).to_uppercase();
+    if symbol.is_empty() {
+        return Err(("symbol", String::new(), "missing value"));
+    }
+    let delivery_percent = match field(6) {
+        "" | "-" => None,
+        value => Some(value.parse::<f64>().map_err(|_| ("delivery_percent", value.to_string(), "not a number"))?),
+    };
+
+    Ok(DeliveryRow {
+        symbol,
+        series: field(3).to_string(),
+        date: date.ok_or(("date", String::new(), "missing value"))?,
+        traded_qty: Some(int("traded_qty", 4)?),
+        deliverable_qty: int("deliverable_qty", 5)?,
+        delivery_percent,
+    })
+}
+
+/// Store delivery positions in one transaction, replacing any already stored for the same day.
+/// Returns the number of rows written.
+pub fn insert_delivery_rows(conn: &Connection, rows: &[DeliveryRow]) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut count = 0;
+    {
+        let mut stmt = tx.prepare_cached(INSERT_DELIVERY_SQL)?;
+        for row in rows {
+            let ts = date_to_timestamp(row.date);
+            count += stmt.execute(params![
+                row.symbol, row.series, ts, row.traded_qty, row.deliverable_qty, row.delivery_percent,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(count)
+}
+
+/// Delivery percentage of a symbol's EQ series by date, for the chart tooltip
+pub fn get_delivery_percentages(conn: &Connection, symbol: &str) -> rusqlite::Result<HashMap<NaiveDate, f64>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT date, delivery_percent FROM delivery_data
+         WHERE symbol = ?1 AND series = 'EQ' AND delivery_percent IS NOT NULL"
+    )?;
+    let rows = stmt.query_map(params![symbol], |row| Ok((timestamp_to_date(row.get(0)?), row.get(1)?)))?;
+    let mut percentages = HashMap::new();
+    for row in rows {
+        if let (Some(date), percent) = row? {
+            percentages.insert(date, percent);
+        }
+    }
+    Ok(percentages)
+}
+
+/// NSE trading days in bhavcopy_data with no delivery positions stored, newest first
+pub fn find_days_missing_delivery(conn: &Connection) -> rusqlite::Result<Vec<NaiveDate>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT b.date FROM bhavcopy_data b
+         WHERE b.exchange = 'NSE' AND b.series = 'EQ'
+           AND NOT EXISTS (SELECT 1 FROM delivery_data d WHERE d.date = b.date)
+         ORDER BY b.date DESC"
+    )?;
+    let rows = stmt.query_map([], |row| Ok(timestamp_to_date(row.get(0)?)))?;
+    rows.filter_map(|row| row.transpose()).collect()
+}
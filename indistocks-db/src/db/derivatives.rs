@@ -0,0 +1,677 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::db::bhavcopy::RowError;
+use crate::db::import::IngestStats;
+use crate::db::{date_to_timestamp, timestamp_to_date};
+use crate::models::{Exchange, FoInstrument, FoRow, OptionType};
+
+/// The NSE F&O BhavCopy layouts we know how to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoFormat {
+    /// `foDDMONYYYYbhav.csv` (up to mid-2024)
+    Legacy,
+    /// `BhavCopy_NSE_FO_0_0_0_YYYYMMDD_F_0000.csv`, the UDiFF layout with `Sgmt` set to FO
+    Udiff,
+}
+
+/// Header names for each field of an F&O format
+struct FoColumnNames {
+    instrument: &'static str,
+    symbol: &'static str,
+    expiry: &'static str,
+    strike: &'static str,
+    option_type: &'static str,
+    date: &'static str,
+    date_formats: &'static [&'static str],
+    open: &'static str,
+    high: &'static str,
+    low: &'static str,
+    close: &'static str,
+    settle_price: &'static str,
+    volume: &'static str,
+    turnover: &'static str,
+    turnover_multiplier: f64,
+    open_interest: &'static str,
+    change_in_oi: &'static str,
+    underlying: Option<&'static str>,
+}
+
+const FO_LEGACY_COLUMNS: FoColumnNames = FoColumnNames {
+    instrument: "INSTRUMENT",
+    symbol: "SYMBOL",
+    expiry: "EXPIRY_DT",
+    strike: "STRIKE_PR",
+    option_type: "OPTION_TYP",
+    date: "TIMESTAMP",
+    date_formats: &["%d-%b-%Y"],
+    open: "OPEN",
+    high: "HIGH",
+    low: "LOW",
+    close: "CLOSE",
+    settle_price: "SETTLE_PR",
+    volume: "CONTRACTS",
+    turnover: "VAL_INLAKH",
+    turnover_multiplier: 100_000.0,
+    open_interest: "OPEN_INT",
+    change_in_oi: "CHG_IN_OI",
+    underlying: None,
+};
+
+const FO_UDIFF_COLUMNS: FoColumnNames = FoColumnNames {
+    instrument: "FinInstrmTp",
+    symbol: "TckrSymb",
+    expiry: "XpryDt",
+    strike: "StrkPric",
+    option_type: "OptnTp",
+    date: "TradDt",
+    date_formats: &["%Y-%m-%d", "%d-%b-%Y"],
+    open: "OpnPric",
+    high: "HghPric",
+    low: "LwPric",
+    close: "ClsPric",
+    settle_price: "SttlmPric",
+    volume: "TtlTradgVol",
+    turnover: "TtlTrfVal",
+    turnover_multiplier: 1.0,
+    open_interest: "OpnIntrst",
+    change_in_oi: "ChngInOpnIntrst",
+    underlying: Some("UndrlygPric"),
+};
+
+impl FoFormat {
+    /// Identify the format from a header row and the first record. UDiFF F&O files share
+    /// their header with the equity BhavCopy, so only the record's `Sgmt` tells them apart.
+    fn detect(headers: &csv::StringRecord, first: Option<&csv::StringRecord>) -> Option<Self> {
+        let find = |name: &str| headers.iter().position(|h| h.trim() == name);
+        if find("INSTRUMENT").is_some() && find("EXPIRY_DT").is_some() && find("OPTION_TYP").is_some() {
+            return Some(FoFormat::Legacy);
+        }
+        let segment = find("Sgmt")?;
+        if find("TckrSymb").is_some() && find("XpryDt").is_some() {
+            let segment = first.and_then(|record| record.get(segment)).map(|s| s.trim());
+            if segment == Some("FO") {
+                return Some(FoFormat::Udiff);
+            }
+        }
+        None
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FoFormat::Legacy => "legacy F&O",
+            FoFormat::Udiff => "UDiFF F&O",
+        }
+    }
+
+    fn column_names(&self) -> &'static FoColumnNames {
+        match self {
+            FoFormat::Legacy => &FO_LEGACY_COLUMNS,
+            FoFormat::Udiff => &FO_UDIFF_COLUMNS,
+        }
+    }
+}
+
+/// Contracts read from one F&O BhavCopy
+#[derive(Debug, Clone)]
+pub struct ParsedFo {
+    pub format: FoFormat,
+    pub rows: Vec<FoRow>,
+    /// Rows that could not be parsed
+    pub errors: Vec<RowError>,
+}
+
+/// A CSV reader with its header and first record already read, which format detection needs
+struct FoReader<'a> {
+    reader: csv::Reader<&'a [u8]>,
+    headers: csv::StringRecord,
+    first: Option<csv::StringRecord>,
+}
+
+impl<'a> FoReader<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+        let headers = reader.headers()?.clone();
+        let mut record = csv::StringRecord::new();
+        let first = reader.read_record(&mut record)?.then_some(record);
+        Ok(Self { reader, headers, first })
+    }
+
+    fn format(&self) -> Option<FoFormat> {
+        FoFormat::detect(&self.headers, self.first.as_ref())
+    }
+}
+
+/// Whether a CSV is an NSE F&O BhavCopy in either layout
+pub fn is_fo_bhavcopy(data: &[u8]) -> bool {
+    FoReader::new(data).ok().and_then(|reader| reader.format()).is_some()
+}
+
+/// Parse an NSE F&O BhavCopy in either layout. The trading date is read from each row
+/// and falls back to `fallback_date`. Malformed rows are reported rather than coerced to zero.
+pub fn parse_fo_bhavcopy(data: &[u8], fallback_date: Option<NaiveDate>) -> Result<ParsedFo, Box<dyn std::error::Error>> {
+    let FoReader { mut reader, headers, first } = FoReader::new(data)?;
+    let format = FoFormat::detect(&headers, first.as_ref()).ok_or("CSV header does not match a known F&O BhavCopy format")?;
+    let names = format.column_names();
+
+    let find = |name: &'static str| headers.iter().position(|h| h.trim() == name);
+    let require = |name: &'static str| find(name)
+        .ok_or_else(|| format!("{} BhavCopy is missing the {} column", format.label(), name));
+    let columns = FoColumnMap {
+        instrument: require(names.instrument)?,
+        symbol: require(names.symbol)?,
+        expiry: require(names.expiry)?,
+        strike: require(names.strike)?,
+        option_type: require(names.option_type)?,
+        date: find(names.date),
+        open: require(names.open)?,
+        high: require(names.high)?,
+        low: require(names.low)?,
+        close: require(names.close)?,
+        settle_price: require(names.settle_price)?,
+        volume: require(names.volume)?,
+        turnover: require(names.turnover)?,
+        open_interest: require(names.open_interest)?,
+        change_in_oi: require(names.change_in_oi)?,
+        underlying: names.underlying.and_then(find),
+    };
+
+    let mut parsed = ParsedFo { format, rows: Vec::new(), errors: Vec::new() };
+    let mut record = first;
+    loop {
+        let current = match record.take() {
+            Some(current) => current,
+            None => {
+                let mut next = csv::StringRecord::new();
+                match reader.read_record(&mut next) {
+                    Ok(true) => next,
+                    Ok(false) => break,
+                    Err(e) => {
+                        parsed.errors.push(RowError {
+                            line: e.position().map(|p| p.line()),
+                            field: "record",
+                            value: String::new(),
+                            reason: e.to_string(),
+                        });
+                        continue;
+                    }
+                }
+            }
+        };
+        // Skip blank lines some files end with
+        if current.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        match parse_fo_record(&current, names, &columns, fallback_date) {
+            Ok(row) => parsed.rows.push(row),
+            Err(e) => parsed.errors.push(e),
+        }
+    }
+    Ok(parsed)
+}
+
+struct FoColumnMap {
+    instrument: usize,
+    symbol: usize,
+    expiry: usize,
+    strike: usize,
+    option_type: usize,
+    date: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    settle_price: usize,
+    volume: usize,
+    turnover: usize,
+    open_interest: usize,
+    change_in_oi: usize,
+    underlying: Option<usize>,
+}
+
+fn parse_fo_record(record: &csv::StringRecord, names: &FoColumnNames, cols: &FoColumnMap, fallback_date: Option<NaiveDate>) -> Result<FoRow, RowError> {
+    let line = record.position().map(|p| p.line());
+    let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+    let error = |field: &'static str, value: &str, reason: &str| RowError {
+        line,
+        field,
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+    let real = |field: &'static str, idx: usize| -> Result<Option<f64>, RowError> {
+        match text(idx) {
+            "" | "-" => Ok(None),
+            value => value.parse::<f64>().map(Some).map_err(|_| error(field, value, "not a number")),
+        }
+    };
+    // Some files write whole quantities as "1234.00"
+    let int = |field: &'static str, idx: usize| -> Result<Option<i64>, RowError> {
+        match text(idx) {
+            "" | "-" => Ok(None),
+            value => value.parse::<i64>()
+                .or_else(|_| match value.parse::<f64>() {
+                    Ok(v) if v.fract() == 0.0 => Ok(v as i64),
+                    _ => Err(()),
+                })
+                .map(Some)
+                .map_err(|_| error(field, value, "not an integer")),
+        }
+    };
+    let date = |field: &'static str, value: &str| names.date_formats.iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+        .ok_or_else(|| error(field, value, "unrecognised date"));
+
+    let symbol = text(cols.symbol).to_uppercase();
+    if symbol.is_empty() {
+        return Err(error("symbol", "", "missing value"));
+    }
+    let instrument = FoInstrument::parse(text(cols.instrument))
+        .ok_or_else(|| error("instrument", text(cols.instrument), "unknown instrument"))?;
+    let expiry = match text(cols.expiry) {
+        "" => return Err(error("expiry", "", "missing value")),
+        value => date("expiry", value)?,
+    };
+    let trade_date = match cols.date.map(text).filter(|s| !s.is_empty()) {
+        Some(value) => date("date", value)?,
+        None => fallback_date.ok_or_else(|| error("date", "", "missing value"))?,
+    };
+
+    // Futures carry a zero or blank strike and an `XX` or blank option type
+    let (strike, option_type) = if instrument.is_future() {
+        (None, None)
+    } else {
+        let strike = real("strike", cols.strike)?.ok_or_else(|| error("strike", "", "missing value"))?;
+        let option_type = OptionType::parse(text(cols.option_type))
+            .ok_or_else(|| error("option_type", text(cols.option_type), "not CE or PE"))?;
+        (Some(strike), Some(option_type))
+    };
+
+    Ok(FoRow {
+        symbol,
+        instrument,
+        expiry,
+        strike,
+        option_type,
+        date: trade_date,
+        open: real("open", cols.open)?,
+        high: real("high", cols.high)?,
+        low: real("low", cols.low)?,
+        close: real("close", cols.close)?,
+        settle_price: real("settle_price", cols.settle_price)?,
+        underlying: match cols.underlying {
+            Some(idx) => real("underlying", idx)?,
+            None => None,
+        },
+        volume: int("volume", cols.volume)?,
+        turnover: real("turnover", cols.turnover)?.map(|v| v * names.turnover_multiplier),
+        open_interest: int("open_interest", cols.open_interest)?,
+        change_in_oi: int("change_in_oi", cols.change_in_oi)?,
+    })
+}
+
+/// Insert parsed contracts into futures_data and options_data in one transaction.
+/// Contracts already present are counted as skipped.
+pub fn insert_fo_rows(conn: &Connection, parsed: &ParsedFo) -> rusqlite::Result<IngestStats> {
+    let mut stats = IngestStats {
+        exchange: Exchange::Nse,
+        rejected: parsed.errors.len(),
+        errors: parsed.errors.clone(),
+        ..IngestStats::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut futures_stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO futures_data (symbol, instrument, expiry, date, open, high, low, close, settle_price, underlying, volume, turnover, open_interest, change_in_oi)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
+        )?;
+        let mut options_stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO options_data (symbol, instrument, expiry, strike, option_type, date, open, high, low, close, settle_price, underlying, volume, turnover, open_interest, change_in_oi)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"
+        )?;
+        for row in &parsed.rows {
+            let ts = date_to_timestamp(row.date);
+            let expiry = date_to_timestamp(row.expiry);
+            let inserted = match (row.strike, row.option_type) {
+                (Some(strike), Some(option_type)) => options_stmt.execute(params![
+                    row.symbol, row.instrument.key(), expiry, strike, option_type.key(), ts,
+                    row.open, row.high, row.low, row.close, row.settle_price, row.underlying,
+                    row.volume, row.turnover, row.open_interest, row.change_in_oi,
+                ])?,
+                _ => futures_stmt.execute(params![
+                    row.symbol, row.instrument.key(), expiry, ts,
+                    row.open, row.high, row.low, row.close, row.settle_price, row.underlying,
+                    row.volume, row.turnover, row.open_interest, row.change_in_oi,
+                ])?,
+            };
+            stats.inserted += inserted;
+            stats.skipped += 1 - inserted;
+        }
+    }
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Parse an F&O BhavCopy and store its contracts
+pub fn ingest_fo_csv(conn: &Connection, data: &[u8], fallback_date: Option<NaiveDate>) -> Result<IngestStats, Box<dyn std::error::Error>> {
+    let parsed = parse_fo_bhavcopy(data, fallback_date)?;
+    Ok(insert_fo_rows(conn, &parsed)?)
+}
+
+/// The latest `sessions` NSE trading days in bhavcopy_data with no F&O contracts stored, newest first
+pub fn find_days_missing_derivatives(conn: &Connection, sessions: usize) -> rusqlite::Result<Vec<NaiveDate>> {
+    let mut stmt = conn.prepare(
+        "SELECT d.date FROM (
+            SELECT DISTINCT date FROM bhavcopy_data
+            WHERE exchange = 'NSE' AND series = 'EQ'
+            ORDER BY date DESC
+            LIMIT ?1
+         ) d
+         WHERE NOT EXISTS (SELECT 1 FROM futures_data f WHERE f.date = d.date)
+           AND NOT EXISTS (SELECT 1 FROM options_data o WHERE o.date = d.date)
+         ORDER BY d.date DESC"
+    )?;
+    let rows = stmt.query_map(params![sessions as i64], |row| row.get::<_, i64>(0))?;
+    let mut dates = Vec::new();
+    for ts in rows {
+        dates.extend(timestamp_to_date(ts?));
+    }
+    Ok(dates)
+}
+
+/// Underlyings with futures or options on the latest stored F&O session, sorted
+pub fn get_fo_symbols(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT symbol FROM futures_data WHERE date = (SELECT MAX(date) FROM futures_data)
+         UNION
+         SELECT symbol FROM options_data WHERE date = (SELECT MAX(date) FROM options_data)
+         ORDER BY symbol"
+    )?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Near-month future of a symbol on one session, against the stock's closing price
+#[derive(Debug, Clone, Serialize)]
+pub struct FuturesBasis {
+    pub date: NaiveDate,
+    pub expiry: NaiveDate,
+    /// Closing price of the near-month contract, or its settlement price when it did not trade
+    pub future_price: Option<f64>,
+    /// NSE EQ close, or the underlying price carried by UDiFF files for indices
+    pub spot: Option<f64>,
+    pub open_interest: Option<i64>,
+    pub change_in_oi: Option<i64>,
+    /// Open interest summed over every expiry
+    pub total_open_interest: Option<i64>,
+}
+
+impl FuturesBasis {
+    /// Future minus spot
+    pub fn basis(&self) -> Option<f64> {
+        Some(self.future_price? - self.spot?)
+    }
+
+    /// Basis as a percentage of spot
+    pub fn basis_percent(&self) -> Option<f64> {
+        let spot = self.spot.filter(|s| *s > 0.0)?;
+        Some(self.basis()? / spot * 100.0)
+    }
+}
+
+/// Near-month futures basis for the latest `sessions` sessions of a symbol, newest first
+pub fn get_futures_basis(conn: &Connection, symbol: &str, sessions: usize) -> rusqlite::Result<Vec<FuturesBasis>> {
+    let mut stmt = conn.prepare(
+        "SELECT f.date, f.expiry, COALESCE(f.close, f.settle_price), COALESCE(b.close, f.underlying),
+                f.open_interest, f.change_in_oi,
+                (SELECT SUM(open_interest) FROM futures_data t WHERE t.symbol = f.symbol AND t.date = f.date)
+         FROM futures_data f
+         LEFT JOIN bhavcopy_data b
+           ON b.symbol = f.symbol AND b.date = f.date AND b.exchange = 'NSE' AND b.series = 'EQ'
+         WHERE f.symbol = ?1
+           AND f.expiry = (SELECT MIN(expiry) FROM futures_data n WHERE n.symbol = f.symbol AND n.date = f.date)
+         ORDER BY f.date DESC
+         LIMIT ?2"
+    )?;
+    let rows = stmt.query_map(params![symbol, sessions as i64], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, FuturesBasis {
+            date: NaiveDate::MIN,
+            expiry: NaiveDate::MIN,
+            future_price: row.get(2)?,
+            spot: row.get(3)?,
+            open_interest: row.get(4)?,
+            change_in_oi: row.get(5)?,
+            total_open_interest: row.get(6)?,
+        }))
+    })?;
+
+    let mut basis = Vec::new();
+    for row in rows {
+        let (date, expiry, mut entry) = row?;
+        if let (Some(date), Some(expiry)) = (timestamp_to_date(date), timestamp_to_date(expiry)) {
+            entry.date = date;
+            entry.expiry = expiry;
+            basis.push(entry);
+        }
+    }
+    Ok(basis)
+}
+
+/// End-of-day figures for one option contract
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OptionQuote {
+    pub close: Option<f64>,
+    pub settle_price: Option<f64>,
+    pub volume: Option<i64>,
+    pub open_interest: Option<i64>,
+    pub change_in_oi: Option<i64>,
+}
+
+/// The call and put at one strike
+#[derive(Debug, Clone, Serialize)]
+pub struct OptionChainRow {
+    pub strike: f64,
+    pub call: Option<OptionQuote>,
+    pub put: Option<OptionQuote>,
+}
+
+/// Every strike of one expiry on one session
+#[derive(Debug, Clone)]
+pub struct OptionChain {
+    pub symbol: String,
+    pub date: NaiveDate,
+    pub expiry: NaiveDate,
+    /// Expiries with options on `date`, nearest first
+    pub expiries: Vec<NaiveDate>,
+    /// NSE EQ close, or the underlying price carried by UDiFF files
+    pub spot: Option<f64>,
+    pub rows: Vec<OptionChainRow>,
+}
+
+impl OptionChain {
+    fn total_oi(&self, side: impl Fn(&OptionChainRow) -> Option<&OptionQuote>) -> i64 {
+        self.rows.iter().filter_map(|row| side(row)?.open_interest).sum()
+    }
+
+    pub fn call_open_interest(&self) -> i64 {
+        self.total_oi(|row| row.call.as_ref())
+    }
+
+    pub fn put_open_interest(&self) -> i64 {
+        self.total_oi(|row| row.put.as_ref())
+    }
+
+    /// Put open interest over call open interest
+    pub fn put_call_ratio(&self) -> Option<f64> {
+        let calls = self.call_open_interest();
+        (calls > 0).then(|| self.put_open_interest() as f64 / calls as f64)
+    }
+
+    /// Strike nearest the spot price
+    pub fn atm_strike(&self) -> Option<f64> {
+        let spot = self.spot?;
+        self.rows.iter()
+            .map(|row| row.strike)
+            .min_by(|a, b| (a - spot).abs().total_cmp(&(b - spot).abs()))
+    }
+}
+
+/// Option chain of a symbol on its latest stored session. Uses `expiry` when options expiring
+/// then were traded that day and the nearest expiry otherwise. None when the symbol has no options.
+pub fn get_option_chain(conn: &Connection, symbol: &str, expiry: Option<NaiveDate>) -> rusqlite::Result<Option<OptionChain>> {
+    let latest: Option<i64> = conn.query_row(
+        "SELECT MAX(date) FROM options_data WHERE symbol = ?1",
+        params![symbol],
+        |row| row.get(0),
+    )?;
+    let Some(ts) = latest else {
+        return Ok(None);
+    };
+    let Some(date) = timestamp_to_date(ts) else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT expiry FROM options_data WHERE symbol = ?1 AND date = ?2 ORDER BY expiry"
+    )?;
+    let expiries: Vec<NaiveDate> = stmt.query_map(params![symbol, ts], |row| row.get::<_, i64>(0))?
+        .filter_map(|r| r.map(timestamp_to_date).transpose())
+        .collect::<rusqlite::Result<_>>()?;
+    let Some(expiry) = expiry.filter(|e| expiries.contains(e)).or_else(|| expiries.first().copied()) else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT strike, option_type, close, settle_price, volume, open_interest, change_in_oi
+         FROM options_data
+         WHERE symbol = ?1 AND date = ?2 AND expiry = ?3
+         ORDER BY strike"
+    )?;
+    let quotes = stmt.query_map(params![symbol, ts, date_to_timestamp(expiry)], |row| {
+        Ok((row.get::<_, f64>(0)?, row.get::<_, String>(1)?, OptionQuote {
+            close: row.get(2)?,
+            settle_price: row.get(3)?,
+            volume: row.get(4)?,
+            open_interest: row.get(5)?,
+            change_in_oi: row.get(6)?,
+        }))
+    })?;
+
+    // Strikes are keyed by their value in paise so they can be ordered and matched exactly
+    let mut strikes: BTreeMap<i64, OptionChainRow> = BTreeMap::new();
+    for quote in quotes {
+        let (strike, option_type, quote) = quote?;
+        let row = strikes.entry((strike * 100.0).round() as i64)
+            .or_insert(OptionChainRow { strike, call: None, put: None });
+        match OptionType::parse(&option_type) {
+            Some(OptionType::Call) => row.call = Some(quote),
+            Some(OptionType::Put) => row.put = Some(quote),
+            None => {}
+        }
+    }
+
+    let spot: Option<f64> = conn.query_row(
+        "SELECT COALESCE(
+            (SELECT close FROM bhavcopy_data WHERE symbol = ?1 AND date = ?2 AND exchange = 'NSE' AND series = 'EQ'),
+            (SELECT MAX(underlying) FROM options_data WHERE symbol = ?1 AND date = ?2),
+            (SELECT MAX(underlying) FROM futures_data WHERE symbol = ?1 AND date = ?2)
+         )",
+        params![symbol, ts],
+        |row| row.get(0),
+    ).optional()?.flatten();
+
+    Ok(Some(OptionChain {
+        symbol: symbol.to_string(),
+        date,
+        expiry,
+        expiries,
+        spot,
+        rows: strikes.into_values().collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::test_db;
+
+    const UDIFF_FO: &str = "\
+TradDt,BizDt,Sgmt,Src,FinInstrmTp,FinInstrmId,ISIN,TckrSymb,SctySrs,XpryDt,FininstrmActlXpryDt,StrkPric,OptnTp,FinInstrmNm,OpnPric,HghPric,LwPric,ClsPric,LastPric,PrvsClsgPric,UndrlygPric,SttlmPric,OpnIntrst,ChngInOpnIntrst,TtlTradgVol,TtlTrfVal,TtlNbOfTxsExctd,SsnId,NewBrdLotQty,Rmks,Rsvd1,Rsvd2,Rsvd3,Rsvd4
+2024-07-05,2024-07-05,FO,NSE,STF,35001,,RELIANCE,,2024-07-25,2024-07-25,,,RELIANCE24JULFUT,3100.00,3150.00,3090.00,3140.50,3141.00,3095.00,3135.20,3139.80,1000000,25000,5000,15700000.00,1200,F1,250,,,,,
+2024-07-05,2024-07-05,FO,NSE,IDO,42001,,NIFTY,,2024-07-11,2024-07-11,24300.00,CE,NIFTY24JUL24300CE,120.00,150.00,100.00,140.00,140.00,110.00,24320.50,140.00,500000,-20000,1000000,14000000.00,8000,F1,25,,,,,
+2024-07-05,2024-07-05,FO,NSE,IDO,42002,,NIFTY,,2024-07-11,2024-07-11,24300.00,XX,NIFTY24JUL24300XX,1,1,1,1,1,1,1,1,1,1,1,1,1,F1,25,,,,,
+
+";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parses_a_udiff_fo_bhavcopy() {
+        assert!(is_fo_bhavcopy(UDIFF_FO.as_bytes()));
+        let parsed = parse_fo_bhavcopy(UDIFF_FO.as_bytes(), None).unwrap();
+        assert_eq!(parsed.format, FoFormat::Udiff);
+        assert_eq!(parsed.rows.len(), 2);
+
+        let future = &parsed.rows[0];
+        assert_eq!((future.symbol.as_str(), future.instrument), ("RELIANCE", FoInstrument::FutStk));
+        assert_eq!((future.date, future.expiry), (date(2024, 7, 5), date(2024, 7, 25)));
+        assert_eq!((future.strike, future.option_type), (None, None));
+        assert_eq!((future.close, future.settle_price, future.underlying), (Some(3140.5), Some(3139.8), Some(3135.2)));
+        assert_eq!((future.volume, future.turnover), (Some(5000), Some(15_700_000.0)));
+        assert_eq!((future.open_interest, future.change_in_oi), (Some(1_000_000), Some(25_000)));
+
+        let call = &parsed.rows[1];
+        assert_eq!((call.instrument, call.strike, call.option_type), (FoInstrument::OptIdx, Some(24300.0), Some(OptionType::Call)));
+        assert_eq!(call.change_in_oi, Some(-20_000));
+
+        // A bad row is reported, not stored with made-up values
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!((parsed.errors[0].line, parsed.errors[0].field, parsed.errors[0].value.as_str()), (Some(4), "option_type", "XX"));
+    }
+
+    #[test]
+    fn parses_a_legacy_fo_bhavcopy() {
+        let data = "\
+INSTRUMENT,SYMBOL,EXPIRY_DT,STRIKE_PR,OPTION_TYP,OPEN,HIGH,LOW,CLOSE,SETTLE_PR,CONTRACTS,VAL_INLAKH,OPEN_INT,CHG_IN_OI,TIMESTAMP,
+FUTSTK,INFY,25-Jul-2024,0,XX,1500,1520,1490,1510,1511,2000,1200.5,300000,1500,05-JUL-2024,
+OPTSTK,INFY,25-Jul-2024,1600,PE,12,14,-,13.5,13.5,10.00,0.8,4000,-,05-JUL-2024,
+";
+        let parsed = parse_fo_bhavcopy(data.as_bytes(), None).unwrap();
+        assert_eq!(parsed.format, FoFormat::Legacy);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+
+        let future = &parsed.rows[0];
+        assert_eq!((future.date, future.expiry, future.strike), (date(2024, 7, 5), date(2024, 7, 25), None));
+        // Turnover is reported in lakhs
+        assert_eq!((future.turnover, future.underlying), (Some(120_050_000.0), None));
+
+        let put = &parsed.rows[1];
+        assert_eq!((put.strike, put.option_type), (Some(1600.0), Some(OptionType::Put)));
+        assert_eq!((put.low, put.volume, put.change_in_oi), (None, Some(10), None));
+    }
+
+    #[test]
+    fn equity_udiff_files_are_not_fo() {
+        let equity = UDIFF_FO.replace(",FO,NSE,", ",CM,NSE,");
+        assert!(!is_fo_bhavcopy(equity.as_bytes()));
+        assert!(parse_fo_bhavcopy(equity.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn contracts_are_stored_once() {
+        let conn = test_db();
+        let stats = ingest_fo_csv(&conn, UDIFF_FO.as_bytes(), None).unwrap();
+        assert_eq!((stats.inserted, stats.skipped, stats.rejected), (2, 0, 1));
+        let stats = ingest_fo_csv(&conn, UDIFF_FO.as_bytes(), None).unwrap();
+        assert_eq!((stats.inserted, stats.skipped), (0, 2));
+
+        let basis = get_futures_basis(&conn, "RELIANCE", 5).unwrap();
+        assert_eq!(basis.len(), 1);
+        // No EQ close stored, so the underlying price stands in for spot
+        assert!((basis[0].basis().unwrap() - 5.3).abs() < 1e-9);
+    }
+}
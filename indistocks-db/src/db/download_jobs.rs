@@ -0,0 +1,535 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
+use crate::{BhavCopyMessage, DayOutcome, StopReason};
+use crate::db::downloads::{DownloadControl, download_bhavcopy, sync_bhavcopy, download_bhavcopy_with_date_range, fill_bhavcopy_gaps, fill_delivery_gaps, fill_derivatives_gaps, fill_index_gaps};
+use crate::db::source::default_data_source;
+use crate::db::{date_to_timestamp, timestamp_to_date};
+use crate::db::schema::reopen_db_connection;
+
+/// Days fetched by a sync job when the database is still empty
+const SYNC_INITIAL_DAYS: i64 = 30;
+
+/// What a download job fetches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadJobKind {
+    /// The missing days after the latest stored date, then a year before the earliest one
+    Backfill,
+    /// Only the days after the latest stored date
+    Sync,
+    /// Every trading day from `from` to `to`, inclusive
+    Range { from: NaiveDate, to: NaiveDate },
+    /// Trading days missing inside the span already stored
+    FillGaps,
+    /// Delivery positions for stored trading days that have none
+    Delivery,
+    /// F&O BhavCopies for the latest stored trading days that have none
+    Derivatives,
+    /// Index closes for stored trading days that have none
+    Indices,
+}
+
+impl DownloadJobKind {
+    fn key(&self) -> &'static str {
+        match self {
+            DownloadJobKind::Backfill => "backfill",
+            DownloadJobKind::Sync => "sync",
+            DownloadJobKind::Range { .. } => "range",
+            DownloadJobKind::FillGaps => "fill_gaps",
+            DownloadJobKind::Delivery => "delivery",
+            DownloadJobKind::Derivatives => "derivatives",
+            DownloadJobKind::Indices => "indices",
+        }
+    }
+
+    fn from_row(key: &str, from: Option<i64>, to: Option<i64>) -> Self {
+        match (key, from.and_then(timestamp_to_date), to.and_then(timestamp_to_date)) {
+            ("range", Some(from), Some(to)) => DownloadJobKind::Range { from, to },
+            ("sync", _, _) => DownloadJobKind::Sync,
+            ("fill_gaps", _, _) => DownloadJobKind::FillGaps,
+            ("delivery", _, _) => DownloadJobKind::Delivery,
+            ("derivatives", _, _) => DownloadJobKind::Derivatives,
+            ("indices", _, _) => DownloadJobKind::Indices,
+            _ => DownloadJobKind::Backfill,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            DownloadJobKind::Backfill => "Backfill".to_string(),
+            DownloadJobKind::Sync => "Sync".to_string(),
+            DownloadJobKind::Range { from, to } => format!("{} to {}", from, to),
+            DownloadJobKind::FillGaps => "Fill gaps".to_string(),
+            DownloadJobKind::Delivery => "Delivery data".to_string(),
+            DownloadJobKind::Derivatives => "F&O data".to_string(),
+            DownloadJobKind::Indices => "Index data".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Cancelled,
+    Failed,
+    /// The app closed while the job was queued or running
+    Interrupted,
+}
+
+impl JobStatus {
+    pub fn key(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Completed => "completed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Failed => "failed",
+            JobStatus::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        [JobStatus::Queued, JobStatus::Running, JobStatus::Paused, JobStatus::Completed,
+         JobStatus::Cancelled, JobStatus::Failed, JobStatus::Interrupted]
+            .into_iter()
+            .find(|status| status.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "Queued",
+            JobStatus::Running => "Running",
+            JobStatus::Paused => "Paused",
+            JobStatus::Completed => "Completed",
+            JobStatus::Cancelled => "Cancelled",
+            JobStatus::Failed => "Failed",
+            JobStatus::Interrupted => "Interrupted",
+        }
+    }
+}
+
+/// A row of the download job history
+#[derive(Debug, Clone)]
+pub struct DownloadJob {
+    pub id: i64,
+    pub kind: DownloadJobKind,
+    pub status: JobStatus,
+    pub days_total: usize,
+    pub days_done: usize,
+    pub files: usize,
+    pub rows_inserted: usize,
+    pub error_message: Option<String>,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+pub fn create_download_job(conn: &Connection, kind: DownloadJobKind) -> rusqlite::Result<i64> {
+    let (from, to) = match kind {
+        DownloadJobKind::Range { from, to } => (Some(date_to_timestamp(from)), Some(date_to_timestamp(to))),
+        _ => (None, None),
+    };
+    conn.execute(
+        "INSERT INTO download_jobs (kind, from_date, to_date, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![kind.key(), from, to, JobStatus::Queued.key(), chrono::Utc::now().timestamp()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record a status change, stamping `started_at` the first time a job runs and `finished_at` when it ends
+pub fn update_download_job_status(conn: &Connection, id: i64, status: JobStatus, error_message: Option<&str>) -> rusqlite::Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let finished = matches!(status, JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed | JobStatus::Interrupted);
+    conn.execute(
+        "UPDATE download_jobs SET status = ?2,
+             error_message = COALESCE(?3, error_message),
+             started_at = CASE WHEN ?2 = 'running' THEN COALESCE(started_at, ?4) ELSE started_at END,
+             finished_at = CASE WHEN ?5 THEN ?4 ELSE finished_at END
+         WHERE id = ?1",
+        params![id, status.key(), error_message, now, finished],
+    )?;
+    Ok(())
+}
+
+pub fn update_download_job_progress(conn: &Connection, id: i64, progress: &JobProgress) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE download_jobs SET days_total = ?2, days_done = ?3, files = ?4, rows_inserted = ?5 WHERE id = ?1",
+        params![id, progress.days_total as i64, progress.days_done as i64, progress.files as i64, progress.rows_inserted as i64],
+    )?;
+    Ok(())
+}
+
+/// Most recent jobs first
+pub fn get_download_jobs(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<DownloadJob>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, from_date, to_date, status, days_total, days_done, files, rows_inserted,
+                error_message, created_at, started_at, finished_at
+         FROM download_jobs ORDER BY id DESC LIMIT ?1"
+    )?;
+    let rows = stmt.query_map(params![limit as i64], |row| {
+        let kind: String = row.get(1)?;
+        let status: String = row.get(4)?;
+        Ok(DownloadJob {
+            id: row.get(0)?,
+            kind: DownloadJobKind::from_row(&kind, row.get(2)?, row.get(3)?),
+            status: JobStatus::parse(&status).unwrap_or(JobStatus::Interrupted),
+            days_total: row.get::<_, i64>(5)? as usize,
+            days_done: row.get::<_, i64>(6)? as usize,
+            files: row.get::<_, i64>(7)? as usize,
+            rows_inserted: row.get::<_, i64>(8)? as usize,
+            error_message: row.get(9)?,
+            created_at: row.get(10)?,
+            started_at: row.get(11)?,
+            finished_at: row.get(12)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Jobs left queued, running or paused by a previous session can't be resumed; mark them interrupted
+pub fn mark_interrupted_download_jobs(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE download_jobs SET status = 'interrupted', finished_at = ?1
+         WHERE status IN ('queued', 'running', 'paused')",
+        params![chrono::Utc::now().timestamp()],
+    )
+}
+
+/// Progress of the running job, built from its [`BhavCopyMessage`]s
+#[derive(Debug, Clone)]
+pub struct JobProgress {
+    pub days_total: usize,
+    pub days_done: usize,
+    pub files: usize,
+    pub rows_inserted: usize,
+    pub current_day: Option<NaiveDate>,
+    started: Instant,
+    paused_at: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl JobProgress {
+    fn new() -> Self {
+        Self {
+            days_total: 0,
+            days_done: 0,
+            files: 0,
+            rows_inserted: 0,
+            current_day: None,
+            started: Instant::now(),
+            paused_at: None,
+            paused_for: Duration::ZERO,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.days_total == 0 {
+            0.0
+        } else {
+            (self.days_done as f32 / self.days_total as f32).min(1.0)
+        }
+    }
+
+    /// Time spent downloading, not counting pauses
+    pub fn elapsed(&self) -> Duration {
+        let paused = self.paused_for + self.paused_at.map(|at| at.elapsed()).unwrap_or_default();
+        self.started.elapsed().saturating_sub(paused)
+    }
+
+    /// Remaining time at the average pace so far
+    pub fn eta(&self) -> Option<Duration> {
+        if self.days_done == 0 || self.days_done >= self.days_total {
+            return None;
+        }
+        let per_day = self.elapsed() / self.days_done as u32;
+        Some(per_day * (self.days_total - self.days_done) as u32)
+    }
+
+    fn apply(&mut self, message: &BhavCopyMessage) {
+        match message {
+            BhavCopyMessage::Planned { days, .. } => self.days_total += days,
+            BhavCopyMessage::DayStarted(date) => self.current_day = Some(*date),
+            BhavCopyMessage::DayFinished { outcome, .. } => {
+                self.days_done += 1;
+                if let DayOutcome::Ingested { inserted, .. } = outcome {
+                    self.files += 1;
+                    self.rows_inserted += inserted;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct ActiveJob {
+    id: i64,
+    kind: DownloadJobKind,
+    control: DownloadControl,
+    receiver: Receiver<BhavCopyMessage>,
+    handle: Option<JoinHandle<()>>,
+    progress: JobProgress,
+    stopped: Option<StopReason>,
+    /// Progress not yet written to the database
+    dirty: bool,
+}
+
+/// Runs BhavCopy download jobs one at a time on a background thread, in the order they were queued.
+/// Every job is recorded in `download_jobs`.
+pub struct DownloadManager {
+    db_conn: Arc<Mutex<Connection>>,
+    queue: VecDeque<(i64, DownloadJobKind)>,
+    active: Option<ActiveJob>,
+}
+
+impl DownloadManager {
+    pub fn new(db_conn: Arc<Mutex<Connection>>) -> Self {
+        if let Err(e) = mark_interrupted_download_jobs(&db_conn.lock().unwrap()) {
+            warn!("Failed to mark interrupted download jobs: {}", e);
+        }
+        Self { db_conn, queue: VecDeque::new(), active: None }
+    }
+
+    /// Queue a job; it starts on the next [`poll`](Self::poll) once nothing else is running
+    pub fn enqueue(&mut self, kind: DownloadJobKind) -> rusqlite::Result<i64> {
+        let id = create_download_job(&self.db_conn.lock().unwrap(), kind)?;
+        info!("Queued download job {} ({})", id, kind.label());
+        self.queue.push_back((id, kind));
+        Ok(id)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.active.is_some() || !self.queue.is_empty()
+    }
+
+    pub fn active_kind(&self) -> Option<DownloadJobKind> {
+        self.active.as_ref().map(|job| job.kind)
+    }
+
+    pub fn active_progress(&self) -> Option<&JobProgress> {
+        self.active.as_ref().map(|job| &job.progress)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.active.as_ref().is_some_and(|job| job.control.is_paused())
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.active.as_ref().is_some_and(|job| job.control.is_cancelled())
+    }
+
+    pub fn queued(&self) -> impl Iterator<Item = &(i64, DownloadJobKind)> {
+        self.queue.iter()
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(job) = &mut self.active {
+            if !job.control.is_paused() {
+                job.control.pause();
+                job.progress.paused_at = Some(Instant::now());
+                self.set_status(JobStatus::Paused, None);
+            }
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(job) = &mut self.active {
+            if let Some(at) = job.progress.paused_at.take() {
+                job.progress.paused_for += at.elapsed();
+            }
+            job.control.resume();
+            self.set_status(JobStatus::Running, None);
+        }
+    }
+
+    /// Stop the running job after the day it is working on
+    pub fn cancel_active(&mut self) {
+        if let Some(job) = &self.active {
+            info!("Cancelling download job {}", job.id);
+            job.control.cancel();
+        }
+    }
+
+    /// Drop a job from the queue before it starts
+    pub fn cancel_queued(&mut self, id: i64) {
+        self.queue.retain(|(queued_id, _)| *queued_id != id);
+        if let Err(e) = update_download_job_status(&self.db_conn.lock().unwrap(), id, JobStatus::Cancelled, None) {
+            warn!("Failed to update download job {}: {}", id, e);
+        }
+    }
+
+    /// Collect the running job's messages, start the next queued job when it ends, and save progress.
+    /// Returns the messages so callers can react to date range updates and completion.
+    pub fn poll(&mut self) -> Vec<BhavCopyMessage> {
+        let mut messages = Vec::new();
+
+        if let Some(job) = &mut self.active {
+            loop {
+                match job.receiver.try_recv() {
+                    Ok(message) => {
+                        job.progress.apply(&message);
+                        job.dirty |= matches!(message, BhavCopyMessage::Planned { .. } | BhavCopyMessage::DayFinished { .. });
+                        if let BhavCopyMessage::Stopped(reason) = &message {
+                            job.stopped = Some(reason.clone());
+                        }
+                        let done = matches!(message, BhavCopyMessage::Done(_));
+                        messages.push(message);
+                        if done {
+                            break;
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        messages.push(BhavCopyMessage::Done(Err("Download thread disconnected".to_string())));
+                        break;
+                    }
+                }
+            }
+
+            // A download sharing the app's connection may be holding it; try again next frame
+            if job.dirty {
+                if let Ok(conn) = self.db_conn.try_lock() {
+                    match update_download_job_progress(&conn, job.id, &job.progress) {
+                        Ok(()) => job.dirty = false,
+                        Err(e) => warn!("Failed to save progress of download job {}: {}", job.id, e),
+                    }
+                }
+            }
+        }
+
+        if let Some(BhavCopyMessage::Done(result)) = messages.last() {
+            let result = result.clone();
+            self.finish(result);
+        }
+
+        if self.active.is_none() {
+            if let Some((id, kind)) = self.queue.pop_front() {
+                self.start(id, kind);
+            }
+        }
+
+        messages
+    }
+
+    fn start(&mut self, id: i64, kind: DownloadJobKind) {
+        info!("Starting download job {} ({})", id, kind.label());
+        let (tx, rx) = mpsc::channel();
+        let control = DownloadControl::default();
+        let db_conn = self.worker_connection();
+        let thread_control = control.clone();
+        let handle = std::thread::spawn(move || {
+            let result = default_data_source()
+                .map_err(|e| e.into())
+                .and_then(|source| match kind {
+                    DownloadJobKind::Backfill => download_bhavcopy(&db_conn, &tx, &thread_control, &*source),
+                    DownloadJobKind::Sync => sync_bhavcopy(&db_conn, &tx, &thread_control, &*source, SYNC_INITIAL_DAYS),
+                    DownloadJobKind::Range { from, to } => {
+                        download_bhavcopy_with_date_range(&db_conn, &tx, &thread_control, &*source, to, from, None)
+                    }
+                    DownloadJobKind::FillGaps => fill_bhavcopy_gaps(&db_conn, &tx, &thread_control, &*source, None, None),
+                    DownloadJobKind::Delivery => fill_delivery_gaps(&db_conn, &tx, &thread_control, &*source),
+                    DownloadJobKind::Derivatives => fill_derivatives_gaps(&db_conn, &tx, &thread_control, &*source),
+                    DownloadJobKind::Indices => fill_index_gaps(&db_conn, &tx, &thread_control, &*source),
+                });
+            let _ = tx.send(BhavCopyMessage::Done(result.map_err(|e| e.to_string())));
+        });
+
+        self.active = Some(ActiveJob {
+            id,
+            kind,
+            control,
+            receiver: rx,
+            handle: Some(handle),
+            progress: JobProgress::new(),
+            stopped: None,
+            dirty: false,
+        });
+        self.set_status(JobStatus::Running, None);
+    }
+
+    /// A connection of the job's own, so ingesting never holds the lock the UI reads through.
+    /// Falls back to sharing the UI's connection for in-memory databases.
+    fn worker_connection(&self) -> Arc<Mutex<Connection>> {
+        let reopened = reopen_db_connection(&self.db_conn.lock().unwrap());
+        match reopened {
+            Some(Ok(conn)) => Arc::new(Mutex::new(conn)),
+            Some(Err(e)) => {
+                warn!("Failed to open a connection for the download, sharing the app's: {}", e);
+                self.db_conn.clone()
+            }
+            None => self.db_conn.clone(),
+        }
+    }
+
+    fn finish(&mut self, result: Result<(), String>) {
+        let Some(mut job) = self.active.take() else {
+            return;
+        };
+        if let Some(handle) = job.handle.take() {
+            let _ = handle.join();
+        }
+
+        let (status, message) = match (&result, &job.stopped) {
+            (Err(e), _) => (JobStatus::Failed, Some(e.clone())),
+            (Ok(()), Some(StopReason::Cancelled)) => (JobStatus::Cancelled, None),
+            (Ok(()), Some(StopReason::TooManyMissingDays(days))) => {
+                (JobStatus::Completed, Some(format!("Stopped after {} consecutive trading days without data", days)))
+            }
+            (Ok(()), _) => (JobStatus::Completed, None),
+        };
+        match &result {
+            Ok(()) => info!("Download job {} finished: {}", job.id, status.label()),
+            Err(e) => error!("Download job {} failed: {}", job.id, e),
+        }
+
+        let conn = self.db_conn.lock().unwrap();
+        let saved = update_download_job_progress(&conn, job.id, &job.progress)
+            .and_then(|_| update_download_job_status(&conn, job.id, status, message.as_deref()));
+        if let Err(e) = saved {
+            warn!("Failed to update download job {}: {}", job.id, e);
+        }
+    }
+
+    fn set_status(&self, status: JobStatus, error_message: Option<&str>) {
+        if let Some(job) = &self.active {
+            if let Err(e) = update_download_job_status(&self.db_conn.lock().unwrap(), job.id, status, error_message) {
+                warn!("Failed to update download job {}: {}", job.id, e);
+            }
+        }
+    }
+}
+
+impl Drop for DownloadManager {
+    /// Closing the app cancels everything, waiting for the running job to finish its current day
+    fn drop(&mut self) {
+        let queued: Vec<i64> = self.queue.iter().map(|(id, _)| *id).collect();
+        for id in queued {
+            self.cancel_queued(id);
+        }
+
+        if let Some(job) = &mut self.active {
+            job.control.cancel();
+            if let Some(handle) = job.handle.take() {
+                let _ = handle.join();
+            }
+            let mut result = Ok(());
+            for message in job.receiver.try_iter() {
+                job.progress.apply(&message);
+                match message {
+                    BhavCopyMessage::Stopped(reason) => job.stopped = Some(reason),
+                    BhavCopyMessage::Done(done) => result = done,
+                    _ => {}
+                }
+            }
+            self.finish(result);
+        }
+    }
+}
@@ -1,17 +1,20 @@
 use rusqlite::Connection;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
 use directories::ProjectDirs;
 use chrono::{Utc, NaiveDate, Datelike};
-use reqwest::blocking::Client;
-use std::time::Duration;
-use std::thread;
 use zip;
-use csv;
-
-
-
-
+use log::{debug, info, warn};
+use crate::db::{date_to_timestamp, timestamp_to_date};
+use crate::db::import::{IngestStats, parse_bhavcopy_csv, insert_bhavcopy_rows};
+use crate::db::alerts::evaluate_alerts;
+use crate::db::calendar::{TradingCalendar, find_missing_trading_days};
+use crate::db::delivery::{find_days_missing_delivery, insert_delivery_rows, parse_mto};
+use crate::db::derivatives::{ParsedFo, find_days_missing_derivatives, insert_fo_rows, parse_fo_bhavcopy};
+use crate::db::indices::{ParsedIndices, find_days_missing_index_data, insert_index_rows, parse_index_close};
+use crate::db::source::{DataSource, FetchError, bhavcopy_path, delivery_path, fo_bhavcopy_path, index_close_path, mto_path, create_http_client, rate_limit_delay, EQUITY_LIST_PATH};
+use crate::models::DeliveryRow;
 
 #[derive(Debug)]
 pub struct DownloadRecord {
@@ -34,41 +37,6 @@ pub fn get_downloads_dir() -> PathBuf {
     downloads_dir
 }
 
-fn create_http_client() -> Client {
-    Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; rv:109.0) Gecko/20100101 Firefox/118.0")
-        .timeout(Duration::from_secs(15))
-        .cookie_store(true)
-        .gzip(true)
-        .build()
-        .expect("Failed to create HTTP client")
-}
-
-fn rate_limit_delay() {
-    thread::sleep(Duration::from_millis(350)); // ~3 requests per second
-}
-
-fn get_bhavcopy_url(date: NaiveDate) -> String {
-    let year = date.year();
-
-    if year >= 2024 {
-        // New format (2024 onwards)
-        let date_str = date.format("%Y%m%d");
-        format!("https://nsearchives.nseindia.com/content/cm/BhavCopy_NSE_CM_0_0_0_{}_F_0000.csv.zip", date_str)
-    } else {
-        // Old format (2023 and before)
-        let day = date.format("%d");
-        let month = date.format("%b").to_string().to_uppercase();
-        let year_str = date.format("%Y");
-        format!("https://nsearchives.nseindia.com/content/historical/EQUITIES/{}/{}/cm{}{}{}bhav.csv.zip",
-                year_str, month, day, month, year_str)
-    }
-}
-
-
-
-
-
 pub fn download_historical_data(symbol: &str, from_date: NaiveDate, to_date: NaiveDate) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     let client = create_http_client();
     let downloads_dir = get_downloads_dir();
@@ -160,10 +128,13 @@ pub fn save_download_record(conn: &Connection, symbol: Option<&str>, from_date:
     let now = Utc::now().timestamp();
     let file_size = fs::metadata(file_path).ok().map(|m| m.len() as i64);
 
+    let download_type = if symbol.is_some() { "historical" } else { "bhavcopy" };
+
     conn.execute(
-        "INSERT INTO nse_downloads (symbol, from_date, to_date, file_path, file_size, status, error_message, downloaded_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO nse_downloads (download_type, symbol, from_date, to_date, file_path, file_size, status, error_message, downloaded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         rusqlite::params![
+            download_type,
             symbol,
             from_date,
             to_date,
@@ -201,89 +172,306 @@ pub fn get_download_records(conn: &Connection) -> Result<Vec<DownloadRecord>, Bo
     Ok(records)
 }
 
-pub fn download_bhavcopy(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>) -> Result<(), Box<dyn std::error::Error>> {
-    download_bhavcopy_with_limit(db_conn, tx, None)
+pub fn download_bhavcopy(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource) -> Result<(), Box<dyn std::error::Error>> {
+    download_bhavcopy_with_limit(db_conn, tx, control, source, None)
 }
 
-pub fn download_bhavcopy_with_date_range(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, start_date: NaiveDate, end_date: NaiveDate, max_files: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
-    let client = create_http_client();
-    let downloads_dir = get_downloads_dir();
+/// Lets the thread running a download be paused, resumed and cancelled from another thread.
+/// The download checks it between days, so a day is always either fully ingested or not started.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadControl {
+    state: Arc<(Mutex<ControlState>, Condvar)>,
+    /// Where downloaded files are staged, instead of the config directory's `downloads` folder
+    downloads_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: bool,
+    cancelled: bool,
+}
+
+impl DownloadControl {
+    pub fn with_downloads_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.downloads_dir = Some(dir.into());
+        self
+    }
+
+    fn downloads_dir(&self) -> std::io::Result<PathBuf> {
+        match &self.downloads_dir {
+            Some(dir) => {
+                fs::create_dir_all(dir)?;
+                Ok(dir.clone())
+            }
+            None => Ok(get_downloads_dir()),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.update(|state| state.paused = false);
+    }
+
+    pub fn cancel(&self) {
+        self.update(|state| state.cancelled = true);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.0.lock().unwrap().paused
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.0.lock().unwrap().cancelled
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ControlState)) {
+        let (lock, condvar) = &*self.state;
+        change(&mut lock.lock().unwrap());
+        condvar.notify_all();
+    }
+
+    /// Block while paused. Returns false once the download has been cancelled.
+    fn proceed(&self) -> bool {
+        let (lock, condvar) = &*self.state;
+        let state = condvar.wait_while(lock.lock().unwrap(), |state| state.paused && !state.cancelled).unwrap();
+        !state.cancelled
+    }
+}
+/// Download the NSE equity list and return (symbol, company name) pairs
+pub fn download_nse_equity_list(source: &dyn DataSource) -> Result<Vec<(String, String)>, String> {
+    let bytes = source.fetch(EQUITY_LIST_PATH)
+        .map_err(|e| format!("Failed to download: {}", e))?;
+
+    let mut rdr = csv::Reader::from_reader(bytes.as_slice());
+    let mut symbols = Vec::new();
+
+    for result in rdr.records() {
+        let record = result.map_err(|e| format!("CSV parse error: {}", e))?;
+        if let (Some(symbol), Some(name)) = (record.get(0), record.get(1)) {
+            if !symbol.trim().is_empty() && !name.trim().is_empty() {
+                symbols.push((symbol.trim().to_string(), name.trim().to_string()));
+            }
+        }
+    }
+
+    Ok(symbols)
+}
+
+/// Check the alert rules against the latest stored session once a download job has finished ingesting,
+/// rather than after every day, so a long backfill doesn't re-check every rule per file
+fn announce_alerts(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>) {
+    let alerts = evaluate_alerts(&db_conn.lock().unwrap()).unwrap_or_else(|e| {
+        warn!("Failed to evaluate alerts: {}", e);
+        Vec::new()
+    });
+    if !alerts.is_empty() {
+        info!("{} alerts triggered", alerts.len());
+        let _ = tx.send(crate::BhavCopyMessage::AlertsTriggered(alerts));
+    }
+}
+
+/// Stop a download after this many trading days in a row without a BhavCopy
+const MAX_CONSECUTIVE_MISSING_DAYS: usize = 10;
+
+/// Download and ingest one BhavCopy per trading day, walking back from `start_date` to `end_date`.
+/// Weekends and imported market holidays are skipped without a request.
+pub fn download_bhavcopy_with_date_range(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource, start_date: NaiveDate, end_date: NaiveDate, max_files: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let calendar = TradingCalendar::load(&db_conn.lock().unwrap())?;
+    let dates = plan_range(tx, &calendar, start_date, end_date);
+    fetch_days(db_conn, tx, control, source, &dates, Some(MAX_CONSECUTIVE_MISSING_DAYS), max_files)?;
+    announce_alerts(db_conn, tx);
+    Ok(())
+}
+
+/// Download only the trading days missing from `bhavcopy_data` between `from` and `to`
+/// (by default the span already stored), newest first
+pub fn fill_bhavcopy_gaps(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dates = find_missing_trading_days(&db_conn.lock().unwrap(), from, to)?;
+    dates.reverse();
+    if let (Some(&last), Some(&first)) = (dates.first(), dates.last()) {
+        let _ = tx.send(crate::BhavCopyMessage::Planned { from: first, to: last, days: dates.len() });
+    }
+    info!("Filling {} missing trading days", dates.len());
+    // Every gap is worth trying, however many in a row turn out to be unpublished
+    fetch_days(db_conn, tx, control, source, &dates, None, None)?;
+    announce_alerts(db_conn, tx);
+    Ok(())
+}
+
+/// Download delivery positions for stored NSE trading days that have none, newest first
+pub fn fill_delivery_gaps(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource) -> Result<(), Box<dyn std::error::Error>> {
+    let dates = find_days_missing_delivery(&db_conn.lock().unwrap())?;
+    fill_days(tx, control, "delivery data", &dates, |date| {
+        let rows = fetch_delivery(source, date)?;
+        let inserted = insert_delivery_rows(&db_conn.lock().unwrap(), &rows)?;
+        Ok(IngestStats { inserted, ..Default::default() })
+    })
+}
+
+/// The day loop behind the delivery, F&O and index downloads, over `dates` in the order given.
+/// `fetch_and_store` ingests one day: a missing file marks the day not published, a database error
+/// stops the download, and any other error fails just that day.
+fn fill_days(tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, what: &str, dates: &[NaiveDate], mut fetch_and_store: impl FnMut(NaiveDate) -> Result<IngestStats, Box<dyn std::error::Error>>) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::{BhavCopyMessage, DayOutcome, StopReason};
+
+    if let (Some(&last), Some(&first)) = (dates.first(), dates.last()) {
+        let _ = tx.send(BhavCopyMessage::Planned { from: first, to: last, days: dates.len() });
+    }
+    info!("Fetching {} for {} trading days", what, dates.len());
+
+    for &date in dates {
+        if !control.proceed() {
+            info!("Cancelled fetching {} before {}", what, date);
+            let _ = tx.send(BhavCopyMessage::Stopped(StopReason::Cancelled));
+            return Ok(());
+        }
+
+        let _ = tx.send(BhavCopyMessage::DayStarted(date));
+        let outcome = match fetch_and_store(date) {
+            Ok(stats) => {
+                info!("Stored {} rows of {} for {}", stats.inserted, what, date);
+                DayOutcome::Ingested { inserted: stats.inserted, skipped: stats.skipped, rejected: stats.rejected }
+            }
+            Err(e) if e.is::<rusqlite::Error>() => return Err(e),
+            Err(e) if matches!(e.downcast_ref::<FetchError>(), Some(FetchError::NotFound(_))) => {
+                info!("{} ({})", e, date);
+                DayOutcome::NotPublished
+            }
+            Err(e) => {
+                warn!("Failed to fetch {} for {}: {}", what, date, e);
+                DayOutcome::Failed(e.to_string())
+            }
+        };
+        let _ = tx.send(BhavCopyMessage::DayFinished { date, outcome });
+    }
+    Ok(())
+}
+
+/// Delivery positions for one day, from the security-wise full BhavCopy or,
+/// where that was not published, the MTO file
+pub fn fetch_delivery(source: &dyn DataSource, date: NaiveDate) -> Result<Vec<DeliveryRow>, Box<dyn std::error::Error>> {
+    let bytes = match source.fetch(&delivery_path(date)) {
+        Ok(bytes) => bytes,
+        Err(FetchError::NotFound(_)) => return Ok(parse_mto(&source.fetch(&mto_path(date))?, Some(date))?.rows),
+        Err(e) => return Err(e.into()),
+    };
+
+    let parsed = parse_bhavcopy_csv(&bytes, Some(date))?;
+    Ok(parsed.rows.into_iter()
+        .filter_map(|row| Some(DeliveryRow {
+            deliverable_qty: row.deliverable_qty?,
+            traded_qty: Some(row.volume),
+            delivery_percent: row.delivery_percent,
+            symbol: row.symbol,
+            series: row.series,
+            date: row.date,
+        }))
+        .collect())
+}
+
+/// Download NSE index closes for stored trading days that have none, newest first
+pub fn fill_index_gaps(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource) -> Result<(), Box<dyn std::error::Error>> {
+    let dates = find_days_missing_index_data(&db_conn.lock().unwrap())?;
+    fill_days(tx, control, "index data", &dates, |date| {
+        let parsed = fetch_index_closes(source, date)?;
+        Ok(insert_index_rows(&db_conn.lock().unwrap(), &parsed)?)
+    })
+}
+
+/// Closing values of every NSE index for one day
+pub fn fetch_index_closes(source: &dyn DataSource, date: NaiveDate) -> Result<ParsedIndices, Box<dyn std::error::Error>> {
+    parse_index_close(&source.fetch(&index_close_path(date))?, Some(date))
+}
+
+/// How many of the latest stored sessions an F&O download covers, since each file runs to tens of MB
+pub const DERIVATIVES_SESSIONS: usize = 60;
+
+/// Download F&O BhavCopies for the latest stored NSE sessions that have no contracts, newest first
+pub fn fill_derivatives_gaps(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource) -> Result<(), Box<dyn std::error::Error>> {
+    let dates = find_days_missing_derivatives(&db_conn.lock().unwrap(), DERIVATIVES_SESSIONS)?;
+    fill_days(tx, control, "F&O data", &dates, |date| {
+        let parsed = fetch_derivatives(source, date)?;
+        Ok(insert_fo_rows(&db_conn.lock().unwrap(), &parsed)?)
+    })
+}
+
+/// Futures and options contracts for one day, from the zipped F&O BhavCopy
+pub fn fetch_derivatives(source: &dyn DataSource, date: NaiveDate) -> Result<ParsedFo, Box<dyn std::error::Error>> {
+    let bytes = source.fetch(&fo_bhavcopy_path(date))?;
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut file = archive.by_index(0)?;
+    let mut csv_data = Vec::new();
+    std::io::copy(&mut file, &mut csv_data)?;
+    parse_fo_bhavcopy(&csv_data, Some(date))
+}
+
+/// Trading days from `start_date` back to `end_date`, announced to the receiver
+fn plan_range(tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, calendar: &TradingCalendar, start_date: NaiveDate, end_date: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = calendar.trading_days(end_date, start_date);
+    dates.reverse();
+    let _ = tx.send(crate::BhavCopyMessage::Planned { from: end_date, to: start_date, days: dates.len() });
+    dates
+}
 
-    let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-        "Downloading BhavCopy data from {} to {}",
-        end_date.format("%Y-%m-%d"),
-        start_date.format("%Y-%m-%d")
-    )));
+/// The day loop behind every BhavCopy download, over `dates` in the order given.
+/// Returns false if the download was cancelled.
+fn fetch_days(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource, dates: &[NaiveDate], max_consecutive_missing: Option<usize>, max_files: Option<usize>) -> Result<bool, Box<dyn std::error::Error>> {
+    use crate::{BhavCopyMessage, DayOutcome, StopReason};
+
+    let downloads_dir = control.downloads_dir()?;
+    if let (Some(newest), Some(oldest)) = (dates.first(), dates.last()) {
+        info!("Downloading BhavCopy data for {} trading days from {} back to {}", dates.len(), newest, oldest);
+    }
 
-    let mut current_date = start_date;
     let mut downloaded_count = 0;
     let mut consecutive_error_days = 0;
-    let mut attempts = 0;
-    let max_consecutive_error_days = 10; // Stop if we get 10 consecutive days of errors
 
-    while current_date >= end_date {
+    for &current_date in dates {
+        if !control.proceed() {
+            info!("BhavCopy download cancelled before {}", current_date);
+            let _ = tx.send(BhavCopyMessage::Stopped(StopReason::Cancelled));
+            return Ok(false);
+        }
+
         // Check if we've reached the download limit
         if let Some(limit) = max_files {
             if downloaded_count >= limit {
-                println!("Reached download limit of {} files", limit);
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!("Reached download limit of {} files", limit)));
+                info!("Reached download limit of {} files", limit);
+                let _ = tx.send(BhavCopyMessage::Stopped(StopReason::LimitReached(limit)));
                 break;
             }
         }
 
-        // Stop if too many consecutive days with errors
-        if consecutive_error_days >= max_consecutive_error_days {
-            let msg = format!("Stopping after {} consecutive days with no data available", max_consecutive_error_days);
-            println!("{}", msg);
-            let _ = tx.send(crate::BhavCopyMessage::Progress(msg));
-            break;
+        // Stop if too many consecutive trading days had no data
+        if let Some(max_missing) = max_consecutive_missing {
+            if consecutive_error_days >= max_missing {
+                warn!("Stopping after {} consecutive trading days with no data available", max_missing);
+                let _ = tx.send(BhavCopyMessage::Stopped(StopReason::TooManyMissingDays(max_missing)));
+                break;
+            }
         }
 
-        attempts += 1;
         let year = current_date.year();
         let month = current_date.month();
 
-        let url = get_bhavcopy_url(current_date);
-
-        rate_limit_delay();
-
-        println!("Downloading: {}", url);
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Downloading {} (attempt {}, {} downloaded, {} consecutive error days)",
-            current_date.format("%Y-%m-%d"),
-            attempts,
-            downloaded_count,
-            consecutive_error_days
-        )));
-
-        let response = client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; rv:109.0) Gecko/20100101 Firefox/118.0")
-            .header("Referer", "https://www.nseindia.com/get-quotes/equity?symbol=HDFCBANK")
-            .send();
-
-        let response = match response {
-            Ok(resp) if resp.status().is_success() => resp,
-            Ok(resp) => {
-                println!("   HTTP error {}: {}", resp.status(), current_date.format("%Y-%m-%d"));
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-                    "   HTTP error {} for {}",
-                    resp.status(),
-                    current_date.format("%Y-%m-%d")
-                )));
-                consecutive_error_days += 1;
-                current_date = current_date - chrono::Duration::days(1);
-                continue;
-            }
+        let url = source.location(&bhavcopy_path(current_date));
+
+        info!("Downloading {}", url);
+        let _ = tx.send(BhavCopyMessage::DayStarted(current_date));
+
+        let bytes = match source.fetch(&bhavcopy_path(current_date)) {
+            Ok(bytes) => bytes,
             Err(e) => {
-                println!("   Network error: {} for {}", e, current_date.format("%Y-%m-%d"));
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-                    "   Network error: {} for {}",
-                    e,
-                    current_date.format("%Y-%m-%d")
-                )));
+                info!("{} ({})", e, current_date.format("%Y-%m-%d"));
+                let outcome = match e {
+                    FetchError::NotFound(_) => DayOutcome::NotPublished,
+                    other => DayOutcome::Failed(other.to_string()),
+                };
+                let _ = tx.send(BhavCopyMessage::DayFinished { date: current_date, outcome });
                 consecutive_error_days += 1;
-                current_date = current_date - chrono::Duration::days(1);
                 continue;
             }
         };
@@ -297,8 +485,7 @@ pub fn download_bhavcopy_with_date_range(db_conn: &std::sync::Arc<std::sync::Mut
         let zip_path = month_dir.join(format!("bhavcopy_{}.zip", date_str));
         let csv_path = month_dir.join(format!("bhavcopy_{}.csv", date_str));
 
-        // Download ZIP
-        let bytes = response.bytes()?;
+        // Save ZIP
         fs::write(&zip_path, &bytes)?;
 
         // Extract ZIP
@@ -307,122 +494,70 @@ pub fn download_bhavcopy_with_date_range(db_conn: &std::sync::Arc<std::sync::Mut
         let mut csv_data = Vec::new();
         std::io::copy(&mut file, &mut csv_data)?;
 
-        // Validate CSV
-        let csv_str = String::from_utf8_lossy(&csv_data);
-        let lines: Vec<&str> = csv_str.lines().collect();
-        // Check for either new format (TradDt) or old format (SYMBOL)
-        if lines.len() < 2 || (!lines[0].contains("TradDt") && !lines[0].contains("SYMBOL")) {
-            println!("   Invalid CSV for {}", current_date.format("%Y-%m-%d"));
-            fs::remove_file(&zip_path)?;
-            consecutive_error_days += 1;
-            current_date = current_date - chrono::Duration::days(1);
-            continue;
-        }
+        // Parse before taking the connection, so the lock is only held while inserting
+        let parsed = match parse_bhavcopy_csv(&csv_data, Some(current_date)) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Invalid CSV for {}: {}", current_date.format("%Y-%m-%d"), e);
+                fs::remove_file(&zip_path)?;
+                let _ = tx.send(BhavCopyMessage::DayFinished {
+                    date: current_date,
+                    outcome: DayOutcome::Failed(format!("Invalid CSV: {}", e)),
+                });
+                consecutive_error_days += 1;
+                continue;
+            }
+        };
 
         // Save CSV
         fs::write(&csv_path, &csv_data)?;
         fs::remove_file(&zip_path)?; // Remove ZIP after extraction
 
-        // Record in DB
-        let ts = current_date.and_hms_opt(0,0,0).unwrap().and_utc().timestamp();
-        {
+        // Insert the day's rows in one transaction, then record the download
+        debug!("Processing {}", csv_path.display());
+        let ts = date_to_timestamp(current_date);
+        let stats = {
             let conn = db_conn.lock().unwrap();
-            save_download_record(&*conn, None, ts, ts, &csv_path.to_string_lossy(), "completed", None)?;
-        }
+            let stats = insert_bhavcopy_rows(&conn, &parsed)?;
+            save_download_record(&conn, None, ts, ts, &csv_path.to_string_lossy(), "completed", None)?;
+            stats
+        };
 
-        // Parse CSV and insert into bhavcopy_data
-        println!("Processing: {}", csv_path.display());
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Processing {} data into database...",
-            current_date.format("%Y-%m-%d")
-        )));
-        {
-            let conn = db_conn.lock().unwrap();
-            let mut rdr = csv::ReaderBuilder::new()
-                .flexible(true)
-                .from_path(&csv_path)?;
-
-            let headers = rdr.headers()?.clone();
-
-            // Support both old format (2023 and before) and new format (2024 onwards)
-            let symbol_idx = headers.iter().position(|h| h == "TckrSymb" || h == "SYMBOL").unwrap_or(0);
-            let series_idx = headers.iter().position(|h| h == "SctySrs" || h == "SERIES").unwrap_or(1);
-            let open_idx = headers.iter().position(|h| h == "OpnPric" || h == "OPEN").unwrap_or(2);
-            let high_idx = headers.iter().position(|h| h == "HghPric" || h == "HIGH").unwrap_or(3);
-            let low_idx = headers.iter().position(|h| h == "LwPric" || h == "LOW").unwrap_or(4);
-            let close_idx = headers.iter().position(|h| h == "ClsPric" || h == "CLOSE").unwrap_or(5);
-            let last_idx = headers.iter().position(|h| h == "LastPric" || h == "LAST").unwrap_or(6);
-            let prev_close_idx = headers.iter().position(|h| h == "PrvsClsgPric" || h == "PREVCLOSE").unwrap_or(7);
-            let volume_idx = headers.iter().position(|h| h == "TtlTradgVol" || h == "TOTTRDQTY").unwrap_or(8);
-            let turnover_idx = headers.iter().position(|h| h == "TtlTrfVal" || h == "TOTTRDVAL").unwrap_or(9);
-            let trades_idx = headers.iter().position(|h| h == "TtlNbOfTxsExctd" || h == "TOTALTRADES").unwrap_or(10);
-            let isin_idx = headers.iter().position(|h| h == "ISIN").unwrap_or(11);
-
-            let mut rows: Vec<(String, String, i64, f64, f64, f64, f64, f64, f64, i64, f64, i64, String)> = Vec::new();
-            for result in rdr.records() {
-                let record = result?;
-                if record.len() <= symbol_idx { continue; }
-                let symbol = record.get(symbol_idx).unwrap_or("").trim().to_uppercase();
-                if symbol.is_empty() { continue; }
-                let series = record.get(series_idx).unwrap_or("").trim().to_string();
-                let open: f64 = record.get(open_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let high: f64 = record.get(high_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let low: f64 = record.get(low_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let close: f64 = record.get(close_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let last: f64 = record.get(last_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let prev_close: f64 = record.get(prev_close_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let volume: i64 = record.get(volume_idx).unwrap_or("0").trim().parse().unwrap_or(0);
-                let turnover: f64 = record.get(turnover_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let trades: i64 = record.get(trades_idx).unwrap_or("0").trim().parse().unwrap_or(0);
-                let isin = record.get(isin_idx).unwrap_or("").trim().to_string();
-                rows.push((symbol, series, ts, open, high, low, close, last, prev_close, volume, turnover, trades, isin));
-            }
-            for chunk in rows.chunks(100) {
-                if chunk.is_empty() { continue; }
-                let placeholders: Vec<String> = chunk.iter().map(|_| "(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)".to_string()).collect();
-                let query = format!("INSERT OR IGNORE INTO bhavcopy_data (symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin) VALUES {}", placeholders.join(", "));
-                let params: Vec<&dyn rusqlite::ToSql> = chunk.iter().flat_map(|(symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin)| vec![symbol as &dyn rusqlite::ToSql, series as &dyn rusqlite::ToSql, date as &dyn rusqlite::ToSql, open as &dyn rusqlite::ToSql, high as &dyn rusqlite::ToSql, low as &dyn rusqlite::ToSql, close as &dyn rusqlite::ToSql, last as &dyn rusqlite::ToSql, prev_close as &dyn rusqlite::ToSql, volume as &dyn rusqlite::ToSql, turnover as &dyn rusqlite::ToSql, trades as &dyn rusqlite::ToSql, isin as &dyn rusqlite::ToSql]).collect();
-                conn.execute(&query, rusqlite::params_from_iter(params))?;
-            }
+        info!("Finished {} ({} inserted, {} skipped, {} rejected)",
+            csv_path.display(), stats.inserted, stats.skipped, stats.rejected);
+        for error in stats.errors.iter().take(5) {
+            warn!("Rejected row in {}: {}", csv_path.display(), error);
         }
 
-        println!("Finished: {}", csv_path.display());
-
         // Delete CSV file after processing
         fs::remove_file(&csv_path)?;
 
         // Success! Reset consecutive error day counter
         consecutive_error_days = 0;
         downloaded_count += 1;
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Completed {} ({} files processed)",
-            current_date.format("%Y-%m-%d"),
-            downloaded_count
-        )));
+        let _ = tx.send(BhavCopyMessage::DayFinished {
+            date: current_date,
+            outcome: DayOutcome::Ingested { inserted: stats.inserted, skipped: stats.skipped, rejected: stats.rejected },
+        });
 
         // Send updated date range
         {
             let conn = db_conn.lock().unwrap();
-            if let Ok(Some((min_date, max_date))) = get_bhavcopy_date_range(&*conn) {
-                let _ = tx.send(crate::BhavCopyMessage::DateRangeUpdated(min_date, max_date));
+            if let Ok(Some((min_date, max_date))) = get_bhavcopy_date_range(&conn) {
+                let _ = tx.send(BhavCopyMessage::DateRangeUpdated(min_date, max_date));
             }
         }
-
-        current_date = current_date - chrono::Duration::days(1);
     }
 
-    Ok(())
+    Ok(true)
 }
 
-pub fn download_bhavcopy_with_limit(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, max_files: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
-    let client = create_http_client();
-    let downloads_dir = get_downloads_dir();
-
+pub fn download_bhavcopy_with_limit(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource, max_files: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
     // Get both the earliest and latest dates in bhavcopy_data
     let (earliest_data_date, latest_data_date): (Option<i64>, Option<i64>) = {
         let conn = db_conn.lock().unwrap();
         conn.query_row(
-            "SELECT MIN(date), MAX(date) FROM bhavcopy_data",
+            "SELECT MIN(date), MAX(date) FROM bhavcopy_data WHERE exchange = 'NSE'",
             [],
             |row| Ok((row.get(0)?, row.get(1)?)),
         ).unwrap_or((None, None))
@@ -431,248 +566,68 @@ pub fn download_bhavcopy_with_limit(db_conn: &std::sync::Arc<std::sync::Mutex<ru
     let yesterday = chrono::Utc::now().date_naive() - chrono::Duration::days(1);
 
     // First, download future data (from latest date to yesterday)
+    let mut ranges = Vec::new();
     if let Some(ts) = latest_data_date {
-        let latest_date = chrono::DateTime::from_timestamp(ts, 0)
-            .map(|dt| dt.naive_utc().date())
-            .unwrap_or(yesterday);
+        let latest_date = timestamp_to_date(ts).unwrap_or(yesterday);
 
         // Check if there's newer data to download
         if latest_date < yesterday {
-            let future_start = latest_date + chrono::Duration::days(1);
-            let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-                "Downloading recent BhavCopy data from {} to {}",
-                future_start.format("%Y-%m-%d"),
-                yesterday.format("%Y-%m-%d")
-            )));
-
-            // Download future data
-            download_bhavcopy_with_date_range(db_conn, tx, yesterday, future_start, max_files)?;
+            ranges.push((yesterday, latest_date + chrono::Duration::days(1)));
         }
     }
 
     // Then, download past data (from earliest date backwards)
     let start_date = if let Some(ts) = earliest_data_date {
         // If we have data, start from the day before the earliest date
-        chrono::DateTime::from_timestamp(ts, 0)
-            .map(|dt| dt.naive_utc().date() - chrono::Duration::days(1))
-            .unwrap_or_else(|| yesterday)
+        timestamp_to_date(ts)
+            .map(|date| date - chrono::Duration::days(1))
+            .unwrap_or(yesterday)
     } else {
         // No data yet, start from yesterday
         yesterday
     };
-
-    let end_date = start_date - chrono::Duration::days(365); // 12 months back
-
-    let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-        "Downloading historical BhavCopy data from {} to {}",
-        end_date.format("%Y-%m-%d"),
-        start_date.format("%Y-%m-%d")
-    )));
-
-    let mut current_date = start_date;
-    let mut downloaded_count = 0;
-    let mut consecutive_error_days = 0;
-    let mut attempts = 0;
-    let max_consecutive_error_days = 10; // Stop if we get 10 consecutive days of errors
-
-    while current_date >= end_date {
-        // Check if we've reached the download limit
-        if let Some(limit) = max_files {
-            if downloaded_count >= limit {
-                println!("Reached download limit of {} files", limit);
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!("Reached download limit of {} files", limit)));
-                break;
-            }
-        }
-
-        // Stop if too many consecutive days with errors
-        if consecutive_error_days >= max_consecutive_error_days {
-            let msg = format!("Stopping after {} consecutive days with no data available", max_consecutive_error_days);
-            println!("{}", msg);
-            let _ = tx.send(crate::BhavCopyMessage::Progress(msg));
+    ranges.push((start_date, start_date - chrono::Duration::days(365))); // 12 months back
+
+    // Announce every range first so progress covers the whole job
+    let calendar = TradingCalendar::load(&db_conn.lock().unwrap())?;
+    let planned: Vec<Vec<NaiveDate>> = ranges.into_iter()
+        .map(|(start, end)| plan_range(tx, &calendar, start, end))
+        .collect();
+    for dates in planned {
+        if !fetch_days(db_conn, tx, control, source, &dates, Some(MAX_CONSECUTIVE_MISSING_DAYS), max_files)? {
             break;
         }
+    }
+    announce_alerts(db_conn, tx);
+    Ok(())
+}
 
-        attempts += 1;
-        let year = current_date.year();
-        let month = current_date.month();
-
-        let url = get_bhavcopy_url(current_date);
-
-        rate_limit_delay();
-
-        println!("Downloading: {}", url);
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Downloading {} (attempt {}, {} downloaded, {} consecutive error days)",
-            current_date.format("%Y-%m-%d"),
-            attempts,
-            downloaded_count,
-            consecutive_error_days
-        )));
-
-        let response = client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; rv:109.0) Gecko/20100101 Firefox/118.0")
-            .header("Referer", "https://www.nseindia.com/get-quotes/equity?symbol=HDFCBANK")
-            .send();
-
-        let response = match response {
-            Ok(resp) if resp.status().is_success() => resp,
-            Ok(resp) => {
-                println!("   HTTP error {}: {}", resp.status(), current_date.format("%Y-%m-%d"));
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-                    "   HTTP error {} for {}",
-                    resp.status(),
-                    current_date.format("%Y-%m-%d")
-                )));
-                consecutive_error_days += 1;
-                current_date = current_date - chrono::Duration::days(1);
-                continue;
-            }
-            Err(e) => {
-                println!("   Network error: {} for {}", e, current_date.format("%Y-%m-%d"));
-                let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-                    "   Network error: {} for {}",
-                    e,
-                    current_date.format("%Y-%m-%d")
-                )));
-                consecutive_error_days += 1;
-                current_date = current_date - chrono::Duration::days(1);
-                continue;
-            }
-        };
-
-        // Create directory
-        let year_dir = downloads_dir.join(year.to_string());
-        let month_dir = year_dir.join(format!("{:02}", month));
-        fs::create_dir_all(&month_dir)?;
-
-        let date_str = current_date.format("%Y%m%d").to_string();
-        let zip_path = month_dir.join(format!("bhavcopy_{}.zip", date_str));
-        let csv_path = month_dir.join(format!("bhavcopy_{}.csv", date_str));
-
-        // Download ZIP
-        let bytes = response.bytes()?;
-        fs::write(&zip_path, &bytes)?;
-
-        // Extract ZIP
-        let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path)?)?;
-        let mut file = archive.by_index(0)?;
-        let mut csv_data = Vec::new();
-        std::io::copy(&mut file, &mut csv_data)?;
-
-        // Validate CSV
-        let csv_str = String::from_utf8_lossy(&csv_data);
-        let lines: Vec<&str> = csv_str.lines().collect();
-        // Check for either new format (TradDt) or old format (SYMBOL)
-        if lines.len() < 2 || (!lines[0].contains("TradDt") && !lines[0].contains("SYMBOL")) {
-            println!("   Invalid CSV for {}", current_date.format("%Y-%m-%d"));
-            fs::remove_file(&zip_path)?;
-            consecutive_error_days += 1;
-            current_date = current_date - chrono::Duration::days(1);
-            continue;
-        }
-
-        // Save CSV
-        fs::write(&csv_path, &csv_data)?;
-        fs::remove_file(&zip_path)?; // Remove ZIP after extraction
-
-        // Record in DB
-        let ts = current_date.and_hms_opt(0,0,0).unwrap().and_utc().timestamp();
-        {
-            let conn = db_conn.lock().unwrap();
-            save_download_record(&*conn, None, ts, ts, &csv_path.to_string_lossy(), "completed", None)?;
-        }
-
-        // Parse CSV and insert into bhavcopy_data
-        println!("Processing: {}", csv_path.display());
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Processing {} data into database...",
-            current_date.format("%Y-%m-%d")
-        )));
-        {
-            let conn = db_conn.lock().unwrap();
-            // Configure CSV reader to be flexible about field counts
-            // Some NSE files (e.g., 2024-06-19, 2024-06-20) have trailing commas in headers
-            let mut rdr = csv::ReaderBuilder::new()
-                .flexible(true)
-                .from_path(&csv_path)?;
-
-            // Get headers to determine column mapping
-            let headers = rdr.headers()?.clone();
-
-            // Find column indices
-            let symbol_idx = headers.iter().position(|h| h == "TckrSymb").unwrap_or(1);
-            let series_idx = headers.iter().position(|h| h == "SctySrs").unwrap_or(2);
-            let open_idx = headers.iter().position(|h| h == "OpnPric").unwrap_or(4);
-            let high_idx = headers.iter().position(|h| h == "HghPric").unwrap_or(5);
-            let low_idx = headers.iter().position(|h| h == "LwPric").unwrap_or(6);
-            let close_idx = headers.iter().position(|h| h == "ClsPric").unwrap_or(7);
-            let last_idx = headers.iter().position(|h| h == "LastPric").unwrap_or(8);
-            let prev_close_idx = headers.iter().position(|h| h == "PrvsClsgPric").unwrap_or(9);
-            let volume_idx = headers.iter().position(|h| h == "TtlTradgVol").unwrap_or(10);
-            let turnover_idx = headers.iter().position(|h| h == "TtlTrfVal").unwrap_or(11);
-            let trades_idx = headers.iter().position(|h| h == "TtlNbOfTxsExctd").unwrap_or(12);
-            let isin_idx = headers.iter().position(|h| h == "ISIN").unwrap_or(13);
-
-            let mut rows: Vec<(String, String, i64, f64, f64, f64, f64, f64, f64, i64, f64, i64, String)> = Vec::new();
-            for result in rdr.records() {
-                let record = result?;
-                if record.len() <= symbol_idx { continue; }
-                let symbol = record.get(symbol_idx).unwrap_or("").trim().to_uppercase();
-                if symbol.is_empty() { continue; }
-                let series = record.get(series_idx).unwrap_or("").trim().to_string();
-                let open: f64 = record.get(open_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let high: f64 = record.get(high_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let low: f64 = record.get(low_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let close: f64 = record.get(close_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let last: f64 = record.get(last_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let prev_close: f64 = record.get(prev_close_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let volume: i64 = record.get(volume_idx).unwrap_or("0").trim().parse().unwrap_or(0);
-                let turnover: f64 = record.get(turnover_idx).unwrap_or("0").trim().parse().unwrap_or(0.0);
-                let trades: i64 = record.get(trades_idx).unwrap_or("0").trim().parse().unwrap_or(0);
-                let isin = record.get(isin_idx).unwrap_or("").trim().to_string();
-                rows.push((symbol, series, ts, open, high, low, close, last, prev_close, volume, turnover, trades, isin));
-            }
-            for chunk in rows.chunks(100) {
-                if chunk.is_empty() { continue; }
-                let placeholders: Vec<String> = chunk.iter().map(|_| "(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)".to_string()).collect();
-                let query = format!("INSERT OR IGNORE INTO bhavcopy_data (symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin) VALUES {}", placeholders.join(", "));
-                let params: Vec<&dyn rusqlite::ToSql> = chunk.iter().flat_map(|(symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin)| vec![symbol as &dyn rusqlite::ToSql, series as &dyn rusqlite::ToSql, date as &dyn rusqlite::ToSql, open as &dyn rusqlite::ToSql, high as &dyn rusqlite::ToSql, low as &dyn rusqlite::ToSql, close as &dyn rusqlite::ToSql, last as &dyn rusqlite::ToSql, prev_close as &dyn rusqlite::ToSql, volume as &dyn rusqlite::ToSql, turnover as &dyn rusqlite::ToSql, trades as &dyn rusqlite::ToSql, isin as &dyn rusqlite::ToSql]).collect();
-                conn.execute(&query, rusqlite::params_from_iter(params))?;
-            }
-        }
-
-        println!("Finished: {}", csv_path.display());
-
-        // Delete CSV file after processing
-        fs::remove_file(&csv_path)?;
-
-        // Success! Reset consecutive error day counter
-        consecutive_error_days = 0;
-        downloaded_count += 1;
-        let _ = tx.send(crate::BhavCopyMessage::Progress(format!(
-            "Completed {} ({} files processed)",
-            current_date.format("%Y-%m-%d"),
-            downloaded_count
-        )));
+/// Incremental update: download BhavCopy files after the latest stored date, up to today.
+/// With no data stored yet, the last `initial_days` days are downloaded instead.
+pub fn sync_bhavcopy(db_conn: &std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>, tx: &std::sync::mpsc::Sender<crate::BhavCopyMessage>, control: &DownloadControl, source: &dyn DataSource, initial_days: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let latest = {
+        let conn = db_conn.lock().unwrap();
+        get_bhavcopy_date_range(&conn)?.map(|(_, max)| max)
+    };
 
-        // Send updated date range
-        {
-            let conn = db_conn.lock().unwrap();
-            if let Ok(Some((min_date, max_date))) = get_bhavcopy_date_range(&*conn) {
-                let _ = tx.send(crate::BhavCopyMessage::DateRangeUpdated(min_date, max_date));
-            }
-        }
+    let today = chrono::Utc::now().date_naive();
+    let from = match latest {
+        Some(latest) => latest + chrono::Duration::days(1),
+        None => today - chrono::Duration::days(initial_days),
+    };
 
-        current_date = current_date - chrono::Duration::days(1);
+    if from > today {
+        info!("BhavCopy data is already up to date");
+        let _ = tx.send(crate::BhavCopyMessage::Planned { from, to: today, days: 0 });
+        return Ok(());
     }
 
-    Ok(())
+    download_bhavcopy_with_date_range(db_conn, tx, control, source, today, from, None)
 }
 
+/// First and last dates of the stored NSE data; BSE files are imported separately and don't count
 pub fn get_bhavcopy_date_range(conn: &Connection) -> Result<Option<(chrono::NaiveDate, chrono::NaiveDate)>, Box<dyn std::error::Error>> {
-    let mut stmt = conn.prepare("SELECT MIN(date), MAX(date) FROM bhavcopy_data")?;
+    let mut stmt = conn.prepare("SELECT MIN(date), MAX(date) FROM bhavcopy_data WHERE exchange = 'NSE'")?;
     let mut rows = stmt.query_map([], |row| {
         let min_ts: Option<i64> = row.get(0)?;
         let max_ts: Option<i64> = row.get(1)?;
@@ -682,11 +637,7 @@ pub fn get_bhavcopy_date_range(conn: &Connection) -> Result<Option<(chrono::Naiv
     if let Some(row) = rows.next() {
         let (min_ts, max_ts) = row?;
         if let (Some(min_ts), Some(max_ts)) = (min_ts, max_ts) {
-            let min_date = chrono::DateTime::from_timestamp(min_ts, 0)
-                .map(|dt| dt.naive_utc().date());
-            let max_date = chrono::DateTime::from_timestamp(max_ts, 0)
-                .map(|dt| dt.naive_utc().date());
-            if let (Some(min), Some(max)) = (min_date, max_date) {
+            if let (Some(min), Some(max)) = (timestamp_to_date(min_ts), timestamp_to_date(max_ts)) {
                 return Ok(Some((min, max)));
             }
         }
@@ -698,4 +649,128 @@ pub fn clear_bhavcopy_data(conn: &Connection) -> Result<(), Box<dyn std::error::
     conn.execute("DELETE FROM bhavcopy_data", [])?;
     conn.execute("DELETE FROM nse_downloads WHERE symbol IS NULL", [])?;
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::mpsc;
+    use crate::{BhavCopyMessage, DayOutcome};
+    use crate::db::source::DirectorySource;
+    use crate::db::test_support::{TempDir, test_db};
+
+    const UDIFF_HEADER: &str = "TradDt,BizDt,Sgmt,Src,FinInstrmTp,FinInstrmId,ISIN,TckrSymb,SctySrs,XpryDt,FininstrmActlXpryDt,StrkPric,OptnTp,FinInstrmNm,OpnPric,HghPric,LwPric,ClsPric,LastPric,PrvsClsgPric,UndrlygPric,SttlmPric,OpnIntrst,ChngInOpnIntrst,TtlTradgVol,TtlTrfVal,TtlNbOfTxsExctd,SsnId,NewBrdLotQty,Rmks,Rsvd1,Rsvd2,Rsvd3,Rsvd4";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn write_file(root: &std::path::Path, path: &str, bytes: &[u8]) {
+        let file = root.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, bytes).unwrap();
+    }
+
+    /// A zipped UDiFF BhavCopy holding one RELIANCE and one TCS row
+    fn bhavcopy_zip(day: NaiveDate, reliance_close: f64) -> Vec<u8> {
+        let d = day.format("%Y-%m-%d");
+        let csv = format!(
+            "{header}\n\
+             {d},{d},CM,NSE,STK,2885,INE002A01018,RELIANCE,EQ,,,,,RELIANCE INDUSTRIES LTD,3130,3150,3120,{close},{close},3131,,{close},,,500000,1570000000,20000,F1,1,,,,,\n\
+             {d},{d},CM,NSE,STK,11536,INE467B01029,TCS,EQ,,,,,TATA CONSULTANCY SERV LT,4300,4350,4280,4320,4320,4290,,4320,,,200000,864000000,15000,F1,1,,,,,\n",
+            header = UDIFF_HEADER, d = d, close = reliance_close,
+        );
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file(format!("BhavCopy_NSE_CM_0_0_0_{}_F_0000.csv", day.format("%Y%m%d")), zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(csv.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn bhavcopy_range_downloads_from_a_directory_mirror() {
+        let temp = TempDir::new("downloads-bhavcopy");
+        let mirror = temp.path().join("mirror");
+        write_file(&mirror, &bhavcopy_path(date(2024, 7, 29)), &bhavcopy_zip(date(2024, 7, 29), 3120.0));
+        write_file(&mirror, &bhavcopy_path(date(2024, 7, 31)), &bhavcopy_zip(date(2024, 7, 31), 3140.0));
+        // 30 July is left out, as if NSE never published it
+
+        let conn = Arc::new(Mutex::new(test_db()));
+        let (tx, rx) = mpsc::channel();
+        let control = DownloadControl::default().with_downloads_dir(temp.path().join("downloads"));
+        let source = DirectorySource::new(&mirror);
+        download_bhavcopy_with_date_range(&conn, &tx, &control, &source, date(2024, 7, 31), date(2024, 7, 29), None).unwrap();
+        drop(tx);
+
+        let outcomes: Vec<(NaiveDate, DayOutcome)> = rx.iter()
+            .filter_map(|message| match message {
+                BhavCopyMessage::DayFinished { date, outcome } => Some((date, outcome)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(outcomes, vec![
+            (date(2024, 7, 31), DayOutcome::Ingested { inserted: 2, skipped: 0, rejected: 0 }),
+            (date(2024, 7, 30), DayOutcome::NotPublished),
+            (date(2024, 7, 29), DayOutcome::Ingested { inserted: 2, skipped: 0, rejected: 0 }),
+        ]);
+
+        let conn = conn.lock().unwrap();
+        let closes: Vec<f64> = conn
+            .prepare("SELECT close FROM bhavcopy_data WHERE symbol = 'RELIANCE' AND exchange = 'NSE' ORDER BY date").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(closes, vec![3120.0, 3140.0]);
+    }
+
+    #[test]
+    fn alerts_are_checked_once_when_the_download_finishes() {
+        let temp = TempDir::new("downloads-alerts");
+        let mirror = temp.path().join("mirror");
+        for (day, close) in [(29, 3120.0), (30, 3125.0), (31, 3140.0)] {
+            write_file(&mirror, &bhavcopy_path(date(2024, 7, day)), &bhavcopy_zip(date(2024, 7, day), close));
+        }
+
+        let conn = Arc::new(Mutex::new(test_db()));
+        conn.lock().unwrap().execute(
+            "INSERT INTO nse_symbols (symbol, name, created_at, updated_at) VALUES ('RELIANCE', 'Reliance Industries', 0, 0)",
+            [],
+        ).unwrap();
+        crate::db::alerts::create_alert_rule(&conn.lock().unwrap(), "RELIANCE", crate::db::alerts::AlertKind::CrossesAbove, 3135.0, None).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let control = DownloadControl::default().with_downloads_dir(temp.path().join("downloads"));
+        download_bhavcopy_with_date_range(&conn, &tx, &control, &DirectorySource::new(&mirror), date(2024, 7, 31), date(2024, 7, 29), None).unwrap();
+        drop(tx);
+
+        // One check once every day is stored, reported after the last day rather than per day
+        let messages: Vec<BhavCopyMessage> = rx.iter().collect();
+        let alerts: Vec<_> = messages.iter()
+            .filter_map(|message| match message {
+                BhavCopyMessage::AlertsTriggered(alerts) => Some(alerts),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].len(), 1);
+        assert_eq!(alerts[0][0].date, date(2024, 7, 31));
+        assert!(matches!(messages.last(), Some(BhavCopyMessage::AlertsTriggered(_))));
+    }
+
+    #[test]
+    fn equity_list_downloads_from_a_directory_mirror() {
+        let mirror = TempDir::new("downloads-equity-list");
+        write_file(mirror.path(), EQUITY_LIST_PATH, b"SYMBOL,NAME OF COMPANY, SERIES, DATE OF LISTING\n\
+            RELIANCE,Reliance Industries Limited,EQ,29-NOV-1995\n\
+            TCS,Tata Consultancy Services Limited,EQ,25-AUG-2004\n\
+            ,Blank symbol is skipped,EQ,01-JAN-2000\n");
+
+        let symbols = download_nse_equity_list(&DirectorySource::new(mirror.path())).unwrap();
+        assert_eq!(symbols, vec![
+            ("RELIANCE".to_string(), "Reliance Industries Limited".to_string()),
+            ("TCS".to_string(), "Tata Consultancy Services Limited".to_string()),
+        ]);
+
+        let err = download_nse_equity_list(&DirectorySource::new(mirror.path().join("empty"))).unwrap_err();
+        assert!(err.contains("Not found"), "unexpected error: {}", err);
+    }
+}
@@ -0,0 +1,380 @@
+use rusqlite::Connection;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDate;
+use crate::db::date_to_timestamp;
+use crate::db::bhavcopy::{BhavCopyParser, RowError};
+use crate::db::delivery::{INSERT_DELIVERY_SQL, insert_delivery_rows, parse_mto};
+use crate::db::derivatives::{ingest_fo_csv, is_fo_bhavcopy};
+use crate::db::indices::{ingest_index_close, is_index_close_file};
+use crate::models::{BhavCopyRow, Exchange};
+
+/// Result of importing a single BhavCopy CSV, F&O BhavCopy, index close file or MTO delivery file, either a loose file or an entry inside a ZIP
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub file: String,
+    pub date: Option<NaiveDate>,
+    /// Exchange the file came from, when it could be parsed
+    pub exchange: Option<Exchange>,
+    pub inserted: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    /// Delivery positions stored, from security-wise full and MTO files
+    pub delivery: usize,
+    /// Futures and options contracts stored, from F&O BhavCopies
+    pub contracts: usize,
+    /// Index closes stored, from `ind_close_all` files
+    pub indices: usize,
+    /// Why each rejected row was rejected
+    pub row_errors: Vec<RowError>,
+    pub error: Option<String>,
+}
+
+impl ImportReport {
+    fn failed(file: String, date: Option<NaiveDate>, error: String) -> Self {
+        Self { file, date, exchange: None, inserted: 0, skipped: 0, rejected: 0, delivery: 0, contracts: 0, indices: 0, row_errors: Vec::new(), error: Some(error) }
+    }
+}
+
+/// Row counts produced by ingesting one BhavCopy CSV into bhavcopy_data
+#[derive(Debug, Clone, Default)]
+pub struct IngestStats {
+    pub exchange: Exchange,
+    pub inserted: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    /// Delivery positions stored alongside the rows
+    pub delivery: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// Extract the trading date from a BhavCopy file name.
+/// Understands the old NSE style (`cm01JAN2020bhav.csv`), the old BSE style (`EQ_ISINCODE_010720.CSV`),
+/// the new UDiFF style of either exchange (`BhavCopy_NSE_CM_0_0_0_20240701_F_0000.csv`),
+/// both NSE F&O styles (`fo01JAN2020bhav.csv`, `BhavCopy_NSE_FO_0_0_0_20240701_F_0000.csv`),
+/// NSE's delivery files (`sec_bhavdata_full_01072024.csv`, `MTO_01072024.DAT`), its index close file (`ind_close_all_01072024.csv`)
+/// and our own `bhavcopy_20240701.csv`.
+pub fn bhavcopy_date_from_file_name(file_name: &str) -> Option<NaiveDate> {
+    let name = Path::new(file_name).file_name()?.to_str()?;
+    let lower = name.to_lowercase();
+    let lower = lower.strip_suffix(".zip").unwrap_or(&lower);
+
+    if let Some(rest) = lower.strip_prefix("mto_").and_then(|s| s.strip_suffix(".dat")) {
+        return NaiveDate::parse_from_str(rest, "%d%m%Y").ok();
+    }
+
+    let stem = lower.strip_suffix(".csv")?;
+
+    if let Some(rest) = stem.strip_prefix("sec_bhavdata_full_").or_else(|| stem.strip_prefix("ind_close_all_")) {
+        return NaiveDate::parse_from_str(rest, "%d%m%Y").ok();
+    }
+
+    if let Some(rest) = stem.strip_prefix("cm").or_else(|| stem.strip_prefix("fo")).and_then(|s| s.strip_suffix("bhav")) {
+        return NaiveDate::parse_from_str(rest, "%d%b%Y").ok();
+    }
+
+    if let Some(rest) = stem.strip_prefix("eq_isincode_") {
+        return NaiveDate::parse_from_str(rest, "%d%m%y").ok();
+    }
+
+    if ["bhavcopy_nse_cm_", "bhavcopy_bse_cm_", "bhavcopy_nse_fo_"].iter().any(|prefix| stem.starts_with(prefix)) {
+        return stem.split('_')
+            .find(|part| part.len() == 8 && part.chars().all(|c| c.is_ascii_digit()))
+            .and_then(|part| NaiveDate::parse_from_str(part, "%Y%m%d").ok());
+    }
+
+    if let Some(rest) = stem.strip_prefix("bhavcopy_") {
+        return NaiveDate::parse_from_str(rest, "%Y%m%d").ok();
+    }
+
+    None
+}
+
+/// Import every BhavCopy CSV, F&O BhavCopy, index close file, MTO delivery file or ZIP archive found under `dir` (recursively).
+/// Returns one report per file that was recognised as a BhavCopy or MTO file, in path order.
+pub fn import_bhavcopy_dir(conn: &Connection, dir: &Path) -> Result<Vec<ImportReport>, Box<dyn std::error::Error>> {
+    let mut reports = Vec::new();
+    for path in find_bhavcopy_files(dir)? {
+        reports.extend(import_bhavcopy_file(conn, &path));
+    }
+
+    Ok(reports)
+}
+
+/// The files under `dir` (recursively) that [`import_bhavcopy_dir`] would try, in path order
+pub fn find_bhavcopy_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()).into());
+    }
+
+    let mut files = Vec::new();
+    collect_candidate_files(dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Import a single BhavCopy CSV, an F&O BhavCopy, an MTO delivery file, or a ZIP archive containing any of them
+pub fn import_bhavcopy_file(conn: &Connection, path: &Path) -> Vec<ImportReport> {
+    let display = path.to_string_lossy().to_string();
+    let is_zip = path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false);
+
+    if !is_zip {
+        let date = bhavcopy_date_from_file_name(&display);
+        return match fs::read(path) {
+            Ok(data) => import_bytes(conn, display.clone(), &display, &data, date).into_iter().collect(),
+            Err(e) => vec![ImportReport::failed(display, date, e.to_string())],
+        };
+    }
+
+    let archive_date = bhavcopy_date_from_file_name(&display);
+    let mut archive = match fs::File::open(path).map_err(|e| e.to_string())
+        .and_then(|f| zip::ZipArchive::new(f).map_err(|e| e.to_string())) {
+        Ok(archive) => archive,
+        Err(e) => return vec![ImportReport::failed(display, archive_date, e)],
+    };
+
+    let mut reports = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(e) => {
+                reports.push(ImportReport::failed(display.clone(), archive_date, e.to_string()));
+                continue;
+            }
+        };
+        let entry_name = entry.name().to_string();
+        if !is_candidate_name(&entry_name) {
+            continue;
+        }
+
+        let label = format!("{}!{}", display, entry_name);
+        let date = bhavcopy_date_from_file_name(&entry_name).or(archive_date);
+        let mut data = Vec::new();
+        if let Err(e) = entry.read_to_end(&mut data) {
+            reports.push(ImportReport::failed(label, date, e.to_string()));
+            continue;
+        }
+        reports.extend(import_bytes(conn, label, &entry_name, &data, date));
+    }
+
+    reports
+}
+
+fn is_candidate_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower.ends_with(".csv") || lower.ends_with(".dat")
+}
+
+fn import_bytes(conn: &Connection, label: String, name: &str, data: &[u8], date: Option<NaiveDate>) -> Option<ImportReport> {
+    if name.to_lowercase().ends_with(".dat") {
+        Some(import_mto_bytes(conn, label, data, date))
+    } else {
+        import_csv_bytes(conn, label, data, date)
+    }
+}
+
+fn import_mto_bytes(conn: &Connection, label: String, data: &[u8], date: Option<NaiveDate>) -> ImportReport {
+    let result = parse_mto(data, date).and_then(|parsed| {
+        let delivery = insert_delivery_rows(conn, &parsed.rows)?;
+        Ok((parsed, delivery))
+    });
+    match result {
+        Ok((parsed, delivery)) => ImportReport {
+            file: label,
+            date: parsed.date,
+            exchange: Some(Exchange::Nse),
+            inserted: 0,
+            skipped: 0,
+            rejected: parsed.errors.len(),
+            delivery,
+            contracts: 0,
+            indices: 0,
+            row_errors: parsed.errors,
+            error: None,
+        },
+        Err(e) => ImportReport::failed(label, date, e.to_string()),
+    }
+}
+
+fn import_csv_bytes(conn: &Connection, label: String, data: &[u8], date: Option<NaiveDate>) -> Option<ImportReport> {
+    // UDiFF F&O files share the equity header, so they are told apart before the equity parser sees them
+    if is_fo_bhavcopy(data) {
+        return Some(import_fo_bytes(conn, label, data, date));
+    }
+    if is_index_close_file(data) {
+        return Some(import_index_bytes(conn, label, data, date));
+    }
+    // Files that are neither named nor shaped like a BhavCopy are left out of the report
+    if date.is_none() && !csv_has_bhavcopy_header(data) {
+        return None;
+    }
+
+    let report = match ingest_bhavcopy_csv(conn, data, date) {
+        Ok(stats) => ImportReport {
+            file: label,
+            date,
+            exchange: Some(stats.exchange),
+            inserted: stats.inserted,
+            skipped: stats.skipped,
+            rejected: stats.rejected,
+            delivery: stats.delivery,
+            contracts: 0,
+            indices: 0,
+            row_errors: stats.errors,
+            error: None,
+        },
+        Err(e) => ImportReport::failed(label, date, e.to_string()),
+    };
+    Some(report)
+}
+
+fn import_index_bytes(conn: &Connection, label: String, data: &[u8], date: Option<NaiveDate>) -> ImportReport {
+    match ingest_index_close(conn, data, date) {
+        Ok(stats) => ImportReport {
+            file: label,
+            date,
+            exchange: Some(Exchange::Nse),
+            inserted: 0,
+            skipped: stats.skipped,
+            rejected: stats.rejected,
+            delivery: 0,
+            contracts: 0,
+            indices: stats.inserted,
+            row_errors: stats.errors,
+            error: None,
+        },
+        Err(e) => ImportReport::failed(label, date, e.to_string()),
+    }
+}
+
+fn import_fo_bytes(conn: &Connection, label: String, data: &[u8], date: Option<NaiveDate>) -> ImportReport {
+    match ingest_fo_csv(conn, data, date) {
+        Ok(stats) => ImportReport {
+            file: label,
+            date,
+            exchange: Some(Exchange::Nse),
+            inserted: 0,
+            skipped: stats.skipped,
+            rejected: stats.rejected,
+            delivery: 0,
+            contracts: stats.inserted,
+            indices: 0,
+            row_errors: stats.errors,
+            error: None,
+        },
+        Err(e) => ImportReport::failed(label, date, e.to_string()),
+    }
+}
+
+fn csv_has_bhavcopy_header(data: &[u8]) -> bool {
+    BhavCopyParser::from_bytes(data).is_ok()
+}
+
+fn collect_candidate_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_candidate_files(&path, files)?;
+        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            if ext.eq_ignore_ascii_case("csv") || ext.eq_ignore_ascii_case("dat") || ext.eq_ignore_ascii_case("zip") {
+                files.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rows parsed from one BhavCopy CSV, ready to insert
+#[derive(Debug, Clone, Default)]
+pub struct ParsedBhavCopy {
+    pub exchange: Exchange,
+    pub rows: Vec<BhavCopyRow>,
+    /// Rows that could not be parsed
+    pub errors: Vec<RowError>,
+}
+
+/// Parse a BhavCopy CSV in any known NSE or BSE format without touching the database.
+/// The trading date is read from each row and falls back to `fallback_date`.
+pub fn parse_bhavcopy_csv(data: &[u8], fallback_date: Option<NaiveDate>) -> Result<ParsedBhavCopy, Box<dyn std::error::Error>> {
+    let parser = BhavCopyParser::from_bytes(data)?.with_fallback_date(fallback_date);
+
+    let mut parsed = ParsedBhavCopy {
+        exchange: parser.format().exchange(),
+        ..ParsedBhavCopy::default()
+    };
+    for result in parser {
+        match result {
+            Ok(row) => parsed.rows.push(row),
+            Err(e) => parsed.errors.push(e),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Insert parsed rows into bhavcopy_data in one transaction, so a file is either fully stored or not at all.
+/// Rows already present are counted as skipped. BSE rows also record their scrip in bse_symbols,
+/// and rows carrying delivery figures store them in delivery_data even when the prices were already present.
+pub fn insert_bhavcopy_rows(conn: &Connection, parsed: &ParsedBhavCopy) -> rusqlite::Result<IngestStats> {
+    let mut stats = IngestStats {
+        exchange: parsed.exchange,
+        rejected: parsed.errors.len(),
+        errors: parsed.errors.clone(),
+        ..IngestStats::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO bhavcopy_data (symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin, exchange)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
+        )?;
+        // Files can be imported in any order, so only the latest session's name and ISIN are kept
+        let mut bse_stmt = tx.prepare_cached(
+            "INSERT INTO bse_symbols (symbol, name, isin, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)
+             ON CONFLICT(symbol) DO UPDATE SET
+                name = COALESCE(excluded.name, name),
+                isin = COALESCE(excluded.isin, isin),
+                updated_at = excluded.updated_at
+             WHERE NOT EXISTS(
+                SELECT 1 FROM bhavcopy_data WHERE symbol = excluded.symbol AND exchange = 'BSE' AND date > ?5
+             )"
+        )?;
+        let mut delivery_stmt = tx.prepare_cached(INSERT_DELIVERY_SQL)?;
+        let now = chrono::Utc::now().timestamp();
+        for row in &parsed.rows {
+            let ts = date_to_timestamp(row.date);
+            let inserted = stmt.execute(rusqlite::params![
+                row.symbol, row.series, ts, row.open, row.high, row.low, row.close,
+                row.last, row.prev_close, row.volume, row.turnover, row.trades, row.isin,
+                row.exchange.key(),
+            ])?;
+            stats.inserted += inserted;
+            stats.skipped += 1 - inserted;
+
+            if row.exchange == Exchange::Bse && inserted > 0 {
+                bse_stmt.execute(rusqlite::params![row.symbol, row.name, row.isin, now, ts])?;
+            }
+            if let Some(deliverable_qty) = row.deliverable_qty {
+                stats.delivery += delivery_stmt.execute(rusqlite::params![
+                    row.symbol, row.series, ts, row.volume, deliverable_qty, row.delivery_percent,
+                ])?;
+            }
+        }
+    }
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Parse a BhavCopy CSV in any known NSE or BSE format and insert its rows into bhavcopy_data.
+/// The trading date is read from each row and falls back to `fallback_date`.
+/// Rows already present are counted as skipped; malformed rows are rejected and reported.
+pub fn ingest_bhavcopy_csv(conn: &Connection, data: &[u8], fallback_date: Option<NaiveDate>) -> Result<IngestStats, Box<dyn std::error::Error>> {
+    let parsed = parse_bhavcopy_csv(data, fallback_date)?;
+    Ok(insert_bhavcopy_rows(conn, &parsed)?)
+}
@@ -0,0 +1,303 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::db::bhavcopy::RowError;
+use crate::db::import::IngestStats;
+use crate::db::{date_to_timestamp, timestamp_to_date};
+use crate::models::{Exchange, IndexRow, PriceBar};
+
+/// Header names of NSE's `ind_close_all_DDMMYYYY.csv`
+const INDEX_NAME: &str = "Index Name";
+const INDEX_DATE: &str = "Index Date";
+const INDEX_CLOSE: &str = "Closing Index Value";
+
+/// Index closes read from one `ind_close_all` file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedIndices {
+    pub rows: Vec<IndexRow>,
+    /// Rows that could not be parsed
+    pub errors: Vec<RowError>,
+}
+
+fn find_column(headers: &csv::StringRecord, name: &str) -> Option<usize> {
+    headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name))
+}
+
+/// Whether a CSV is NSE's daily index close file
+pub fn is_index_close_file(data: &[u8]) -> bool {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    match reader.headers() {
+        Ok(headers) => find_column(headers, INDEX_NAME).is_some() && find_column(headers, INDEX_CLOSE).is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Column positions of one index close file; only the name and close are required
+struct IndexColumns {
+    name: usize,
+    date: Option<usize>,
+    open: Option<usize>,
+    high: Option<usize>,
+    low: Option<usize>,
+    close: usize,
+    points_change: Option<usize>,
+    change_percent: Option<usize>,
+    volume: Option<usize>,
+    turnover: Option<usize>,
+    pe: Option<usize>,
+    pb: Option<usize>,
+    div_yield: Option<usize>,
+}
+
+impl IndexColumns {
+    fn build(headers: &csv::StringRecord) -> Result<Self, String> {
+        let find = |name: &str| find_column(headers, name);
+        let require = |name: &str| find(name).ok_or_else(|| format!("Index close file is missing the {} column", name));
+        Ok(IndexColumns {
+            name: require(INDEX_NAME)?,
+            date: find(INDEX_DATE),
+            open: find("Open Index Value"),
+            high: find("High Index Value"),
+            low: find("Low Index Value"),
+            close: require(INDEX_CLOSE)?,
+            points_change: find("Points Change"),
+            change_percent: find("Change(%)"),
+            volume: find("Volume"),
+            turnover: find("Turnover (Rs. Cr.)"),
+            pe: find("P/E"),
+            pb: find("P/B"),
+            div_yield: find("Div Yield"),
+        })
+    }
+}
+
+/// Parse NSE's daily index close file. The date is read from each row's `Index Date`
+/// and falls back to `fallback_date`; values NSE leaves as `-` are stored as missing.
+pub fn parse_index_close(data: &[u8], fallback_date: Option<NaiveDate>) -> Result<ParsedIndices, Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let columns = IndexColumns::build(reader.headers()?)?;
+
+    let mut parsed = ParsedIndices::default();
+    for result in reader.records() {
+        match result {
+            // Skip blank lines some files end with
+            Ok(record) if record.iter().all(|f| f.trim().is_empty()) => {}
+            Ok(record) => match parse_index_record(&record, &columns, fallback_date) {
+                Ok(row) => parsed.rows.push(row),
+                Err(e) => parsed.errors.push(e),
+            },
+            Err(e) => parsed.errors.push(RowError {
+                line: e.position().map(|p| p.line()),
+                field: "record",
+                value: String::new(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_index_record(record: &csv::StringRecord, cols: &IndexColumns, fallback_date: Option<NaiveDate>) -> Result<IndexRow, RowError> {
+    let line = record.position().map(|p| p.line());
+    let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+    let error = |field: &'static str, value: &str, reason: &str| RowError {
+        line,
+        field,
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+    let real = |field: &'static str, idx: Option<usize>| -> Result<Option<f64>, RowError> {
+        match idx.map(text).unwrap_or("") {
+            "" | "-" => Ok(None),
+            value => value.replace(',', "").parse::<f64>().map(Some).map_err(|_| error(field, value, "not a number")),
+        }
+    };
+
+    let index_name = text(cols.name).to_string();
+    if index_name.is_empty() {
+        return Err(error("index_name", "", "missing value"));
+    }
+    let date = match cols.date.map(text).filter(|s| !s.is_empty()) {
+        Some(value) => ["%d-%m-%Y", "%d-%b-%Y", "%Y-%m-%d"].iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+            .ok_or_else(|| error("date", value, "unrecognised date"))?,
+        None => fallback_date.ok_or_else(|| error("date", "", "missing value"))?,
+    };
+
+    Ok(IndexRow {
+        index_name,
+        date,
+        open: real("open", cols.open)?,
+        high: real("high", cols.high)?,
+        low: real("low", cols.low)?,
+        close: real("close", Some(cols.close))?.ok_or_else(|| error("close", "", "missing value"))?,
+        points_change: real("points_change", cols.points_change)?,
+        change_percent: real("change_percent", cols.change_percent)?,
+        // Some index volumes are written with decimals
+        volume: real("volume", cols.volume)?.map(|v| v.round() as i64),
+        turnover: real("turnover", cols.turnover)?.map(|v| v * 10_000_000.0),
+        pe: real("pe", cols.pe)?,
+        pb: real("pb", cols.pb)?,
+        div_yield: real("div_yield", cols.div_yield)?,
+    })
+}
+
+/// Store index closes in one transaction. Rows already present are counted as skipped.
+pub fn insert_index_rows(conn: &Connection, parsed: &ParsedIndices) -> rusqlite::Result<IngestStats> {
+    let mut stats = IngestStats {
+        exchange: Exchange::Nse,
+        rejected: parsed.errors.len(),
+        errors: parsed.errors.clone(),
+        ..IngestStats::default()
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO index_data (index_name, date, open, high, low, close, points_change, change_percent, volume, turnover, pe, pb, div_yield)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+        )?;
+        for row in &parsed.rows {
+            let ts = date_to_timestamp(row.date);
+            let inserted = stmt.execute(params![
+                row.index_name, ts, row.open, row.high, row.low, row.close, row.points_change,
+                row.change_percent, row.volume, row.turnover, row.pe, row.pb, row.div_yield,
+            ])?;
+            stats.inserted += inserted;
+            stats.skipped += 1 - inserted;
+        }
+    }
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Parse an index close file and store its rows
+pub fn ingest_index_close(conn: &Connection, data: &[u8], fallback_date: Option<NaiveDate>) -> Result<IngestStats, Box<dyn std::error::Error>> {
+    let parsed = parse_index_close(data, fallback_date)?;
+    Ok(insert_index_rows(conn, &parsed)?)
+}
+
+/// NSE trading days in bhavcopy_data with no index closes stored, newest first
+pub fn find_days_missing_index_data(conn: &Connection) -> rusqlite::Result<Vec<NaiveDate>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT b.date FROM bhavcopy_data b
+         WHERE b.exchange = 'NSE' AND b.series = 'EQ'
+           AND NOT EXISTS (SELECT 1 FROM index_data i WHERE i.date = b.date)
+         ORDER BY b.date DESC"
+    )?;
+    let rows = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+    let mut dates = Vec::new();
+    for ts in rows {
+        dates.extend(timestamp_to_date(ts?));
+    }
+    Ok(dates)
+}
+
+/// Indices with a close on the latest stored session, sorted by name
+pub fn get_index_names(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT index_name FROM index_data
+         WHERE date = (SELECT MAX(date) FROM index_data)
+         ORDER BY index_name"
+    )?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Daily bars for an index between two dates, oldest first. Sessions without an open, high
+/// or low use the close, and volume is zero where NSE reports none.
+pub fn get_index_bars_in_range(conn: &Connection, index_name: &str, start_date: NaiveDate, end_date: NaiveDate) -> rusqlite::Result<Vec<PriceBar>> {
+    let start_ts = date_to_timestamp(start_date);
+    let end_ts = end_date.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT date, COALESCE(open, close), COALESCE(high, close), COALESCE(low, close), close, COALESCE(volume, 0)
+         FROM index_data
+         WHERE index_name = ?1 AND date >= ?2 AND date <= ?3
+         ORDER BY date"
+    )?;
+    let rows = stmt.query_map(params![index_name, start_ts, end_ts], |row| {
+        Ok((row.get::<_, i64>(0)?, PriceBar {
+            date: NaiveDate::MIN,
+            open: row.get(1)?,
+            high: row.get(2)?,
+            low: row.get(3)?,
+            close: row.get(4)?,
+            volume: row.get(5)?,
+        }))
+    })?;
+
+    let mut bars = Vec::new();
+    for row in rows {
+        let (ts, mut bar) = row?;
+        if let Some(date) = timestamp_to_date(ts) {
+            bar.date = date;
+            bars.push(bar);
+        }
+    }
+    Ok(bars)
+}
+
+/// How a stock moved against an index over the sessions both traded
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexComparison {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Sessions both series have a close for
+    pub sessions: usize,
+    pub stock_return_pct: f64,
+    pub index_return_pct: f64,
+    /// Stock's growth over the index's growth, minus one: positive when the stock outperformed
+    pub relative_strength_pct: f64,
+    /// Sensitivity of the stock's daily returns to the index's
+    pub beta: Option<f64>,
+    /// Correlation of daily returns, from -1 to 1
+    pub correlation: Option<f64>,
+}
+
+/// Compare a stock's closes with an index's over the dates both have. Returns None when
+/// fewer than two sessions overlap. Beta and correlation need at least two daily returns.
+pub fn compare_with_index(stock: &[PriceBar], index: &[PriceBar]) -> Option<IndexComparison> {
+    let index_closes: HashMap<NaiveDate, f64> = index.iter().map(|bar| (bar.date, bar.close)).collect();
+    let paired: Vec<(NaiveDate, f64, f64)> = stock.iter()
+        .filter_map(|bar| Some((bar.date, bar.close, *index_closes.get(&bar.date)?)))
+        .filter(|(_, s, i)| *s > 0.0 && *i > 0.0)
+        .collect();
+    let (&(from, stock_first, index_first), &(to, stock_last, index_last)) = (paired.first()?, paired.last()?);
+    if paired.len() < 2 {
+        return None;
+    }
+
+    let stock_growth = stock_last / stock_first;
+    let index_growth = index_last / index_first;
+
+    let returns: Vec<(f64, f64)> = paired.windows(2)
+        .map(|w| (w[1].1 / w[0].1 - 1.0, w[1].2 / w[0].2 - 1.0))
+        .collect();
+    let (beta, correlation) = if returns.len() >= 2 {
+        let n = returns.len() as f64;
+        let mean_s = returns.iter().map(|(s, _)| s).sum::<f64>() / n;
+        let mean_i = returns.iter().map(|(_, i)| i).sum::<f64>() / n;
+        let covariance = returns.iter().map(|(s, i)| (s - mean_s) * (i - mean_i)).sum::<f64>() / (n - 1.0);
+        let var_s = returns.iter().map(|(s, _)| (s - mean_s).powi(2)).sum::<f64>() / (n - 1.0);
+        let var_i = returns.iter().map(|(_, i)| (i - mean_i).powi(2)).sum::<f64>() / (n - 1.0);
+        let beta = (var_i > 0.0).then(|| covariance / var_i);
+        let correlation = (var_s > 0.0 && var_i > 0.0).then(|| covariance / (var_s.sqrt() * var_i.sqrt()));
+        (beta, correlation)
+    } else {
+        (None, None)
+    };
+
+    Some(IndexComparison {
+        from,
+        to,
+        sessions: paired.len(),
+        stock_return_pct: (stock_growth - 1.0) * 100.0,
+        index_return_pct: (index_growth - 1.0) * 100.0,
+        relative_strength_pct: (stock_growth / index_growth - 1.0) * 100.0,
+        beta,
+        correlation,
+    })
+}
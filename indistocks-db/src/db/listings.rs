@@ -0,0 +1,95 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use crate::db::corporate_actions::get_adjustment_factors;
+use crate::db::operations::StockData;
+use crate::db::timestamp_to_date;
+use crate::models::Exchange;
+
+/// ISIN of a listing, taken from its latest BhavCopy row that carries one.
+/// BSE scrips fall back to the ISIN recorded in bse_symbols.
+pub fn get_listing_isin(conn: &Connection, exchange: Exchange, symbol: &str) -> rusqlite::Result<Option<String>> {
+    let isin: Option<String> = conn.query_row(
+        "SELECT isin FROM bhavcopy_data
+         WHERE symbol = ?1 AND exchange = ?2 AND isin IS NOT NULL
+         ORDER BY date DESC
+         LIMIT 1",
+        params![symbol, exchange.key()],
+        |row| row.get(0),
+    ).optional()?;
+
+    if isin.is_some() || exchange != Exchange::Bse {
+        return Ok(isin);
+    }
+    conn.query_row(
+        "SELECT isin FROM bse_symbols WHERE symbol = ?1",
+        params![symbol],
+        |row| row.get(0),
+    ).optional().map(Option::flatten)
+}
+
+/// Symbol of the same company on `target`, matched by ISIN. When the ISIN has traded under
+/// more than one symbol, the one seen most recently wins.
+pub fn find_linked_listing(conn: &Connection, exchange: Exchange, symbol: &str, target: Exchange) -> rusqlite::Result<Option<String>> {
+    if exchange == target {
+        return Ok(Some(symbol.to_string()));
+    }
+    let Some(isin) = get_listing_isin(conn, exchange, symbol)? else {
+        return Ok(None);
+    };
+
+    conn.query_row(
+        "SELECT symbol FROM bhavcopy_data
+         WHERE isin = ?1 AND exchange = ?2 AND series = 'EQ'
+         ORDER BY date DESC
+         LIMIT 1",
+        params![isin, target.key()],
+        |row| row.get(0),
+    ).optional()
+}
+
+/// Company name of a listing from the exchange's symbols table
+pub fn get_listing_name(conn: &Connection, exchange: Exchange, symbol: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        &format!("SELECT name FROM {} WHERE symbol = ?1", exchange.symbols_table()),
+        params![symbol],
+        |row| row.get(0),
+    ).optional().map(Option::flatten)
+}
+
+/// Adjustment factors for a listing. Corporate actions are stored against NSE symbols,
+/// so a BSE scrip uses those of its linked NSE listing, or none if it has no NSE listing.
+pub fn get_listing_adjustment_factors(conn: &Connection, exchange: Exchange, symbol: &str) -> rusqlite::Result<Vec<(NaiveDate, f64)>> {
+    match find_linked_listing(conn, exchange, symbol, Exchange::Nse)? {
+        Some(nse_symbol) => get_adjustment_factors(conn, &nse_symbol),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// First and last stored dates for one exchange
+pub fn get_exchange_date_range(conn: &Connection, exchange: Exchange) -> rusqlite::Result<Option<(NaiveDate, NaiveDate)>> {
+    let (min, max): (Option<i64>, Option<i64>) = conn.query_row(
+        "SELECT MIN(date), MAX(date) FROM bhavcopy_data WHERE exchange = ?1",
+        params![exchange.key()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(min.and_then(timestamp_to_date).zip(max.and_then(timestamp_to_date)))
+}
+
+/// Keep the stocks listed under one of the given NSE symbols. Watchlists and screens work on
+/// NSE symbols, so other exchanges' stocks are matched to them by ISIN.
+pub fn retain_nse_symbols(conn: &Connection, exchange: Exchange, stocks: &mut Vec<StockData>, symbols: &HashSet<String>) -> rusqlite::Result<()> {
+    if exchange == Exchange::Nse {
+        stocks.retain(|stock| symbols.contains(&stock.symbol));
+        return Ok(());
+    }
+
+    let mut isins = HashSet::new();
+    for symbol in symbols {
+        if let Some(isin) = get_listing_isin(conn, Exchange::Nse, symbol)? {
+            isins.insert(isin);
+        }
+    }
+    stocks.retain(|stock| stock.isin.as_ref().is_some_and(|isin| isins.contains(isin)));
+    Ok(())
+}
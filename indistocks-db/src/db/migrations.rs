@@ -0,0 +1,607 @@
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use log::info;
+
+/// A single schema change. Migration N in [`MIGRATIONS`] upgrades a database
+/// from `PRAGMA user_version` N-1 to N, so entries must only ever be appended.
+pub struct Migration {
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Initial schema",
+        // IF NOT EXISTS so databases created before versioning (user_version 0) upgrade cleanly
+        sql: "
+        CREATE TABLE IF NOT EXISTS nse_symbols (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL UNIQUE,
+            name TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS bse_symbols (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL UNIQUE,
+            name TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS recently_viewed (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol_id INTEGER NOT NULL,
+            viewed_at INTEGER NOT NULL,
+            FOREIGN KEY (symbol_id) REFERENCES nse_symbols(id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_recently_viewed_symbol_id
+        ON recently_viewed(symbol_id);
+
+        CREATE INDEX IF NOT EXISTS idx_recently_viewed_time
+        ON recently_viewed(viewed_at DESC);
+
+        CREATE TABLE IF NOT EXISTS nse_downloads (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT,
+            from_date INTEGER NOT NULL,
+            to_date INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER,
+            status TEXT NOT NULL,
+            error_message TEXT,
+            downloaded_at INTEGER NOT NULL,
+            UNIQUE(symbol, from_date, to_date)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_nse_downloads_downloaded_at
+        ON nse_downloads(downloaded_at DESC);
+
+        CREATE TABLE IF NOT EXISTS bhavcopy_data (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            series TEXT,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL,
+            last REAL,
+            prev_close REAL,
+            volume INTEGER,
+            turnover REAL,
+            trades INTEGER,
+            isin TEXT,
+            UNIQUE(symbol, date)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_bhavcopy_data_symbol_date
+        ON bhavcopy_data(symbol, date);
+        ",
+    },
+    Migration {
+        description: "Add download_type to nse_downloads",
+        sql: "
+        ALTER TABLE nse_downloads ADD COLUMN download_type TEXT;
+
+        UPDATE nse_downloads SET download_type = CASE
+            WHEN symbol IS NULL THEN 'bhavcopy'
+            ELSE 'historical'
+        END;
+        ",
+    },
+    Migration {
+        description: "Add corporate_actions",
+        sql: "
+        CREATE TABLE corporate_actions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            series TEXT,
+            ex_date INTEGER NOT NULL,
+            action_type TEXT NOT NULL,
+            purpose TEXT NOT NULL,
+            factor REAL,
+            amount REAL,
+            created_at INTEGER NOT NULL,
+            UNIQUE(symbol, ex_date, action_type, purpose)
+        );
+
+        CREATE INDEX idx_corporate_actions_symbol_ex_date
+        ON corporate_actions(symbol, ex_date);
+        ",
+    },
+    Migration {
+        description: "Add chart_indicators",
+        sql: "
+        CREATE TABLE chart_indicators (
+            symbol TEXT PRIMARY KEY,
+            indicators TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        ",
+    },
+    Migration {
+        description: "Add watchlists",
+        sql: "
+        CREATE TABLE watchlists (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE watchlist_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            watchlist_id INTEGER NOT NULL,
+            symbol TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            note TEXT,
+            added_at INTEGER NOT NULL,
+            FOREIGN KEY (watchlist_id) REFERENCES watchlists(id) ON DELETE CASCADE,
+            UNIQUE(watchlist_id, symbol)
+        );
+
+        CREATE INDEX idx_watchlist_items_watchlist_position
+        ON watchlist_items(watchlist_id, position);
+        ",
+    },
+    Migration {
+        description: "Add portfolio_transactions",
+        sql: "
+        CREATE TABLE portfolio_transactions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            side TEXT NOT NULL CHECK (side IN ('buy', 'sell')),
+            quantity INTEGER NOT NULL CHECK (quantity > 0),
+            price REAL NOT NULL,
+            charges REAL NOT NULL DEFAULT 0,
+            trade_date INTEGER NOT NULL,
+            note TEXT,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX idx_portfolio_transactions_symbol_date
+        ON portfolio_transactions(symbol, trade_date);
+        ",
+    },
+    Migration {
+        description: "Track broker trade IDs and broker symbol mappings for tradebook imports",
+        sql: "
+        ALTER TABLE portfolio_transactions ADD COLUMN broker TEXT;
+        ALTER TABLE portfolio_transactions ADD COLUMN trade_id TEXT;
+
+        CREATE UNIQUE INDEX idx_portfolio_transactions_trade
+        ON portfolio_transactions(broker, trade_id)
+        WHERE trade_id IS NOT NULL;
+
+        CREATE TABLE broker_symbol_map (
+            broker TEXT NOT NULL,
+            broker_symbol TEXT NOT NULL,
+            symbol TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (broker, broker_symbol)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_bhavcopy_data_isin
+        ON bhavcopy_data(isin);
+        ",
+    },
+    Migration {
+        description: "Add saved_screens",
+        sql: "
+        CREATE TABLE saved_screens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            expression TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        ",
+    },
+    Migration {
+        description: "Add app_settings",
+        sql: "
+        CREATE TABLE app_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        ",
+    },
+    Migration {
+        description: "Add download_jobs",
+        sql: "
+        CREATE TABLE download_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            from_date INTEGER,
+            to_date INTEGER,
+            status TEXT NOT NULL,
+            days_total INTEGER NOT NULL DEFAULT 0,
+            days_done INTEGER NOT NULL DEFAULT 0,
+            files INTEGER NOT NULL DEFAULT 0,
+            rows_inserted INTEGER NOT NULL DEFAULT 0,
+            error_message TEXT,
+            created_at INTEGER NOT NULL,
+            started_at INTEGER,
+            finished_at INTEGER
+        );
+        ",
+    },
+    Migration {
+        description: "Add market_holidays",
+        sql: "
+        CREATE TABLE market_holidays (
+            date INTEGER PRIMARY KEY,
+            description TEXT
+        );
+        ",
+    },
+    Migration {
+        description: "Index bhavcopy_data by date",
+        sql: "
+        CREATE INDEX idx_bhavcopy_data_date_series
+        ON bhavcopy_data(date, series);
+        ",
+    },
+    Migration {
+        description: "Add alert rules and notifications",
+        sql: "
+        CREATE TABLE alert_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            value REAL NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE alert_notifications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule_id INTEGER,
+            symbol TEXT NOT NULL,
+            date INTEGER NOT NULL,
+            message TEXT NOT NULL,
+            is_read INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            UNIQUE(rule_id, date),
+            FOREIGN KEY (rule_id) REFERENCES alert_rules(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX idx_alert_notifications_created_at
+        ON alert_notifications(created_at DESC);
+        ",
+    },
+    Migration {
+        description: "Add an exchange column to bhavcopy_data and ISINs to bse_symbols",
+        // SQLite can't change a UNIQUE constraint in place, so the table is rebuilt
+        sql: "
+        CREATE TABLE bhavcopy_data_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            series TEXT,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL,
+            last REAL,
+            prev_close REAL,
+            volume INTEGER,
+            turnover REAL,
+            trades INTEGER,
+            isin TEXT,
+            exchange TEXT NOT NULL DEFAULT 'NSE',
+            UNIQUE(symbol, exchange, date)
+        );
+
+        INSERT INTO bhavcopy_data_new (id, symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin)
+        SELECT id, symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin
+        FROM bhavcopy_data;
+
+        DROP TABLE bhavcopy_data;
+        ALTER TABLE bhavcopy_data_new RENAME TO bhavcopy_data;
+
+        CREATE INDEX idx_bhavcopy_data_symbol_date
+        ON bhavcopy_data(symbol, date);
+
+        CREATE INDEX idx_bhavcopy_data_isin
+        ON bhavcopy_data(isin);
+
+        CREATE INDEX idx_bhavcopy_data_date_series
+        ON bhavcopy_data(date, series);
+
+        ALTER TABLE bse_symbols ADD COLUMN isin TEXT;
+
+        CREATE INDEX idx_bse_symbols_isin
+        ON bse_symbols(isin);
+        ",
+    },
+    Migration {
+        description: "Add delivery_data",
+        // NSE security-wise delivery positions, from sec_bhavdata_full or MTO files
+        sql: "
+        CREATE TABLE delivery_data (
+            symbol TEXT NOT NULL,
+            series TEXT NOT NULL,
+            date INTEGER NOT NULL,
+            traded_qty INTEGER,
+            deliverable_qty INTEGER NOT NULL,
+            delivery_percent REAL,
+            PRIMARY KEY (symbol, series, date)
+        );
+
+        CREATE INDEX idx_delivery_data_date
+        ON delivery_data(date);
+        ",
+    },
+    Migration {
+        description: "Add futures_data and options_data",
+        // NSE F&O BhavCopy contracts; futures have no strike or option type
+        sql: "
+        CREATE TABLE futures_data (
+            symbol TEXT NOT NULL,
+            instrument TEXT NOT NULL,
+            expiry INTEGER NOT NULL,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL,
+            settle_price REAL,
+            underlying REAL,
+            volume INTEGER,
+            turnover REAL,
+            open_interest INTEGER,
+            change_in_oi INTEGER,
+            UNIQUE(symbol, expiry, date)
+        );
+
+        CREATE INDEX idx_futures_data_date
+        ON futures_data(date);
+
+        CREATE TABLE options_data (
+            symbol TEXT NOT NULL,
+            instrument TEXT NOT NULL,
+            expiry INTEGER NOT NULL,
+            strike REAL NOT NULL,
+            option_type TEXT NOT NULL,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL,
+            settle_price REAL,
+            underlying REAL,
+            volume INTEGER,
+            turnover REAL,
+            open_interest INTEGER,
+            change_in_oi INTEGER,
+            UNIQUE(symbol, date, expiry, strike, option_type)
+        );
+
+        CREATE INDEX idx_options_data_date
+        ON options_data(date);
+        ",
+    },
+    Migration {
+        description: "Add index_data",
+        // NSE index closes from ind_close_all files; names are matched without regard to case
+        sql: "
+        CREATE TABLE index_data (
+            index_name TEXT NOT NULL COLLATE NOCASE,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL NOT NULL,
+            points_change REAL,
+            change_percent REAL,
+            volume INTEGER,
+            turnover REAL,
+            pe REAL,
+            pb REAL,
+            div_yield REAL,
+            PRIMARY KEY (index_name, date)
+        );
+
+        CREATE INDEX idx_index_data_date
+        ON index_data(date);
+        ",
+    },
+    Migration {
+        description: "Add an indicator period to alert_rules",
+        sql: "
+        ALTER TABLE alert_rules ADD COLUMN period INTEGER;
+        ",
+    },
+];
+
+/// The schema version this build of Indistocks writes
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+pub fn get_schema_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Bring the database up to [`latest_schema_version`], applying each pending migration in its own transaction.
+/// When `db_path` is given and the database already has tables, a copy is written next to it first.
+/// Returns the path of that backup, if one was taken.
+pub fn run_migrations(conn: &Connection, db_path: Option<&Path>) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let current = get_schema_version(conn)?;
+    let latest = latest_schema_version();
+
+    if current > latest {
+        return Err(format!(
+            "Database schema version {} is newer than this version of Indistocks supports ({}). \
+             Please upgrade Indistocks to open this database.",
+            current, latest
+        ).into());
+    }
+
+    if current == latest {
+        return Ok(None);
+    }
+
+    let backup_path = match db_path {
+        Some(path) if has_tables(conn)? => Some(backup_database(conn, path, current)?),
+        _ => None,
+    };
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as i64 + 1;
+        info!("Applying database migration {}: {}", version, migration.description);
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sql)
+            .map_err(|e| format!("Migration {} ({}) failed: {}", version, migration.description, e))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok(backup_path)
+}
+
+fn has_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Write a consistent copy of the database to `<db file>.v<version>.bak`, replacing an older backup of the same version
+fn backup_database(conn: &Connection, db_path: &Path, version: i64) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file_name = db_path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "db.sqlite3".to_string());
+    let backup_path = db_path.with_file_name(format!("{}.v{}.bak", file_name, version));
+
+    if backup_path.exists() {
+        std::fs::remove_file(&backup_path)?;
+    }
+
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy().to_string()])?;
+    info!("Backed up database to {}", backup_path.display());
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TempDir;
+
+    /// Tables as created by releases before schema versioning, which left user_version at 0
+    const BASELINE_SCHEMA: &str = "
+        CREATE TABLE nse_symbols (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL UNIQUE,
+            name TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE nse_downloads (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT,
+            from_date INTEGER NOT NULL,
+            to_date INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER,
+            status TEXT NOT NULL,
+            error_message TEXT,
+            downloaded_at INTEGER NOT NULL,
+            UNIQUE(symbol, from_date, to_date)
+        );
+        CREATE TABLE bhavcopy_data (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            series TEXT,
+            date INTEGER NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            close REAL,
+            last REAL,
+            prev_close REAL,
+            volume INTEGER,
+            turnover REAL,
+            trades INTEGER,
+            isin TEXT,
+            UNIQUE(symbol, date)
+        );
+        INSERT INTO nse_symbols (symbol, name, created_at, updated_at) VALUES ('RELIANCE', 'Reliance Industries', 0, 0);
+        INSERT INTO bhavcopy_data (symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin)
+        VALUES ('RELIANCE', 'EQ', 1719792000, 3130.0, 3150.0, 3120.0, 3140.0, 3140.0, 3120.0, 500000, 1.5e9, 20000, 'INE002A01018');
+    ";
+
+    #[test]
+    fn fresh_database_reaches_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), 0);
+
+        let backup = run_migrations(&conn, None).unwrap();
+        assert!(backup.is_none());
+        assert_eq!(get_schema_version(&conn).unwrap(), latest_schema_version());
+
+        // Running again is a no-op
+        assert!(run_migrations(&conn, None).unwrap().is_none());
+        assert_eq!(get_schema_version(&conn).unwrap(), latest_schema_version());
+    }
+
+    #[test]
+    fn baseline_database_keeps_its_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), latest_schema_version());
+
+        let (symbol, close, exchange): (String, f64, String) = conn.query_row(
+            "SELECT symbol, close, exchange FROM bhavcopy_data",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!((symbol.as_str(), close, exchange.as_str()), ("RELIANCE", 3140.0, "NSE"));
+
+        let name: Option<String> = conn.query_row("SELECT name FROM nse_symbols WHERE symbol = 'RELIANCE'", [], |row| row.get(0)).unwrap();
+        assert_eq!(name.as_deref(), Some("Reliance Industries"));
+    }
+
+    #[test]
+    fn newer_database_is_rejected() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_schema_version() + 1).unwrap();
+
+        let err = run_migrations(&conn, None).unwrap_err();
+        assert!(err.to_string().contains("newer"), "unexpected error: {}", err);
+        assert_eq!(get_schema_version(&conn).unwrap(), latest_schema_version() + 1);
+    }
+
+    #[test]
+    fn existing_database_is_backed_up_before_upgrading() {
+        let dir = TempDir::new("migrations-backup");
+        let path = dir.path().join("db.sqlite3");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+
+        let backup = run_migrations(&conn, Some(&path)).unwrap().expect("a backup should be taken");
+        assert_eq!(backup, path.with_file_name("db.sqlite3.v0.bak"));
+
+        // The backup holds the database as it was before any migration ran
+        let copy = Connection::open(&backup).unwrap();
+        assert_eq!(get_schema_version(&copy).unwrap(), 0);
+        let rows: i64 = copy.query_row("SELECT COUNT(*) FROM bhavcopy_data", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+        let has_exchange: bool = copy.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('bhavcopy_data') WHERE name = 'exchange')",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert!(!has_exchange);
+    }
+}
@@ -1,7 +1,60 @@
+use chrono::NaiveDate;
+
 pub mod schema;
+pub mod migrations;
 pub mod operations;
 pub mod downloads;
+pub mod download_jobs;
+pub mod import;
+pub mod bhavcopy;
+pub mod source;
+pub mod corporate_actions;
+pub mod portfolio;
+pub mod tax;
+pub mod tradebook;
+pub mod screens;
+pub mod calendar;
+pub mod breadth;
+pub mod alerts;
+pub mod listings;
+pub mod delivery;
+pub mod derivatives;
+pub mod indices;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 pub use schema::*;
+pub use migrations::*;
 pub use operations::*;
 pub use downloads::*;
+pub use download_jobs::*;
+pub use import::*;
+pub use bhavcopy::*;
+pub use source::*;
+pub use corporate_actions::*;
+pub use portfolio::*;
+pub use tax::*;
+pub use tradebook::*;
+pub use screens::*;
+pub use calendar::*;
+pub use breadth::*;
+pub use alerts::*;
+pub use listings::*;
+pub use delivery::*;
+pub use derivatives::*;
+pub use indices::*;
+
+/// Dates are stored as the timestamp of midnight UTC
+pub(crate) fn date_to_timestamp(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp()
+}
+
+pub(crate) fn timestamp_to_date(ts: i64) -> Option<NaiveDate> {
+    chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.naive_utc().date())
+}
+
+/// Read a stored date column, failing the row instead of panicking on a timestamp out of range
+pub(crate) fn date_column(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<NaiveDate> {
+    let ts: i64 = row.get(idx)?;
+    timestamp_to_date(ts).ok_or(rusqlite::Error::IntegralValueOutOfRange(idx, ts))
+}
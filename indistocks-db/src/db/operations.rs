@@ -1,9 +1,15 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, OptionalExtension, Result, params};
 use chrono::{Utc, Datelike};
-use crate::models::NseDownload;
+use crate::models::{BhavCopyRow, Exchange, NseDownload, PriceBar};
+use crate::indicators::Indicator;
+use crate::db::corporate_actions::{get_adjustment_factors, cumulative_adjustment};
+use crate::db::listings::{get_listing_adjustment_factors, get_listing_isin};
+use crate::db::{date_column, date_to_timestamp, timestamp_to_date};
 use std::fs;
 use std::path::PathBuf;
 use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use log::info;
 
 #[derive(Debug, Clone)]
 pub struct NseSymbol {
@@ -18,6 +24,20 @@ pub struct RecentlyViewed {
     pub name: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchlist {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchlistItem {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub position: i64,
+    pub note: Option<String>,
+}
+
 pub fn save_nse_symbols(conn: &Connection, symbols: Vec<String>) -> Result<(usize, Vec<String>)> {
     let now = Utc::now().timestamp();
     let mut saved_count = 0;
@@ -180,14 +200,14 @@ fn scan_dir_for_missing_records(conn: &Connection, dir: &std::path::Path, existi
                             };
 
                             let from_ts = chrono::NaiveDate::parse_from_str(&from_str, "%d-%m-%Y")
-                                .map(|d| d.and_hms_opt(0,0,0).unwrap().and_utc().timestamp())
+                                .map(date_to_timestamp)
                                 .unwrap_or(0);
                             let to_ts = chrono::NaiveDate::parse_from_str(&to_str, "%d-%m-%Y")
-                                .map(|d| d.and_hms_opt(0,0,0).unwrap().and_utc().timestamp())
+                                .map(date_to_timestamp)
                                 .unwrap_or(0);
                             let file_size = path.metadata().ok().map(|m| m.len() as i64);
 
-                            println!("Adding missing record for file: {}", path_str);
+                            info!("Adding missing download record for {}", path_str);
                             conn.execute(
                                 "INSERT INTO nse_downloads (download_type, symbol, from_date, to_date, file_path, file_size, status, downloaded_at)
                                  VALUES (?, ?, ?, ?, ?, ?, 'completed', ?)",
@@ -239,6 +259,134 @@ pub fn record_recently_viewed(conn: &Connection, symbol: &str) -> Result<()> {
     Ok(())
 }
 
+pub fn create_watchlist(conn: &Connection, name: &str) -> Result<i64> {
+    let now = Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO watchlists (name, created_at, updated_at) VALUES (?1, ?2, ?2)",
+        params![name.trim(), now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn rename_watchlist(conn: &Connection, watchlist_id: i64, name: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE watchlists SET name = ?1, updated_at = ?2 WHERE id = ?3",
+        params![name.trim(), Utc::now().timestamp(), watchlist_id],
+    )?;
+    Ok(())
+}
+
+/// Delete a watchlist together with its symbols
+pub fn delete_watchlist(conn: &Connection, watchlist_id: i64) -> Result<()> {
+    conn.execute("DELETE FROM watchlists WHERE id = ?1", params![watchlist_id])?;
+    Ok(())
+}
+
+pub fn get_watchlists(conn: &Connection) -> Result<Vec<Watchlist>> {
+    let mut stmt = conn.prepare("SELECT id, name FROM watchlists ORDER BY name COLLATE NOCASE")?;
+    let lists = stmt.query_map([], |row| {
+        Ok(Watchlist {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    })?;
+    lists.collect()
+}
+
+/// Symbols in a watchlist, in the user's order
+pub fn get_watchlist_items(conn: &Connection, watchlist_id: i64) -> Result<Vec<WatchlistItem>> {
+    let mut stmt = conn.prepare(
+        "SELECT wi.symbol, ns.name, wi.position, wi.note
+         FROM watchlist_items wi
+         LEFT JOIN nse_symbols ns ON ns.symbol = wi.symbol
+         WHERE wi.watchlist_id = ?1
+         ORDER BY wi.position"
+    )?;
+    let items = stmt.query_map(params![watchlist_id], |row| {
+        Ok(WatchlistItem {
+            symbol: row.get(0)?,
+            name: row.get(1)?,
+            position: row.get(2)?,
+            note: row.get(3)?,
+        })
+    })?;
+    items.collect()
+}
+
+/// Append a symbol to the end of a watchlist. Returns false if it was already there.
+pub fn add_to_watchlist(conn: &Connection, watchlist_id: i64, symbol: &str) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO watchlist_items (watchlist_id, symbol, position, added_at)
+         VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM watchlist_items WHERE watchlist_id = ?1), ?3)",
+        params![watchlist_id, symbol, Utc::now().timestamp()],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn remove_from_watchlist(conn: &Connection, watchlist_id: i64, symbol: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM watchlist_items WHERE watchlist_id = ?1 AND symbol = ?2",
+        params![watchlist_id, symbol],
+    )?;
+    Ok(())
+}
+
+/// Move a symbol to a new index within its watchlist, shifting the others
+pub fn move_watchlist_item(conn: &Connection, watchlist_id: i64, symbol: &str, new_index: usize) -> Result<()> {
+    let mut symbols: Vec<String> = get_watchlist_items(conn, watchlist_id)?
+        .into_iter()
+        .map(|item| item.symbol)
+        .collect();
+    let Some(current) = symbols.iter().position(|s| s == symbol) else {
+        return Ok(());
+    };
+    let moved = symbols.remove(current);
+    symbols.insert(new_index.min(symbols.len()), moved);
+
+    let tx = conn.unchecked_transaction()?;
+    for (position, symbol) in symbols.iter().enumerate() {
+        tx.execute(
+            "UPDATE watchlist_items SET position = ?1 WHERE watchlist_id = ?2 AND symbol = ?3",
+            params![position as i64, watchlist_id, symbol],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn set_watchlist_note(conn: &Connection, watchlist_id: i64, symbol: &str, note: Option<&str>) -> Result<()> {
+    let note = note.map(str::trim).filter(|n| !n.is_empty());
+    conn.execute(
+        "UPDATE watchlist_items SET note = ?1 WHERE watchlist_id = ?2 AND symbol = ?3",
+        params![note, watchlist_id, symbol],
+    )?;
+    Ok(())
+}
+
+/// Chart indicators last used for a symbol, empty if none were saved
+pub fn get_chart_indicators(conn: &Connection, symbol: &str) -> Result<Vec<Indicator>> {
+    let json: Option<String> = conn.query_row(
+        "SELECT indicators FROM chart_indicators WHERE symbol = ?1",
+        params![symbol],
+        |row| row.get(0),
+    ).optional()?;
+
+    // A layout written by a newer version that we can't read is treated as no layout
+    Ok(json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+}
+
+/// Remember the chart indicators for a symbol
+pub fn save_chart_indicators(conn: &Connection, symbol: &str, indicators: &[Indicator]) -> Result<()> {
+    let json = serde_json::to_string(indicators)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO chart_indicators (symbol, indicators, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(symbol) DO UPDATE SET indicators = excluded.indicators, updated_at = excluded.updated_at",
+        params![symbol, json, Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
 pub fn get_recently_viewed(conn: &Connection, limit: usize) -> Result<Vec<RecentlyViewed>> {
     let mut stmt = conn.prepare(
         "SELECT ns.symbol, ns.name
@@ -364,7 +512,7 @@ pub fn get_date_directory_path(date: chrono::NaiveDate) -> PathBuf {
     month_dir
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct StockData {
     pub symbol: String,
     pub name: Option<String>,
@@ -373,6 +521,182 @@ pub struct StockData {
     pub volume: i64,
     pub range_low: f64,
     pub range_high: f64,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub prev_close: Option<f64>,
+    pub turnover: Option<f64>,
+    pub trades: Option<i64>,
+    pub isin: Option<String>,
+    pub series: String,
+    pub week52_high: Option<f64>,
+    /// From NSE delivery positions, when stored for the latest session
+    pub deliverable_qty: Option<i64>,
+    pub delivery_percent: Option<f64>,
+}
+
+impl StockData {
+    /// Percentage below (negative) the 52-week high
+    pub fn from_52w_high_pct(&self) -> Option<f64> {
+        self.week52_high.filter(|h| *h > 0.0).map(|h| (self.ltp - h) / h * 100.0)
+    }
+}
+
+/// Columns of the Stocks grid, in the order the column chooser lists them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StockColumn {
+    Symbol,
+    Name,
+    Ltp,
+    ChangePercent,
+    Volume,
+    RangeLow,
+    RangeHigh,
+    Open,
+    High,
+    Low,
+    PrevClose,
+    Turnover,
+    Trades,
+    Isin,
+    Series,
+    From52wHigh,
+    DeliveryQty,
+    DeliveryPercent,
+}
+
+impl StockColumn {
+    pub const ALL: [StockColumn; 18] = [
+        StockColumn::Symbol,
+        StockColumn::Name,
+        StockColumn::Ltp,
+        StockColumn::ChangePercent,
+        StockColumn::Volume,
+        StockColumn::RangeLow,
+        StockColumn::RangeHigh,
+        StockColumn::Open,
+        StockColumn::High,
+        StockColumn::Low,
+        StockColumn::PrevClose,
+        StockColumn::Turnover,
+        StockColumn::Trades,
+        StockColumn::Isin,
+        StockColumn::Series,
+        StockColumn::From52wHigh,
+        StockColumn::DeliveryQty,
+        StockColumn::DeliveryPercent,
+    ];
+
+    /// The columns shown before the grid was configurable
+    pub fn defaults() -> Vec<StockColumn> {
+        Self::ALL[..7].to_vec()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StockColumn::Symbol => "Symbol",
+            StockColumn::Name => "Name",
+            StockColumn::Ltp => "LTP",
+            StockColumn::ChangePercent => "% Change",
+            StockColumn::Volume => "Volume",
+            StockColumn::RangeLow => "Range Low",
+            StockColumn::RangeHigh => "Range High",
+            StockColumn::Open => "Open",
+            StockColumn::High => "High",
+            StockColumn::Low => "Low",
+            StockColumn::PrevClose => "Prev Close",
+            StockColumn::Turnover => "Turnover",
+            StockColumn::Trades => "Trades",
+            StockColumn::Isin => "ISIN",
+            StockColumn::Series => "Series",
+            StockColumn::From52wHigh => "From 52W High",
+            StockColumn::DeliveryQty => "Delivery Qty",
+            StockColumn::DeliveryPercent => "Delivery %",
+        }
+    }
+
+    /// Name used on the command line, matching the field names in CSV/JSON output
+    pub fn key(&self) -> &'static str {
+        match self {
+            StockColumn::Symbol => "symbol",
+            StockColumn::Name => "name",
+            StockColumn::Ltp => "ltp",
+            StockColumn::ChangePercent => "change_percent",
+            StockColumn::Volume => "volume",
+            StockColumn::RangeLow => "range_low",
+            StockColumn::RangeHigh => "range_high",
+            StockColumn::Open => "open",
+            StockColumn::High => "high",
+            StockColumn::Low => "low",
+            StockColumn::PrevClose => "prev_close",
+            StockColumn::Turnover => "turnover",
+            StockColumn::Trades => "trades",
+            StockColumn::Isin => "isin",
+            StockColumn::Series => "series",
+            StockColumn::From52wHigh => "from_52w_high",
+            StockColumn::DeliveryQty => "deliverable_qty",
+            StockColumn::DeliveryPercent => "delivery_percent",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        let key = key.trim().to_lowercase();
+        Self::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    /// Text columns sort A-Z first; numbers sort largest first
+    pub fn is_text(&self) -> bool {
+        matches!(self, StockColumn::Symbol | StockColumn::Name | StockColumn::Isin | StockColumn::Series)
+    }
+
+    /// SQL expression in [`get_all_stocks_with_metrics`] to order by
+    fn order_by(&self) -> &'static str {
+        match self {
+            StockColumn::Symbol => "ns.symbol",
+            StockColumn::Name => "ns.name COLLATE NOCASE",
+            StockColumn::Ltp => "latest.close",
+            StockColumn::ChangePercent => "change_percent",
+            StockColumn::Volume => "latest.volume",
+            StockColumn::RangeLow => "range_stats.range_low",
+            StockColumn::RangeHigh => "range_stats.range_high",
+            StockColumn::Open => "latest.open",
+            StockColumn::High => "latest.high",
+            StockColumn::Low => "latest.low",
+            StockColumn::PrevClose => "latest.prev_close",
+            StockColumn::Turnover => "latest.turnover",
+            StockColumn::Trades => "latest.trades",
+            StockColumn::Isin => "latest.isin",
+            StockColumn::Series => "latest.series",
+            StockColumn::From52wHigh => "from_52w_high",
+            StockColumn::DeliveryQty => "delivery.deliverable_qty",
+            StockColumn::DeliveryPercent => "delivery.delivery_percent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StockSort {
+    pub column: StockColumn,
+    pub descending: bool,
+}
+
+impl Default for StockSort {
+    fn default() -> Self {
+        StockSort { column: StockColumn::Symbol, descending: false }
+    }
+}
+
+/// Visible columns and sort order of the Stocks grid, kept between sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StocksGridLayout {
+    pub columns: Vec<StockColumn>,
+    pub sort: StockSort,
+}
+
+impl Default for StocksGridLayout {
+    fn default() -> Self {
+        StocksGridLayout { columns: StockColumn::defaults(), sort: StockSort::default() }
+    }
 }
 
 /// Get stock price data for a specific symbol within a date range
@@ -383,47 +707,251 @@ pub fn get_stock_data_in_range(
     start_date: chrono::NaiveDate,
     end_date: chrono::NaiveDate
 ) -> Result<Vec<(chrono::NaiveDate, f64)>> {
-    let start_ts = start_date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
-    let end_ts = end_date.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
+    let start_ts = date_to_timestamp(start_date);
+    let end_ts = date_to_timestamp(end_date);
 
     let mut stmt = conn.prepare(
         "SELECT date, close FROM bhavcopy_data
-         WHERE symbol = ? AND series = 'EQ'
+         WHERE symbol = ? AND series = 'EQ' AND exchange = 'NSE'
          AND date >= ? AND date <= ?
          ORDER BY date"
     )?;
 
     let rows = stmt.query_map(params![symbol, start_ts, end_ts], |row| {
-        let ts: i64 = row.get(0)?;
-        let date = chrono::DateTime::from_timestamp(ts, 0)
-            .unwrap()
-            .naive_utc()
-            .date();
-        let close: f64 = row.get(1)?;
-        Ok((date, close))
+        Ok((date_column(row, 0)?, row.get(1)?))
     })?;
 
     rows.collect()
 }
 
-pub fn get_all_stocks_with_metrics(conn: &Connection, price_from: Option<f64>, price_to: Option<f64>, range_days: i64) -> Result<Vec<StockData>> {
-    // Get the latest date we have data for
-    let latest_date: Option<i64> = conn.query_row(
-        "SELECT MAX(date) FROM bhavcopy_data WHERE series = 'EQ'",
-        [],
+/// Same as [`get_stock_data_in_range`], with closes adjusted for splits, bonuses and dividends
+pub fn get_adjusted_stock_data_in_range(
+    conn: &Connection,
+    symbol: &str,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate
+) -> Result<Vec<(chrono::NaiveDate, f64)>> {
+    let factors = get_adjustment_factors(conn, symbol)?;
+    let data = get_stock_data_in_range(conn, symbol, start_date, end_date)?;
+
+    Ok(data.into_iter()
+        .map(|(date, close)| (date, close * cumulative_adjustment(&factors, date)))
+        .collect())
+}
+
+/// Get daily OHLCV bars for a symbol on one exchange within a date range, ordered by date.
+/// Missing open/high/low values (very old files) fall back to the close.
+pub fn get_price_bars_in_range(
+    conn: &Connection,
+    exchange: Exchange,
+    symbol: &str,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate
+) -> Result<Vec<PriceBar>> {
+    let start_ts = date_to_timestamp(start_date);
+    let end_ts = date_to_timestamp(end_date);
+
+    let mut stmt = conn.prepare(
+        "SELECT date, COALESCE(open, close), COALESCE(high, close), COALESCE(low, close), close, COALESCE(volume, 0)
+         FROM bhavcopy_data
+         WHERE symbol = ? AND exchange = ? AND series = 'EQ'
+         AND date >= ? AND date <= ?
+         ORDER BY date"
+    )?;
+
+    let rows = stmt.query_map(params![symbol, exchange.key(), start_ts, end_ts], |row| {
+        Ok(PriceBar {
+            date: date_column(row, 0)?,
+            open: row.get(1)?,
+            high: row.get(2)?,
+            low: row.get(3)?,
+            close: row.get(4)?,
+            volume: row.get(5)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Same as [`get_price_bars_in_range`], with prices adjusted for splits, bonuses and dividends.
+/// Volumes are left as traded.
+pub fn get_adjusted_price_bars_in_range(
+    conn: &Connection,
+    exchange: Exchange,
+    symbol: &str,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate
+) -> Result<Vec<PriceBar>> {
+    let factors = get_listing_adjustment_factors(conn, exchange, symbol)?;
+    let bars = get_price_bars_in_range(conn, exchange, symbol, start_date, end_date)?;
+
+    Ok(bars.into_iter()
+        .map(|bar| {
+            let adjustment = cumulative_adjustment(&factors, bar.date);
+            PriceBar {
+                open: bar.open * adjustment,
+                high: bar.high * adjustment,
+                low: bar.low * adjustment,
+                close: bar.close * adjustment,
+                ..bar
+            }
+        })
+        .collect())
+}
+
+/// Latest EQ session for one symbol with its 52-week range
+#[derive(Debug, Clone, Serialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub exchange: Exchange,
+    pub name: Option<String>,
+    pub date: chrono::NaiveDate,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: f64,
+    pub prev_close: Option<f64>,
+    pub change_percent: Option<f64>,
+    pub volume: Option<i64>,
+    pub turnover: Option<f64>,
+    pub trades: Option<i64>,
+    pub week52_low: Option<f64>,
+    pub week52_high: Option<f64>,
+}
+
+/// Latest quote for a symbol on one exchange, or `None` if there is no EQ data for it
+pub fn get_quote(conn: &Connection, exchange: Exchange, symbol: &str) -> Result<Option<Quote>> {
+    let quote = conn.query_row(
+        &format!(
+            "SELECT b.symbol, s.name, b.date, b.open, b.high, b.low, b.close, b.prev_close,
+                    b.volume, b.turnover, b.trades
+             FROM bhavcopy_data b
+             LEFT JOIN {} s ON s.symbol = b.symbol
+             WHERE b.symbol = ?1 AND b.exchange = ?2 AND b.series = 'EQ'
+             ORDER BY b.date DESC
+             LIMIT 1",
+            exchange.symbols_table()
+        ),
+        params![symbol, exchange.key()],
+        |row| {
+            let close: f64 = row.get(6)?;
+            let prev_close: Option<f64> = row.get(7)?;
+            Ok(Quote {
+                symbol: row.get(0)?,
+                exchange,
+                name: row.get(1)?,
+                date: date_column(row, 2)?,
+                open: row.get(3)?,
+                high: row.get(4)?,
+                low: row.get(5)?,
+                close,
+                prev_close,
+                change_percent: prev_close.filter(|p| *p > 0.0).map(|p| (close - p) / p * 100.0),
+                volume: row.get(8)?,
+                turnover: row.get(9)?,
+                trades: row.get(10)?,
+                week52_low: None,
+                week52_high: None,
+            })
+        },
+    ).optional()?;
+
+    let Some(mut quote) = quote else {
+        return Ok(None);
+    };
+
+    let latest_ts = date_to_timestamp(quote.date);
+    let (low, high) = conn.query_row(
+        "SELECT MIN(low), MAX(high) FROM bhavcopy_data
+         WHERE symbol = ?1 AND exchange = ?2 AND series = 'EQ' AND date > ?3 AND date <= ?4",
+        params![symbol, exchange.key(), latest_ts - 365 * 24 * 60 * 60, latest_ts],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    quote.week52_low = low;
+    quote.week52_high = high;
+
+    Ok(Some(quote))
+}
+
+/// Visit every stored BhavCopy row between two dates (inclusive), ordered by date and symbol,
+/// optionally limited to one exchange and one symbol. Rows are streamed so large exports don't
+/// have to fit in memory. Returns the number of rows visited.
+pub fn for_each_bhavcopy_row<F>(
+    conn: &Connection,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+    exchange: Option<Exchange>,
+    symbol: Option<&str>,
+    mut f: F,
+) -> std::result::Result<usize, Box<dyn std::error::Error>>
+where
+    F: FnMut(BhavCopyRow) -> std::result::Result<(), Box<dyn std::error::Error>>,
+{
+    let start_ts = date_to_timestamp(start_date);
+    let end_ts = date_to_timestamp(end_date);
+
+    let mut stmt = conn.prepare(
+        "SELECT symbol, series, date, open, high, low, close, last, prev_close, volume, turnover, trades, isin, exchange
+         FROM bhavcopy_data
+         WHERE date >= ?1 AND date <= ?2 AND (?3 IS NULL OR symbol = ?3) AND (?4 IS NULL OR exchange = ?4)
+         ORDER BY date, exchange DESC, symbol, series"
+    )?;
+
+    let rows = stmt.query_map(params![start_ts, end_ts, symbol, exchange.map(|e| e.key())], |row| {
+        let close: f64 = row.get(6)?;
+        Ok(BhavCopyRow {
+            symbol: row.get(0)?,
+            series: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            date: date_column(row, 2)?,
+            open: row.get::<_, Option<f64>>(3)?.unwrap_or(close),
+            high: row.get::<_, Option<f64>>(4)?.unwrap_or(close),
+            low: row.get::<_, Option<f64>>(5)?.unwrap_or(close),
+            close,
+            last: row.get(7)?,
+            prev_close: row.get::<_, Option<f64>>(8)?.unwrap_or_default(),
+            volume: row.get::<_, Option<i64>>(9)?.unwrap_or_default(),
+            turnover: row.get::<_, Option<f64>>(10)?.unwrap_or_default(),
+            trades: row.get(11)?,
+            isin: row.get(12)?,
+            exchange: Exchange::parse(&row.get::<_, String>(13)?).unwrap_or_default(),
+            name: None,
+            deliverable_qty: None,
+            delivery_percent: None,
+        })
+    })?;
+
+    let mut count = 0;
+    for row in rows {
+        f(row?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Latest date (as a timestamp) with EQ data for an exchange in bhavcopy_data
+fn latest_eq_date(conn: &Connection, exchange: Exchange) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT MAX(date) FROM bhavcopy_data WHERE series = 'EQ' AND exchange = ?1",
+        params![exchange.key()],
         |row| row.get(0)
-    ).ok();
+    )
+}
 
-    let latest_date = match latest_date {
+/// Every EQ stock of an exchange in its latest session with its low/high over `range_days`,
+/// filtered by latest close and ordered by `sort` in SQL (missing values last, ties by symbol)
+pub fn get_all_stocks_with_metrics(conn: &Connection, exchange: Exchange, price_from: Option<f64>, price_to: Option<f64>, range_days: i64, sort: StockSort) -> Result<Vec<StockData>> {
+    // Get the latest date we have data for
+    let latest_date = match latest_eq_date(conn, exchange)? {
         Some(d) => d,
         None => return Ok(Vec::new()),
     };
 
-    // Calculate range start based on days
+    // Calculate range start based on days; the 52-week high needs at least a year
     let range_start = latest_date - (range_days * 24 * 60 * 60);
+    let year_start = latest_date - (365 * 24 * 60 * 60);
 
     // Build query with optional price filters
-    let mut query = String::from(
+    let mut query = format!(
         "SELECT
             ns.symbol,
             ns.name,
@@ -435,29 +963,52 @@ pub fn get_all_stocks_with_metrics(conn: &Connection, price_from: Option<f64>, p
             END as change_percent,
             latest.volume,
             range_stats.range_low,
-            range_stats.range_high
-        FROM nse_symbols ns
+            range_stats.range_high,
+            latest.open,
+            latest.high,
+            latest.low,
+            latest.turnover,
+            latest.trades,
+            latest.isin,
+            latest.series,
+            range_stats.week52_high,
+            CASE
+                WHEN range_stats.week52_high > 0 THEN ((latest.close - range_stats.week52_high) / range_stats.week52_high * 100.0)
+            END as from_52w_high,
+            delivery.deliverable_qty,
+            delivery.delivery_percent
+        FROM {} ns
         INNER JOIN (
-            SELECT symbol, close, prev_close, volume
+            SELECT symbol, date, exchange, open, high, low, close, prev_close, volume, turnover, trades, isin, series
             FROM bhavcopy_data
-            WHERE date = ? AND series = 'EQ'
+            WHERE date = ? AND series = 'EQ' AND exchange = ?
         ) latest ON ns.symbol = latest.symbol
         INNER JOIN (
             SELECT
                 symbol,
-                MIN(low) as range_low,
-                MAX(high) as range_high
+                MIN(CASE WHEN date >= ? THEN low END) as range_low,
+                MAX(CASE WHEN date >= ? THEN high END) as range_high,
+                MAX(CASE WHEN date >= ? THEN high END) as week52_high
             FROM bhavcopy_data
-            WHERE date >= ? AND date <= ? AND series = 'EQ'
+            WHERE date >= ? AND date <= ? AND series = 'EQ' AND exchange = ?
             GROUP BY symbol
         ) range_stats ON ns.symbol = range_stats.symbol
-        WHERE 1=1"
+        LEFT JOIN delivery_data delivery
+            ON delivery.symbol = latest.symbol AND delivery.series = latest.series
+            AND delivery.date = latest.date AND latest.exchange = 'NSE'
+        WHERE range_stats.range_low IS NOT NULL",
+        exchange.symbols_table()
     );
 
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![
         Box::new(latest_date),
+        Box::new(exchange.key()),
+        Box::new(range_start),
         Box::new(range_start),
+        Box::new(year_start),
+        Box::new(range_start.min(year_start)),
         Box::new(latest_date),
+        Box::new(exchange.key()),
     ];
 
     if let Some(from) = price_from {
@@ -470,7 +1021,9 @@ pub fn get_all_stocks_with_metrics(conn: &Connection, price_from: Option<f64>, p
         params.push(Box::new(to));
     }
 
-    query.push_str(" ORDER BY ns.symbol");
+    let order = sort.column.order_by();
+    let direction = if sort.descending { "DESC" } else { "ASC" };
+    query.push_str(&format!(" ORDER BY ({} IS NULL), {} {}, ns.symbol", order, order, direction));
 
     let mut stmt = conn.prepare(&query)?;
     let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
@@ -480,14 +1033,157 @@ pub fn get_all_stocks_with_metrics(conn: &Connection, price_from: Option<f64>, p
             symbol: row.get(0)?,
             name: row.get(1)?,
             ltp: row.get(2)?,
+            prev_close: row.get(3)?,
             change_percent: row.get(4)?,
             volume: row.get(5)?,
             range_low: row.get(6)?,
             range_high: row.get(7)?,
+            open: row.get(8)?,
+            high: row.get(9)?,
+            low: row.get(10)?,
+            turnover: row.get(11)?,
+            trades: row.get(12)?,
+            isin: row.get(13)?,
+            series: row.get(14)?,
+            week52_high: row.get(15)?,
+            deliverable_qty: row.get(17)?,
+            delivery_percent: row.get(18)?,
         })
     })?;
 
     stocks.collect()
 }
 
+/// Same as [`get_all_stocks_with_metrics`], with the low/high range and 52-week high adjusted for corporate actions
+pub fn get_all_stocks_with_metrics_adjusted(conn: &Connection, exchange: Exchange, price_from: Option<f64>, price_to: Option<f64>, range_days: i64, sort: StockSort) -> Result<Vec<StockData>> {
+    let mut stocks = get_all_stocks_with_metrics(conn, exchange, price_from, price_to, range_days, sort)?;
+
+    let latest_date = match latest_eq_date(conn, exchange)? {
+        Some(d) => d,
+        None => return Ok(stocks),
+    };
+    let range_start = latest_date - (range_days * 24 * 60 * 60);
+    let year_start = latest_date - (365 * 24 * 60 * 60);
+    let window_start = range_start.min(year_start);
+
+    // Only symbols with an action inside the window need their range recomputed
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT symbol FROM corporate_actions WHERE ex_date > ? AND ex_date <= ?"
+    )?;
+    let mut affected: std::collections::HashSet<String> = stmt
+        .query_map(params![window_start, latest_date], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+    if affected.is_empty() {
+        return Ok(stocks);
+    }
+
+    // Actions are recorded against NSE symbols, so BSE scrips are matched to them by ISIN
+    if exchange != Exchange::Nse {
+        let mut linked = std::collections::HashSet::new();
+        for symbol in &affected {
+            if let Some(isin) = get_listing_isin(conn, Exchange::Nse, symbol)? {
+                linked.insert(isin);
+            }
+        }
+        affected = stocks.iter()
+            .filter(|s| s.isin.as_ref().is_some_and(|isin| linked.contains(isin)))
+            .map(|s| s.symbol.clone())
+            .collect();
+    }
+
+    let mut range_stmt = conn.prepare(
+        "SELECT date, low, high FROM bhavcopy_data
+         WHERE symbol = ? AND exchange = ? AND series = 'EQ' AND date >= ? AND date <= ?"
+    )?;
+
+    for stock in stocks.iter_mut().filter(|s| affected.contains(&s.symbol)) {
+        let factors = get_listing_adjustment_factors(conn, exchange, &stock.symbol)?;
+        let rows = range_stmt.query_map(params![stock.symbol, exchange.key(), window_start, latest_date], |row| {
+            let ts: i64 = row.get(0)?;
+            let low: Option<f64> = row.get(1)?;
+            let high: Option<f64> = row.get(2)?;
+            Ok((ts, low, high))
+        })?;
+
+        let mut range_low = f64::MAX;
+        let mut range_high = f64::MIN;
+        let mut week52_high = f64::MIN;
+        for row in rows {
+            let (ts, low, high) = row?;
+            let Some(date) = timestamp_to_date(ts) else {
+                continue;
+            };
+            let adjustment = cumulative_adjustment(&factors, date);
+            if let Some(low) = low.filter(|_| ts >= range_start) {
+                range_low = range_low.min(low * adjustment);
+            }
+            if let Some(high) = high {
+                if ts >= range_start {
+                    range_high = range_high.max(high * adjustment);
+                }
+                if ts >= year_start {
+                    week52_high = week52_high.max(high * adjustment);
+                }
+            }
+        }
+
+        if range_low <= range_high {
+            stock.range_low = range_low;
+            stock.range_high = range_high;
+        }
+        if week52_high > f64::MIN {
+            stock.week52_high = Some(week52_high);
+        }
+    }
+
+    // Adjusted values can move a row, so re-sort by the columns they feed
+    if matches!(sort.column, StockColumn::RangeLow | StockColumn::RangeHigh | StockColumn::From52wHigh) {
+        let key = |stock: &StockData| match sort.column {
+            StockColumn::RangeLow => Some(stock.range_low),
+            StockColumn::RangeHigh => Some(stock.range_high),
+            _ => stock.from_52w_high_pct(),
+        };
+        stocks.sort_by(|a, b| match (key(a), key(b)) {
+            (Some(x), Some(y)) => {
+                let order = x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal);
+                if sort.descending { order.reverse() } else { order }
+            }
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }.then_with(|| a.symbol.cmp(&b.symbol)));
+    }
+
+    Ok(stocks)
+}
+
+/// A value from the app_settings table, e.g. the Stocks grid layout as JSON
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row("SELECT value FROM app_settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        params![key, value, Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
+const STOCKS_GRID_LAYOUT_KEY: &str = "stocks_grid_layout";
 
+/// The saved Stocks grid layout; the default layout if none was saved or it can't be read
+pub fn get_stocks_grid_layout(conn: &Connection) -> Result<StocksGridLayout> {
+    let layout = get_setting(conn, STOCKS_GRID_LAYOUT_KEY)?
+        .and_then(|json| serde_json::from_str::<StocksGridLayout>(&json).ok())
+        .filter(|layout| layout.columns.contains(&StockColumn::Symbol));
+    Ok(layout.unwrap_or_default())
+}
+
+pub fn save_stocks_grid_layout(conn: &Connection, layout: &StocksGridLayout) -> Result<()> {
+    let json = serde_json::to_string(layout)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    set_setting(conn, STOCKS_GRID_LAYOUT_KEY, &json)
+}
@@ -0,0 +1,403 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use crate::db::{date_column, date_to_timestamp, timestamp_to_date};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeSide::Buy => "buy",
+            TradeSide::Sell => "sell",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "buy" | "b" => Some(TradeSide::Buy),
+            "sell" | "s" => Some(TradeSide::Sell),
+            _ => None,
+        }
+    }
+}
+
+/// One buy or sell in the portfolio ledger
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioTransaction {
+    pub id: i64,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub quantity: i64,
+    /// Price per share, excluding charges
+    pub price: f64,
+    /// Brokerage, STT, stamp duty and other charges for the whole trade
+    pub charges: f64,
+    pub date: NaiveDate,
+    pub note: Option<String>,
+}
+
+/// Shares still held from one buy
+#[derive(Debug, Clone, Serialize)]
+pub struct Lot {
+    pub symbol: String,
+    pub buy_date: NaiveDate,
+    pub quantity: i64,
+    /// Cost per share including the buy's charges
+    pub cost_per_share: f64,
+}
+
+/// Part of a sell matched against one earlier buy (FIFO)
+#[derive(Debug, Clone, Serialize)]
+pub struct RealizedGain {
+    pub symbol: String,
+    pub quantity: i64,
+    pub buy_date: NaiveDate,
+    /// Cost per share including the buy's charges
+    pub buy_price: f64,
+    pub sell_date: NaiveDate,
+    /// Sale price per share, before charges
+    pub sell_price: f64,
+    /// The sell's charges allocated to this quantity
+    pub sell_charges: f64,
+}
+
+impl RealizedGain {
+    pub fn cost(&self) -> f64 {
+        self.buy_price * self.quantity as f64
+    }
+
+    /// Sale value net of the sell's charges
+    pub fn proceeds(&self) -> f64 {
+        self.sell_price * self.quantity as f64 - self.sell_charges
+    }
+
+    pub fn pnl(&self) -> f64 {
+        self.proceeds() - self.cost()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Holding {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub quantity: i64,
+    /// Average cost per share of the lots still held, including charges
+    pub avg_cost: f64,
+    pub invested: f64,
+    pub last_close: Option<f64>,
+    pub last_date: Option<NaiveDate>,
+    /// Valued at the last close, or at cost when there is no price yet
+    pub market_value: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+}
+
+impl Holding {
+    pub fn unrealized_pct(&self) -> f64 {
+        if self.invested > 0.0 { self.unrealized_pnl / self.invested * 100.0 } else { 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioSummary {
+    /// Open positions, by symbol
+    pub holdings: Vec<Holding>,
+    /// Closed quantity, in the order the sells happened
+    pub realized: Vec<RealizedGain>,
+    /// Sells that exceeded the quantity held at the time
+    pub warnings: Vec<String>,
+}
+
+impl PortfolioSummary {
+    pub fn invested(&self) -> f64 {
+        self.holdings.iter().map(|h| h.invested).sum()
+    }
+
+    pub fn market_value(&self) -> f64 {
+        self.holdings.iter().map(|h| h.market_value).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.holdings.iter().map(|h| h.unrealized_pnl).sum()
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized.iter().map(|r| r.pnl()).sum()
+    }
+}
+
+/// Record a transaction. Sells may not exceed the quantity held on the trade date or on any later day.
+pub fn add_transaction(conn: &Connection, transaction: &PortfolioTransaction) -> Result<i64, Box<dyn std::error::Error>> {
+    insert_transaction(conn, transaction, None)
+}
+
+/// [`add_transaction`] for a trade imported from a broker, identified by its (broker, trade ID)
+pub(crate) fn insert_transaction(
+    conn: &Connection,
+    transaction: &PortfolioTransaction,
+    source: Option<(&str, &str)>,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let symbol = transaction.symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol is required".into());
+    }
+    if transaction.quantity <= 0 {
+        return Err("Quantity must be positive".into());
+    }
+    if transaction.price <= 0.0 {
+        return Err("Price must be positive".into());
+    }
+    if transaction.charges < 0.0 {
+        return Err("Charges cannot be negative".into());
+    }
+
+    if transaction.side == TradeSide::Sell {
+        // A back-dated sell also lowers the holding on every later day, so it must fit under the lowest of them
+        let (held, date) = lowest_quantity_held(conn, &symbol, transaction.date)?;
+        if transaction.quantity > held {
+            return Err(format!("Cannot sell {} {}: only {} held on {}", transaction.quantity, symbol, held, date).into());
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO portfolio_transactions (symbol, side, quantity, price, charges, trade_date, note, created_at, broker, trade_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            symbol,
+            transaction.side.as_str(),
+            transaction.quantity,
+            transaction.price,
+            transaction.charges,
+            date_to_timestamp(transaction.date),
+            transaction.note,
+            chrono::Utc::now().timestamp(),
+            source.map(|(broker, _)| broker),
+            source.map(|(_, trade_id)| trade_id),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_transaction(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM portfolio_transactions WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Lowest net quantity of a symbol held at the end of `date` or of any later trade date,
+/// with the day it occurs on
+fn lowest_quantity_held(conn: &Connection, symbol: &str, date: NaiveDate) -> rusqlite::Result<(i64, NaiveDate)> {
+    let mut stmt = conn.prepare(
+        "SELECT trade_date, SUM(CASE WHEN side = 'buy' THEN quantity ELSE -quantity END)
+         FROM portfolio_transactions
+         WHERE symbol = ?1
+         GROUP BY trade_date
+         ORDER BY trade_date"
+    )?;
+    let days = stmt.query_map(params![symbol], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?;
+
+    let ts = date_to_timestamp(date);
+    let mut held = 0;
+    let mut lowest: Option<(i64, NaiveDate)> = None;
+    for day in days {
+        let (day_ts, net) = day?;
+        if day_ts > ts && lowest.is_none() {
+            // The holding at the end of `date` itself, before the first later trade
+            lowest = Some((held, date));
+        }
+        held += net;
+        if let Some((least, _)) = lowest {
+            if held < least {
+                lowest = Some((held, timestamp_to_date(day_ts).unwrap_or(date)));
+            }
+        }
+    }
+    Ok(lowest.unwrap_or((held, date)))
+}
+
+/// All transactions in trade order (date, then entry order), optionally for one symbol
+pub fn get_transactions(conn: &Connection, symbol: Option<&str>) -> rusqlite::Result<Vec<PortfolioTransaction>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, side, quantity, price, charges, trade_date, note
+         FROM portfolio_transactions
+         WHERE ?1 IS NULL OR symbol = ?1
+         ORDER BY trade_date, id"
+    )?;
+
+    let rows = stmt.query_map(params![symbol], |row| {
+        let side: String = row.get(2)?;
+        Ok(PortfolioTransaction {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            side: TradeSide::parse(&side).unwrap_or(TradeSide::Buy),
+            quantity: row.get(3)?,
+            price: row.get(4)?,
+            charges: row.get(5)?,
+            date: date_column(row, 6)?,
+            note: row.get(7)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Match sells against the oldest open buys of the same symbol (FIFO).
+/// `transactions` must be in trade order. Returns the open lots, the realized matches and
+/// a warning for every sell that exceeded the quantity held.
+pub fn match_lots_fifo(transactions: &[PortfolioTransaction]) -> (Vec<Lot>, Vec<RealizedGain>, Vec<String>) {
+    let mut open: HashMap<&str, VecDeque<Lot>> = HashMap::new();
+    let mut realized = Vec::new();
+    let mut warnings = Vec::new();
+
+    for t in transactions {
+        let lots = open.entry(t.symbol.as_str()).or_default();
+        match t.side {
+            TradeSide::Buy => lots.push_back(Lot {
+                symbol: t.symbol.clone(),
+                buy_date: t.date,
+                quantity: t.quantity,
+                cost_per_share: t.price + t.charges / t.quantity as f64,
+            }),
+            TradeSide::Sell => {
+                let charges_per_share = t.charges / t.quantity as f64;
+                let mut remaining = t.quantity;
+                while remaining > 0 {
+                    let Some(lot) = lots.front_mut() else {
+                        warnings.push(format!("{} sell of {} on {} exceeds the quantity held by {}", t.symbol, t.quantity, t.date, remaining));
+                        break;
+                    };
+                    let quantity = remaining.min(lot.quantity);
+                    realized.push(RealizedGain {
+                        symbol: t.symbol.clone(),
+                        quantity,
+                        buy_date: lot.buy_date,
+                        buy_price: lot.cost_per_share,
+                        sell_date: t.date,
+                        sell_price: t.price,
+                        sell_charges: charges_per_share * quantity as f64,
+                    });
+                    lot.quantity -= quantity;
+                    remaining -= quantity;
+                    if lot.quantity == 0 {
+                        lots.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    let mut lots: Vec<Lot> = open.into_values().flatten().collect();
+    lots.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.buy_date.cmp(&b.buy_date)));
+    (lots, realized, warnings)
+}
+
+/// Holdings and P&L, with open positions valued at the latest EQ close in bhavcopy_data
+pub fn get_portfolio(conn: &Connection) -> rusqlite::Result<PortfolioSummary> {
+    let transactions = get_transactions(conn, None)?;
+    let (lots, realized, warnings) = match_lots_fifo(&transactions);
+
+    let mut realized_by_symbol: HashMap<&str, f64> = HashMap::new();
+    for gain in &realized {
+        *realized_by_symbol.entry(gain.symbol.as_str()).or_default() += gain.pnl();
+    }
+
+    let mut latest_stmt = conn.prepare(
+        "SELECT b.close, b.date, ns.name
+         FROM bhavcopy_data b
+         LEFT JOIN nse_symbols ns ON ns.symbol = b.symbol
+         WHERE b.symbol = ?1 AND b.series = 'EQ' AND b.exchange = 'NSE'
+         ORDER BY b.date DESC
+         LIMIT 1"
+    )?;
+
+    let mut holdings: Vec<Holding> = Vec::new();
+    for lot in &lots {
+        match holdings.last_mut() {
+            Some(holding) if holding.symbol == lot.symbol => {
+                holding.quantity += lot.quantity;
+                holding.invested += lot.cost_per_share * lot.quantity as f64;
+            }
+            _ => holdings.push(Holding {
+                symbol: lot.symbol.clone(),
+                name: None,
+                quantity: lot.quantity,
+                avg_cost: 0.0,
+                invested: lot.cost_per_share * lot.quantity as f64,
+                last_close: None,
+                last_date: None,
+                market_value: 0.0,
+                unrealized_pnl: 0.0,
+                realized_pnl: 0.0,
+            }),
+        }
+    }
+
+    for holding in &mut holdings {
+        let latest: Option<(f64, i64, Option<String>)> = latest_stmt
+            .query_row(params![holding.symbol], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .optional()?;
+        if let Some((close, ts, name)) = latest {
+            holding.last_close = Some(close);
+            holding.last_date = timestamp_to_date(ts);
+            holding.name = name;
+        }
+
+        holding.avg_cost = holding.invested / holding.quantity as f64;
+        holding.market_value = holding.last_close.map(|c| c * holding.quantity as f64).unwrap_or(holding.invested);
+        holding.unrealized_pnl = holding.market_value - holding.invested;
+        holding.realized_pnl = realized_by_symbol.get(holding.symbol.as_str()).copied().unwrap_or(0.0);
+    }
+
+    Ok(PortfolioSummary { holdings, realized, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::test_db;
+
+    fn trade(side: TradeSide, quantity: i64, date: (i32, u32, u32)) -> PortfolioTransaction {
+        PortfolioTransaction {
+            id: 0,
+            symbol: "INFY".to_string(),
+            side,
+            quantity,
+            price: 1500.0,
+            charges: 0.0,
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn sells_are_limited_to_the_quantity_held() {
+        let conn = test_db();
+        add_transaction(&conn, &trade(TradeSide::Buy, 100, (2024, 1, 10))).unwrap();
+
+        let err = add_transaction(&conn, &trade(TradeSide::Sell, 10, (2024, 1, 9))).unwrap_err();
+        assert_eq!(err.to_string(), "Cannot sell 10 INFY: only 0 held on 2024-01-09");
+        assert!(add_transaction(&conn, &trade(TradeSide::Sell, 101, (2024, 1, 10))).is_err());
+        add_transaction(&conn, &trade(TradeSide::Sell, 100, (2024, 1, 10))).unwrap();
+    }
+
+    #[test]
+    fn back_dated_sells_are_checked_against_later_days() {
+        let conn = test_db();
+        add_transaction(&conn, &trade(TradeSide::Buy, 100, (2024, 1, 10))).unwrap();
+        add_transaction(&conn, &trade(TradeSide::Sell, 80, (2024, 3, 1))).unwrap();
+        add_transaction(&conn, &trade(TradeSide::Buy, 50, (2024, 4, 1))).unwrap();
+
+        // 100 are held in February, but selling 30 then would leave -10 after the March sell
+        let err = add_transaction(&conn, &trade(TradeSide::Sell, 30, (2024, 2, 1))).unwrap_err();
+        assert_eq!(err.to_string(), "Cannot sell 30 INFY: only 20 held on 2024-03-01");
+
+        add_transaction(&conn, &trade(TradeSide::Sell, 20, (2024, 2, 1))).unwrap();
+        assert!(add_transaction(&conn, &trade(TradeSide::Sell, 1, (2024, 2, 15))).is_err());
+        add_transaction(&conn, &trade(TradeSide::Sell, 50, (2024, 4, 1))).unwrap();
+    }
+}
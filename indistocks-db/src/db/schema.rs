@@ -1,6 +1,7 @@
-use rusqlite::{Connection, Result};
-use std::path::PathBuf;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
 use directories::ProjectDirs;
+use crate::db::migrations::run_migrations;
 
 pub fn get_db_path() -> PathBuf {
     let proj_dirs = ProjectDirs::from("", "", "Indistocks")
@@ -19,83 +20,45 @@ pub fn get_logs_path() -> PathBuf {
     logs_dir
 }
 
-pub fn init_db() -> Result<Connection> {
-    let db_path = get_db_path();
-    let conn = Connection::open(&db_path)?;
-
-    // Enable foreign key constraints
-    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
-
-    // Create tables
-    conn.execute_batch(
-        "
-        CREATE TABLE IF NOT EXISTS nse_symbols (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            symbol TEXT NOT NULL UNIQUE,
-            name TEXT,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
-
-        CREATE TABLE IF NOT EXISTS bse_symbols (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            symbol TEXT NOT NULL UNIQUE,
-            name TEXT,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
+pub fn init_db() -> Result<Connection, Box<dyn std::error::Error>> {
+    init_db_at(&get_db_path())
+}
 
-        CREATE TABLE IF NOT EXISTS recently_viewed (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            symbol_id INTEGER NOT NULL,
-            viewed_at INTEGER NOT NULL,
-            FOREIGN KEY (symbol_id) REFERENCES nse_symbols(id) ON DELETE CASCADE
-        );
+/// Open (or create) the database at `db_path` and apply any pending migrations
+pub fn init_db_at(db_path: &Path) -> Result<Connection, Box<dyn std::error::Error>> {
+    let conn = open_db_connection(db_path)?;
 
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_recently_viewed_symbol_id
-        ON recently_viewed(symbol_id);
+    run_migrations(&conn, Some(db_path))?;
 
-        CREATE INDEX IF NOT EXISTS idx_recently_viewed_time
-        ON recently_viewed(viewed_at DESC);
+    Ok(conn)
+}
 
-        CREATE TABLE IF NOT EXISTS nse_downloads (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            symbol TEXT,
-            from_date INTEGER NOT NULL,
-            to_date INTEGER NOT NULL,
-            file_path TEXT NOT NULL,
-            file_size INTEGER,
-            status TEXT NOT NULL,
-            error_message TEXT,
-            downloaded_at INTEGER NOT NULL,
-            UNIQUE(symbol, from_date, to_date)
-        );
+/// Open another connection to an already initialised database, e.g. for a background import.
+/// In WAL mode readers on other connections are not blocked while it writes.
+pub fn open_db_connection(db_path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(db_path)?;
 
-        CREATE INDEX IF NOT EXISTS idx_nse_downloads_downloaded_at
-        ON nse_downloads(downloaded_at DESC);
+    // Enable foreign key constraints
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
-        CREATE TABLE IF NOT EXISTS bhavcopy_data (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            symbol TEXT NOT NULL,
-            series TEXT,
-            date INTEGER NOT NULL,
-            open REAL,
-            high REAL,
-            low REAL,
-            close REAL,
-            last REAL,
-            prev_close REAL,
-            volume INTEGER,
-            turnover REAL,
-            trades INTEGER,
-            isin TEXT,
-            UNIQUE(symbol, date)
-        );
+    // Wait for another connection's write to finish instead of failing with "database is locked"
+    conn.busy_timeout(BUSY_TIMEOUT)?;
 
-        CREATE INDEX IF NOT EXISTS idx_bhavcopy_data_symbol_date
-        ON bhavcopy_data(symbol, date);
-        "
-    )?;
+    // WAL lets the UI keep reading while a download commits; NORMAL sync is safe with WAL
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    if mode.eq_ignore_ascii_case("wal") {
+        conn.execute_batch("PRAGMA synchronous = NORMAL;")?;
+    }
 
     Ok(conn)
 }
+
+/// Open a second connection to the same database file as `conn`, so background work doesn't hold
+/// the connection the UI uses. None for in-memory databases, which can't be shared this way.
+pub fn reopen_db_connection(conn: &Connection) -> Option<rusqlite::Result<Connection>> {
+    conn.path()
+        .filter(|path| !path.is_empty())
+        .map(|path| open_db_connection(Path::new(path)))
+}
+
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
@@ -0,0 +1,185 @@
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use crate::db::{date_column, timestamp_to_date};
+use crate::db::corporate_actions::{get_adjustment_factors, cumulative_adjustment};
+use crate::models::PriceBar;
+use crate::screener::{Screen, ScreenInput};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedScreen {
+    pub id: i64,
+    pub name: String,
+    pub expression: String,
+}
+
+/// A stock that passed a screen, as of the latest stored session
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenMatch {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub date: NaiveDate,
+    pub close: f64,
+    pub change_percent: f64,
+    pub volume: i64,
+}
+
+/// The latest session's values that aren't part of a [`PriceBar`]
+#[derive(Debug, Clone, Copy, Default)]
+struct SessionExtras {
+    prev_close: Option<f64>,
+    turnover: Option<f64>,
+    trades: Option<i64>,
+    delivery_qty: Option<i64>,
+    delivery_pct: Option<f64>,
+}
+
+/// Save a screen under `name`, replacing the expression of an existing screen with that name
+pub fn save_screen(conn: &Connection, name: &str, expression: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Screen name is required".into());
+    }
+    let screen = Screen::parse(expression).map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO saved_screens (name, expression, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT(name) DO UPDATE SET expression = excluded.expression, updated_at = excluded.updated_at",
+        params![name, screen.source(), now],
+    )?;
+    Ok(conn.query_row("SELECT id FROM saved_screens WHERE name = ?1", params![name], |row| row.get(0))?)
+}
+
+pub fn get_saved_screens(conn: &Connection) -> rusqlite::Result<Vec<SavedScreen>> {
+    let mut stmt = conn.prepare("SELECT id, name, expression FROM saved_screens ORDER BY name COLLATE NOCASE")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SavedScreen {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            expression: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn delete_saved_screen(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM saved_screens WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Evaluate a screen against every EQ symbol that traded in the latest stored session,
+/// using as many sessions of history as the expression looks back
+pub fn run_screen(conn: &Connection, screen: &Screen, adjusted: bool) -> rusqlite::Result<Vec<ScreenMatch>> {
+    let mut dates_stmt = conn.prepare(
+        "SELECT DISTINCT date FROM bhavcopy_data WHERE series = 'EQ' AND exchange = 'NSE' ORDER BY date DESC LIMIT ?1"
+    )?;
+    let dates: Vec<i64> = dates_stmt
+        .query_map(params![screen.lookback() as i64], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let (Some(&latest), Some(&start)) = (dates.first(), dates.last()) else {
+        return Ok(Vec::new());
+    };
+
+    let names: HashMap<String, Option<String>> = conn
+        .prepare("SELECT symbol, name FROM nse_symbols")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let affected: HashSet<String> = if adjusted {
+        conn.prepare("SELECT DISTINCT symbol FROM corporate_actions WHERE ex_date > ?1 AND ex_date <= ?2")?
+            .query_map(params![start, latest], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?
+    } else {
+        HashSet::new()
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT b.symbol, b.date, COALESCE(b.open, b.close), COALESCE(b.high, b.close), COALESCE(b.low, b.close), b.close,
+                COALESCE(b.volume, 0), b.prev_close, b.turnover, b.trades, d.deliverable_qty, d.delivery_percent
+         FROM bhavcopy_data b
+         LEFT JOIN delivery_data d ON d.symbol = b.symbol AND d.series = b.series AND d.date = b.date
+         WHERE b.series = 'EQ' AND b.exchange = 'NSE' AND b.date >= ?1 AND b.close IS NOT NULL
+         ORDER BY b.symbol, b.date"
+    )?;
+    let mut rows = stmt.query(params![start])?;
+
+    let mut matches = Vec::new();
+    let mut symbol = String::new();
+    let mut bars: Vec<PriceBar> = Vec::new();
+    let mut extras = SessionExtras::default();
+    let Some(latest_date) = timestamp_to_date(latest) else {
+        return Ok(Vec::new());
+    };
+
+    let mut evaluate = |symbol: &str, bars: &mut Vec<PriceBar>, extras: SessionExtras| -> rusqlite::Result<()> {
+        // Only stocks that traded in the latest session
+        if bars.last().map(|b| b.date) != Some(latest_date) {
+            return Ok(());
+        }
+        if affected.contains(symbol) {
+            let factors = get_adjustment_factors(conn, symbol)?;
+            for bar in bars.iter_mut() {
+                let adjustment = cumulative_adjustment(&factors, bar.date);
+                bar.open *= adjustment;
+                bar.high *= adjustment;
+                bar.low *= adjustment;
+                bar.close *= adjustment;
+            }
+        }
+
+        let prev_close = extras.prev_close;
+        let input = ScreenInput {
+            bars,
+            prev_close,
+            turnover: extras.turnover,
+            trades: extras.trades,
+            delivery_qty: extras.delivery_qty,
+            delivery_pct: extras.delivery_pct,
+        };
+        if screen.matches(&input) {
+            let last = bars[bars.len() - 1];
+            let change_percent = match prev_close {
+                Some(prev) if prev > 0.0 => (last.close - prev) / prev * 100.0,
+                _ => 0.0,
+            };
+            matches.push(ScreenMatch {
+                symbol: symbol.to_string(),
+                name: names.get(symbol).cloned().flatten(),
+                date: last.date,
+                close: last.close,
+                change_percent,
+                volume: last.volume,
+            });
+        }
+        Ok(())
+    };
+
+    while let Some(row) = rows.next()? {
+        let row_symbol: String = row.get(0)?;
+        if row_symbol != symbol {
+            evaluate(&symbol, &mut bars, extras)?;
+            bars.clear();
+            symbol = row_symbol;
+        }
+
+        bars.push(PriceBar {
+            date: date_column(row, 1)?,
+            open: row.get(2)?,
+            high: row.get(3)?,
+            low: row.get(4)?,
+            close: row.get(5)?,
+            volume: row.get(6)?,
+        });
+        extras = SessionExtras {
+            prev_close: row.get(7)?,
+            turnover: row.get(8)?,
+            trades: row.get(9)?,
+            delivery_qty: row.get(10)?,
+            delivery_pct: row.get(11)?,
+        };
+    }
+    evaluate(&symbol, &mut bars, extras)?;
+
+    Ok(matches)
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use chrono::{Datelike, NaiveDate};
+use reqwest::blocking::Client;
+
+/// Root of the official NSE archives
+pub const NSE_ARCHIVES_URL: &str = "https://nsearchives.nseindia.com";
+
+/// Environment variable that points the downloader at a different archive root,
+/// e.g. `http://localhost:8080` or `file:///srv/nse-mirror`
+pub const DATA_SOURCE_ENV: &str = "INDISTOCKS_DATA_SOURCE";
+
+/// Archive path of the NSE equity list
+pub const EQUITY_LIST_PATH: &str = "content/equities/EQUITY_L.csv";
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// The file does not exist at the source (weekends and holidays have no BhavCopy)
+    NotFound(String),
+    Http(u16, String),
+    Network(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::NotFound(location) => write!(f, "Not found: {}", location),
+            FetchError::Http(status, location) => write!(f, "HTTP error {} for {}", status, location),
+            FetchError::Network(e) => write!(f, "Network error: {}", e),
+            FetchError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Where NSE archive files come from. Paths are relative to the archive root,
+/// e.g. `content/equities/EQUITY_L.csv`, so a local mirror only has to keep the same layout.
+pub trait DataSource: Send + Sync {
+    fn fetch(&self, path: &str) -> Result<Vec<u8>, FetchError>;
+
+    /// Full location of `path`, for progress messages and errors
+    fn location(&self, path: &str) -> String;
+}
+
+/// Fetches archive files over HTTP(S) from a configurable base URL
+pub struct HttpSource {
+    client: Client,
+    base_url: String,
+}
+
+impl HttpSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: create_http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl DataSource for HttpSource {
+    fn fetch(&self, path: &str) -> Result<Vec<u8>, FetchError> {
+        let url = self.location(path);
+
+        rate_limit_delay();
+
+        let response = self.client
+            .get(&url)
+            .header("Referer", "https://www.nseindia.com/get-quotes/equity?symbol=HDFCBANK")
+            .send()
+            .map_err(|e| FetchError::Network(e.to_string()))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(FetchError::NotFound(url));
+        }
+        if !status.is_success() {
+            return Err(FetchError::Http(status.as_u16(), url));
+        }
+
+        let bytes = response.bytes().map_err(|e| FetchError::Network(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
+
+    fn location(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+}
+
+/// Reads archive files from a local directory laid out like the NSE archives
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl DataSource for DirectorySource {
+    fn fetch(&self, path: &str) -> Result<Vec<u8>, FetchError> {
+        let file_path = self.root.join(path.trim_start_matches('/'));
+        match fs::read(&file_path) {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(FetchError::NotFound(file_path.display().to_string()))
+            }
+            Err(e) => Err(FetchError::Io(e)),
+        }
+    }
+
+    fn location(&self, path: &str) -> String {
+        self.root.join(path.trim_start_matches('/')).display().to_string()
+    }
+}
+
+/// Build a data source from a URL: `http://` and `https://` use HTTP,
+/// `file://` or a plain directory path read from disk
+pub fn data_source_from_url(url: &str) -> Result<Box<dyn DataSource>, String> {
+    let url = url.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(Box::new(HttpSource::new(url)));
+    }
+
+    let dir = PathBuf::from(url.strip_prefix("file://").unwrap_or(url));
+    if dir.is_dir() {
+        Ok(Box::new(DirectorySource::new(dir)))
+    } else {
+        Err(format!("Data source {} is neither an http(s) URL nor an existing directory", url))
+    }
+}
+
+/// The data source configured through `INDISTOCKS_DATA_SOURCE`, or the NSE archives
+pub fn default_data_source() -> Result<Box<dyn DataSource>, String> {
+    match std::env::var(DATA_SOURCE_ENV) {
+        Ok(url) if !url.trim().is_empty() => data_source_from_url(&url),
+        _ => Ok(Box::new(HttpSource::new(NSE_ARCHIVES_URL))),
+    }
+}
+
+/// Archive path of the equity BhavCopy ZIP for a date
+pub fn bhavcopy_path(date: NaiveDate) -> String {
+    let year = date.year();
+
+    if year >= 2024 {
+        // New format (2024 onwards)
+        let date_str = date.format("%Y%m%d");
+        format!("content/cm/BhavCopy_NSE_CM_0_0_0_{}_F_0000.csv.zip", date_str)
+    } else {
+        // Old format (2023 and before)
+        let day = date.format("%d");
+        let month = date.format("%b").to_string().to_uppercase();
+        let year_str = date.format("%Y");
+        format!("content/historical/EQUITIES/{}/{}/cm{}{}{}bhav.csv.zip",
+                year_str, month, day, month, year_str)
+    }
+}
+
+/// Archive path of the daily close file covering every NSE index
+pub fn index_close_path(date: NaiveDate) -> String {
+    format!("content/indices/ind_close_all_{}.csv", date.format("%d%m%Y"))
+}
+
+/// Archive path of the F&O BhavCopy ZIP for a date
+pub fn fo_bhavcopy_path(date: NaiveDate) -> String {
+    if date.year() >= 2024 {
+        format!("content/fo/BhavCopy_NSE_FO_0_0_0_{}_F_0000.csv.zip", date.format("%Y%m%d"))
+    } else {
+        let month = date.format("%b").to_string().to_uppercase();
+        format!("content/historical/DERIVATIVES/{}/{}/fo{}{}{}bhav.csv.zip",
+                date.format("%Y"), month, date.format("%d"), month, date.format("%Y"))
+    }
+}
+
+/// Archive path of the security-wise full BhavCopy for a date, which carries delivery quantities
+pub fn delivery_path(date: NaiveDate) -> String {
+    format!("products/content/sec_bhavdata_full_{}.csv", date.format("%d%m%Y"))
+}
+
+/// Archive path of the MTO delivery position file for a date, published for older years too
+pub fn mto_path(date: NaiveDate) -> String {
+    format!("archives/equities/mto/MTO_{}.DAT", date.format("%d%m%Y"))
+}
+
+pub(crate) fn create_http_client() -> Client {
+    Client::builder()
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; rv:109.0) Gecko/20100101 Firefox/118.0")
+        .timeout(Duration::from_secs(15))
+        .cookie_store(true)
+        .gzip(true)
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+pub(crate) fn rate_limit_delay() {
+    thread::sleep(Duration::from_millis(350)); // ~3 requests per second
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TempDir;
+
+    #[test]
+    fn http_urls_use_http() {
+        let source = data_source_from_url("http://localhost:8080/").unwrap();
+        assert_eq!(source.location(EQUITY_LIST_PATH), "http://localhost:8080/content/equities/EQUITY_L.csv");
+
+        let source = data_source_from_url(" https://mirror.example.com ").unwrap();
+        assert_eq!(source.location("/content/cm/x.zip"), "https://mirror.example.com/content/cm/x.zip");
+    }
+
+    #[test]
+    fn file_urls_and_plain_paths_read_from_disk() {
+        let temp = TempDir::new("source-urls");
+        let dir = temp.path();
+        let expected = dir.join("content/equities/EQUITY_L.csv").display().to_string();
+
+        let source = data_source_from_url(&format!("file://{}", dir.display())).unwrap();
+        assert_eq!(source.location(EQUITY_LIST_PATH), expected);
+
+        let source = data_source_from_url(&dir.display().to_string()).unwrap();
+        assert_eq!(source.location(EQUITY_LIST_PATH), expected);
+    }
+
+    #[test]
+    fn bad_sources_are_rejected() {
+        let missing = std::env::temp_dir().join(format!("indistocks-source-{}-missing", std::process::id()));
+        for url in ["", "ftp://example.com", "file:///definitely/not/here", missing.to_str().unwrap()] {
+            let err = data_source_from_url(url).err().unwrap_or_else(|| panic!("{:?} should be rejected", url));
+            assert!(err.contains("neither an http(s) URL nor an existing directory"), "unexpected error: {}", err);
+        }
+    }
+
+    #[test]
+    fn directory_source_reports_missing_files_as_not_found() {
+        let temp = TempDir::new("source-not-found");
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("content/cm")).unwrap();
+        fs::write(dir.join("content/cm/present.zip"), b"data").unwrap();
+        let source = DirectorySource::new(dir);
+
+        assert_eq!(source.fetch("content/cm/present.zip").unwrap(), b"data");
+
+        let path = bhavcopy_path(NaiveDate::from_ymd_opt(2024, 7, 30).unwrap());
+        match source.fetch(&path) {
+            Err(FetchError::NotFound(location)) => assert_eq!(location, dir.join(&path).display().to_string()),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+    }
+}
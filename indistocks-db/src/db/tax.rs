@@ -0,0 +1,531 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::db::date_to_timestamp;
+use crate::db::portfolio::{get_transactions, match_lots_fifo, RealizedGain};
+
+/// Shares bought on or before this date are grandfathered under section 112A
+pub fn grandfathering_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2018, 1, 31).unwrap()
+}
+
+/// Indian financial year, April to March, identified by the calendar year it starts in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FinancialYear(pub i32);
+
+impl FinancialYear {
+    pub fn containing(date: NaiveDate) -> Self {
+        if date.month() >= 4 { FinancialYear(date.year()) } else { FinancialYear(date.year() - 1) }
+    }
+
+    /// Parse `2024-25`, `2024-2025` or `2024`
+    pub fn parse(s: &str) -> Option<Self> {
+        let start: i32 = s.trim().split('-').next()?.trim().parse().ok()?;
+        (1990..=2100).contains(&start).then_some(FinancialYear(start))
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.0, 4, 1).unwrap()
+    }
+
+    pub fn end(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.0 + 1, 3, 31).unwrap()
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}-{:02}", self.0, (self.0 + 1) % 100)
+    }
+
+    /// Long-term gains exempt from tax each year. Before FY 2018-19 listed equity LTCG
+    /// was fully exempt under section 10(38); 1 lakh from 2018-19, 1.25 lakh from 2024-25.
+    pub fn ltcg_exemption(&self) -> Option<f64> {
+        match self.0 {
+            ..=2017 => None,
+            2018..=2023 => Some(100_000.0),
+            _ => Some(125_000.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GainTerm {
+    #[serde(rename = "STCG")]
+    Short,
+    #[serde(rename = "LTCG")]
+    Long,
+}
+
+impl GainTerm {
+    /// Listed equity is long-term when held for more than 12 months
+    pub fn for_holding(buy_date: NaiveDate, sell_date: NaiveDate) -> Self {
+        match buy_date.checked_add_months(Months::new(12)) {
+            Some(anniversary) if sell_date > anniversary => GainTerm::Long,
+            _ => GainTerm::Short,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GainTerm::Short => "STCG",
+            GainTerm::Long => "LTCG",
+        }
+    }
+}
+
+/// One FIFO-matched sale, valued the way the income-tax return asks for it
+#[derive(Debug, Clone, Serialize)]
+pub struct CapitalGain {
+    pub symbol: String,
+    pub isin: Option<String>,
+    pub name: Option<String>,
+    pub term: GainTerm,
+    pub quantity: i64,
+    pub buy_date: NaiveDate,
+    pub sell_date: NaiveDate,
+    pub sale_price: f64,
+    /// Quantity × sale price
+    pub full_value: f64,
+    /// Actual cost including purchase charges
+    pub actual_cost: f64,
+    /// Highest price on 31-Jan-2018, for grandfathered long-term lots
+    pub fmv_per_share: Option<f64>,
+    /// Actual cost, or for grandfathered lots the higher of actual cost and lower of FMV and sale value
+    pub cost_of_acquisition: f64,
+    pub transfer_expenses: f64,
+    pub gain: f64,
+}
+
+impl CapitalGain {
+    pub fn is_grandfathered(&self) -> bool {
+        self.buy_date <= grandfathering_date()
+    }
+
+    fn total_fmv(&self) -> Option<f64> {
+        self.fmv_per_share.map(|fmv| fmv * self.quantity as f64)
+    }
+}
+
+/// Capital gains for one financial year, with losses set off and the LTCG exemption applied
+#[derive(Debug, Clone)]
+pub struct CapitalGainsReport {
+    pub year: FinancialYear,
+    pub gains: Vec<CapitalGain>,
+    pub stcg: f64,
+    pub ltcg: f64,
+    /// Short-term loss set off against long-term gains
+    pub stcl_set_off: f64,
+    pub ltcg_exemption: f64,
+    pub taxable_stcg: f64,
+    pub taxable_ltcg: f64,
+    /// Losses left to carry forward (short-term, long-term)
+    pub carry_forward: (f64, f64),
+    /// Grandfathered lots whose 31-Jan-2018 price is missing from bhavcopy_data
+    pub warnings: Vec<String>,
+}
+
+/// Build the capital-gains report for a financial year from the portfolio ledger (FIFO)
+pub fn capital_gains_report(conn: &Connection, year: FinancialYear) -> rusqlite::Result<CapitalGainsReport> {
+    let transactions = get_transactions(conn, None)?;
+    let (_, realized, _) = match_lots_fifo(&transactions);
+
+    let mut fmv_cache: HashMap<String, Option<f64>> = HashMap::new();
+    let mut warnings = Vec::new();
+    let mut gains = Vec::new();
+
+    for sale in realized.iter().filter(|r| FinancialYear::containing(r.sell_date) == year) {
+        let term = GainTerm::for_holding(sale.buy_date, sale.sell_date);
+        let fmv_per_share = if term == GainTerm::Long && sale.buy_date <= grandfathering_date() {
+            match fmv_cache.get(&sale.symbol) {
+                Some(fmv) => *fmv,
+                None => {
+                    let fmv = grandfathered_price(conn, &sale.symbol)?;
+                    fmv_cache.insert(sale.symbol.clone(), fmv);
+                    if fmv.is_none() {
+                        warnings.push(format!("No 31-Jan-2018 price for {}; using actual cost", sale.symbol));
+                    }
+                    fmv
+                }
+            }
+        } else {
+            None
+        };
+
+        gains.push(value_gain(conn, sale, term, fmv_per_share)?);
+    }
+
+    // Adding 0.0 turns the -0.0 an empty sum produces into 0.0
+    let sum = |term: GainTerm| gains.iter().filter(|g| g.term == term).map(|g| g.gain).sum::<f64>() + 0.0;
+    let mut stcg = sum(GainTerm::Short);
+    let mut ltcg = sum(GainTerm::Long);
+    let (raw_stcg, raw_ltcg) = (stcg, ltcg);
+
+    // A short-term loss can be set off against long-term gains; a long-term loss only against long-term gains
+    let mut stcl_set_off = 0.0;
+    if stcg < 0.0 && ltcg > 0.0 {
+        stcl_set_off = (-stcg).min(ltcg);
+        stcg += stcl_set_off;
+        ltcg -= stcl_set_off;
+    }
+
+    let (ltcg_exemption, taxable_ltcg) = match year.ltcg_exemption() {
+        None => (positive(ltcg), 0.0),
+        Some(limit) => {
+            let exempt = positive(ltcg).min(limit);
+            (exempt, positive(ltcg - exempt))
+        }
+    };
+
+    Ok(CapitalGainsReport {
+        year,
+        gains,
+        stcg: raw_stcg,
+        ltcg: raw_ltcg,
+        stcl_set_off,
+        ltcg_exemption,
+        taxable_stcg: positive(stcg),
+        taxable_ltcg,
+        carry_forward: (positive(-stcg), positive(-ltcg)),
+        warnings,
+    })
+}
+
+fn positive(value: f64) -> f64 {
+    if value > 0.0 { value } else { 0.0 }
+}
+
+fn value_gain(conn: &Connection, sale: &RealizedGain, term: GainTerm, fmv_per_share: Option<f64>) -> rusqlite::Result<CapitalGain> {
+    let (isin, name): (Option<String>, Option<String>) = conn.query_row(
+        "SELECT
+            (SELECT isin FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE' AND isin IS NOT NULL ORDER BY date DESC LIMIT 1),
+            (SELECT name FROM nse_symbols WHERE symbol = ?1)",
+        params![sale.symbol],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let quantity = sale.quantity as f64;
+    let full_value = sale.sell_price * quantity;
+    let actual_cost = sale.cost();
+    let cost_of_acquisition = match fmv_per_share {
+        // Section 55(2)(ac): higher of actual cost and the lower of FMV and sale value
+        Some(fmv) => actual_cost.max((fmv * quantity).min(full_value)),
+        None => actual_cost,
+    };
+
+    Ok(CapitalGain {
+        symbol: sale.symbol.clone(),
+        isin,
+        name,
+        term,
+        quantity: sale.quantity,
+        buy_date: sale.buy_date,
+        sell_date: sale.sell_date,
+        sale_price: sale.sell_price,
+        full_value,
+        actual_cost,
+        fmv_per_share,
+        cost_of_acquisition,
+        transfer_expenses: sale.sell_charges,
+        gain: full_value - cost_of_acquisition - sale.sell_charges,
+    })
+}
+
+/// Fair market value for grandfathering: the highest price quoted on 31-Jan-2018,
+/// or on the last trading day before it if the stock did not trade that day
+pub fn grandfathered_price(conn: &Connection, symbol: &str) -> rusqlite::Result<Option<f64>> {
+    let ts = date_to_timestamp(grandfathering_date());
+    conn.query_row(
+        "SELECT COALESCE(high, close) FROM bhavcopy_data
+         WHERE symbol = ?1 AND exchange = 'NSE' AND series = 'EQ' AND date <= ?2
+         ORDER BY date DESC LIMIT 1",
+        params![symbol, ts],
+        |row| row.get(0),
+    ).optional().map(Option::flatten)
+}
+
+/// One row of ITR Schedule 112A (long-term gains on listed equity shares, section 112A)
+#[derive(Debug, Clone, Serialize)]
+pub struct Schedule112aRow {
+    #[serde(rename = "Share/Unit acquired")]
+    pub acquired: &'static str,
+    #[serde(rename = "ISIN Code")]
+    pub isin: String,
+    #[serde(rename = "Name of the Share/Unit")]
+    pub name: String,
+    #[serde(rename = "No. of Shares/Units")]
+    pub quantity: i64,
+    #[serde(rename = "Sale-price per Share/Unit")]
+    pub sale_price: f64,
+    #[serde(rename = "Full Value of Consideration")]
+    pub full_value: f64,
+    #[serde(rename = "Cost of acquisition without indexation")]
+    pub cost_without_indexation: f64,
+    #[serde(rename = "Cost of acquisition")]
+    pub actual_cost: f64,
+    #[serde(rename = "If the long term capital asset was acquired before 01.02.2018, Lower of Full Value of Consideration and Total Fair Market Value")]
+    pub lower_of_value_and_fmv: Option<f64>,
+    #[serde(rename = "Fair Market Value per share/unit as on 31st January 2018")]
+    pub fmv_per_share: Option<f64>,
+    #[serde(rename = "Total Fair Market Value of capital asset as per section 55(2)(ac)")]
+    pub total_fmv: Option<f64>,
+    #[serde(rename = "Expenditure wholly and exclusively in connection with transfer")]
+    pub transfer_expenses: f64,
+    #[serde(rename = "Total deductions")]
+    pub total_deductions: f64,
+    #[serde(rename = "Balance")]
+    pub balance: f64,
+}
+
+impl CapitalGainsReport {
+    /// Long-term lots as Schedule 112A rows, amounts rounded to the rupee as the return expects
+    pub fn schedule_112a_rows(&self) -> Vec<Schedule112aRow> {
+        let round = |v: f64| v.round();
+        self.gains.iter()
+            .filter(|g| g.term == GainTerm::Long)
+            .map(|g| {
+                let grandfathered = g.is_grandfathered();
+                Schedule112aRow {
+                    acquired: if grandfathered { "On or before 31st January 2018" } else { "After 31st January 2018" },
+                    isin: g.isin.clone().unwrap_or_default(),
+                    name: g.name.clone().unwrap_or_else(|| g.symbol.clone()),
+                    quantity: g.quantity,
+                    sale_price: g.sale_price,
+                    full_value: round(g.full_value),
+                    cost_without_indexation: round(g.cost_of_acquisition),
+                    actual_cost: round(g.actual_cost),
+                    lower_of_value_and_fmv: g.total_fmv().map(|fmv| round(fmv.min(g.full_value))),
+                    fmv_per_share: g.fmv_per_share,
+                    total_fmv: g.total_fmv().map(round),
+                    transfer_expenses: round(g.transfer_expenses),
+                    total_deductions: round(g.cost_of_acquisition + g.transfer_expenses),
+                    balance: round(g.gain),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Write the report's long-term lots as a Schedule 112A CSV
+pub fn write_schedule_112a_csv<W: std::io::Write>(report: &CapitalGainsReport, writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    let rows = report.schedule_112a_rows();
+    if rows.is_empty() {
+        // Still write the header so the file is recognisable
+        wtr.write_record(SCHEDULE_112A_HEADERS)?;
+    }
+    for row in rows {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+const SCHEDULE_112A_HEADERS: [&str; 14] = [
+    "Share/Unit acquired",
+    "ISIN Code",
+    "Name of the Share/Unit",
+    "No. of Shares/Units",
+    "Sale-price per Share/Unit",
+    "Full Value of Consideration",
+    "Cost of acquisition without indexation",
+    "Cost of acquisition",
+    "If the long term capital asset was acquired before 01.02.2018, Lower of Full Value of Consideration and Total Fair Market Value",
+    "Fair Market Value per share/unit as on 31st January 2018",
+    "Total Fair Market Value of capital asset as per section 55(2)(ac)",
+    "Expenditure wholly and exclusively in connection with transfer",
+    "Total deductions",
+    "Balance",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::portfolio::{add_transaction, PortfolioTransaction, TradeSide};
+    use crate::db::test_support::test_db;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn trade(conn: &Connection, symbol: &str, side: TradeSide, quantity: i64, price: f64, date: NaiveDate) {
+        let transaction = PortfolioTransaction { id: 0, symbol: symbol.to_string(), side, quantity, price, charges: 0.0, date, note: None };
+        add_transaction(conn, &transaction).unwrap();
+    }
+
+    /// Buy on `bought` and sell all of it on `sold`
+    fn round_trip(conn: &Connection, symbol: &str, quantity: i64, (bought, buy_price): (NaiveDate, f64), (sold, sell_price): (NaiveDate, f64)) {
+        trade(conn, symbol, TradeSide::Buy, quantity, buy_price, bought);
+        trade(conn, symbol, TradeSide::Sell, quantity, sell_price, sold);
+    }
+
+    /// An NSE EQ session whose high is the grandfathering FMV
+    fn store_high(conn: &Connection, symbol: &str, date: NaiveDate, high: f64) {
+        conn.execute(
+            "INSERT INTO bhavcopy_data (symbol, series, date, open, high, low, close, volume, isin, exchange)
+             VALUES (?1, 'EQ', ?2, ?3, ?3, ?3, ?3, 1000, ?4, 'NSE')",
+            params![symbol, date_to_timestamp(date), high, format!("INE{}", symbol)],
+        ).unwrap();
+    }
+
+    #[test]
+    fn long_term_starts_the_day_after_twelve_months() {
+        // 366 days across 29-Feb-2024 is still exactly twelve months
+        assert_eq!(GainTerm::for_holding(date(2023, 3, 15), date(2024, 3, 15)), GainTerm::Short);
+        assert_eq!(GainTerm::for_holding(date(2023, 3, 15), date(2024, 3, 16)), GainTerm::Long);
+        // 365 days in an ordinary year
+        assert_eq!(GainTerm::for_holding(date(2022, 6, 1), date(2023, 6, 1)), GainTerm::Short);
+        assert_eq!(GainTerm::for_holding(date(2022, 6, 1), date(2023, 6, 2)), GainTerm::Long);
+        // A leap-day purchase reaches twelve months on 28-Feb
+        assert_eq!(GainTerm::for_holding(date(2024, 2, 29), date(2025, 2, 28)), GainTerm::Short);
+        assert_eq!(GainTerm::for_holding(date(2024, 2, 29), date(2025, 3, 1)), GainTerm::Long);
+    }
+
+    #[test]
+    fn grandfathered_cost_is_the_higher_of_cost_and_the_lower_of_fmv_and_sale_value() {
+        let conn = test_db();
+        // Each bought at 100 and sold at 200; only the 31-Jan-2018 FMV differs
+        for (symbol, fmv) in [("BETWEEN", 150.0), ("ABOVE", 250.0), ("BELOW", 80.0)] {
+            store_high(&conn, symbol, grandfathering_date(), fmv);
+            round_trip(&conn, symbol, 10, (date(2017, 6, 1), 100.0), (date(2023, 6, 1), 200.0));
+        }
+        // No session on 31-Jan-2018 itself: the last one before it counts
+        store_high(&conn, "EARLIER", date(2018, 1, 30), 120.0);
+        round_trip(&conn, "EARLIER", 10, (date(2017, 6, 1), 100.0), (date(2023, 6, 1), 200.0));
+        // Bought after the grandfathering date
+        store_high(&conn, "LATER", grandfathering_date(), 150.0);
+        round_trip(&conn, "LATER", 10, (date(2018, 2, 1), 100.0), (date(2023, 6, 1), 200.0));
+
+        let report = capital_gains_report(&conn, FinancialYear(2023)).unwrap();
+        let cost = |symbol: &str| report.gains.iter().find(|g| g.symbol == symbol).unwrap().cost_of_acquisition;
+        assert_eq!(cost("BETWEEN"), 1500.0);
+        assert_eq!(cost("ABOVE"), 2000.0); // Capped at the sale value, so no gain
+        assert_eq!(cost("BELOW"), 1000.0); // Never below the actual cost
+        assert_eq!(cost("EARLIER"), 1200.0);
+        assert_eq!(cost("LATER"), 1000.0);
+        assert_eq!(report.ltcg, 500.0 + 0.0 + 1000.0 + 800.0 + 1000.0);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn grandfathered_lots_without_an_fmv_use_actual_cost_and_warn() {
+        let conn = test_db();
+        round_trip(&conn, "NOPRICE", 10, (date(2017, 6, 1), 100.0), (date(2023, 6, 1), 200.0));
+
+        let report = capital_gains_report(&conn, FinancialYear(2023)).unwrap();
+        assert_eq!(report.gains[0].cost_of_acquisition, 1000.0);
+        assert_eq!(report.warnings, vec!["No 31-Jan-2018 price for NOPRICE; using actual cost"]);
+    }
+
+    #[test]
+    fn the_ltcg_exemption_rises_to_125000_from_fy_2024_25() {
+        assert_eq!(FinancialYear(2017).ltcg_exemption(), None);
+        assert_eq!(FinancialYear(2018).ltcg_exemption(), Some(100_000.0));
+        assert_eq!(FinancialYear(2023).ltcg_exemption(), Some(100_000.0));
+        assert_eq!(FinancialYear(2024).ltcg_exemption(), Some(125_000.0));
+        assert_eq!(FinancialYear::containing(date(2024, 3, 31)), FinancialYear(2023));
+        assert_eq!(FinancialYear::containing(date(2024, 4, 1)), FinancialYear(2024));
+
+        // A 1.5 lakh long-term gain sold on either side of 1-Apr-2024
+        for (sold, year, exemption, taxable) in [
+            (date(2024, 3, 28), FinancialYear(2023), 100_000.0, 50_000.0),
+            (date(2024, 4, 2), FinancialYear(2024), 125_000.0, 25_000.0),
+        ] {
+            let conn = test_db();
+            round_trip(&conn, "INFY", 1000, (date(2022, 1, 3), 100.0), (sold, 250.0));
+            let report = capital_gains_report(&conn, year).unwrap();
+            assert_eq!(report.ltcg, 150_000.0);
+            assert_eq!(report.ltcg_exemption, exemption);
+            assert_eq!(report.taxable_ltcg, taxable);
+        }
+
+        // Before 2018-19 all of it was exempt
+        let conn = test_db();
+        round_trip(&conn, "INFY", 1000, (date(2015, 1, 5), 100.0), (date(2017, 6, 1), 250.0));
+        let report = capital_gains_report(&conn, FinancialYear(2017)).unwrap();
+        assert_eq!((report.ltcg_exemption, report.taxable_ltcg), (150_000.0, 0.0));
+    }
+
+    #[test]
+    fn short_term_losses_are_set_off_against_long_term_gains() {
+        let conn = test_db();
+        round_trip(&conn, "LONG", 100, (date(2023, 1, 2), 1000.0), (date(2024, 6, 3), 1500.0)); // +50,000
+        round_trip(&conn, "SHORT", 100, (date(2024, 5, 2), 1000.0), (date(2024, 7, 1), 800.0)); // -20,000
+
+        let report = capital_gains_report(&conn, FinancialYear(2024)).unwrap();
+        assert_eq!((report.stcg, report.ltcg), (-20_000.0, 50_000.0));
+        assert_eq!(report.stcl_set_off, 20_000.0);
+        assert_eq!(report.ltcg_exemption, 30_000.0);
+        assert_eq!((report.taxable_stcg, report.taxable_ltcg), (0.0, 0.0));
+        assert_eq!(report.carry_forward, (0.0, 0.0));
+    }
+
+    #[test]
+    fn losses_left_after_set_off_are_carried_forward() {
+        // A short-term loss bigger than the long-term gain
+        let conn = test_db();
+        round_trip(&conn, "LONG", 100, (date(2023, 1, 2), 1000.0), (date(2024, 6, 3), 1500.0)); // +50,000
+        round_trip(&conn, "SHORT", 100, (date(2024, 5, 2), 1000.0), (date(2024, 7, 1), 200.0)); // -80,000
+        let report = capital_gains_report(&conn, FinancialYear(2024)).unwrap();
+        assert_eq!(report.stcl_set_off, 50_000.0);
+        assert_eq!(report.carry_forward, (30_000.0, 0.0));
+        assert_eq!((report.taxable_stcg, report.taxable_ltcg), (0.0, 0.0));
+
+        // A long-term loss can't reduce short-term gains
+        let conn = test_db();
+        round_trip(&conn, "LONG", 100, (date(2023, 1, 2), 1000.0), (date(2024, 6, 3), 950.0)); // -5,000
+        round_trip(&conn, "SHORT", 100, (date(2024, 5, 2), 1000.0), (date(2024, 7, 1), 1100.0)); // +10,000
+        let report = capital_gains_report(&conn, FinancialYear(2024)).unwrap();
+        assert_eq!(report.stcl_set_off, 0.0);
+        assert_eq!((report.taxable_stcg, report.taxable_ltcg), (10_000.0, 0.0));
+        assert_eq!(report.carry_forward, (0.0, 5_000.0));
+    }
+
+    #[test]
+    fn schedule_112a_lists_long_term_lots_only() {
+        let conn = test_db();
+        conn.execute("INSERT INTO nse_symbols (symbol, name, created_at, updated_at) VALUES ('OLD', 'Old Industries Ltd', 0, 0)", []).unwrap();
+        store_high(&conn, "OLD", grandfathering_date(), 150.4);
+        round_trip(&conn, "OLD", 10, (date(2017, 6, 1), 100.0), (date(2023, 6, 1), 200.0));
+        trade(&conn, "NEW", TradeSide::Buy, 10, 100.0, date(2021, 6, 1));
+        add_transaction(&conn, &PortfolioTransaction {
+            id: 0,
+            symbol: "NEW".to_string(),
+            side: TradeSide::Sell,
+            quantity: 10,
+            price: 120.25,
+            charges: 12.6,
+            date: date(2023, 7, 3),
+            note: None,
+        }).unwrap();
+        round_trip(&conn, "SHORT", 10, (date(2023, 5, 2), 100.0), (date(2023, 8, 1), 110.0));
+
+        let rows = capital_gains_report(&conn, FinancialYear(2023)).unwrap().schedule_112a_rows();
+        assert_eq!(rows.len(), 2);
+
+        let old = &rows[0];
+        assert_eq!(old.acquired, "On or before 31st January 2018");
+        assert_eq!((old.isin.as_str(), old.name.as_str()), ("INEOLD", "Old Industries Ltd"));
+        assert_eq!((old.full_value, old.actual_cost, old.cost_without_indexation), (2000.0, 1000.0, 1504.0));
+        assert_eq!((old.fmv_per_share, old.total_fmv, old.lower_of_value_and_fmv), (Some(150.4), Some(1504.0), Some(1504.0)));
+        assert_eq!((old.total_deductions, old.balance), (1504.0, 496.0));
+
+        // Not grandfathered: no FMV columns, and the name falls back to the symbol
+        let new = &rows[1];
+        assert_eq!(new.acquired, "After 31st January 2018");
+        assert_eq!((new.isin.as_str(), new.name.as_str()), ("", "NEW"));
+        assert_eq!((new.sale_price, new.full_value, new.transfer_expenses), (120.25, 1203.0, 13.0));
+        assert_eq!((new.fmv_per_share, new.total_fmv, new.lower_of_value_and_fmv), (None, None, None));
+        assert_eq!((new.total_deductions, new.balance), (1013.0, 190.0));
+
+        let mut csv = Vec::new();
+        write_schedule_112a_csv(&capital_gains_report(&conn, FinancialYear(2022)).unwrap(), &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().trim_end(), csv_header());
+    }
+
+    fn csv_header() -> String {
+        SCHEDULE_112A_HEADERS.iter()
+            .map(|h| if h.contains(',') { format!("\"{}\"", h) } else { h.to_string() })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
@@ -0,0 +1,34 @@
+//! Fixtures shared by the db unit tests
+
+use std::path::{Path, PathBuf};
+use rusqlite::Connection;
+use crate::db::migrations::run_migrations;
+
+/// An in-memory database with every migration applied
+pub(crate) fn test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    run_migrations(&conn, None).unwrap();
+    conn
+}
+
+/// A fresh scratch directory, removed again when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("indistocks-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,653 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::db::portfolio::{PortfolioTransaction, TradeSide, insert_transaction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Broker {
+    Zerodha,
+    Upstox,
+    IciciDirect,
+}
+
+impl Broker {
+    pub const ALL: [Broker; 3] = [Broker::Zerodha, Broker::Upstox, Broker::IciciDirect];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Broker::Zerodha => "zerodha",
+            Broker::Upstox => "upstox",
+            Broker::IciciDirect => "icici",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Broker::Zerodha => "Zerodha",
+            Broker::Upstox => "Upstox",
+            Broker::IciciDirect => "ICICI Direct",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().replace([' ', '-', '_'], "").as_str() {
+            "zerodha" | "kite" => Some(Broker::Zerodha),
+            "upstox" => Some(Broker::Upstox),
+            "icici" | "icicidirect" => Some(Broker::IciciDirect),
+            _ => None,
+        }
+    }
+
+    /// Guess the broker from a tradebook's header row (lowercase, trimmed)
+    fn detect(headers: &[String]) -> Option<Self> {
+        let has = |name: &str| headers.iter().any(|h| h == name);
+        if has("trade_type") && has("trade_id") {
+            Some(Broker::Zerodha)
+        } else if has("trade num") || has("scrip code") {
+            Some(Broker::Upstox)
+        } else if has("stock") && has("action") {
+            Some(Broker::IciciDirect)
+        } else {
+            None
+        }
+    }
+
+    fn columns(&self) -> ColumnNames {
+        match self {
+            Broker::Zerodha => ColumnNames {
+                symbol: &["symbol"],
+                isin: &["isin"],
+                date: &["trade_date"],
+                side: &["trade_type"],
+                quantity: &["quantity"],
+                price: &["price"],
+                trade_id: &["trade_id"],
+                segment: &["segment"],
+                charges: &[],
+            },
+            Broker::Upstox => ColumnNames {
+                symbol: &["scrip code", "symbol", "scrip name"],
+                isin: &["isin"],
+                date: &["date", "trade date"],
+                side: &["side", "buy/sell", "transaction type"],
+                quantity: &["quantity", "qty"],
+                price: &["price", "rate", "trade price"],
+                trade_id: &["trade num", "trade no", "trade id"],
+                segment: &["segment", "instrument type"],
+                charges: &["brokerage"],
+            },
+            // ICICI's order reference is shared by every fill of an order, so trades get a synthetic ID
+            Broker::IciciDirect => ColumnNames {
+                symbol: &["stock", "stock code", "symbol"],
+                isin: &["isin", "isin code"],
+                date: &["date", "trade date", "transaction date"],
+                side: &["action", "buy/sell", "transaction type"],
+                quantity: &["qty", "quantity"],
+                price: &["price", "transaction price", "rate"],
+                trade_id: &[],
+                segment: &["segment"],
+                charges: &[
+                    "brokerage",
+                    "brokerage + service tax",
+                    "stt",
+                    "transaction and sebi turnover charges",
+                    "stamp duty",
+                ],
+            },
+        }
+    }
+}
+
+/// Header names a broker uses for each field, lowercase. The first one present is used,
+/// except for charges, where every present column is added up.
+struct ColumnNames {
+    symbol: &'static [&'static str],
+    isin: &'static [&'static str],
+    date: &'static [&'static str],
+    side: &'static [&'static str],
+    quantity: &'static [&'static str],
+    price: &'static [&'static str],
+    trade_id: &'static [&'static str],
+    segment: &'static [&'static str],
+    charges: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TradeStatus {
+    /// Will be added to the portfolio
+    Ready,
+    /// Already imported, or repeated in the file
+    Duplicate,
+    /// No NSE symbol found for the broker symbol or ISIN
+    Unmatched,
+    /// Not an equity delivery trade, or unreadable
+    Skipped,
+}
+
+/// One trade from a broker tradebook, with the NSE symbol it maps to
+#[derive(Debug, Clone, Serialize)]
+pub struct TradebookRow {
+    pub line: u64,
+    pub status: TradeStatus,
+    pub symbol: Option<String>,
+    pub broker_symbol: String,
+    pub isin: Option<String>,
+    pub date: NaiveDate,
+    pub side: TradeSide,
+    pub quantity: i64,
+    pub price: f64,
+    pub charges: f64,
+    /// The broker's trade ID, or one built from the row when the export has none
+    pub trade_id: String,
+    /// Why the row is skipped or unmatched
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TradebookPreview {
+    pub broker: Broker,
+    pub rows: Vec<TradebookRow>,
+    /// Lines that could not be read at all
+    pub rejected: Vec<String>,
+}
+
+impl TradebookPreview {
+    pub fn count(&self, status: TradeStatus) -> usize {
+        self.rows.iter().filter(|r| r.status == status).count()
+    }
+
+    /// Distinct broker symbols that did not map to an NSE symbol
+    pub fn unmatched_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.rows.iter()
+            .filter(|r| r.status == TradeStatus::Unmatched)
+            .map(|r| r.broker_symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TradebookImportReport {
+    pub inserted: usize,
+    pub duplicates: usize,
+    pub unmatched: usize,
+    pub skipped: usize,
+    /// Trades the ledger refused, e.g. sells of shares bought before the tradebook starts
+    pub rejected: Vec<String>,
+}
+
+/// Read a tradebook CSV and match its trades against the database, without changing anything
+pub fn preview_tradebook(conn: &Connection, path: &Path, broker: Option<Broker>) -> Result<TradebookPreview, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    let data = data.strip_prefix(b"\xEF\xBB\xBF".as_slice()).unwrap_or(&data);
+    let mut preview = parse_tradebook(data, broker)?;
+    resolve_tradebook(conn, &mut preview)?;
+    Ok(preview)
+}
+
+/// Parse tradebook CSV data. Rows come back as `Ready` unless they are not equity trades;
+/// [`resolve_tradebook`] fills in symbols and duplicates.
+pub fn parse_tradebook(data: &[u8], broker: Option<Broker>) -> Result<TradebookPreview, Box<dyn std::error::Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+    let records: Vec<csv::StringRecord> = rdr.records().collect::<Result<_, _>>()?;
+
+    // Some exports put a title or client details above the header row
+    let normalize = |record: &csv::StringRecord| -> Vec<String> {
+        record.iter().map(|h| h.trim().trim_matches('"').to_lowercase()).collect()
+    };
+    let (header_idx, broker) = records.iter()
+        .take(20)
+        .enumerate()
+        .find_map(|(i, record)| {
+            let headers = normalize(record);
+            match broker {
+                Some(broker) => broker.columns().date.iter().any(|c| headers.iter().any(|h| h == c))
+                    .then_some((i, broker)),
+                None => Broker::detect(&headers).map(|b| (i, b)),
+            }
+        })
+        .ok_or("Unrecognised tradebook: no Zerodha, Upstox or ICICI Direct header row found")?;
+
+    let headers = normalize(&records[header_idx]);
+    let names = broker.columns();
+    let column = |aliases: &[&str]| aliases.iter().find_map(|a| headers.iter().position(|h| h == a));
+    let symbol_idx = column(names.symbol).ok_or("Missing symbol column")?;
+    let date_idx = column(names.date).ok_or("Missing trade date column")?;
+    let side_idx = column(names.side).ok_or("Missing buy/sell column")?;
+    let quantity_idx = column(names.quantity).ok_or("Missing quantity column")?;
+    let price_idx = column(names.price).ok_or("Missing price column")?;
+    let isin_idx = column(names.isin);
+    let trade_id_idx = column(names.trade_id);
+    let segment_idx = column(names.segment);
+    let charge_idxs: Vec<usize> = names.charges.iter()
+        .filter_map(|c| headers.iter().position(|h| h == c))
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut rejected = Vec::new();
+    // Occurrences of identical trades, so synthetic IDs stay unique and stable across re-imports
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    for record in &records[header_idx + 1..] {
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let text = |idx: usize| record.get(idx).map(|s| s.trim()).unwrap_or("");
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let broker_symbol = text(symbol_idx).to_uppercase();
+        let date = parse_trade_date(text(date_idx));
+        let side = TradeSide::parse(text(side_idx));
+        let quantity = parse_number(text(quantity_idx)).filter(|q| q.fract() == 0.0).map(|q| q as i64);
+        let price = parse_number(text(price_idx));
+        let (Some(date), Some(side), Some(quantity), Some(price)) = (date, side, quantity, price) else {
+            // Summary and footer lines are common at the end of broker exports
+            if !broker_symbol.is_empty() {
+                rejected.push(format!("line {}: could not read date, side, quantity or price", line));
+            }
+            continue;
+        };
+        let isin = isin_idx.map(text).filter(|s| !s.is_empty()).map(|s| s.to_uppercase());
+        let charges: f64 = charge_idxs.iter().filter_map(|&idx| parse_number(text(idx))).sum();
+
+        let trade_id = match trade_id_idx.map(text).filter(|s| !s.is_empty()) {
+            Some(id) => id.to_string(),
+            None => {
+                let key = format!("{}|{}|{}|{}|{}", date, broker_symbol, side.as_str(), quantity, price);
+                let n = occurrences.entry(key.clone()).or_default();
+                *n += 1;
+                format!("{}#{}", key, n)
+            }
+        };
+
+        let segment = segment_idx.map(text).unwrap_or("").to_uppercase();
+        let reason = if !is_equity_segment(&segment) {
+            Some(format!("Not an equity trade (segment {})", segment))
+        } else if quantity <= 0 || price <= 0.0 {
+            Some("Quantity and price must be positive".to_string())
+        } else {
+            None
+        };
+
+        rows.push(TradebookRow {
+            line,
+            status: if reason.is_some() { TradeStatus::Skipped } else { TradeStatus::Ready },
+            symbol: None,
+            broker_symbol,
+            isin,
+            date,
+            side,
+            quantity,
+            price,
+            charges: charges.abs(),
+            trade_id,
+            reason,
+        });
+    }
+
+    Ok(TradebookPreview { broker, rows, rejected })
+}
+
+/// Map every non-skipped row to an NSE symbol and flag trades that were already imported.
+/// Call again after saving a mapping with [`save_broker_symbol_mapping`].
+pub fn resolve_tradebook(conn: &Connection, preview: &mut TradebookPreview) -> rusqlite::Result<()> {
+    let broker = preview.broker;
+    let mut symbols: HashMap<(Option<String>, String), Option<String>> = HashMap::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut imported_stmt = conn.prepare(
+        "SELECT EXISTS(SELECT 1 FROM portfolio_transactions WHERE broker = ?1 AND trade_id = ?2)"
+    )?;
+
+    for row in &mut preview.rows {
+        if row.status == TradeStatus::Skipped {
+            continue;
+        }
+
+        let imported: bool = imported_stmt.query_row(params![broker.as_str(), row.trade_id], |r| r.get(0))?;
+        if imported || !seen.insert(row.trade_id.clone()) {
+            row.status = TradeStatus::Duplicate;
+            row.reason = Some(if imported { "Already imported" } else { "Repeated in this file" }.to_string());
+            continue;
+        }
+
+        let key = (row.isin.clone(), row.broker_symbol.clone());
+        let symbol = match symbols.get(&key) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol = resolve_symbol(conn, broker, row.isin.as_deref(), &row.broker_symbol)?;
+                symbols.insert(key, symbol.clone());
+                symbol
+            }
+        };
+
+        row.symbol = symbol;
+        if row.symbol.is_some() {
+            row.status = TradeStatus::Ready;
+            row.reason = None;
+        } else {
+            row.status = TradeStatus::Unmatched;
+            row.reason = Some(match &row.isin {
+                Some(isin) => format!("No NSE symbol for {} or ISIN {}", row.broker_symbol, isin),
+                None => format!("No NSE symbol for {}", row.broker_symbol),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// NSE symbol for a broker's trade: by ISIN in bhavcopy_data, then a saved mapping,
+/// then the broker symbol itself (with any `-EQ`/`-BE` series suffix removed)
+fn resolve_symbol(conn: &Connection, broker: Broker, isin: Option<&str>, broker_symbol: &str) -> rusqlite::Result<Option<String>> {
+    if let Some(isin) = isin {
+        let symbol: Option<String> = conn.query_row(
+            "SELECT symbol FROM bhavcopy_data WHERE isin = ?1 AND exchange = 'NSE' ORDER BY date DESC LIMIT 1",
+            params![isin],
+            |row| row.get(0),
+        ).optional()?;
+        if symbol.is_some() {
+            return Ok(symbol);
+        }
+    }
+
+    let mapped: Option<String> = conn.query_row(
+        "SELECT symbol FROM broker_symbol_map WHERE broker = ?1 AND broker_symbol = ?2",
+        params![broker.as_str(), broker_symbol],
+        |row| row.get(0),
+    ).optional()?;
+    if mapped.is_some() {
+        return Ok(mapped);
+    }
+
+    let mut candidates = vec![broker_symbol];
+    if let Some((base, series)) = broker_symbol.rsplit_once('-') {
+        if series.len() == 2 && series.chars().all(|c| c.is_ascii_alphabetic()) {
+            candidates.push(base);
+        }
+    }
+    for candidate in candidates {
+        let known: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM nse_symbols WHERE symbol = ?1)
+                 OR EXISTS(SELECT 1 FROM bhavcopy_data WHERE symbol = ?1 AND exchange = 'NSE')",
+            params![candidate],
+            |row| row.get(0),
+        )?;
+        if known {
+            return Ok(Some(candidate.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Remember that a broker's symbol (e.g. ICICI's `RELIND`) is an NSE symbol, for this and later imports
+pub fn save_broker_symbol_mapping(conn: &Connection, broker: Broker, broker_symbol: &str, symbol: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO broker_symbol_map (broker, broker_symbol, symbol, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(broker, broker_symbol) DO UPDATE SET symbol = excluded.symbol, updated_at = excluded.updated_at",
+        params![broker.as_str(), broker_symbol.trim().to_uppercase(), symbol.trim().to_uppercase(), chrono::Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
+/// Add the preview's `Ready` trades to the portfolio in one transaction.
+/// Buys on a day go in before that day's sells so intraday round trips are covered.
+pub fn commit_tradebook(conn: &Connection, preview: &TradebookPreview) -> Result<TradebookImportReport, Box<dyn std::error::Error>> {
+    let mut report = TradebookImportReport {
+        duplicates: preview.count(TradeStatus::Duplicate),
+        unmatched: preview.count(TradeStatus::Unmatched),
+        skipped: preview.count(TradeStatus::Skipped),
+        ..Default::default()
+    };
+
+    let mut ready: Vec<&TradebookRow> = preview.rows.iter()
+        .filter(|r| r.status == TradeStatus::Ready)
+        .collect();
+    ready.sort_by_key(|r| (r.date, r.side == TradeSide::Sell, r.line));
+
+    let tx = conn.unchecked_transaction()?;
+    for row in ready {
+        let Some(symbol) = &row.symbol else {
+            continue;
+        };
+        let transaction = PortfolioTransaction {
+            id: 0,
+            symbol: symbol.clone(),
+            side: row.side,
+            quantity: row.quantity,
+            price: row.price,
+            charges: row.charges,
+            date: row.date,
+            note: None,
+        };
+        match insert_transaction(&tx, &transaction, Some((preview.broker.as_str(), &row.trade_id))) {
+            Ok(_) => report.inserted += 1,
+            Err(e) => report.rejected.push(format!("line {}: {}", row.line, e)),
+        }
+    }
+    tx.commit()?;
+
+    Ok(report)
+}
+
+fn parse_trade_date(value: &str) -> Option<NaiveDate> {
+    const FORMATS: [&str; 8] = ["%Y-%m-%d", "%d-%m-%Y", "%d/%m/%Y", "%d-%b-%Y", "%d-%b-%y", "%d %b %Y", "%d/%m/%y", "%d-%m-%y"];
+    let value = value.trim();
+    // Drop a time of day, as in `2024-06-10T09:15:02` or `10-06-2024 09:15:02`
+    let date_part = value.split(['T', ' ']).next().unwrap_or(value);
+    // %Y takes any number of digits, so `10-06-24` would otherwise be read as the year 24
+    [value, date_part].iter()
+        .find_map(|v| FORMATS.iter().find_map(|fmt| NaiveDate::parse_from_str(v, fmt).ok().filter(|d| d.year() >= 1900)))
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim().replace(',', "").parse().ok()
+}
+
+/// Cash-market segments; futures, options, currency and commodity trades are left out
+fn is_equity_segment(segment: &str) -> bool {
+    !["FO", "F&O", "FUT", "OPT", "CD", "CURR", "COM", "MCX"].iter().any(|s| segment.contains(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::portfolio::get_transactions;
+    use crate::db::test_support::test_db;
+
+    const ZERODHA: &str = "\
+symbol,isin,trade_date,exchange,segment,series,trade_type,auction,quantity,price,trade_id,order_id,order_execution_time
+INFY,INE009A01021,2024-06-10,NSE,EQ,EQ,buy,false,10.000000,1500.50,1001,2001,2024-06-10T09:15:02
+INFY,INE009A01021,2024-06-12,NSE,EQ,EQ,sell,false,4.000000,1550.00,1002,2002,2024-06-12T10:01:00
+NIFTY24JUN23000CE,,2024-06-12,NFO,FO,,buy,false,50.000000,120.00,1003,2003,2024-06-12T10:05:00
+";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn add_nse_symbol(conn: &Connection, symbol: &str) {
+        conn.execute("INSERT INTO nse_symbols (symbol, created_at, updated_at) VALUES (?1, 0, 0)", params![symbol]).unwrap();
+    }
+
+    fn statuses(preview: &TradebookPreview) -> Vec<TradeStatus> {
+        preview.rows.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn reads_a_zerodha_tradebook() {
+        let preview = parse_tradebook(ZERODHA.as_bytes(), None).unwrap();
+        assert_eq!(preview.broker, Broker::Zerodha);
+        assert_eq!(preview.rows.len(), 3);
+
+        let buy = &preview.rows[0];
+        assert_eq!((buy.broker_symbol.as_str(), buy.isin.as_deref()), ("INFY", Some("INE009A01021")));
+        assert_eq!((buy.date, buy.side, buy.quantity, buy.price), (date(2024, 6, 10), TradeSide::Buy, 10, 1500.5));
+        assert_eq!((buy.trade_id.as_str(), buy.line), ("1001", 2));
+        assert_eq!(preview.rows[1].side, TradeSide::Sell);
+
+        // Derivatives are left out of the portfolio
+        assert_eq!(statuses(&preview), vec![TradeStatus::Ready, TradeStatus::Ready, TradeStatus::Skipped]);
+        assert_eq!(preview.rows[2].reason.as_deref(), Some("Not an equity trade (segment FO)"));
+    }
+
+    #[test]
+    fn finds_the_upstox_header_below_a_title() {
+        let data = "\
+Trade Report,,,,,,,,,,
+Client ID: ABC123,,,,,,,,,,
+Date,Company,Exchange,Segment,Scrip Code,Instrument Type,Side,Quantity,Price,Trade Num,Brokerage
+10-06-2024,Tata Consultancy,NSE,EQ,TCS-EQ,EQUITY,Buy,\"1,000\",\"3,850.25\",T1,20.00
+11/06/2024,Tata Consultancy,NSE,EQ,TCS-EQ,EQUITY,S,500,3900,T2,-15.5
+,,,,,,,,,,
+Total,,,,,,,,,,
+";
+        let preview = parse_tradebook(data.as_bytes(), None).unwrap();
+        assert_eq!(preview.broker, Broker::Upstox);
+        assert_eq!(preview.rows.len(), 2);
+        assert!(preview.rejected.is_empty(), "{:?}", preview.rejected);
+
+        let buy = &preview.rows[0];
+        assert_eq!((buy.broker_symbol.as_str(), buy.date, buy.side), ("TCS-EQ", date(2024, 6, 10), TradeSide::Buy));
+        assert_eq!((buy.quantity, buy.price, buy.charges, buy.trade_id.as_str()), (1000, 3850.25, 20.0, "T1"));
+        let sell = &preview.rows[1];
+        assert_eq!((sell.date, sell.side, sell.charges), (date(2024, 6, 11), TradeSide::Sell, 15.5));
+    }
+
+    #[test]
+    fn reads_an_icici_direct_trade_book() {
+        let data = "\
+Date,Stock,Action,Qty,Price,Trade Value,Order Ref.,Settlement,Segment,DP Id,Exchange,STT,Transaction and SEBI Turnover charges,Stamp Duty,Brokerage + Service Tax
+10-Jun-2024,RELIND,Buy,5,2900,14500,OR1,2024111,Cash,IN30,NSE,14.5,0.45,2.18,10.62
+10-Jun-2024,RELIND,Buy,5,2900,14500,OR1,2024111,Cash,IN30,NSE,14.5,0.45,2.18,10.62
+12 Jun 2024,RELIND,Sell,x,2950,,OR2,2024112,Cash,IN30,NSE,,,,
+";
+        let preview = parse_tradebook(data.as_bytes(), None).unwrap();
+        assert_eq!(preview.broker, Broker::IciciDirect);
+        assert_eq!(preview.rows.len(), 2);
+        assert_eq!(preview.rejected, vec!["line 4: could not read date, side, quantity or price"]);
+
+        // Every charge column is added up
+        assert!((preview.rows[0].charges - 27.75).abs() < 1e-9);
+        // Identical fills get distinct synthetic IDs
+        assert_eq!(preview.rows[0].trade_id, "2024-06-10|RELIND|buy|5|2900#1");
+        assert_eq!(preview.rows[1].trade_id, "2024-06-10|RELIND|buy|5|2900#2");
+    }
+
+    #[test]
+    fn unknown_headers_are_rejected_unless_the_broker_is_given() {
+        let data = "Trade Date,Symbol,Type,Qty,Price\n2024-06-10,INFY,B,1,1500\n";
+        let err = parse_tradebook(data.as_bytes(), None).unwrap_err();
+        assert_eq!(err.to_string(), "Unrecognised tradebook: no Zerodha, Upstox or ICICI Direct header row found");
+
+        let err = parse_tradebook(data.as_bytes(), Some(Broker::Upstox)).unwrap_err();
+        assert_eq!(err.to_string(), "Missing buy/sell column");
+
+        let data = "Trade Date,Symbol,Buy/Sell,Qty,Price\n2024-06-10,INFY,B,1,1500\n";
+        let preview = parse_tradebook(data.as_bytes(), Some(Broker::Upstox)).unwrap();
+        assert_eq!((preview.rows[0].side, preview.rows[0].quantity), (TradeSide::Buy, 1));
+    }
+
+    #[test]
+    fn trade_dates_in_every_broker_format() {
+        let expected = Some(date(2024, 6, 10));
+        for value in [
+            "2024-06-10",
+            "10-06-2024",
+            "10/06/2024",
+            "10-Jun-2024",
+            "10-jun-24",
+            "10 Jun 2024",
+            "10/06/24",
+            "10-06-24",
+            "2024-06-10T09:15:02",
+            "10-06-2024 09:15:02",
+            " 10-Jun-2024 ",
+        ] {
+            assert_eq!(parse_trade_date(value), expected, "{}", value);
+        }
+        assert_eq!(parse_trade_date("2024-13-01"), None);
+        assert_eq!(parse_trade_date(""), None);
+    }
+
+    #[test]
+    fn reimporting_a_tradebook_adds_nothing() {
+        let conn = test_db();
+        add_nse_symbol(&conn, "INFY");
+
+        let mut preview = parse_tradebook(ZERODHA.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut preview).unwrap();
+        let report = commit_tradebook(&conn, &preview).unwrap();
+        assert_eq!((report.inserted, report.duplicates, report.skipped), (2, 0, 1));
+
+        let mut again = parse_tradebook(ZERODHA.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut again).unwrap();
+        assert_eq!(statuses(&again), vec![TradeStatus::Duplicate, TradeStatus::Duplicate, TradeStatus::Skipped]);
+        assert_eq!(again.rows[0].reason.as_deref(), Some("Already imported"));
+        let report = commit_tradebook(&conn, &again).unwrap();
+        assert_eq!((report.inserted, report.duplicates), (0, 2));
+        assert_eq!(get_transactions(&conn, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn repeated_trade_ids_in_one_file_are_duplicates() {
+        let conn = test_db();
+        add_nse_symbol(&conn, "INFY");
+        let data = format!("{}{}", ZERODHA, "INFY,INE009A01021,2024-06-10,NSE,EQ,EQ,buy,false,10,1500.50,1001,2001,2024-06-10T09:15:02\n");
+
+        let mut preview = parse_tradebook(data.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut preview).unwrap();
+        assert_eq!(preview.rows[3].status, TradeStatus::Duplicate);
+        assert_eq!(preview.rows[3].reason.as_deref(), Some("Repeated in this file"));
+    }
+
+    #[test]
+    fn synthetic_ids_are_stable_across_reimports() {
+        let conn = test_db();
+        add_nse_symbol(&conn, "RELIANCE");
+        save_broker_symbol_mapping(&conn, Broker::IciciDirect, "relind", "reliance").unwrap();
+        let data = "\
+Date,Stock,Action,Qty,Price
+10-Jun-2024,RELIND,Buy,5,2900
+10-Jun-2024,RELIND,Buy,5,2900
+";
+        let mut preview = parse_tradebook(data.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut preview).unwrap();
+        assert_eq!(preview.rows[0].symbol.as_deref(), Some("RELIANCE"));
+        assert_eq!(commit_tradebook(&conn, &preview).unwrap().inserted, 2);
+
+        let mut again = parse_tradebook(data.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut again).unwrap();
+        assert_eq!(again.count(TradeStatus::Duplicate), 2);
+    }
+
+    #[test]
+    fn symbols_resolve_by_isin_then_mapping_then_name() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO bhavcopy_data (symbol, series, date, close, isin, exchange) VALUES ('HDFCBANK', 'EQ', 0, 1600, 'INE040A01034', 'NSE')",
+            [],
+        ).unwrap();
+        add_nse_symbol(&conn, "TCS");
+        let data = "\
+Date,Company,Exchange,Segment,Scrip Code,ISIN,Side,Quantity,Price,Trade Num
+10-06-2024,HDFC Bank,NSE,EQ,HDFC BANK LTD,INE040A01034,Buy,1,1600,T1
+10-06-2024,TCS,NSE,EQ,TCS-EQ,,Buy,1,3850,T2
+10-06-2024,Unknown,NSE,EQ,NOSUCH,INE000000000,Buy,1,10,T3
+";
+        let mut preview = parse_tradebook(data.as_bytes(), None).unwrap();
+        resolve_tradebook(&conn, &mut preview).unwrap();
+        let symbols: Vec<Option<&str>> = preview.rows.iter().map(|r| r.symbol.as_deref()).collect();
+        assert_eq!(symbols, vec![Some("HDFCBANK"), Some("TCS"), None]);
+        assert_eq!(preview.rows[2].reason.as_deref(), Some("No NSE symbol for NOSUCH or ISIN INE000000000"));
+        assert_eq!(preview.unmatched_symbols(), vec!["NOSUCH"]);
+    }
+}
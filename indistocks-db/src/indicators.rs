@@ -0,0 +1,388 @@
+use serde::{Deserialize, Serialize};
+use crate::models::PriceBar;
+
+/// An indicator together with its parameters, as shown on the chart
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Indicator {
+    Sma { period: usize },
+    Ema { period: usize },
+    Rsi { period: usize },
+    Macd { fast: usize, slow: usize, signal: usize },
+    Bollinger { period: usize, std_dev: f64 },
+    Atr { period: usize },
+    Vwap { period: usize },
+}
+
+/// One line of an indicator, e.g. the upper Bollinger band or the MACD signal line
+#[derive(Debug, Clone)]
+pub struct IndicatorSeries {
+    pub name: String,
+    /// One value per input bar; `None` while the indicator is still warming up
+    pub values: Vec<Option<f64>>,
+    /// Draw as a histogram instead of a line
+    pub histogram: bool,
+}
+
+impl Indicator {
+    /// Every indicator with its customary default parameters
+    pub fn defaults() -> [Indicator; 7] {
+        [
+            Indicator::Sma { period: 20 },
+            Indicator::Ema { period: 20 },
+            Indicator::Bollinger { period: 20, std_dev: 2.0 },
+            Indicator::Vwap { period: 20 },
+            Indicator::Rsi { period: 14 },
+            Indicator::Macd { fast: 12, slow: 26, signal: 9 },
+            Indicator::Atr { period: 14 },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Indicator::Sma { .. } => "SMA",
+            Indicator::Ema { .. } => "EMA",
+            Indicator::Rsi { .. } => "RSI",
+            Indicator::Macd { .. } => "MACD",
+            Indicator::Bollinger { .. } => "Bollinger Bands",
+            Indicator::Atr { .. } => "ATR",
+            Indicator::Vwap { .. } => "VWAP",
+        }
+    }
+
+    /// Short label including the parameters, e.g. `SMA(20)` or `MACD(12,26,9)`
+    pub fn label(&self) -> String {
+        match self {
+            Indicator::Sma { period } => format!("SMA({})", period),
+            Indicator::Ema { period } => format!("EMA({})", period),
+            Indicator::Rsi { period } => format!("RSI({})", period),
+            Indicator::Macd { fast, slow, signal } => format!("MACD({},{},{})", fast, slow, signal),
+            Indicator::Bollinger { period, std_dev } => format!("BB({},{})", period, std_dev),
+            Indicator::Atr { period } => format!("ATR({})", period),
+            Indicator::Vwap { period } => format!("VWAP({})", period),
+        }
+    }
+
+    /// Overlays share the price axis; the rest (RSI, MACD, ATR) get their own pane
+    pub fn is_overlay(&self) -> bool {
+        matches!(self, Indicator::Sma { .. } | Indicator::Ema { .. } | Indicator::Bollinger { .. } | Indicator::Vwap { .. })
+    }
+
+    /// Compute the indicator over `bars` (oldest first)
+    pub fn compute(&self, bars: &[PriceBar]) -> Vec<IndicatorSeries> {
+        let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+        let line = |name: String, values: Vec<Option<f64>>| IndicatorSeries { name, values, histogram: false };
+
+        match *self {
+            Indicator::Sma { period } => vec![line(self.label(), sma(&closes, period))],
+            Indicator::Ema { period } => vec![line(self.label(), ema(&closes, period))],
+            Indicator::Rsi { period } => vec![line(self.label(), rsi(&closes, period))],
+            Indicator::Atr { period } => vec![line(self.label(), atr(bars, period))],
+            Indicator::Vwap { period } => vec![line(self.label(), vwap(bars, period))],
+            Indicator::Bollinger { period, std_dev } => {
+                let bands = bollinger_bands(&closes, period, std_dev);
+                vec![
+                    line(format!("{} upper", self.label()), bands.upper),
+                    line(format!("{} middle", self.label()), bands.middle),
+                    line(format!("{} lower", self.label()), bands.lower),
+                ]
+            }
+            Indicator::Macd { fast, slow, signal } => {
+                let macd = macd(&closes, fast, slow, signal);
+                vec![
+                    line(self.label(), macd.macd),
+                    line("Signal".to_string(), macd.signal),
+                    IndicatorSeries { name: "Histogram".to_string(), values: macd.histogram, histogram: true },
+                ]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    pub upper: Vec<Option<f64>>,
+    pub middle: Vec<Option<f64>>,
+    pub lower: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Macd {
+    pub macd: Vec<Option<f64>>,
+    pub signal: Vec<Option<f64>>,
+    pub histogram: Vec<Option<f64>>,
+}
+
+/// Simple moving average
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 {
+        return result;
+    }
+
+    let mut sum = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            result[i] = Some(sum / period as f64);
+        }
+    }
+    result
+}
+
+/// Exponential moving average, seeded with the SMA of the first `period` values
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return result;
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+    for i in period..values.len() {
+        current += alpha * (values[i] - current);
+        result[i] = Some(current);
+    }
+    result
+}
+
+/// EMA over a series that itself starts with a warm-up period
+fn ema_of_optional(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    let start = values.iter().position(|v| v.is_some()).unwrap_or(values.len());
+    let defined: Vec<f64> = values[start..].iter().map(|v| v.unwrap_or(0.0)).collect();
+
+    let mut result = vec![None; start];
+    result.extend(ema(&defined, period));
+    result
+}
+
+/// Relative strength index with Wilder's smoothing
+pub fn rsi(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 || values.len() <= period {
+        return result;
+    }
+
+    let change = |i: usize| values[i] - values[i - 1];
+    let mut avg_gain = (1..=period).map(|i| change(i).max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = (1..=period).map(|i| (-change(i)).max(0.0)).sum::<f64>() / period as f64;
+
+    let rsi_value = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            if gain == 0.0 { 50.0 } else { 100.0 }
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+
+    result[period] = Some(rsi_value(avg_gain, avg_loss));
+    for (i, slot) in result.iter_mut().enumerate().skip(period + 1) {
+        let delta = change(i);
+        avg_gain = (avg_gain * (period - 1) as f64 + delta.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-delta).max(0.0)) / period as f64;
+        *slot = Some(rsi_value(avg_gain, avg_loss));
+    }
+    result
+}
+
+/// MACD line (fast EMA - slow EMA), its signal EMA and the histogram between them
+pub fn macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> Macd {
+    let fast_ema = ema(values, fast);
+    let slow_ema = ema(values, slow);
+
+    let macd: Vec<Option<f64>> = fast_ema.iter().zip(&slow_ema)
+        .map(|(f, s)| Some((*f)? - (*s)?))
+        .collect();
+    let signal_line = ema_of_optional(&macd, signal);
+    let histogram = macd.iter().zip(&signal_line)
+        .map(|(m, s)| Some((*m)? - (*s)?))
+        .collect();
+
+    Macd { macd, signal: signal_line, histogram }
+}
+
+/// Bollinger bands: SMA ± `std_dev` population standard deviations
+pub fn bollinger_bands(values: &[f64], period: usize, std_dev: f64) -> BollingerBands {
+    let middle = sma(values, period);
+    let mut upper = vec![None; values.len()];
+    let mut lower = vec![None; values.len()];
+
+    for (i, mean) in middle.iter().enumerate() {
+        if let Some(mean) = mean {
+            let window = &values[i + 1 - period..=i];
+            let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / period as f64;
+            let width = std_dev * variance.sqrt();
+            upper[i] = Some(mean + width);
+            lower[i] = Some(mean - width);
+        }
+    }
+
+    BollingerBands { upper, middle, lower }
+}
+
+/// Average true range with Wilder's smoothing
+pub fn atr(bars: &[PriceBar], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; bars.len()];
+    if period == 0 || bars.len() < period {
+        return result;
+    }
+
+    let true_range: Vec<f64> = bars.iter().enumerate()
+        .map(|(i, bar)| {
+            let range = bar.high - bar.low;
+            match i.checked_sub(1).map(|p| bars[p].close) {
+                Some(prev_close) => range.max((bar.high - prev_close).abs()).max((bar.low - prev_close).abs()),
+                None => range,
+            }
+        })
+        .collect();
+
+    let mut current = true_range[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+    for i in period..bars.len() {
+        current = (current * (period - 1) as f64 + true_range[i]) / period as f64;
+        result[i] = Some(current);
+    }
+    result
+}
+
+/// Rolling volume-weighted average of the typical price (high + low + close) / 3 over `period` days.
+/// With daily bars a session VWAP is meaningless, so the window is anchored on the last `period` sessions.
+pub fn vwap(bars: &[PriceBar], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; bars.len()];
+    if period == 0 {
+        return result;
+    }
+
+    let weighted = |bar: &PriceBar| (bar.high + bar.low + bar.close) / 3.0 * bar.volume as f64;
+    let mut price_volume = 0.0;
+    let mut volume = 0.0;
+    for (i, bar) in bars.iter().enumerate() {
+        price_volume += weighted(bar);
+        volume += bar.volume as f64;
+        if i >= period {
+            price_volume -= weighted(&bars[i - period]);
+            volume -= bars[i - period].volume as f64;
+        }
+        if i + 1 >= period && volume > 0.0 {
+            result[i] = Some(price_volume / volume);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn assert_series(actual: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            match (a, e) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected),
+                _ => assert_eq!(a, e, "{:?} != {:?}", actual, expected),
+            }
+        }
+    }
+
+    fn bar(high: f64, low: f64, close: f64, volume: i64) -> PriceBar {
+        PriceBar { date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), open: close, high, low, close, volume }
+    }
+
+    #[test]
+    fn sma_averages_the_last_period_values() {
+        assert_series(&sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3), &[None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        assert_series(&sma(&[1.0, 2.0], 3), &[None, None]);
+        assert_series(&sma(&[1.0, 2.0], 0), &[None, None]);
+    }
+
+    #[test]
+    fn ema_is_seeded_with_the_sma() {
+        // Alpha is 0.5 for a period of 3
+        assert_series(&ema(&[2.0, 4.0, 6.0, 8.0, 12.0], 3), &[None, None, Some(4.0), Some(6.0), Some(9.0)]);
+        assert_series(&ema(&[2.0, 4.0], 3), &[None, None]);
+        assert_series(&ema(&[2.0, 4.0], 0), &[None, None]);
+    }
+
+    #[test]
+    fn rsi_uses_wilders_smoothing() {
+        // Changes +1, -1, +2, -1. A simple average of the last two would give 66.67 on the last bar.
+        assert_series(&rsi(&[10.0, 11.0, 10.0, 12.0, 11.0], 2), &[None, None, Some(50.0), Some(100.0 - 100.0 / 6.0), Some(50.0)]);
+        // Only gains, and no change at all
+        assert_series(&rsi(&[1.0, 2.0, 3.0], 2), &[None, None, Some(100.0)]);
+        assert_series(&rsi(&[5.0, 5.0, 5.0], 2), &[None, None, Some(50.0)]);
+        // The first value needs `period` changes
+        assert_series(&rsi(&[1.0, 2.0], 2), &[None, None]);
+    }
+
+    #[test]
+    fn macd_signal_and_histogram() {
+        let macd = macd(&[1.0, 2.0, 4.0, 8.0, 16.0], 2, 3, 2);
+        // EMA(2) - EMA(3) from the third value, when the slow EMA has its seed
+        assert_series(&macd.macd, &[None, None, Some(5.0 / 6.0), Some(11.0 / 9.0), Some(239.0 / 108.0)]);
+        // The signal EMA(2) needs two MACD values
+        assert_series(&macd.signal, &[None, None, None, Some(37.0 / 36.0), Some(589.0 / 324.0)]);
+        assert_series(&macd.histogram, &[None, None, None, Some(7.0 / 36.0), Some(32.0 / 81.0)]);
+    }
+
+    #[test]
+    fn bollinger_bands_use_the_population_standard_deviation() {
+        // Mean 5, population standard deviation 2
+        let bands = bollinger_bands(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], 8, 2.0);
+        let warm_up = [None; 7];
+        assert_series(&bands.middle, &[&warm_up[..], &[Some(5.0)]].concat());
+        assert_series(&bands.upper, &[&warm_up[..], &[Some(9.0)]].concat());
+        assert_series(&bands.lower, &[&warm_up[..], &[Some(1.0)]].concat());
+    }
+
+    #[test]
+    fn atr_includes_gaps_from_the_previous_close() {
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),   // TR 2
+            bar(11.0, 9.0, 10.5, 100),  // TR 2
+            bar(14.0, 13.0, 13.5, 100), // Gap up: TR 14 - 10.5 = 3.5
+            bar(12.0, 11.0, 11.5, 100), // Gap down: TR 13.5 - 11 = 2.5
+        ];
+        assert_series(&atr(&bars, 2), &[None, Some(2.0), Some(2.75), Some(2.625)]);
+        assert_series(&atr(&bars[..1], 2), &[None]);
+    }
+
+    #[test]
+    fn vwap_weights_the_typical_price_by_volume() {
+        let bars = [
+            bar(11.0, 9.0, 10.0, 100),
+            bar(21.0, 19.0, 20.0, 300),
+            bar(31.0, 29.0, 30.0, 100),
+            bar(41.0, 39.0, 40.0, 0),
+            bar(51.0, 49.0, 50.0, 0),
+        ];
+        // A window without any volume has no VWAP
+        assert_series(&vwap(&bars, 2), &[None, Some(17.5), Some(22.5), Some(30.0), None]);
+    }
+
+    #[test]
+    fn compute_returns_each_line_of_the_indicator() {
+        let bars: Vec<PriceBar> = (1..=40).map(|i| bar(i as f64 + 1.0, i as f64 - 1.0, i as f64, 100)).collect();
+        // Index of the first value of each line with the default parameters
+        let warm_up = [vec![19], vec![19], vec![19, 19, 19], vec![19], vec![14], vec![25, 33, 33], vec![13]];
+        for (indicator, first_values) in Indicator::defaults().iter().zip(warm_up) {
+            let series = indicator.compute(&bars);
+            assert_eq!(series.len(), first_values.len(), "{}", indicator.label());
+            for (line, first) in series.iter().zip(first_values) {
+                assert_eq!(line.values.len(), bars.len(), "{}", line.name);
+                assert_eq!(line.values.iter().position(Option::is_some), Some(first), "{}", line.name);
+                assert!(line.values[first..].iter().all(Option::is_some), "{}", line.name);
+            }
+        }
+
+        let names: Vec<(String, bool)> = Indicator::Macd { fast: 12, slow: 26, signal: 9 }.compute(&bars)
+            .into_iter()
+            .map(|s| (s.name, s.histogram))
+            .collect();
+        assert_eq!(names, vec![("MACD(12,26,9)".to_string(), false), ("Signal".to_string(), false), ("Histogram".to_string(), true)]);
+    }
+}
@@ -1,16 +1,72 @@
 pub mod db;
 pub mod models;
+pub mod indicators;
+pub mod screener;
+pub mod backtest;
+pub mod logging;
 
 pub use db::*;
-pub use db::downloads::{download_bhavcopy_with_limit, download_bhavcopy_with_date_range};
-pub use db::operations::{StockData, get_all_stocks_with_metrics, get_stock_data_in_range};
+pub use db::downloads::{download_bhavcopy_with_limit, download_bhavcopy_with_date_range, sync_bhavcopy, fill_bhavcopy_gaps, DownloadControl};
+pub use db::operations::{StockData, StockColumn, StockSort, StocksGridLayout, get_all_stocks_with_metrics, get_stock_data_in_range};
+pub use models::{DeliveryRow, Exchange, FoInstrument, FoRow, IndexRow, OptionType, PriceBar};
 
 // Re-export rusqlite types
 pub use rusqlite::{Connection, Result};
 
+/// What happened to one trading day of a BhavCopy download
+#[derive(Debug, Clone, PartialEq)]
+pub enum DayOutcome {
+    Ingested { inserted: usize, skipped: usize, rejected: usize },
+    /// No file for the day, usually a weekend or market holiday
+    NotPublished,
+    Failed(String),
+}
+
+/// Why a BhavCopy download stopped before covering its whole range
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    LimitReached(usize),
+    TooManyMissingDays(usize),
+    Cancelled,
+}
+
+/// Events sent from a BhavCopy download thread
 #[derive(Debug)]
 pub enum BhavCopyMessage {
-    Progress(String),
+    /// A range of `days` calendar days is about to be downloaded; sent once per range before any day starts
+    Planned { from: chrono::NaiveDate, to: chrono::NaiveDate, days: usize },
+    DayStarted(chrono::NaiveDate),
+    DayFinished { date: chrono::NaiveDate, outcome: DayOutcome },
+    Stopped(StopReason),
     DateRangeUpdated(chrono::NaiveDate, chrono::NaiveDate),
+    /// Alert rules that fired on the latest session once the download finished ingesting
+    AlertsTriggered(Vec<db::alerts::AlertNotification>),
     Done(Result<(), String>),
 }
+
+impl BhavCopyMessage {
+    /// One line of text for terminal output
+    pub fn describe(&self) -> String {
+        match self {
+            BhavCopyMessage::Planned { from, to, days } => format!("Planned {} to {} ({} days)", from, to, days),
+            BhavCopyMessage::DayStarted(date) => format!("Downloading BhavCopy for {}", date),
+            BhavCopyMessage::DayFinished { date, outcome } => match outcome {
+                DayOutcome::Ingested { inserted, skipped, rejected } => {
+                    format!("{}: {} inserted, {} skipped, {} rejected", date, inserted, skipped, rejected)
+                }
+                DayOutcome::NotPublished => format!("{}: not published", date),
+                DayOutcome::Failed(e) => format!("{}: failed: {}", date, e),
+            },
+            BhavCopyMessage::Stopped(StopReason::LimitReached(limit)) => format!("Stopped after {} files", limit),
+            BhavCopyMessage::Stopped(StopReason::TooManyMissingDays(days)) => format!("Stopped after {} consecutive trading days without data", days),
+            BhavCopyMessage::Stopped(StopReason::Cancelled) => "Cancelled".to_string(),
+            BhavCopyMessage::DateRangeUpdated(min, max) => format!("Stored data now covers {} to {}", min, max),
+            BhavCopyMessage::AlertsTriggered(alerts) => alerts.iter()
+                .map(|alert| format!("Alert: {}", alert.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            BhavCopyMessage::Done(Ok(())) => "Done".to_string(),
+            BhavCopyMessage::Done(Err(e)) => format!("Error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::db::schema::get_logs_path;
+
+pub const LOG_FILE_NAME: &str = "indistocks.log";
+
+/// Rotate once the current file passes this size
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept next to the current one (`indistocks.1.log` is the newest)
+const MAX_ROTATED_FILES: usize = 4;
+
+/// One line of the log file, stored as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Local>,
+    pub level: Level,
+    pub module: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Parse one line of the log file; anything that isn't a log entry is skipped
+    pub fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str(line.trim()).ok()
+    }
+}
+
+struct FileLogger {
+    level: LevelFilter,
+    file: Mutex<LogFile>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> Self {
+        let file = OpenOptions::new().create(true).append(true).open(&path).ok();
+        let size = file.as_ref().and_then(|f| f.metadata().ok()).map(|m| m.len()).unwrap_or(0);
+        Self { path, file, size }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_LOG_SIZE {
+            self.rotate();
+        }
+        if let Some(file) = &mut self.file {
+            if file.write_all(line.as_bytes()).is_ok() {
+                self.size += line.len() as u64;
+            }
+        }
+    }
+
+    /// Shift `indistocks.N.log` up by one, dropping the oldest, and start a fresh file
+    fn rotate(&mut self) {
+        self.file = None;
+        let _ = fs::remove_file(rotated_path(&self.path, MAX_ROTATED_FILES));
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let _ = fs::rename(rotated_path(&self.path, n), rotated_path(&self.path, n + 1));
+        }
+        let _ = fs::rename(&self.path, rotated_path(&self.path, 1));
+        *self = LogFile::open(self.path.clone());
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    path.with_file_name(format!("indistocks.{}.log", n))
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies (HTTP client, windowing, ...) only get through with warnings and errors
+        if metadata.target().starts_with("indistocks") {
+            metadata.level() <= self.level
+        } else {
+            metadata.level() <= Level::Warn
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: record.level(),
+            module: record.module_path().unwrap_or(record.target()).to_string(),
+            message: record.args().to_string(),
+        };
+
+        // Warnings and errors also go to the terminal, as they did before there was a log file
+        if entry.level <= Level::Warn {
+            let _ = writeln!(std::io::stderr(), "{}: {}", entry.level, entry.message);
+        }
+
+        if let Ok(mut line) = serde_json::to_string(&entry) {
+            line.push('\n');
+            self.file.lock().unwrap().write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &mut self.file.lock().unwrap().file {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Path of the file currently being written
+pub fn log_file_path() -> PathBuf {
+    get_logs_path().join(LOG_FILE_NAME)
+}
+
+/// Send `log` records to the rotating log file. The level comes from `$INDISTOCKS_LOG`
+/// (error, warn, info, debug or trace) and defaults to info.
+pub fn init_logging() {
+    let level = std::env::var("INDISTOCKS_LOG")
+        .ok()
+        .and_then(|value| value.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::Info);
+
+    let logger = FileLogger { level, file: Mutex::new(LogFile::open(log_file_path())) };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level.max(LevelFilter::Warn));
+    }
+}
+
+/// Follows the log file as it grows, like `tail -f`
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+    /// Start of the file being followed; rotation starts a file with a different first line
+    head: Vec<u8>,
+}
+
+impl LogTail {
+    /// Start at the beginning of `path`, so the first poll returns everything already logged
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, offset: 0, partial: String::new(), head: Vec::new() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries appended since the last poll. Starts over when the file has been rotated.
+    pub fn poll(&mut self) -> std::io::Result<Vec<LogEntry>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let len = file.metadata()?.len();
+        let mut head = vec![0; self.head.len().min(len as usize)];
+        file.read_exact(&mut head)?;
+        if len < self.offset || head != self.head {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        if self.offset == 0 {
+            self.head = bytes[..bytes.len().min(64)].to_vec();
+        }
+        self.offset += bytes.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+
+        // Keep a trailing half-written line for the next poll
+        let complete = match self.partial.rfind('\n') {
+            Some(end) => {
+                let rest = self.partial.split_off(end + 1);
+                std::mem::replace(&mut self.partial, rest)
+            }
+            None => return Ok(Vec::new()),
+        };
+        Ok(complete.lines().filter_map(LogEntry::parse).collect())
+    }
+}
@@ -12,3 +12,206 @@ pub struct NseDownload {
     pub error_message: Option<String>,
     pub downloaded_at: i64,
 }
+
+/// Exchange a BhavCopy row was traded on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Exchange {
+    #[default]
+    Nse,
+    Bse,
+}
+
+impl Exchange {
+    pub const ALL: [Exchange; 2] = [Exchange::Nse, Exchange::Bse];
+
+    /// Value stored in the `exchange` column
+    pub fn key(&self) -> &'static str {
+        match self {
+            Exchange::Nse => "NSE",
+            Exchange::Bse => "BSE",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        let key = key.trim().to_uppercase();
+        Self::ALL.into_iter().find(|exchange| exchange.key() == key)
+    }
+
+    /// Table holding the exchange's listed symbols and company names
+    pub fn symbols_table(&self) -> &'static str {
+        match self {
+            Exchange::Nse => "nse_symbols",
+            Exchange::Bse => "bse_symbols",
+        }
+    }
+}
+
+impl std::fmt::Display for Exchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// One row of an NSE or BSE equity BhavCopy, independent of the file format it came from.
+/// BSE rows use the scrip code as the symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BhavCopyRow {
+    pub symbol: String,
+    pub series: String,
+    pub date: chrono::NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub last: Option<f64>,
+    pub prev_close: f64,
+    pub volume: i64,
+    pub turnover: f64,
+    pub trades: Option<i64>,
+    pub isin: Option<String>,
+    #[serde(default)]
+    pub exchange: Exchange,
+    /// Company name, only carried by BSE files
+    #[serde(skip)]
+    pub name: Option<String>,
+    /// Shares marked for delivery, only carried by NSE security-wise full files
+    #[serde(skip)]
+    pub deliverable_qty: Option<i64>,
+    #[serde(skip)]
+    pub delivery_percent: Option<f64>,
+}
+
+/// One NSE security-wise delivery position, from a `sec_bhavdata_full` or MTO file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryRow {
+    pub symbol: String,
+    pub series: String,
+    pub date: chrono::NaiveDate,
+    pub traded_qty: Option<i64>,
+    pub deliverable_qty: i64,
+    pub delivery_percent: Option<f64>,
+}
+
+/// Daily OHLCV bar for one symbol, as drawn in the chart
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceBar {
+    pub date: chrono::NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+}
+
+/// Kind of contract in an NSE F&O BhavCopy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FoInstrument {
+    FutStk,
+    FutIdx,
+    OptStk,
+    OptIdx,
+}
+
+impl FoInstrument {
+    pub const ALL: [FoInstrument; 4] = [FoInstrument::FutStk, FoInstrument::FutIdx, FoInstrument::OptStk, FoInstrument::OptIdx];
+
+    /// Value stored in the `instrument` column, as written in the legacy F&O BhavCopy
+    pub fn key(&self) -> &'static str {
+        match self {
+            FoInstrument::FutStk => "FUTSTK",
+            FoInstrument::FutIdx => "FUTIDX",
+            FoInstrument::OptStk => "OPTSTK",
+            FoInstrument::OptIdx => "OPTIDX",
+        }
+    }
+
+    /// Code used in the UDiFF `FinInstrmTp` column
+    pub fn udiff_code(&self) -> &'static str {
+        match self {
+            FoInstrument::FutStk => "STF",
+            FoInstrument::FutIdx => "IDF",
+            FoInstrument::OptStk => "STO",
+            FoInstrument::OptIdx => "IDO",
+        }
+    }
+
+    /// Accepts either the legacy name or the UDiFF code
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_uppercase();
+        Self::ALL.into_iter().find(|i| i.key() == value || i.udiff_code() == value)
+    }
+
+    pub fn is_future(&self) -> bool {
+        matches!(self, FoInstrument::FutStk | FoInstrument::FutIdx)
+    }
+}
+
+/// Call or put, stored as `CE` or `PE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OptionType {
+    #[serde(rename = "CE")]
+    Call,
+    #[serde(rename = "PE")]
+    Put,
+}
+
+impl OptionType {
+    pub fn key(&self) -> &'static str {
+        match self {
+            OptionType::Call => "CE",
+            OptionType::Put => "PE",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_uppercase().as_str() {
+            "CE" => Some(OptionType::Call),
+            "PE" => Some(OptionType::Put),
+            _ => None,
+        }
+    }
+}
+
+/// One contract of an NSE F&O BhavCopy. Futures have no strike or option type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoRow {
+    pub symbol: String,
+    pub instrument: FoInstrument,
+    pub expiry: chrono::NaiveDate,
+    pub strike: Option<f64>,
+    pub option_type: Option<OptionType>,
+    pub date: chrono::NaiveDate,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: Option<f64>,
+    pub settle_price: Option<f64>,
+    /// Underlying price, only carried by UDiFF files
+    pub underlying: Option<f64>,
+    /// Contracts traded in legacy files, units traded in UDiFF files
+    pub volume: Option<i64>,
+    pub turnover: Option<f64>,
+    pub open_interest: Option<i64>,
+    pub change_in_oi: Option<i64>,
+}
+
+/// One index's session from NSE's daily index close file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexRow {
+    pub index_name: String,
+    pub date: chrono::NaiveDate,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: f64,
+    pub points_change: Option<f64>,
+    pub change_percent: Option<f64>,
+    pub volume: Option<i64>,
+    /// In rupees; the file reports crores
+    pub turnover: Option<f64>,
+    pub pe: Option<f64>,
+    pub pb: Option<f64>,
+    pub div_yield: Option<f64>,
+}
@@ -0,0 +1,764 @@
+use std::fmt;
+use crate::indicators;
+use crate::models::PriceBar;
+
+/// A parse error with the 1-based column it was found at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenError {
+    pub message: String,
+    pub column: usize,
+}
+
+impl fmt::Display for ScreenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for ScreenError {}
+
+/// Values taken from the latest bar, or `n` bars back with `close(n)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    PrevClose,
+    Turnover,
+    Trades,
+    High52w,
+    Low52w,
+    DeliveryQty,
+    DeliveryPct,
+}
+
+/// Functions over the last `period` bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sma,
+    Ema,
+    Rsi,
+    Atr,
+    AvgVolume,
+    ChangePct,
+    Highest,
+    Lowest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Field { field: Field, offset: usize },
+    Call { function: Function, period: usize },
+    Abs(Box<Expr>),
+    Min(Box<Expr>, Box<Expr>),
+    Max(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Arith(ArithOp, Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// What a symbol's history looks like to a screen: its bars, oldest first, and the latest session's extras
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenInput<'a> {
+    pub bars: &'a [PriceBar],
+    pub prev_close: Option<f64>,
+    pub turnover: Option<f64>,
+    pub trades: Option<i64>,
+    pub delivery_qty: Option<i64>,
+    pub delivery_pct: Option<f64>,
+}
+
+/// A compiled screener expression such as
+/// `close > sma(200) and volume > 2 * avg_volume(20) and change_pct(5) > 3`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+    source: String,
+    expr: Expr,
+}
+
+impl Screen {
+    pub fn parse(source: &str) -> Result<Self, ScreenError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, end: source.chars().count() + 1 };
+        let (expr, kind) = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(ScreenError { message: format!("Unexpected {}", token.kind.describe()), column: token.column });
+        }
+        if kind != Kind::Bool {
+            return Err(ScreenError {
+                message: "Expression must be a condition, e.g. close > sma(50)".to_string(),
+                column: 1,
+            });
+        }
+        Ok(Screen { source: source.trim().to_string(), expr })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Number of bars, oldest to newest, the expression needs to be evaluated
+    pub fn lookback(&self) -> usize {
+        lookback(&self.expr).max(1)
+    }
+
+    pub fn matches(&self, input: &ScreenInput) -> bool {
+        // Missing data (not enough history, no volume, ...) never matches, even under `not`
+        !input.bars.is_empty() && eval_bool(&self.expr, input) == Some(true)
+    }
+}
+
+/// Names usable in expressions, for help text
+pub const SCREEN_HELP: &str = "Fields: open, high, low, close, volume (add (n) for n days ago), prev_close, turnover, trades, \
+high_52w, low_52w, delivery_qty, delivery_pct. Functions: sma(n), ema(n), rsi(n), atr(n), avg_volume(n), change_pct(n), highest(n), lowest(n), \
+abs(x), min(a, b), max(a, b). Combine with + - * /, > >= < <= = !=, and, or, not.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Number,
+    Bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl TokenKind {
+    fn describe(&self) -> String {
+        match self {
+            TokenKind::Number(n) => format!("number {}", n),
+            TokenKind::Ident(name) => format!("'{}'", name),
+            TokenKind::Op(op) => format!("'{}'", op),
+            TokenKind::LParen => "'('".to_string(),
+            TokenKind::RParen => "')'".to_string(),
+            TokenKind::Comma => "','".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ScreenError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| ScreenError { message: format!("Invalid number {}", text), column })?;
+            tokens.push(Token { kind: TokenKind::Number(value), column });
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect::<String>().to_lowercase();
+            let kind = match word.as_str() {
+                "and" => TokenKind::Op("and"),
+                "or" => TokenKind::Op("or"),
+                "not" => TokenKind::Op("not"),
+                _ => TokenKind::Ident(word),
+            };
+            tokens.push(Token { kind, column });
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (kind, len) = match (c, next) {
+            ('>', Some('=')) => (TokenKind::Op(">="), 2),
+            ('<', Some('=')) => (TokenKind::Op("<="), 2),
+            ('=', Some('=')) => (TokenKind::Op("="), 2),
+            ('!', Some('=')) => (TokenKind::Op("!="), 2),
+            ('<', Some('>')) => (TokenKind::Op("!="), 2),
+            ('&', Some('&')) => (TokenKind::Op("and"), 2),
+            ('|', Some('|')) => (TokenKind::Op("or"), 2),
+            ('>', _) => (TokenKind::Op(">"), 1),
+            ('<', _) => (TokenKind::Op("<"), 1),
+            ('=', _) => (TokenKind::Op("="), 1),
+            ('!', _) => (TokenKind::Op("not"), 1),
+            ('+', _) => (TokenKind::Op("+"), 1),
+            ('-', _) => (TokenKind::Op("-"), 1),
+            ('*', _) => (TokenKind::Op("*"), 1),
+            ('/', _) => (TokenKind::Op("/"), 1),
+            ('(', _) => (TokenKind::LParen, 1),
+            (')', _) => (TokenKind::RParen, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            _ => return Err(ScreenError { message: format!("Unexpected character '{}'", c), column }),
+        };
+        tokens.push(Token { kind, column });
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Column reported for errors at the end of the input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn column(&self) -> usize {
+        self.peek().map(|t| t.column).unwrap_or(self.end)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ScreenError> {
+        Err(ScreenError { message: message.into(), column: self.column() })
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ScreenError> {
+        match self.peek() {
+            Some(token) if token.kind == kind => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(token) => self.error(format!("Expected {} but found {}", kind.describe(), token.kind.describe())),
+            None => self.error(format!("Expected {}", kind.describe())),
+        }
+    }
+
+    fn require(&self, kind: Kind, found: Kind, column: usize, what: &str) -> Result<(), ScreenError> {
+        if kind == found {
+            return Ok(());
+        }
+        let message = match kind {
+            Kind::Bool => format!("{} needs a condition, not a number", what),
+            Kind::Number => format!("{} needs a number, not a condition", what),
+        };
+        Err(ScreenError { message, column })
+    }
+
+    fn or(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        let (mut left, mut kind) = self.and()?;
+        while self.eat_op(&["or"]).is_some() {
+            self.require(Kind::Bool, kind, column, "'or'")?;
+            let right_column = self.column();
+            let (right, right_kind) = self.and()?;
+            self.require(Kind::Bool, right_kind, right_column, "'or'")?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+            kind = Kind::Bool;
+        }
+        Ok((left, kind))
+    }
+
+    fn and(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        let (mut left, mut kind) = self.not()?;
+        while self.eat_op(&["and"]).is_some() {
+            self.require(Kind::Bool, kind, column, "'and'")?;
+            let right_column = self.column();
+            let (right, right_kind) = self.not()?;
+            self.require(Kind::Bool, right_kind, right_column, "'and'")?;
+            left = Expr::And(Box::new(left), Box::new(right));
+            kind = Kind::Bool;
+        }
+        Ok((left, kind))
+    }
+
+    fn not(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        if self.eat_op(&["not"]).is_some() {
+            let column = self.column();
+            let (inner, kind) = self.not()?;
+            self.require(Kind::Bool, kind, column, "'not'")?;
+            return Ok((Expr::Not(Box::new(inner)), Kind::Bool));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        let (left, kind) = self.additive()?;
+        let Some(op) = self.eat_op(&[">", ">=", "<", "<=", "=", "!="]) else {
+            return Ok((left, kind));
+        };
+        self.require(Kind::Number, kind, column, "A comparison")?;
+        let right_column = self.column();
+        let (right, right_kind) = self.additive()?;
+        self.require(Kind::Number, right_kind, right_column, "A comparison")?;
+
+        let op = match op {
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            "=" => CompareOp::Eq,
+            _ => CompareOp::Ne,
+        };
+        Ok((Expr::Compare(op, Box::new(left), Box::new(right)), Kind::Bool))
+    }
+
+    fn additive(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        let (mut left, kind) = self.multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            self.require(Kind::Number, kind, column, "Arithmetic")?;
+            let right_column = self.column();
+            let (right, right_kind) = self.multiplicative()?;
+            self.require(Kind::Number, right_kind, right_column, "Arithmetic")?;
+            let op = if op == "+" { ArithOp::Add } else { ArithOp::Sub };
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+        Ok((left, kind))
+    }
+
+    fn multiplicative(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        let (mut left, kind) = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/"]) {
+            self.require(Kind::Number, kind, column, "Arithmetic")?;
+            let right_column = self.column();
+            let (right, right_kind) = self.unary()?;
+            self.require(Kind::Number, right_kind, right_column, "Arithmetic")?;
+            let op = if op == "*" { ArithOp::Mul } else { ArithOp::Div };
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+        Ok((left, kind))
+    }
+
+    fn unary(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        if self.eat_op(&["-"]).is_some() {
+            let column = self.column();
+            let (inner, kind) = self.unary()?;
+            self.require(Kind::Number, kind, column, "'-'")?;
+            return Ok((Expr::Neg(Box::new(inner)), Kind::Number));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<(Expr, Kind), ScreenError> {
+        let column = self.column();
+        match self.next().map(|t| t.kind) {
+            Some(TokenKind::Number(value)) => Ok((Expr::Number(value), Kind::Number)),
+            Some(TokenKind::LParen) => {
+                let inner = self.or()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(inner)
+            }
+            Some(TokenKind::Ident(name)) => self.identifier(&name, column),
+            Some(kind) => Err(ScreenError { message: format!("Unexpected {}", kind.describe()), column }),
+            None => self.error("Expression is incomplete"),
+        }
+    }
+
+    fn identifier(&mut self, name: &str, column: usize) -> Result<(Expr, Kind), ScreenError> {
+        let field = match name {
+            "open" => Some(Field::Open),
+            "high" => Some(Field::High),
+            "low" => Some(Field::Low),
+            "close" | "ltp" => Some(Field::Close),
+            "volume" => Some(Field::Volume),
+            "prev_close" => Some(Field::PrevClose),
+            "turnover" => Some(Field::Turnover),
+            "trades" => Some(Field::Trades),
+            "high_52w" => Some(Field::High52w),
+            "low_52w" => Some(Field::Low52w),
+            "delivery_qty" => Some(Field::DeliveryQty),
+            "delivery_pct" => Some(Field::DeliveryPct),
+            _ => None,
+        };
+        if let Some(field) = field {
+            let offset = if self.peek().is_some_and(|t| t.kind == TokenKind::LParen) {
+                if !matches!(field, Field::Open | Field::High | Field::Low | Field::Close | Field::Volume) {
+                    return Err(ScreenError { message: format!("{} only has a value for the latest day", name), column });
+                }
+                self.pos += 1;
+                let offset = self.integer_argument(name, 0)?;
+                self.expect(TokenKind::RParen)?;
+                offset
+            } else {
+                0
+            };
+            return Ok((Expr::Field { field, offset }, Kind::Number));
+        }
+
+        let function = match name {
+            "sma" => Some(Function::Sma),
+            "ema" => Some(Function::Ema),
+            "rsi" => Some(Function::Rsi),
+            "atr" => Some(Function::Atr),
+            "avg_volume" => Some(Function::AvgVolume),
+            "change_pct" => Some(Function::ChangePct),
+            "highest" => Some(Function::Highest),
+            "lowest" => Some(Function::Lowest),
+            _ => None,
+        };
+        if let Some(function) = function {
+            // change_pct alone is the one-day change, as on the Stocks page
+            if function == Function::ChangePct && !self.peek().is_some_and(|t| t.kind == TokenKind::LParen) {
+                return Ok((Expr::Call { function, period: 1 }, Kind::Number));
+            }
+            self.expect(TokenKind::LParen)?;
+            let period = self.integer_argument(name, 1)?;
+            self.expect(TokenKind::RParen)?;
+            return Ok((Expr::Call { function, period }, Kind::Number));
+        }
+
+        if matches!(name, "abs" | "min" | "max") {
+            self.expect(TokenKind::LParen)?;
+            let mut args = Vec::new();
+            loop {
+                let arg_column = self.column();
+                let (arg, kind) = self.additive()?;
+                self.require(Kind::Number, kind, arg_column, name)?;
+                args.push(arg);
+                if self.peek().is_some_and(|t| t.kind == TokenKind::Comma) {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            self.expect(TokenKind::RParen)?;
+
+            let expected = if name == "abs" { 1 } else { 2 };
+            if args.len() != expected {
+                return Err(ScreenError { message: format!("{} takes {} argument(s)", name, expected), column });
+            }
+            let mut args = args.into_iter().map(Box::new);
+            let first = args.next().unwrap();
+            let expr = match name {
+                "abs" => Expr::Abs(first),
+                "min" => Expr::Min(first, args.next().unwrap()),
+                _ => Expr::Max(first, args.next().unwrap()),
+            };
+            return Ok((expr, Kind::Number));
+        }
+
+        Err(ScreenError { message: format!("Unknown name '{}'", name), column })
+    }
+
+    /// A whole-number argument such as the period in `sma(200)`
+    fn integer_argument(&mut self, name: &str, min: usize) -> Result<usize, ScreenError> {
+        let column = self.column();
+        match self.next().map(|t| t.kind) {
+            Some(TokenKind::Number(value)) if value.fract() == 0.0 && value >= min as f64 && value <= 5000.0 => Ok(value as usize),
+            _ => Err(ScreenError { message: format!("{} needs a whole number of days (at least {})", name, min), column }),
+        }
+    }
+}
+
+fn lookback(expr: &Expr) -> usize {
+    match expr {
+        Expr::Number(_) => 0,
+        Expr::Field { field: Field::High52w | Field::Low52w, .. } => 260,
+        Expr::Field { offset, .. } => offset + 1,
+        Expr::Call { function, period } => match function {
+            Function::Sma | Function::AvgVolume | Function::Highest | Function::Lowest => *period,
+            Function::ChangePct => period + 1,
+            // Exponential smoothing needs a warm-up to settle
+            Function::Ema | Function::Rsi | Function::Atr => period * 4 + 1,
+        },
+        Expr::Abs(inner) | Expr::Neg(inner) | Expr::Not(inner) => lookback(inner),
+        Expr::Min(a, b) | Expr::Max(a, b) | Expr::Arith(_, a, b) | Expr::Compare(_, a, b) | Expr::And(a, b) | Expr::Or(a, b) => {
+            lookback(a).max(lookback(b))
+        }
+    }
+}
+
+/// Three-valued: `None` when the condition can't be decided for lack of data.
+/// `and`/`or` still decide when one side settles it, as in SQL.
+fn eval_bool(expr: &Expr, input: &ScreenInput) -> Option<bool> {
+    match expr {
+        Expr::And(a, b) => match (eval_bool(a, input), eval_bool(b, input)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::Or(a, b) => match (eval_bool(a, input), eval_bool(b, input)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::Not(inner) => eval_bool(inner, input).map(|value| !value),
+        Expr::Compare(op, a, b) => {
+            let (a, b) = (eval_number(a, input)?, eval_number(b, input)?);
+            Some(match op {
+                CompareOp::Gt => a > b,
+                CompareOp::Ge => a >= b,
+                CompareOp::Lt => a < b,
+                CompareOp::Le => a <= b,
+                CompareOp::Eq => (a - b).abs() < 1e-9,
+                CompareOp::Ne => (a - b).abs() >= 1e-9,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn eval_number(expr: &Expr, input: &ScreenInput) -> Option<f64> {
+    let bars = input.bars;
+    match expr {
+        Expr::Number(value) => Some(*value),
+        Expr::Field { field, offset } => {
+            let bar = bars.len().checked_sub(offset + 1).map(|i| &bars[i])?;
+            match field {
+                Field::Open => Some(bar.open),
+                Field::High => Some(bar.high),
+                Field::Low => Some(bar.low),
+                Field::Close => Some(bar.close),
+                Field::Volume => Some(bar.volume as f64),
+                Field::PrevClose => input.prev_close,
+                Field::Turnover => input.turnover,
+                Field::Trades => input.trades.map(|t| t as f64),
+                Field::DeliveryQty => input.delivery_qty.map(|q| q as f64),
+                Field::DeliveryPct => input.delivery_pct,
+                Field::High52w | Field::Low52w => {
+                    let since = bar.date - chrono::Duration::days(365);
+                    let year = bars.iter().filter(|b| b.date > since);
+                    if *field == Field::High52w {
+                        year.map(|b| b.high).reduce(f64::max)
+                    } else {
+                        year.map(|b| b.low).reduce(f64::min)
+                    }
+                }
+            }
+        }
+        Expr::Call { function, period } => {
+            let period = *period;
+            let window = |n: usize| (bars.len() >= n).then(|| &bars[bars.len() - n..]);
+            match function {
+                Function::Sma => window(period).map(|w| w.iter().map(|b| b.close).sum::<f64>() / period as f64),
+                Function::AvgVolume => window(period).map(|w| w.iter().map(|b| b.volume as f64).sum::<f64>() / period as f64),
+                Function::Highest => window(period).and_then(|w| w.iter().map(|b| b.high).reduce(f64::max)),
+                Function::Lowest => window(period).and_then(|w| w.iter().map(|b| b.low).reduce(f64::min)),
+                Function::ChangePct => {
+                    let close = bars.last()?.close;
+                    let base = if period == 1 {
+                        input.prev_close.or_else(|| window(2).map(|w| w[0].close))?
+                    } else {
+                        window(period + 1)?[0].close
+                    };
+                    (base > 0.0).then(|| (close - base) / base * 100.0)
+                }
+                Function::Ema | Function::Rsi => {
+                    let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+                    let series = if *function == Function::Ema {
+                        indicators::ema(&closes, period)
+                    } else {
+                        indicators::rsi(&closes, period)
+                    };
+                    series.last().copied().flatten()
+                }
+                Function::Atr => indicators::atr(bars, period).last().copied().flatten(),
+            }
+        }
+        Expr::Abs(inner) => eval_number(inner, input).map(f64::abs),
+        Expr::Min(a, b) => Some(eval_number(a, input)?.min(eval_number(b, input)?)),
+        Expr::Max(a, b) => Some(eval_number(a, input)?.max(eval_number(b, input)?)),
+        Expr::Neg(inner) => eval_number(inner, input).map(|v| -v),
+        Expr::Arith(op, a, b) => {
+            let (a, b) = (eval_number(a, input)?, eval_number(b, input)?);
+            match op {
+                ArithOp::Add => Some(a + b),
+                ArithOp::Sub => Some(a - b),
+                ArithOp::Mul => Some(a * b),
+                ArithOp::Div => (b != 0.0).then(|| a / b),
+            }
+        }
+        Expr::Compare(..) | Expr::And(..) | Expr::Or(..) | Expr::Not(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn compare(op: CompareOp, value: f64) -> Expr {
+        Expr::Compare(op, Box::new(Expr::Field { field: Field::Close, offset: 0 }), Box::new(Expr::Number(value)))
+    }
+
+    fn error(source: &str) -> (String, usize) {
+        let e = Screen::parse(source).unwrap_err();
+        (e.message, e.column)
+    }
+
+    /// Closes 1..=10 on consecutive days, volume 100 except 300 on the last day
+    fn bars() -> Vec<PriceBar> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (1..=10)
+            .map(|i| PriceBar {
+                date: start + chrono::Duration::days(i - 1),
+                open: i as f64,
+                high: i as f64 + 0.5,
+                low: i as f64 - 0.5,
+                close: i as f64,
+                volume: if i == 10 { 300 } else { 100 },
+            })
+            .collect()
+    }
+
+    fn input(bars: &[PriceBar]) -> ScreenInput<'_> {
+        ScreenInput { bars, prev_close: Some(9.0), turnover: None, trades: None, delivery_qty: None, delivery_pct: None }
+    }
+
+    fn eval(source: &str) -> Option<bool> {
+        let bars = bars();
+        eval_bool(&Screen::parse(source).unwrap().expr, &input(&bars))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or_and_not_tighter_than_and() {
+        let screen = Screen::parse("close > 1 or close > 2 and close > 3").unwrap();
+        let and = Expr::And(Box::new(compare(CompareOp::Gt, 2.0)), Box::new(compare(CompareOp::Gt, 3.0)));
+        assert_eq!(screen.expr, Expr::Or(Box::new(compare(CompareOp::Gt, 1.0)), Box::new(and)));
+
+        let screen = Screen::parse("NOT close < 1 && close >= 2").unwrap();
+        let not = Expr::Not(Box::new(compare(CompareOp::Lt, 1.0)));
+        assert_eq!(screen.expr, Expr::And(Box::new(not), Box::new(compare(CompareOp::Ge, 2.0))));
+
+        let screen = Screen::parse("not (close > 1 or close > 2)").unwrap();
+        let or = Expr::Or(Box::new(compare(CompareOp::Gt, 1.0)), Box::new(compare(CompareOp::Gt, 2.0)));
+        assert_eq!(screen.expr, Expr::Not(Box::new(or)));
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        let screen = Screen::parse("close > 1 + 2 * 3").unwrap();
+        let product = Expr::Arith(ArithOp::Mul, Box::new(Expr::Number(2.0)), Box::new(Expr::Number(3.0)));
+        let sum = Expr::Arith(ArithOp::Add, Box::new(Expr::Number(1.0)), Box::new(product));
+        assert_eq!(screen.expr, Expr::Compare(CompareOp::Gt, Box::new(Expr::Field { field: Field::Close, offset: 0 }), Box::new(sum)));
+    }
+
+    #[test]
+    fn syntax_errors_point_at_their_column() {
+        assert_eq!(error("close > "), ("Expression is incomplete".to_string(), 9));
+        assert_eq!(error("close > sma(200"), ("Expected ')'".to_string(), 16));
+        assert_eq!(error("close @ 1"), ("Unexpected character '@'".to_string(), 7));
+        assert_eq!(error("close > 1 )"), ("Unexpected ')'".to_string(), 11));
+        assert_eq!(error("close > sma(0)"), ("sma needs a whole number of days (at least 1)".to_string(), 13));
+        assert_eq!(error("close > sma(2.5)"), ("sma needs a whole number of days (at least 1)".to_string(), 13));
+    }
+
+    #[test]
+    fn numbers_and_conditions_are_not_interchangeable() {
+        assert_eq!(error("close"), ("Expression must be a condition, e.g. close > sma(50)".to_string(), 1));
+        assert_eq!(error("close > 1 and 5"), ("'and' needs a condition, not a number".to_string(), 15));
+        assert_eq!(error("5 or close > 1"), ("'or' needs a condition, not a number".to_string(), 1));
+        assert_eq!(error("not close"), ("'not' needs a condition, not a number".to_string(), 5));
+        assert_eq!(error("(close > 1) + 2 > 0"), ("Arithmetic needs a number, not a condition".to_string(), 1));
+        assert_eq!(error("close > (volume > 1)"), ("A comparison needs a number, not a condition".to_string(), 9));
+    }
+
+    #[test]
+    fn unknown_names_and_wrong_argument_counts_are_rejected() {
+        assert_eq!(error("foo(3) > 1"), ("Unknown name 'foo'".to_string(), 1));
+        assert_eq!(error("close > max(1)"), ("max takes 2 argument(s)".to_string(), 9));
+        assert_eq!(error("abs(1, 2) > 0"), ("abs takes 1 argument(s)".to_string(), 1));
+        assert_eq!(error("delivery_pct(1) > 50"), ("delivery_pct only has a value for the latest day".to_string(), 1));
+        assert_eq!(error("sma > 1"), ("Expected '(' but found '>'".to_string(), 5));
+    }
+
+    #[test]
+    fn evaluates_against_the_latest_bar() {
+        assert_eq!(eval("close > sma(5)"), Some(true)); // 10 > 8
+        assert_eq!(eval("close < sma(5)"), Some(false));
+        assert_eq!(eval("close(1) = 9 and open(9) = 1"), Some(true));
+        assert_eq!(eval("volume > 2 * avg_volume(5)"), Some(true)); // 300 > 2 * 140
+        assert_eq!(eval("change_pct(5) = 100"), Some(true)); // 5 -> 10
+        assert_eq!(eval("abs(change_pct - 11.11) < 0.01"), Some(true)); // From prev_close 9
+        assert_eq!(eval("highest(3) = 10.5 and lowest(3) = 7.5"), Some(true));
+        assert_eq!(eval("high_52w = 10.5 and low_52w = 0.5"), Some(true));
+        assert_eq!(eval("max(close, 20) / min(close, 5) = 4"), Some(true));
+    }
+
+    #[test]
+    fn missing_data_never_matches() {
+        // Not enough history, a missing field, a division by zero
+        assert_eq!(eval("sma(20) > 0"), None);
+        assert_eq!(eval("not sma(20) > 0"), None);
+        assert_eq!(eval("not delivery_pct > 50"), None);
+        assert_eq!(eval("close / (close - 10) > 0"), None);
+        assert_eq!(eval("close(10) > 0"), None);
+
+        // Unless the other side of and/or decides it
+        assert_eq!(eval("sma(20) > 0 or close > 5"), Some(true));
+        assert_eq!(eval("sma(20) > 0 and close > 50"), Some(false));
+        assert_eq!(eval("sma(20) > 0 and close > 5"), None);
+
+        let bars = bars();
+        assert!(!Screen::parse("not sma(20) > 0").unwrap().matches(&input(&bars)));
+        assert!(Screen::parse("sma(20) > 0 or close > 5").unwrap().matches(&input(&bars)));
+        assert!(!Screen::parse("not close < 0").unwrap().matches(&input(&[])));
+    }
+
+    #[test]
+    fn lookback_covers_every_term() {
+        assert_eq!(Screen::parse("close > sma(200) and close(3) > 0").unwrap().lookback(), 200);
+        assert_eq!(Screen::parse("change_pct(5) > 1").unwrap().lookback(), 6);
+        assert_eq!(Screen::parse("rsi(14) > 70").unwrap().lookback(), 57);
+        assert_eq!(Screen::parse("1 < 2").unwrap().lookback(), 1);
+    }
+}
@@ -1,16 +1,39 @@
-use indistocks_db::{Connection, RecentlyViewed, get_recently_viewed, record_recently_viewed, validate_download_records, get_bhavcopy_date_range, search_nse_symbols, StockData, get_stock_data_in_range};
+use indistocks_db::{Connection, RecentlyViewed, Watchlist, WatchlistItem, get_watchlists, get_watchlist_items, get_recently_viewed, record_recently_viewed, validate_download_records, get_bhavcopy_date_range, search_nse_symbols, StockData, PriceBar, get_price_bars_in_range, get_adjusted_price_bars_in_range, get_chart_indicators, save_chart_indicators, Exchange, find_linked_listing, get_listing_name, get_delivery_percentages, IndexComparison, get_index_names, get_index_bars_in_range, compare_with_index};
+use indistocks_db::indicators::Indicator;
+use indistocks_db::{PortfolioSummary, PortfolioTransaction, TradeSide, get_portfolio, get_transactions};
+use indistocks_db::{CapitalGainsReport, FinancialYear, capital_gains_report};
+use indistocks_db::{Broker, TradebookPreview, SavedScreen, get_saved_screens};
+use indistocks_db::screener::Screen;
+use indistocks_db::backtest::{BacktestResult, FillModel, StrategySpec};
+use indistocks_db::logging::{LogEntry, LogTail};
+use indistocks_db::{StocksGridLayout, StockSort, get_stocks_grid_layout, save_stocks_grid_layout};
+use std::collections::HashMap;
+use log::{debug, error, info};
 use std::sync::{Arc, Mutex};
 use crate::ui::{top_nav, sidebar, main_content, settings};
 use chrono::NaiveDate;
 use std::sync::mpsc::Receiver;
-use indistocks_db::BhavCopyMessage;
+use indistocks_db::{BhavCopyMessage, DownloadJob, DownloadManager, get_download_jobs};
+use indistocks_db::{BreadthPoint, MarketBreadth};
+use indistocks_db::{FuturesBasis, OptionChain};
+use indistocks_db::{AlertKind, AlertNotification, AlertRule, count_unread_alert_notifications, get_alert_notifications, get_alert_rules};
+
+/// Download jobs shown in the Settings history table
+const DOWNLOAD_HISTORY_LIMIT: usize = 50;
+
+/// Notifications shown in the Alerts inbox
+const ALERT_INBOX_LIMIT: usize = 200;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum View {
     Home,
     Stocks,
+    Portfolio,
+    Backtest,
+    Derivatives,
     Settings,
     Logs,
+    Alerts,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -25,7 +48,7 @@ pub enum TimeRange {
 }
 
 impl TimeRange {
-    pub fn to_days(&self) -> Option<i64> {
+    pub fn to_days(self) -> Option<i64> {
         match self {
             TimeRange::FiveDays => Some(5),
             TimeRange::OneMonth => Some(30),
@@ -50,6 +73,23 @@ impl TimeRange {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartMode {
+    Line,
+    Candlestick,
+    Ohlc,
+}
+
+impl ChartMode {
+    pub fn label(&self) -> &str {
+        match self {
+            ChartMode::Line => "Line",
+            ChartMode::Candlestick => "Candles",
+            ChartMode::Ohlc => "OHLC",
+        }
+    }
+}
+
 pub struct IndistocksApp {
     pub current_view: View,
     pub db_conn: Arc<Mutex<Connection>>,
@@ -57,10 +97,23 @@ pub struct IndistocksApp {
     pub search_query: String,
     pub settings_error_symbols: Vec<String>,
     // BhavCopy Download
-    pub bhavcopy_progress: String,
-    pub bhavcopy_status: String,
-    pub is_downloading_bhavcopy: bool,
-    pub bhavcopy_receiver: Option<Receiver<BhavCopyMessage>>,
+    pub download_manager: DownloadManager,
+    pub download_jobs: Vec<DownloadJob>, // history, newest first
+    pub download_range_from: String,
+    pub download_range_to: String,
+    pub download_status: String,
+    // Home market overview
+    pub home_breadth: Option<MarketBreadth>,
+    pub home_breadth_history: Vec<BreadthPoint>, // oldest first
+    pub home_breadth_sessions: usize,
+    pub home_breadth_stale: bool, // reload on the next Home frame
+    pub home_breadth_receiver: Option<Receiver<crate::ui::home::BreadthMessage>>,
+    pub home_status: String,
+    // Trading calendar
+    pub holidays_path: String,
+    pub holidays_status: String,
+    pub calendar_holiday_count: usize,
+    pub calendar_missing_days: Option<Vec<NaiveDate>>, // None until counted
     pub bhavcopy_date_range: Option<(chrono::NaiveDate, chrono::NaiveDate)>,
     // NSE List Download
     pub is_downloading_nse_list: bool,
@@ -68,12 +121,23 @@ pub struct IndistocksApp {
     pub nse_list_receiver: Option<Receiver<crate::ui::settings::NseListMessage>>,
     // Plotting
     pub selected_symbol: Option<String>,
-    pub plot_data: Vec<(NaiveDate, f64)>, // date, close price
+    pub chart_exchange: Exchange, // Exchange the selected symbol's prices come from
+    pub chart_name: Option<String>, // Company name of the charted listing
+    pub chart_listings: Vec<(Exchange, String)>, // The company's symbol on each exchange it trades on
+    pub chart_delivery: HashMap<NaiveDate, f64>, // Delivery % by date, NSE listings only
+    pub plot_data: Vec<PriceBar>, // daily OHLCV bars, oldest first
     pub plot_loaded_range: Option<(NaiveDate, NaiveDate)>, // Track what data is currently loaded
     pub plot_earliest_available: Option<NaiveDate>, // Earliest date available in DB for current symbol
     pub plot_loading_in_progress: bool, // Prevent concurrent loads
     pub selected_time_range: TimeRange, // Current time range filter for the plot
     pub plot_needs_reset: bool, // Flag to reset plot view on next render
+    pub plot_adjusted: bool, // Show prices adjusted for splits, bonuses and dividends
+    pub chart_mode: ChartMode, // How price bars are drawn
+    pub chart_indicators: Vec<Indicator>, // Overlays and oscillator panes, remembered per symbol
+    pub chart_index: Option<String>, // Index overlaid on the chart, kept when switching symbols
+    pub chart_index_names: Vec<String>, // Indices with a close on the latest stored date
+    pub chart_index_closes: HashMap<NaiveDate, f64>, // The overlaid index's closes over the loaded range
+    pub chart_comparison: Option<IndexComparison>, // The stock against the overlaid index over the loaded range
     // Search caching
     pub last_search_query: String,
     pub search_results: Vec<String>,
@@ -85,6 +149,88 @@ pub struct IndistocksApp {
     pub stocks_last_price_from: String,
     pub stocks_last_price_to: String,
     pub stocks_last_range_type: RangeType,
+    pub stocks_adjusted: bool,
+    pub stocks_last_adjusted: bool,
+    pub stocks_exchange: Exchange,
+    pub stocks_last_exchange: Exchange,
+    pub stocks_watchlist: Option<i64>, // Restrict the grid to one watchlist
+    pub stocks_last_watchlist: Option<i64>,
+    pub stocks_layout: StocksGridLayout, // Visible columns and sort, saved on every change
+    pub stocks_last_sort: StockSort,
+    pub stocks_screen_input: String,
+    pub stocks_screen: Option<Screen>, // Applied screener expression
+    pub stocks_screen_error: String,
+    pub stocks_screen_matches: Option<std::collections::HashSet<String>>, // None until the screen has run
+    pub stocks_last_screen: Option<String>,
+    pub stocks_screen_name: String,
+    pub saved_screens: Vec<SavedScreen>,
+    // Watchlists
+    pub watchlists: Vec<Watchlist>,
+    pub active_watchlist: Option<i64>, // Watchlist shown in the sidebar
+    pub watchlist_items: Vec<WatchlistItem>,
+    pub new_watchlist_name: String,
+    pub watchlist_note_draft: String,
+    // Portfolio
+    pub portfolio: Option<PortfolioSummary>, // None until loaded, and after every change
+    pub portfolio_transactions: Vec<PortfolioTransaction>,
+    pub portfolio_symbol: String,
+    pub portfolio_side: TradeSide,
+    pub portfolio_quantity: String,
+    pub portfolio_price: String,
+    pub portfolio_charges: String,
+    pub portfolio_date: String,
+    pub portfolio_status: String,
+    pub tax_year: FinancialYear,
+    pub tax_report: Option<CapitalGainsReport>, // Rebuilt with the portfolio
+    pub tax_export_path: String,
+    // Tradebook import
+    pub tradebook_path: String,
+    pub tradebook_broker: Option<Broker>, // None detects the broker from the header row
+    pub tradebook_preview: Option<TradebookPreview>,
+    pub tradebook_mapping_drafts: HashMap<String, String>, // Broker symbol -> NSE symbol being typed
+    // Backtest
+    pub backtest_symbol: String,
+    pub backtest_from: String, // Blank for the earliest stored date
+    pub backtest_to: String,
+    pub backtest_strategy: StrategySpec,
+    pub backtest_capital: String,
+    pub backtest_size: String,
+    pub backtest_fill: FillModel,
+    pub backtest_costs: bool,
+    pub backtest_adjusted: bool,
+    pub backtest_result: Option<BacktestResult>,
+    pub backtest_status: String,
+    // F&O
+    pub fo_symbol: String,
+    pub fo_expiry: Option<NaiveDate>, // None for the nearest
+    pub fo_basis: Vec<FuturesBasis>,
+    pub fo_chain: Option<OptionChain>,
+    pub fo_status: String,
+    // Logs
+    pub logs_tail: Option<LogTail>, // Opened when the Logs page is first shown
+    pub logs_entries: Vec<LogEntry>,
+    pub logs_level: log::LevelFilter, // Least severe level shown
+    pub logs_module: Option<String>,
+    pub logs_search: String,
+    pub logs_follow: bool,
+    pub logs_status: String,
+    // Alerts
+    pub alerts_unread: usize, // Shown on the bell
+    pub alerts_notifications: Vec<AlertNotification>,
+    pub alerts_rules: Vec<AlertRule>,
+    pub alert_symbol: String,
+    pub alert_kind: AlertKind,
+    pub alert_value: String,
+    pub alert_period: String,
+    pub alerts_status: String,
+    // Corporate actions import
+    pub bhavcopy_import_path: String,
+    pub bhavcopy_import_status: String,
+    pub bhavcopy_import_receiver: Option<Receiver<crate::ui::settings::ImportMessage>>,
+    pub bhavcopy_import_progress: Option<(usize, usize)>, // (files done, files found) once the folder is scanned
+    pub bhavcopy_import_reports: Vec<indistocks_db::ImportReport>, // Reports of the files imported so far
+    pub corporate_actions_path: String,
+    pub corporate_actions_status: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -98,35 +244,65 @@ impl IndistocksApp {
     pub fn new(_cc: &eframe::CreationContext<'_>, db_conn: Connection) -> Self {
         // Validate download records against existing files
         if let Err(e) = validate_download_records(&db_conn) {
-            eprintln!("Failed to validate download records: {}", e);
+            error!("Failed to validate download records: {}", e);
         }
 
         // Load symbols with downloads once at startup
         let db_conn_arc = Arc::new(Mutex::new(db_conn));
+        let download_manager = DownloadManager::new(db_conn_arc.clone());
         let conn = db_conn_arc.lock().unwrap();
-        let bhavcopy_date_range = get_bhavcopy_date_range(&*conn).unwrap_or(None);
+        let bhavcopy_date_range = get_bhavcopy_date_range(&conn).unwrap_or(None);
+        let watchlists = get_watchlists(&conn).unwrap_or_default();
+        let active_watchlist = watchlists.first().map(|w| w.id);
+        let watchlist_items = active_watchlist
+            .and_then(|id| get_watchlist_items(&conn, id).ok())
+            .unwrap_or_default();
+        let saved_screens = get_saved_screens(&conn).unwrap_or_default();
+        let stocks_layout = get_stocks_grid_layout(&conn).unwrap_or_default();
 
         Self {
             current_view: View::Home,
             db_conn: db_conn_arc.clone(),
-            recently_viewed: get_recently_viewed(&*conn, 20).unwrap_or_default(),
+            recently_viewed: get_recently_viewed(&conn, 20).unwrap_or_default(),
             search_query: String::new(),
             settings_error_symbols: Vec::new(),
-            bhavcopy_progress: String::new(),
-            bhavcopy_status: String::new(),
-            is_downloading_bhavcopy: false,
-            bhavcopy_receiver: None,
+            download_manager,
+            download_jobs: get_download_jobs(&conn, DOWNLOAD_HISTORY_LIMIT).unwrap_or_default(),
+            download_range_from: String::new(),
+            download_range_to: String::new(),
+            download_status: String::new(),
+            home_breadth: None,
+            home_breadth_history: Vec::new(),
+            home_breadth_sessions: 60,
+            home_breadth_stale: true,
+            home_breadth_receiver: None,
+            home_status: String::new(),
+            holidays_path: String::new(),
+            holidays_status: String::new(),
+            calendar_holiday_count: 0,
+            calendar_missing_days: None,
             bhavcopy_date_range,
             is_downloading_nse_list: false,
             nse_list_status: String::new(),
             nse_list_receiver: None,
             selected_symbol: None,
+            chart_exchange: Exchange::Nse,
+            chart_name: None,
+            chart_listings: Vec::new(),
+            chart_delivery: HashMap::new(),
             plot_data: Vec::new(),
             plot_loaded_range: None,
             plot_earliest_available: None,
             plot_loading_in_progress: false,
             selected_time_range: TimeRange::ThreeMonths, // Default to 3 months
             plot_needs_reset: false,
+            plot_adjusted: true,
+            chart_mode: ChartMode::Line,
+            chart_indicators: Vec::new(),
+            chart_index: None,
+            chart_index_names: Vec::new(),
+            chart_index_closes: HashMap::new(),
+            chart_comparison: None,
             last_search_query: String::new(),
             search_results: Vec::new(),
             stocks_price_from: String::new(),
@@ -136,11 +312,165 @@ impl IndistocksApp {
             stocks_last_price_from: String::new(),
             stocks_last_price_to: String::new(),
             stocks_last_range_type: RangeType::Last30Days,
+            stocks_adjusted: true,
+            stocks_last_adjusted: true,
+            stocks_exchange: Exchange::Nse,
+            stocks_last_exchange: Exchange::Nse,
+            stocks_watchlist: None,
+            stocks_last_watchlist: None,
+            stocks_last_sort: stocks_layout.sort,
+            stocks_layout,
+            stocks_screen_input: String::new(),
+            stocks_screen: None,
+            stocks_screen_error: String::new(),
+            stocks_screen_matches: None,
+            stocks_last_screen: None,
+            stocks_screen_name: String::new(),
+            saved_screens,
+            watchlists,
+            active_watchlist,
+            watchlist_items,
+            new_watchlist_name: String::new(),
+            watchlist_note_draft: String::new(),
+            portfolio: None,
+            portfolio_transactions: Vec::new(),
+            portfolio_symbol: String::new(),
+            portfolio_side: TradeSide::Buy,
+            portfolio_quantity: String::new(),
+            portfolio_price: String::new(),
+            portfolio_charges: String::new(),
+            portfolio_date: chrono::Local::now().date_naive().format("%Y-%m-%d").to_string(),
+            portfolio_status: String::new(),
+            tax_year: FinancialYear::containing(chrono::Local::now().date_naive()),
+            tax_report: None,
+            tax_export_path: String::new(),
+            tradebook_path: String::new(),
+            tradebook_broker: None,
+            tradebook_preview: None,
+            tradebook_mapping_drafts: HashMap::new(),
+            backtest_symbol: String::new(),
+            backtest_from: String::new(),
+            backtest_to: String::new(),
+            backtest_strategy: StrategySpec::SmaCrossover { fast: 50, slow: 200 },
+            backtest_capital: "100000".to_string(),
+            backtest_size: "100%".to_string(),
+            backtest_fill: FillModel::NextOpen,
+            backtest_costs: true,
+            backtest_adjusted: true,
+            backtest_result: None,
+            backtest_status: String::new(),
+            fo_symbol: String::new(),
+            fo_expiry: None,
+            fo_basis: Vec::new(),
+            fo_chain: None,
+            fo_status: String::new(),
+            logs_tail: None,
+            logs_entries: Vec::new(),
+            logs_level: log::LevelFilter::Info,
+            logs_module: None,
+            logs_search: String::new(),
+            logs_follow: true,
+            logs_status: String::new(),
+            alerts_unread: count_unread_alert_notifications(&conn).unwrap_or(0),
+            alerts_notifications: Vec::new(),
+            alerts_rules: Vec::new(),
+            alert_symbol: String::new(),
+            alert_kind: AlertKind::CrossesAbove,
+            alert_value: String::new(),
+            alert_period: String::new(),
+            alerts_status: String::new(),
+            bhavcopy_import_path: String::new(),
+            bhavcopy_import_status: String::new(),
+            bhavcopy_import_receiver: None,
+            bhavcopy_import_progress: None,
+            bhavcopy_import_reports: Vec::new(),
+            corporate_actions_path: String::new(),
+            corporate_actions_status: String::new(),
         }
     }
 
+    /// Reload the inbox, the rules and the unread count
+    pub fn refresh_alerts(&mut self) {
+        let conn = self.db_conn.lock().unwrap();
+        self.alerts_notifications = get_alert_notifications(&conn, ALERT_INBOX_LIMIT).unwrap_or_default();
+        self.alerts_rules = get_alert_rules(&conn).unwrap_or_default();
+        self.alerts_unread = count_unread_alert_notifications(&conn).unwrap_or(0);
+    }
+
     pub fn refresh_recently_viewed(&mut self) {
-        self.recently_viewed = get_recently_viewed(&*self.db_conn.lock().unwrap(), 20).unwrap_or_default();
+        self.recently_viewed = get_recently_viewed(&self.db_conn.lock().unwrap(), 20).unwrap_or_default();
+    }
+
+    /// Reload watchlists and the sidebar's items after any change.
+    /// Falls back to the first list if the active one no longer exists.
+    pub fn refresh_watchlists(&mut self) {
+        let conn = self.db_conn.lock().unwrap();
+        self.watchlists = get_watchlists(&conn).unwrap_or_default();
+
+        if !self.watchlists.iter().any(|w| Some(w.id) == self.active_watchlist) {
+            self.active_watchlist = self.watchlists.first().map(|w| w.id);
+        }
+        if !self.watchlists.iter().any(|w| Some(w.id) == self.stocks_watchlist) {
+            self.stocks_watchlist = None;
+        }
+
+        self.watchlist_items = self.active_watchlist
+            .and_then(|id| get_watchlist_items(&conn, id).ok())
+            .unwrap_or_default();
+        drop(conn);
+
+        // The Stocks grid may be filtered by a list that just changed
+        self.stocks_cached_data.clear();
+    }
+
+    /// Handle events from the running download job and refresh what depends on stored data
+    pub fn poll_downloads(&mut self) {
+        for message in self.download_manager.poll() {
+            match message {
+                BhavCopyMessage::DateRangeUpdated(min_date, max_date) => {
+                    self.bhavcopy_date_range = Some((min_date, max_date));
+                }
+                BhavCopyMessage::AlertsTriggered(_) => self.refresh_alerts(),
+                BhavCopyMessage::Done(result) => {
+                    self.download_status = match result {
+                        Ok(()) => String::new(),
+                        Err(e) => format!("Error: {}", e),
+                    };
+                    self.bhavcopy_date_range = get_bhavcopy_date_range(&self.db_conn.lock().unwrap()).unwrap_or(None);
+                    self.stocks_cached_data.clear();
+                    self.calendar_missing_days = None;
+                    self.home_breadth_stale = true;
+                    self.refresh_download_jobs();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn refresh_download_jobs(&mut self) {
+        self.download_jobs = get_download_jobs(&self.db_conn.lock().unwrap(), DOWNLOAD_HISTORY_LIMIT).unwrap_or_default();
+    }
+
+    pub fn save_stocks_layout(&self) {
+        if let Err(e) = save_stocks_grid_layout(&self.db_conn.lock().unwrap(), &self.stocks_layout) {
+            error!("Failed to save Stocks grid layout: {}", e);
+        }
+    }
+
+    /// Recompute holdings and P&L from the transactions table
+    pub fn refresh_portfolio(&mut self) {
+        let conn = self.db_conn.lock().unwrap();
+        match get_portfolio(&conn) {
+            Ok(portfolio) => self.portfolio = Some(portfolio),
+            Err(e) => {
+                error!("Failed to load portfolio: {}", e);
+                self.portfolio = Some(PortfolioSummary::default());
+            }
+        }
+        self.portfolio_transactions = get_transactions(&conn, None).unwrap_or_default();
+        self.tax_report = capital_gains_report(&conn, self.tax_year)
+            .map_err(|e| error!("Failed to build tax report: {}", e))
+            .ok();
     }
 
     pub fn update_search_results(&mut self) {
@@ -150,19 +480,27 @@ impl IndistocksApp {
 
         self.last_search_query = self.search_query.clone();
 
-        self.search_results = search_nse_symbols(&*self.db_conn.lock().unwrap(), &self.search_query, 50).unwrap_or_default();
-        println!("Search query: '{}', found {} matching symbols", self.search_query, self.search_results.len());
+        self.search_results = search_nse_symbols(&self.db_conn.lock().unwrap(), &self.search_query, 50).unwrap_or_default();
+        debug!("Search query: '{}', found {} matching symbols", self.search_query, self.search_results.len());
     }
 
     pub fn load_plot_data(&mut self, symbol: &str) {
-        println!("Loading plot data for symbol: {}", symbol);
+        self.load_listing(Exchange::Nse, symbol);
+    }
+
+    /// Chart a symbol's prices on one exchange
+    pub fn load_listing(&mut self, exchange: Exchange, symbol: &str) {
+        debug!("Loading plot data for {} on {}", symbol, exchange);
         self.selected_symbol = Some(symbol.to_string());
+        self.chart_exchange = exchange;
 
-        // Record as recently viewed
-        if let Err(e) = record_recently_viewed(&*self.db_conn.lock().unwrap(), symbol) {
-            eprintln!("Failed to record recently viewed: {}", e);
+        // Record as recently viewed; the list only holds NSE symbols
+        if exchange == Exchange::Nse {
+            if let Err(e) = record_recently_viewed(&self.db_conn.lock().unwrap(), symbol) {
+                error!("Failed to record recently viewed: {}", e);
+            }
+            self.refresh_recently_viewed();
         }
-        self.refresh_recently_viewed();
 
         self.plot_data.clear();
         self.plot_loaded_range = None;
@@ -172,16 +510,49 @@ impl IndistocksApp {
 
         let conn = self.db_conn.lock().unwrap();
 
+        self.chart_indicators = get_chart_indicators(&conn, symbol).unwrap_or_else(|e| {
+            error!("Failed to load chart indicators: {}", e);
+            Vec::new()
+        });
+
+        self.chart_name = get_listing_name(&conn, exchange, symbol).unwrap_or_else(|e| {
+            error!("Failed to load company name: {}", e);
+            None
+        });
+        self.chart_listings = Exchange::ALL.into_iter()
+            .filter_map(|target| match find_linked_listing(&conn, exchange, symbol, target) {
+                Ok(linked) => linked.map(|linked| (target, linked)),
+                Err(e) => {
+                    error!("Failed to find the {} listing: {}", target, e);
+                    None
+                }
+            })
+            .collect();
+
+        // Delivery positions are only published by NSE
+        self.chart_delivery = match exchange {
+            Exchange::Nse => get_delivery_percentages(&conn, symbol).unwrap_or_else(|e| {
+                error!("Failed to load delivery data: {}", e);
+                HashMap::new()
+            }),
+            Exchange::Bse => HashMap::new(),
+        };
+
+        self.chart_index_names = get_index_names(&conn).unwrap_or_else(|e| {
+            error!("Failed to load index names: {}", e);
+            Vec::new()
+        });
+
         // Get the earliest and latest dates available for this symbol
         let earliest_date: Option<i64> = conn.query_row(
-            "SELECT MIN(date) FROM bhavcopy_data WHERE symbol = ? AND series = 'EQ'",
-            [symbol],
+            "SELECT MIN(date) FROM bhavcopy_data WHERE symbol = ? AND exchange = ? AND series = 'EQ'",
+            [symbol, exchange.key()],
             |row| row.get(0)
         ).ok().flatten();
 
         let latest_date: Option<i64> = conn.query_row(
-            "SELECT MAX(date) FROM bhavcopy_data WHERE symbol = ? AND series = 'EQ'",
-            [symbol],
+            "SELECT MAX(date) FROM bhavcopy_data WHERE symbol = ? AND exchange = ? AND series = 'EQ'",
+            [symbol, exchange.key()],
             |row| row.get(0)
         ).ok().flatten();
 
@@ -199,12 +570,12 @@ impl IndistocksApp {
 
             // Count total data points available
             let total_count: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM bhavcopy_data WHERE symbol = ? AND series = 'EQ'",
-                [symbol],
+                "SELECT COUNT(*) FROM bhavcopy_data WHERE symbol = ? AND exchange = ? AND series = 'EQ'",
+                [symbol, exchange.key()],
                 |row| row.get(0)
             ).unwrap_or(0);
 
-            println!("Data available from {} to {} ({} days span, {} data points in DB)",
+            debug!("Data available from {} to {} ({} days span, {} data points in DB)",
                 earliest, latest, (latest - earliest).num_days(), total_count);
 
             // Load data based on selected time range
@@ -216,23 +587,92 @@ impl IndistocksApp {
                 None => earliest, // Load all data
             };
 
-            match get_stock_data_in_range(&conn, symbol, load_from, latest) {
+            match self.query_plot_data(&conn, symbol, load_from, latest) {
                 Ok(data) => {
                     self.plot_data = data;
                     if !self.plot_data.is_empty() {
-                        let actual_start = self.plot_data.first().unwrap().0;
-                        let actual_end = self.plot_data.last().unwrap().0;
+                        let actual_start = self.plot_data.first().unwrap().date;
+                        let actual_end = self.plot_data.last().unwrap().date;
                         self.plot_loaded_range = Some((actual_start, actual_end));
-                        println!("Loaded {} data points for {} (range: {} to {})",
+                        debug!("Loaded {} data points for {} (range: {} to {})",
                             self.plot_data.len(), symbol, actual_start, actual_end);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to load plot data: {}", e);
+                    error!("Failed to load plot data: {}", e);
                 }
             }
         } else {
-            println!("No data available for symbol: {}", symbol);
+            info!("No data available for {}", symbol);
+        }
+
+        drop(conn);
+        self.refresh_chart_index();
+    }
+
+    fn query_plot_data(&self, conn: &Connection, symbol: &str, from: NaiveDate, to: NaiveDate) -> indistocks_db::Result<Vec<PriceBar>> {
+        if self.plot_adjusted {
+            get_adjusted_price_bars_in_range(conn, self.chart_exchange, symbol, from, to)
+        } else {
+            get_price_bars_in_range(conn, self.chart_exchange, symbol, from, to)
+        }
+    }
+
+    /// Chart the same company's listing on another exchange, if it has one
+    pub fn switch_chart_exchange(&mut self, exchange: Exchange) {
+        if exchange == self.chart_exchange {
+            return;
+        }
+        let linked = self.chart_listings.iter()
+            .find(|(e, _)| *e == exchange)
+            .map(|(_, symbol)| symbol.clone());
+        if let Some(symbol) = linked {
+            self.load_listing(exchange, &symbol);
+        }
+    }
+
+    /// Switch between raw and corporate-action adjusted prices and reload the chart
+    pub fn set_plot_adjusted(&mut self, adjusted: bool) {
+        if self.plot_adjusted == adjusted {
+            return;
+        }
+        self.plot_adjusted = adjusted;
+        self.change_time_range(self.selected_time_range);
+    }
+
+    /// Overlay an index on the chart, or remove the overlay with None
+    pub fn set_chart_index(&mut self, index: Option<String>) {
+        if self.chart_index == index {
+            return;
+        }
+        self.chart_index = index;
+        self.refresh_chart_index();
+    }
+
+    /// Reload the overlaid index over the loaded range and compare the charted stock against it
+    fn refresh_chart_index(&mut self) {
+        self.chart_index_closes.clear();
+        self.chart_comparison = None;
+        let (Some(index), Some((from, to))) = (&self.chart_index, self.plot_loaded_range) else {
+            return;
+        };
+
+        match get_index_bars_in_range(&self.db_conn.lock().unwrap(), index, from, to) {
+            Ok(bars) => {
+                self.chart_comparison = compare_with_index(&self.plot_data, &bars);
+                self.chart_index_closes = bars.iter().map(|bar| (bar.date, bar.close)).collect();
+            }
+            Err(e) => error!("Failed to load {} closes: {}", index, e),
+        }
+    }
+
+    /// Replace the chart indicators and remember them for the current symbol
+    pub fn set_chart_indicators(&mut self, indicators: Vec<Indicator>) {
+        self.chart_indicators = indicators;
+        if let Some(symbol) = &self.selected_symbol {
+            if let Err(e) = save_chart_indicators(&self.db_conn.lock().unwrap(), symbol, &self.chart_indicators) {
+                error!("Failed to save chart indicators: {}", e);
+            }
         }
     }
 
@@ -265,26 +705,28 @@ impl IndistocksApp {
             };
 
             let conn = self.db_conn.lock().unwrap();
-            match get_stock_data_in_range(&conn, symbol, load_from, new_end) {
+            match self.query_plot_data(&conn, symbol, load_from, new_end) {
                 Ok(mut new_data) => {
                     if !new_data.is_empty() {
-                        println!("Loading {} earlier data points (range: {} to {})",
+                        debug!("Loading {} earlier data points (range: {} to {})",
                             new_data.len(), load_from, new_end);
 
                         // Prepend new data to existing data
-                        new_data.extend(self.plot_data.drain(..));
+                        new_data.append(&mut self.plot_data);
                         self.plot_data = new_data;
 
                         // Update the loaded range
-                        self.plot_loaded_range = Some((self.plot_data.first().unwrap().0, current_end));
+                        self.plot_loaded_range = Some((self.plot_data.first().unwrap().date, current_end));
                     } else {
-                        println!("No earlier data available in range {} to {}", load_from, new_end);
+                        debug!("No earlier data available in range {} to {}", load_from, new_end);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to load earlier data: {}", e);
+                    error!("Failed to load earlier data: {}", e);
                 }
             }
+            drop(conn);
+            self.refresh_chart_index();
 
             self.plot_loading_in_progress = false;
         }
@@ -302,7 +744,7 @@ impl IndistocksApp {
 
         // Reload the data if a symbol is selected
         if let Some(symbol) = &self.selected_symbol.clone() {
-            self.load_plot_data(symbol);
+            self.load_listing(self.chart_exchange, symbol);
         }
     }
 }
@@ -312,7 +754,12 @@ impl eframe::App for IndistocksApp {
         // Update search results if needed
         self.update_search_results();
 
-
+        self.poll_downloads();
+        settings::poll_bhavcopy_import(self);
+        if self.download_manager.is_busy() || self.bhavcopy_import_receiver.is_some() {
+            // Keep the progress bar moving without user input
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
 
         // If there's a selected symbol or search query, switch to Home view
         if self.selected_symbol.is_some() || !self.search_query.is_empty() {
@@ -336,11 +783,12 @@ impl eframe::App for IndistocksApp {
             match self.current_view {
                 View::Home => main_content::render(ui, self),
                 View::Stocks => crate::ui::stocks::render(ui, self),
+                View::Portfolio => crate::ui::portfolio::render(ui, self),
+                View::Backtest => crate::ui::backtest::render(ui, self),
+                View::Derivatives => crate::ui::derivatives::render(ui, self),
                 View::Settings => settings::render(ui, self),
-                View::Logs => {
-                    ui.heading("Logs");
-                    ui.label("Logs view - Coming soon");
-                }
+                View::Logs => crate::ui::logs::render(ui, self),
+                View::Alerts => crate::ui::alerts::render(ui, self),
             }
         });
     }
@@ -0,0 +1,950 @@
+use std::error::Error;
+use std::io::Write;
+use std::sync::{mpsc, Arc, Mutex};
+use chrono::{Datelike, NaiveDate};
+use indistocks_db::{
+    BhavCopyMessage, DownloadControl, Connection, init_db, data_source_from_url, default_data_source, download_nse_equity_list,
+    save_nse_symbols_with_names, sync_bhavcopy, fill_bhavcopy_gaps, fill_delivery_gaps, fill_derivatives_gaps, get_quote, get_bhavcopy_date_range, for_each_bhavcopy_row,
+    get_price_bars_in_range, get_adjusted_price_bars_in_range, get_all_stocks_with_metrics,
+    get_all_stocks_with_metrics_adjusted, DataSource, FinancialYear, CapitalGainsReport, capital_gains_report,
+    write_schedule_112a_csv, Broker, TradeStatus, preview_tradebook, resolve_tradebook, commit_tradebook,
+    save_broker_symbol_mapping, run_screen, save_screen, get_saved_screens, delete_saved_screen, StockSort,
+    import_holidays_csv, get_market_holidays, find_missing_trading_days, AlertKind, create_alert_rule, get_alert_rules,
+    delete_alert_rule, evaluate_alerts, get_alert_notifications, mark_all_alert_notifications_read, Exchange,
+    get_exchange_date_range, retain_nse_symbols, get_futures_basis, get_option_chain, fill_index_gaps, get_index_names,
+    get_index_bars_in_range, compare_with_index,
+};
+use indistocks_db::screener::Screen;
+use indistocks_db::backtest::{BacktestConfig, CostModel, FillModel, PositionSizing, StrategySpec, run_backtest};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for reading in a terminal
+    Table,
+    Csv,
+    Json,
+}
+
+/// Writes serializable records as an aligned table, CSV or a JSON array.
+/// CSV and JSON are streamed; the table is buffered to size its columns.
+pub struct RecordWriter<W: Write> {
+    sink: Sink<W>,
+}
+
+enum Sink<W: Write> {
+    Table { buffer: csv::Writer<Vec<u8>>, out: W },
+    Csv(csv::Writer<W>),
+    Json { out: W, count: usize },
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(format: OutputFormat, out: W) -> Self {
+        let sink = match format {
+            OutputFormat::Table => Sink::Table { buffer: csv::Writer::from_writer(Vec::new()), out },
+            OutputFormat::Csv => Sink::Csv(csv::Writer::from_writer(out)),
+            OutputFormat::Json => Sink::Json { out, count: 0 },
+        };
+        Self { sink }
+    }
+
+    pub fn write<T: Serialize>(&mut self, record: &T) -> Result<(), Box<dyn Error>> {
+        match &mut self.sink {
+            Sink::Table { buffer, .. } => buffer.serialize(record)?,
+            Sink::Csv(writer) => writer.serialize(record)?,
+            Sink::Json { out, count } => {
+                out.write_all(if *count == 0 { b"[\n  " } else { b",\n  " })?;
+                serde_json::to_writer(&mut *out, record)?;
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self.sink {
+            Sink::Table { buffer, mut out } => {
+                let data = buffer.into_inner().map_err(|e| e.to_string())?;
+                write_table(&data, &mut out)?;
+            }
+            Sink::Csv(mut writer) => writer.flush()?,
+            Sink::Json { mut out, count } => {
+                out.write_all(if count == 0 { b"[]\n" } else { b"\n]\n" })?;
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lay out CSV data (header first) as columns, numbers right-aligned with two decimals
+fn write_table(csv_data: &[u8], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let mut rdr = csv::ReaderBuilder::new().has_headers(false).from_reader(csv_data);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        let cells = record.iter()
+            .map(|cell| {
+                // Keep integers (volumes, trade counts) as they are and round prices
+                match cell.parse::<f64>() {
+                    Ok(value) if cell.contains('.') && !rows.is_empty() => format!("{:.2}", value),
+                    _ => cell.to_string(),
+                }
+            })
+            .collect();
+        rows.push(cells);
+    }
+
+    if rows.len() <= 1 {
+        writeln!(out, "No results")?;
+        return Ok(());
+    }
+
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| rows.iter().filter_map(|r| r.get(c)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+        .collect();
+    let numeric: Vec<bool> = (0..columns)
+        .map(|c| rows[1..].iter().filter_map(|r| r.get(c)).filter(|cell| !cell.is_empty()).all(|cell| cell.parse::<f64>().is_ok()))
+        .collect();
+
+    for (i, row) in rows.iter().enumerate() {
+        let line: Vec<String> = row.iter().enumerate()
+            .map(|(c, cell)| {
+                if numeric[c] { format!("{:>w$}", cell, w = widths[c]) } else { format!("{:<w$}", cell, w = widths[c]) }
+            })
+            .collect();
+        writeln!(out, "{}", line.join(" | ").trim_end())?;
+        if i == 0 {
+            let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            writeln!(out, "{}", rule.join("-|-"))?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {:?}, expected YYYY-MM-DD", value).into())
+}
+
+fn open_source(source: Option<String>) -> Result<Box<dyn DataSource>, Box<dyn Error>> {
+    Ok(match source {
+        Some(url) => data_source_from_url(&url)?,
+        None => default_data_source()?,
+    })
+}
+
+/// Latest stored BhavCopy date, used as the default end of date ranges
+fn latest_date(conn: &Connection, exchange: Exchange) -> Result<NaiveDate, Box<dyn Error>> {
+    match exchange {
+        Exchange::Nse => get_bhavcopy_date_range(conn)?
+            .map(|(_, max)| max)
+            .ok_or_else(|| "No BhavCopy data in the database yet; run `indistocks sync` first".into()),
+        Exchange::Bse => get_exchange_date_range(conn, exchange)?
+            .map(|(_, max)| max)
+            .ok_or_else(|| "No BSE data in the database yet; import BSE BhavCopy files with `indistocks import`".into()),
+    }
+}
+
+/// Download BhavCopy files newer than the latest stored date
+pub fn sync(source: Option<String>, initial_days: i64, fill_gaps: bool, delivery: bool, derivatives: bool, indices: bool) -> Result<(), Box<dyn Error>> {
+    let source = open_source(source)?;
+    let conn = Arc::new(Mutex::new(init_db()?));
+    let (tx, rx) = mpsc::channel();
+
+    let conn_clone = conn.clone();
+    std::thread::spawn(move || {
+        let control = DownloadControl::default();
+        let result = if fill_gaps {
+            fill_bhavcopy_gaps(&conn_clone, &tx, &control, &*source, None, None)
+        } else if delivery {
+            fill_delivery_gaps(&conn_clone, &tx, &control, &*source)
+        } else if derivatives {
+            fill_derivatives_gaps(&conn_clone, &tx, &control, &*source)
+        } else if indices {
+            fill_index_gaps(&conn_clone, &tx, &control, &*source)
+        } else {
+            sync_bhavcopy(&conn_clone, &tx, &control, &*source, initial_days)
+        };
+        let _ = tx.send(BhavCopyMessage::Done(result.map_err(|e| e.to_string())));
+    });
+
+    loop {
+        match rx.recv() {
+            Ok(BhavCopyMessage::Done(result)) => {
+                result?;
+                break;
+            }
+            Ok(message @ (BhavCopyMessage::DayFinished { .. } | BhavCopyMessage::Stopped(_) | BhavCopyMessage::AlertsTriggered(_))) => {
+                println!("{}", message.describe())
+            }
+            Ok(_) => {}
+            Err(_) => return Err("Download thread stopped unexpectedly".into()),
+        }
+    }
+
+    if let Some((min, max)) = get_bhavcopy_date_range(&conn.lock().unwrap())? {
+        println!("BhavCopy data now covers {} to {}", min, max);
+    }
+    Ok(())
+}
+
+/// Refresh the NSE equity list (symbols and company names)
+pub fn symbols_update(source: Option<String>) -> Result<(), Box<dyn Error>> {
+    let source = open_source(source)?;
+    let conn = init_db()?;
+
+    let symbols = download_nse_equity_list(&*source)?;
+    let (count, errors) = save_nse_symbols_with_names(&conn, symbols)?;
+    println!("Saved {} symbols", count);
+    for error in &errors {
+        eprintln!("  {}", error);
+    }
+    Ok(())
+}
+
+pub fn quote(symbol: &str, exchange: Exchange, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let symbol = symbol.to_uppercase();
+    let quote = get_quote(&conn, exchange, &symbol)?
+        .ok_or_else(|| format!("No {} EQ data for symbol {}", exchange, symbol))?;
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    writer.write(&quote)?;
+    writer.finish()
+}
+
+/// Daily bars for a symbol; defaults to the 30 days before the latest stored date
+pub fn history(symbol: &str, exchange: Exchange, from: Option<String>, to: Option<String>, adjusted: bool, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let symbol = symbol.to_uppercase();
+
+    let to = match to {
+        Some(to) => parse_date(&to)?,
+        None => latest_date(&conn, exchange)?,
+    };
+    let from = match from {
+        Some(from) => parse_date(&from)?,
+        None => to - chrono::Duration::days(30),
+    };
+
+    let bars = if adjusted {
+        get_adjusted_price_bars_in_range(&conn, exchange, &symbol, from, to)?
+    } else {
+        get_price_bars_in_range(&conn, exchange, &symbol, from, to)?
+    };
+    if bars.is_empty() {
+        return Err(format!("No {} EQ data for {} between {} and {}", exchange, symbol, from, to).into());
+    }
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for bar in &bars {
+        writer.write(bar)?;
+    }
+    writer.finish()
+}
+
+/// Stock against an index over a date range; defaults to the year before the latest stored date
+pub fn compare(symbol: &str, index: &str, from: Option<String>, to: Option<String>, adjusted: bool, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row {
+        symbol: String,
+        index: String,
+        from: NaiveDate,
+        to: NaiveDate,
+        sessions: usize,
+        stock_return_pct: f64,
+        index_return_pct: f64,
+        relative_strength_pct: f64,
+        beta: Option<f64>,
+        correlation: Option<f64>,
+    }
+
+    let conn = init_db()?;
+    let symbol = symbol.to_uppercase();
+
+    let to = match to {
+        Some(to) => parse_date(&to)?,
+        None => latest_date(&conn, Exchange::Nse)?,
+    };
+    let from = match from {
+        Some(from) => parse_date(&from)?,
+        None => to - chrono::Duration::days(365),
+    };
+
+    let names = get_index_names(&conn)?;
+    if names.is_empty() {
+        return Err("No index data in the database yet; run `indistocks sync --indices` first".into());
+    }
+    let index = names.iter()
+        .find(|name| name.eq_ignore_ascii_case(index))
+        .ok_or_else(|| format!("Unknown index {}; available: {}", index, names.join(", ")))?;
+    let index_bars = get_index_bars_in_range(&conn, index, from, to)?;
+    if index_bars.is_empty() {
+        return Err(format!("No {} data between {} and {}", index, from, to).into());
+    }
+
+    let bars = if adjusted {
+        get_adjusted_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)?
+    } else {
+        get_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)?
+    };
+    let comparison = compare_with_index(&bars, &index_bars)
+        .ok_or_else(|| format!("{} and {} share fewer than two sessions between {} and {}", symbol, index, from, to))?;
+
+    let round = |v: f64| (v * 100.0).round() / 100.0;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    writer.write(&Row {
+        symbol,
+        index: index.clone(),
+        from: comparison.from,
+        to: comparison.to,
+        sessions: comparison.sessions,
+        stock_return_pct: round(comparison.stock_return_pct),
+        index_return_pct: round(comparison.index_return_pct),
+        relative_strength_pct: round(comparison.relative_strength_pct),
+        beta: comparison.beta.map(round),
+        correlation: comparison.correlation.map(round),
+    })?;
+    writer.finish()
+}
+
+/// Near-month futures basis over the latest `days` sessions, oldest first
+pub fn futures(symbol: &str, days: usize, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row {
+        date: NaiveDate,
+        expiry: NaiveDate,
+        future: Option<f64>,
+        spot: Option<f64>,
+        basis: Option<f64>,
+        basis_percent: Option<f64>,
+        open_interest: Option<i64>,
+        change_in_oi: Option<i64>,
+        total_open_interest: Option<i64>,
+    }
+
+    let conn = init_db()?;
+    let symbol = symbol.to_uppercase();
+    let basis = get_futures_basis(&conn, &symbol, days)?;
+    if basis.is_empty() {
+        return Err(format!("No futures data for {}; run `indistocks sync --derivatives` first", symbol).into());
+    }
+
+    let round = |v: f64| (v * 100.0).round() / 100.0;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for entry in basis.iter().rev() {
+        writer.write(&Row {
+            date: entry.date,
+            expiry: entry.expiry,
+            future: entry.future_price,
+            spot: entry.spot,
+            basis: entry.basis().map(round),
+            basis_percent: entry.basis_percent().map(round),
+            open_interest: entry.open_interest,
+            change_in_oi: entry.change_in_oi,
+            total_open_interest: entry.total_open_interest,
+        })?;
+    }
+    writer.finish()
+}
+
+/// Calls and puts side by side per strike, with the put-call ratio as a summary line
+pub fn option_chain(symbol: &str, expiry: Option<String>, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row {
+        call_oi: Option<i64>,
+        call_chg_oi: Option<i64>,
+        call_volume: Option<i64>,
+        call_close: Option<f64>,
+        strike: f64,
+        put_close: Option<f64>,
+        put_volume: Option<i64>,
+        put_chg_oi: Option<i64>,
+        put_oi: Option<i64>,
+    }
+
+    let conn = init_db()?;
+    let symbol = symbol.to_uppercase();
+    let expiry = expiry.as_deref().map(parse_date).transpose()?;
+    let chain = get_option_chain(&conn, &symbol, expiry)?
+        .ok_or_else(|| format!("No options data for {}; run `indistocks sync --derivatives` first", symbol))?;
+    if let Some(expiry) = expiry.filter(|e| *e != chain.expiry) {
+        let available: Vec<String> = chain.expiries.iter().map(|e| e.to_string()).collect();
+        return Err(format!("No {} options expiring {} on {}; expiries: {}", symbol, expiry, chain.date, available.join(", ")).into());
+    }
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for row in &chain.rows {
+        let call = row.call.clone().unwrap_or_default();
+        let put = row.put.clone().unwrap_or_default();
+        writer.write(&Row {
+            call_oi: call.open_interest,
+            call_chg_oi: call.change_in_oi,
+            call_volume: call.volume,
+            call_close: call.close,
+            strike: row.strike,
+            put_close: put.close,
+            put_volume: put.volume,
+            put_chg_oi: put.change_in_oi,
+            put_oi: put.open_interest,
+        })?;
+    }
+    writer.finish()?;
+
+    let summary = format!(
+        "{} options expiring {} on {}: spot {}, call OI {}, put OI {}, PCR {}",
+        symbol, chain.expiry, chain.date,
+        chain.spot.map(|s| format!("{:.2}", s)).unwrap_or_else(|| "-".to_string()),
+        chain.call_open_interest(), chain.put_open_interest(),
+        chain.put_call_ratio().map(|p| format!("{:.2}", p)).unwrap_or_else(|| "-".to_string()),
+    );
+    if matches!(format, OutputFormat::Table) {
+        println!("{}", summary);
+    } else {
+        eprintln!("{}", summary);
+    }
+    Ok(())
+}
+
+/// Stocks page filters: latest close between two prices, with the low/high over `range_days`,
+/// optionally narrowed by a screener expression or a saved screen's name
+#[allow(clippy::too_many_arguments)]
+pub fn screen(
+    exchange: Exchange,
+    price_from: Option<f64>,
+    price_to: Option<f64>,
+    range_days: i64,
+    adjusted: bool,
+    expr: Option<String>,
+    saved: Option<String>,
+    sort: StockSort,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let mut stocks = if adjusted {
+        get_all_stocks_with_metrics_adjusted(&conn, exchange, price_from, price_to, range_days, sort)?
+    } else {
+        get_all_stocks_with_metrics(&conn, exchange, price_from, price_to, range_days, sort)?
+    };
+
+    let expression = match (expr, saved) {
+        (Some(expr), _) => Some(expr),
+        (None, Some(name)) => Some(
+            get_saved_screens(&conn)?
+                .into_iter()
+                .find(|s| s.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| format!("No saved screen named {}", name))?
+                .expression
+        ),
+        (None, None) => None,
+    };
+    if let Some(expression) = expression {
+        let screen = Screen::parse(&expression).map_err(|e| e.to_string())?;
+        let matches: std::collections::HashSet<String> = run_screen(&conn, &screen, adjusted)?
+            .into_iter()
+            .map(|m| m.symbol)
+            .collect();
+        retain_nse_symbols(&conn, exchange, &mut stocks, &matches)?;
+    }
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for stock in &stocks {
+        writer.write(stock)?;
+    }
+    writer.finish()
+}
+
+/// Raw BhavCopy rows for a date range, to stdout or a file; both exchanges unless one is given
+pub fn export(from: Option<String>, to: Option<String>, exchange: Option<Exchange>, symbol: Option<String>, output: Option<std::path::PathBuf>, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+
+    let to = match to {
+        Some(to) => parse_date(&to)?,
+        None => latest_date(&conn, exchange.unwrap_or_default())?,
+    };
+    let from = match from {
+        Some(from) => parse_date(&from)?,
+        None => to,
+    };
+    let symbol = symbol.map(|s| s.to_uppercase());
+
+    let out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let mut writer = RecordWriter::new(format, out);
+    let count = for_each_bhavcopy_row(&conn, from, to, exchange, symbol.as_deref(), |row| writer.write(&row))?;
+    writer.finish()?;
+
+    if let Some(path) = output {
+        println!("Exported {} rows to {}", count, path.display());
+    }
+    Ok(())
+}
+
+/// Capital gains for a financial year: every FIFO-matched sale, or the Schedule 112A CSV
+pub fn tax_report(year: &str, schedule_112a: bool, output: Option<std::path::PathBuf>, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let year = FinancialYear::parse(year).ok_or_else(|| format!("Invalid financial year {}, expected e.g. 2024-25", year))?;
+    let conn = init_db()?;
+    let report = capital_gains_report(&conn, year)?;
+    for warning in &report.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    if schedule_112a {
+        write_schedule_112a_csv(&report, out)?;
+    } else {
+        let mut writer = RecordWriter::new(format, out);
+        for gain in &report.gains {
+            writer.write(gain)?;
+        }
+        writer.finish()?;
+    }
+
+    // Keep CSV/JSON on stdout machine-readable
+    if format == OutputFormat::Table && output.is_none() && !schedule_112a {
+        println!();
+        print_tax_summary(&report, &mut std::io::stdout().lock())?;
+    } else {
+        print_tax_summary(&report, &mut std::io::stderr().lock())?;
+    }
+    Ok(())
+}
+
+fn print_tax_summary(report: &CapitalGainsReport, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "FY {}: {} matched sales", report.year.label(), report.gains.len())?;
+    writeln!(out, "  STCG:                 {:>14.2}", report.stcg)?;
+    writeln!(out, "  LTCG:                 {:>14.2}", report.ltcg)?;
+    if report.stcl_set_off > 0.0 {
+        writeln!(out, "  STCL set off vs LTCG: {:>14.2}", report.stcl_set_off)?;
+    }
+    writeln!(out, "  LTCG exemption:       {:>14.2}", report.ltcg_exemption)?;
+    writeln!(out, "  Taxable STCG:         {:>14.2}", report.taxable_stcg)?;
+    writeln!(out, "  Taxable LTCG:         {:>14.2}", report.taxable_ltcg)?;
+    let (short_loss, long_loss) = report.carry_forward;
+    if short_loss > 0.0 || long_loss > 0.0 {
+        writeln!(out, "  Loss carried forward: {:>14.2} short-term, {:.2} long-term", short_loss, long_loss)?;
+    }
+    Ok(())
+}
+
+/// Preview a broker tradebook against the database, and import its matched trades with `commit`.
+/// `mappings` are `BROKER_SYMBOL=NSE_SYMBOL` pairs saved for this and later imports.
+pub fn import_trades(file: &std::path::Path, broker: Option<String>, mappings: &[String], commit: bool, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let broker = match broker {
+        Some(name) => Some(Broker::parse(&name).ok_or_else(|| format!("Unknown broker {}, expected zerodha, upstox or icici", name))?),
+        None => None,
+    };
+    let conn = init_db()?;
+    let mut preview = preview_tradebook(&conn, file, broker)?;
+
+    if !mappings.is_empty() {
+        for mapping in mappings {
+            let (broker_symbol, symbol) = mapping.split_once('=')
+                .ok_or_else(|| format!("Invalid mapping {}, expected BROKER_SYMBOL=NSE_SYMBOL", mapping))?;
+            save_broker_symbol_mapping(&conn, preview.broker, broker_symbol, symbol)?;
+        }
+        resolve_tradebook(&conn, &mut preview)?;
+    }
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for row in &preview.rows {
+        writer.write(row)?;
+    }
+    writer.finish()?;
+
+    let mut err = std::io::stderr().lock();
+    writeln!(
+        err,
+        "{} tradebook: {} ready, {} duplicate, {} unmatched, {} skipped",
+        preview.broker.label(),
+        preview.count(TradeStatus::Ready),
+        preview.count(TradeStatus::Duplicate),
+        preview.count(TradeStatus::Unmatched),
+        preview.count(TradeStatus::Skipped),
+    )?;
+    for line in &preview.rejected {
+        writeln!(err, "  {}", line)?;
+    }
+    let unmatched = preview.unmatched_symbols();
+    if !unmatched.is_empty() {
+        writeln!(err, "Unmatched symbols: {} (map them with --map SYMBOL=NSE_SYMBOL)", unmatched.join(", "))?;
+    }
+
+    if !commit {
+        writeln!(err, "Nothing imported; re-run with --commit to add the ready trades")?;
+        return Ok(());
+    }
+
+    let report = commit_tradebook(&conn, &preview)?;
+    writeln!(err, "Imported {} trades", report.inserted)?;
+    for line in &report.rejected {
+        writeln!(err, "  {}", line)?;
+    }
+    Ok(())
+}
+
+pub fn screens_list(format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        name: &'a str,
+        expression: &'a str,
+    }
+
+    let conn = init_db()?;
+    let screens = get_saved_screens(&conn)?;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for screen in &screens {
+        writer.write(&Row { name: &screen.name, expression: &screen.expression })?;
+    }
+    writer.finish()
+}
+
+pub fn holidays_import(file: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let report = import_holidays_csv(&conn, file)?;
+    println!("Imported {} holidays from {}", report.inserted, file.display());
+    println!("  {} already in the calendar", report.updated);
+    println!("  {} on weekends (skipped)", report.weekends);
+    println!("  {} rejected", report.rejected.len());
+    for error in report.rejected.iter().take(10) {
+        println!("    {}", error);
+    }
+    Ok(())
+}
+
+pub fn holidays_list(year: Option<i32>, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        date: NaiveDate,
+        day: String,
+        description: &'a str,
+    }
+
+    let conn = init_db()?;
+    let holidays = get_market_holidays(&conn)?;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for holiday in holidays.iter().filter(|h| year.is_none_or(|year| h.date.year() == year)) {
+        writer.write(&Row {
+            date: holiday.date,
+            day: holiday.date.format("%A").to_string(),
+            description: &holiday.description,
+        })?;
+    }
+    writer.finish()
+}
+
+/// Trading days (weekdays that are not imported holidays) with no stored BhavCopy
+pub fn gaps(from: Option<String>, to: Option<String>, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row {
+        date: NaiveDate,
+        day: String,
+    }
+
+    let conn = init_db()?;
+    let from = from.as_deref().map(parse_date).transpose()?;
+    let to = to.as_deref().map(parse_date).transpose()?;
+    let missing = find_missing_trading_days(&conn, from, to)?;
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for date in &missing {
+        writer.write(&Row { date: *date, day: date.format("%A").to_string() })?;
+    }
+    writer.finish()?;
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let summary = format!("{} missing trading days; run `indistocks sync --fill-gaps` to download them", missing.len());
+    if matches!(format, OutputFormat::Table) {
+        println!("{}", summary);
+    } else {
+        eprintln!("{}", summary);
+    }
+    Ok(())
+}
+
+pub fn screens_save(name: &str, expression: &str) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    save_screen(&conn, name, expression)?;
+    println!("Saved screen {}", name.trim());
+    Ok(())
+}
+
+pub fn screens_delete(name: &str) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let screen = get_saved_screens(&conn)?
+        .into_iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("No saved screen named {}", name))?;
+    delete_saved_screen(&conn, screen.id)?;
+    println!("Deleted screen {}", screen.name);
+    Ok(())
+}
+
+/// Notifications shown by `alerts inbox`
+const ALERT_INBOX_LIMIT: usize = 200;
+
+pub fn alerts_list(format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        id: i64,
+        symbol: &'a str,
+        condition: String,
+        enabled: bool,
+    }
+
+    let conn = init_db()?;
+    let rules = get_alert_rules(&conn)?;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for rule in &rules {
+        writer.write(&Row { id: rule.id, symbol: &rule.symbol, condition: rule.describe(), enabled: rule.enabled })?;
+    }
+    writer.finish()
+}
+
+pub fn alerts_add(symbol: &str, kind: &str, value: Option<f64>, period: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let kind = AlertKind::parse(kind).ok_or_else(|| format!("Unknown alert kind {}", kind))?;
+    let value = match (kind.value_label(), value) {
+        (Some(label), None) => return Err(format!("{} needs a value ({})", kind.label(), label.to_lowercase()).into()),
+        (_, value) => value.unwrap_or(0.0),
+    };
+    let conn = init_db()?;
+    let id = create_alert_rule(&conn, symbol, kind, value, period)?;
+    let rule = get_alert_rules(&conn)?.into_iter().find(|rule| rule.id == id).ok_or("Rule was not saved")?;
+    println!("Added alert {}: {} {}", rule.id, rule.symbol, rule.describe());
+    Ok(())
+}
+
+pub fn alerts_delete(id: i64) -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let rule = get_alert_rules(&conn)?
+        .into_iter()
+        .find(|rule| rule.id == id)
+        .ok_or_else(|| format!("No alert with id {}", id))?;
+    delete_alert_rule(&conn, id)?;
+    println!("Deleted alert {}: {} {}", rule.id, rule.symbol, rule.describe());
+    Ok(())
+}
+
+pub fn alerts_check() -> Result<(), Box<dyn Error>> {
+    let conn = init_db()?;
+    let alerts = evaluate_alerts(&conn)?;
+    if alerts.is_empty() {
+        println!("No new alerts");
+    }
+    for alert in &alerts {
+        println!("Alert: {}", alert.message);
+    }
+    Ok(())
+}
+
+pub fn alerts_inbox(unread: bool, mark_read: bool, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        date: NaiveDate,
+        symbol: &'a str,
+        message: &'a str,
+        read: bool,
+    }
+
+    let conn = init_db()?;
+    let notifications = get_alert_notifications(&conn, ALERT_INBOX_LIMIT)?;
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for notification in notifications.iter().filter(|n| !unread || !n.is_read) {
+        writer.write(&Row {
+            date: notification.date,
+            symbol: &notification.symbol,
+            message: &notification.message,
+            read: notification.is_read,
+        })?;
+    }
+    writer.finish()?;
+    if mark_read {
+        mark_all_alert_notifications_read(&conn)?;
+    }
+    Ok(())
+}
+
+#[derive(clap::Args)]
+pub struct BacktestArgs {
+    /// NSE symbol to trade
+    symbol: String,
+    /// sma-cross, rsi, screen or buy-and-hold
+    #[arg(long, default_value = "sma-cross")]
+    strategy: String,
+    /// Fast SMA period (sma-cross)
+    #[arg(long, default_value = "50")]
+    fast: usize,
+    /// Slow SMA period (sma-cross)
+    #[arg(long, default_value = "200")]
+    slow: usize,
+    /// RSI period (rsi)
+    #[arg(long, default_value = "14")]
+    rsi_period: usize,
+    /// Buy below this RSI (rsi)
+    #[arg(long, default_value = "30")]
+    oversold: f64,
+    /// Sell above this RSI (rsi)
+    #[arg(long, default_value = "70")]
+    overbought: f64,
+    /// Screener expression to enter on (screen)
+    #[arg(long)]
+    entry: Option<String>,
+    /// Screener expression to exit on (screen)
+    #[arg(long)]
+    exit: Option<String>,
+    /// Start date (YYYY-MM-DD), defaults to the earliest stored date
+    #[arg(long)]
+    from: Option<String>,
+    /// End date (YYYY-MM-DD), defaults to the latest stored date
+    #[arg(long)]
+    to: Option<String>,
+    /// Starting cash in rupees
+    #[arg(long, default_value = "100000")]
+    capital: f64,
+    /// Position size: a percentage of equity (100%), rupees (50000) or shares (25sh)
+    #[arg(long, default_value = "100%")]
+    size: String,
+    /// next-open fills a signal at the following bar's open, close at the signal bar's close
+    #[arg(long, default_value = "next-open")]
+    fill: String,
+    /// Brokerage as a percentage of each order's value
+    #[arg(long)]
+    brokerage: Option<f64>,
+    /// Cap on brokerage per order in rupees (0 for none)
+    #[arg(long)]
+    brokerage_max: Option<f64>,
+    /// Ignore brokerage, taxes and exchange charges
+    #[arg(long)]
+    no_costs: bool,
+    /// Use raw prices instead of adjusting for splits, bonuses and dividends
+    #[arg(long)]
+    raw: bool,
+    /// Also write the daily equity curve to this CSV file
+    #[arg(long)]
+    equity: Option<std::path::PathBuf>,
+}
+
+impl BacktestArgs {
+    fn strategy(&self) -> Result<StrategySpec, Box<dyn Error>> {
+        Ok(match self.strategy.as_str() {
+            "sma-cross" => StrategySpec::SmaCrossover { fast: self.fast, slow: self.slow },
+            "rsi" => StrategySpec::RsiReversion { period: self.rsi_period, oversold: self.oversold, overbought: self.overbought },
+            "screen" => {
+                let (Some(entry), Some(exit)) = (&self.entry, &self.exit) else {
+                    return Err("The screen strategy needs --entry and --exit expressions".into());
+                };
+                StrategySpec::Screen { entry: entry.clone(), exit: exit.clone() }
+            }
+            "buy-and-hold" => StrategySpec::BuyAndHold,
+            other => return Err(format!("Unknown strategy {}, expected sma-cross, rsi, screen or buy-and-hold", other).into()),
+        })
+    }
+
+    fn config(&self) -> Result<BacktestConfig, Box<dyn Error>> {
+        let mut costs = if self.no_costs { CostModel::zero() } else { CostModel::default() };
+        if let Some(pct) = self.brokerage {
+            costs.brokerage_pct = pct;
+        }
+        if let Some(max) = self.brokerage_max {
+            costs.brokerage_max = max;
+        }
+        if self.capital <= 0.0 {
+            return Err("--capital must be positive".into());
+        }
+        Ok(BacktestConfig {
+            initial_capital: self.capital,
+            fill: FillModel::parse(&self.fill).ok_or_else(|| format!("Invalid fill {}, expected next-open or close", self.fill))?,
+            sizing: PositionSizing::parse(&self.size).ok_or_else(|| format!("Invalid size {}, expected e.g. 100%, 50000 or 25sh", self.size))?,
+            costs,
+        })
+    }
+}
+
+/// Run a strategy over one symbol's stored history: the trade list, then a summary
+pub fn backtest(args: BacktestArgs, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let spec = args.strategy()?;
+    let config = args.config()?;
+    let mut strategy = spec.build().map_err(|e| e.to_string())?;
+
+    let conn = init_db()?;
+    let symbol = args.symbol.to_uppercase();
+    let (earliest, latest) = get_bhavcopy_date_range(&conn)?.ok_or("No BhavCopy data downloaded yet")?;
+    let from = match &args.from {
+        Some(from) => parse_date(from)?,
+        None => earliest,
+    };
+    let to = match &args.to {
+        Some(to) => parse_date(to)?,
+        None => latest,
+    };
+    let bars = if args.raw {
+        get_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)?
+    } else {
+        get_adjusted_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)?
+    };
+    if bars.is_empty() {
+        return Err(format!("No EQ data for {} between {} and {}", symbol, from, to).into());
+    }
+
+    let result = run_backtest(&bars, strategy.as_mut(), &config);
+
+    let mut writer = RecordWriter::new(format, std::io::stdout().lock());
+    for trade in &result.trades {
+        writer.write(trade)?;
+    }
+    writer.finish()?;
+
+    if let Some(path) = &args.equity {
+        let mut writer = csv::Writer::from_path(path)?;
+        for point in &result.equity_curve {
+            writer.serialize(point)?;
+        }
+        writer.flush()?;
+    }
+
+    // Keep CSV/JSON on stdout machine-readable
+    let mut out: Box<dyn Write> = if format == OutputFormat::Table {
+        println!();
+        Box::new(std::io::stdout().lock())
+    } else {
+        Box::new(std::io::stderr().lock())
+    };
+    let summary = &result.summary;
+    let first = bars[0].date;
+    let last = bars[bars.len() - 1].date;
+    writeln!(out, "{} on {}, {} to {} ({} bars)", spec.label(), symbol, first, last, bars.len())?;
+    writeln!(out, "  Final equity:     {:>14.2} (from {:.2})", summary.final_equity, summary.initial_capital)?;
+    writeln!(out, "  Total return:     {:>13.2}% (buy and hold {:.2}%)", summary.total_return_pct, summary.buy_and_hold_pct)?;
+    writeln!(out, "  CAGR:             {:>14}", optional_pct(summary.cagr_pct))?;
+    writeln!(out, "  Max drawdown:     {:>13.2}%", summary.max_drawdown_pct)?;
+    writeln!(out, "  Sharpe:           {:>14}", summary.sharpe.map(|s| format!("{:.2}", s)).unwrap_or_else(|| "-".to_string()))?;
+    writeln!(out, "  Trades:           {:>14} (win rate {})", summary.trades, optional_pct(summary.win_rate_pct))?;
+    writeln!(out, "  Exposure:         {:>13.2}%", summary.exposure_pct)?;
+    writeln!(
+        out,
+        "  Charges:          {:>14.2} (brokerage {:.2}, STT {:.2}, exchange {:.2}, SEBI {:.2}, stamp duty {:.2}, GST {:.2})",
+        summary.total_charges,
+        result.charges.brokerage,
+        result.charges.stt,
+        result.charges.exchange,
+        result.charges.sebi,
+        result.charges.stamp_duty,
+        result.charges.gst,
+    )?;
+    Ok(())
+}
+
+fn optional_pct(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}%", v)).unwrap_or_else(|| "-".to_string())
+}
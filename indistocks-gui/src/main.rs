@@ -1,9 +1,12 @@
 mod app;
+mod cli;
 mod ui;
 
 use app::IndistocksApp;
-use indistocks_db::{init_db, populate_demo_data, clear_bhavcopy_data};
+use indistocks_db::{init_db, populate_demo_data, clear_bhavcopy_data, Exchange, StockColumn, StockSort};
+use indistocks_db::logging::init_logging;
 use clap::{Parser, Subcommand};
+use cli::OutputFormat;
 
 #[derive(Parser)]
 #[command(name = "indistocks")]
@@ -11,6 +14,9 @@ use clap::{Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Output format for quote, history, screen, export, tax-report, import-trades and backtest
+    #[arg(long, value_enum, global = true)]
+    format: Option<OutputFormat>,
 }
 
 #[derive(Subcommand)]
@@ -29,25 +35,368 @@ enum Commands {
         /// End date for download (format: YYYY-MM-DD)
         #[arg(long)]
         to: Option<String>,
+        /// Archive root to download from: an http(s) URL, file:// URL or directory
+        /// (defaults to $INDISTOCKS_DATA_SOURCE, then the NSE archives)
+        #[arg(long)]
+        source: Option<String>,
+        /// Delete all stored BhavCopy data before downloading
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Import NSE or BSE BhavCopy CSV/ZIP files, NSE F&O BhavCopies, NSE index close files, or NSE MTO delivery files, from a local directory
+    Import {
+        /// Directory to scan recursively for BhavCopy files
+        dir: std::path::PathBuf,
+    },
+    /// Import NSE's corporate actions CSV export (splits, bonuses, dividends)
+    ImportCorporateActions {
+        /// Path to the CSV file
+        file: std::path::PathBuf,
+    },
+    /// Download BhavCopy files published since the latest stored date
+    Sync {
+        /// Days to download when the database is still empty
+        #[arg(long, default_value = "30")]
+        initial_days: i64,
+        /// Download only the trading days missing inside the stored range (see `indistocks gaps`)
+        #[arg(long)]
+        fill_gaps: bool,
+        /// Download delivery positions for stored days that have none
+        #[arg(long, conflicts_with = "fill_gaps")]
+        delivery: bool,
+        /// Download F&O BhavCopies for the latest 60 stored days that have none
+        #[arg(long, conflicts_with_all = ["fill_gaps", "delivery"])]
+        derivatives: bool,
+        /// Download NSE index closes for stored days that have none
+        #[arg(long, conflicts_with_all = ["fill_gaps", "delivery", "derivatives"])]
+        indices: bool,
+        /// Archive root to download from (defaults to $INDISTOCKS_DATA_SOURCE, then the NSE archives)
+        #[arg(long)]
+        source: Option<String>,
+    },
+    /// List trading days with no stored BhavCopy (weekends and imported holidays are excluded)
+    Gaps {
+        /// Start date (YYYY-MM-DD), defaults to the earliest stored date
+        #[arg(long)]
+        from: Option<String>,
+        /// End date (YYYY-MM-DD), defaults to the latest stored date
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Manage the market holiday calendar
+    Holidays {
+        #[command(subcommand)]
+        command: HolidaysCommand,
+    },
+    /// Manage price alerts and read the notification inbox
+    Alerts {
+        #[command(subcommand)]
+        command: AlertsCommand,
+    },
+    /// Manage the NSE symbol list
+    Symbols {
+        #[command(subcommand)]
+        command: SymbolsCommand,
+    },
+    /// Latest quote for a symbol with its 52-week range
+    Quote {
+        /// NSE symbol, or BSE scrip code with --exchange bse
+        symbol: String,
+        /// nse or bse
+        #[arg(long, default_value = "nse", value_parser = parse_exchange)]
+        exchange: Exchange,
+    },
+    /// Daily OHLCV history for a symbol
+    History {
+        /// NSE symbol, or BSE scrip code with --exchange bse
+        symbol: String,
+        /// nse or bse
+        #[arg(long, default_value = "nse", value_parser = parse_exchange)]
+        exchange: Exchange,
+        /// Start date (YYYY-MM-DD), defaults to 30 days before --to
+        #[arg(long)]
+        from: Option<String>,
+        /// End date (YYYY-MM-DD), defaults to the latest stored date
+        #[arg(long)]
+        to: Option<String>,
+        /// Adjust prices for splits, bonuses and dividends
+        #[arg(long)]
+        adjusted: bool,
+    },
+    /// Near-month futures price against the stock, with basis and open interest
+    Futures {
+        /// NSE symbol or index name, e.g. NIFTY
+        symbol: String,
+        /// Number of latest sessions to show
+        #[arg(long, default_value = "20")]
+        days: usize,
+    },
+    /// End-of-day option chain from the latest stored F&O BhavCopy, with open interest and put-call ratio
+    OptionChain {
+        /// NSE symbol or index name, e.g. NIFTY
+        symbol: String,
+        /// Expiry date (YYYY-MM-DD), defaults to the nearest
+        #[arg(long)]
+        expiry: Option<String>,
+    },
+    /// Relative strength, beta and correlation of a stock against an NSE index
+    Compare {
+        /// NSE symbol
+        symbol: String,
+        /// Index name as published in NSE's index close file
+        #[arg(long, default_value = "Nifty 50")]
+        index: String,
+        /// Start date (YYYY-MM-DD), defaults to a year before --to
+        #[arg(long)]
+        from: Option<String>,
+        /// End date (YYYY-MM-DD), defaults to the latest stored date
+        #[arg(long)]
+        to: Option<String>,
+        /// Adjust the stock's prices for splits, bonuses and dividends
+        #[arg(long)]
+        adjusted: bool,
+    },
+    /// List stocks by latest close, with the low/high over a range (same filters and screens as the Stocks page)
+    Screen {
+        /// nse or bse
+        #[arg(long, default_value = "nse", value_parser = parse_exchange)]
+        exchange: Exchange,
+        /// Minimum latest close
+        #[arg(long)]
+        price_from: Option<f64>,
+        /// Maximum latest close
+        #[arg(long)]
+        price_to: Option<f64>,
+        /// Days covered by the low/high range
+        #[arg(long, default_value = "365")]
+        range_days: i64,
+        /// Adjust the range for splits, bonuses and dividends
+        #[arg(long)]
+        adjusted: bool,
+        /// Screener expression, e.g. "close > sma(200) and change_pct(5) > 3"
+        #[arg(long, conflicts_with = "saved")]
+        expr: Option<String>,
+        /// Run a saved screen by name
+        #[arg(long)]
+        saved: Option<String>,
+        /// Column to sort by (symbol, ltp, change_percent, volume, turnover, ...)
+        #[arg(long, default_value = "symbol", value_parser = parse_stock_column)]
+        sort: StockColumn,
+        /// Sort in descending order
+        #[arg(long)]
+        desc: bool,
+    },
+    /// Manage saved screener expressions
+    Screens {
+        #[command(subcommand)]
+        command: ScreensCommand,
+    },
+    /// Export stored BhavCopy rows (CSV by default)
+    Export {
+        /// Start date (YYYY-MM-DD), defaults to --to
+        #[arg(long)]
+        from: Option<String>,
+        /// End date (YYYY-MM-DD), defaults to the latest stored date
+        #[arg(long)]
+        to: Option<String>,
+        /// Only export this exchange (nse or bse)
+        #[arg(long, value_parser = parse_exchange)]
+        exchange: Option<Exchange>,
+        /// Only export this symbol
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Capital gains from the portfolio ledger for a financial year (FIFO, STCG/LTCG)
+    TaxReport {
+        /// Financial year, e.g. 2024-25
+        year: String,
+        /// Write the long-term lots in ITR Schedule 112A column layout (CSV)
+        #[arg(long)]
+        schedule_112a: bool,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Import trades from a Zerodha, Upstox or ICICI Direct tradebook CSV into the portfolio
+    ImportTrades {
+        /// Path to the tradebook CSV
+        file: std::path::PathBuf,
+        /// zerodha, upstox or icici (detected from the header row by default)
+        #[arg(long)]
+        broker: Option<String>,
+        /// Map a broker symbol to an NSE symbol, e.g. --map RELIND=RELIANCE (remembered for later imports)
+        #[arg(long = "map")]
+        mappings: Vec<String>,
+        /// Add the ready trades; without this only the preview is shown
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Backtest an end-of-day strategy on one symbol, with Indian delivery charges
+    Backtest(cli::BacktestArgs),
+}
+
+#[derive(Subcommand)]
+enum ScreensCommand {
+    /// List saved screens
+    List,
+    /// Save an expression under a name, replacing any screen with that name
+    Save {
+        name: String,
+        expression: String,
+    },
+    /// Delete a saved screen
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HolidaysCommand {
+    /// Import an exchange holiday list CSV (a date column and a description column)
+    Import {
+        /// Path to the CSV file
+        file: std::path::PathBuf,
     },
+    /// List imported holidays
+    List {
+        /// Only list holidays in this calendar year
+        #[arg(long)]
+        year: Option<i32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlertsCommand {
+    /// List alert rules
+    List,
+    /// Add a rule, e.g. `alerts add RELIANCE crosses-above 3000` or `alerts add TCS rsi-below 30 --period 14`
+    Add {
+        symbol: String,
+        /// crosses-above, crosses-below, change-above, change-below, new-52w-high, volume-above-average,
+        /// crosses-above-sma, crosses-below-sma, crosses-above-ema, crosses-below-ema, rsi-above or rsi-below
+        kind: String,
+        /// Price level, percent change, volume multiple or RSI level (not needed for new-52w-high or SMA/EMA crosses)
+        #[arg(allow_negative_numbers = true)]
+        value: Option<f64>,
+        /// Indicator period for SMA, EMA and RSI rules (defaults to 50 for SMA/EMA, 14 for RSI)
+        #[arg(long)]
+        period: Option<usize>,
+    },
+    /// Delete a rule by id
+    Delete {
+        id: i64,
+    },
+    /// Check the rules against the latest stored session now
+    Check,
+    /// Show notifications, newest first
+    Inbox {
+        /// Only unread notifications
+        #[arg(long)]
+        unread: bool,
+        /// Mark everything as read after listing
+        #[arg(long)]
+        mark_read: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SymbolsCommand {
+    /// Download the NSE equity list and save symbols and company names
+    Update {
+        /// Archive root to download from (defaults to $INDISTOCKS_DATA_SOURCE, then the NSE archives)
+        #[arg(long)]
+        source: Option<String>,
+    },
+}
+
+fn parse_exchange(key: &str) -> Result<Exchange, String> {
+    Exchange::parse(key).ok_or_else(|| format!("unknown exchange '{}', expected nse or bse", key))
+}
+
+fn parse_stock_column(key: &str) -> Result<StockColumn, String> {
+    StockColumn::parse(key).ok_or_else(|| {
+        let keys: Vec<&str> = StockColumn::ALL.iter().map(|c| c.key()).collect();
+        format!("unknown column '{}', expected one of: {}", key, keys.join(", "))
+    })
+}
+
+fn import_mode(dir: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    use indistocks_db::import_bhavcopy_dir;
+
+    let conn = init_db()?;
+    println!("Importing BhavCopy files from {}\n", dir.display());
+
+    let reports = import_bhavcopy_dir(&conn, dir)?;
+    if reports.is_empty() {
+        println!("No BhavCopy files found");
+        return Ok(());
+    }
+
+    println!("{:<12} | {:<4} | {:>8} | {:>8} | {:>8} | {:>8} | {:>8} | {:>8} | File", "Date", "Exch", "Inserted", "Skipped", "Rejected", "Delivery", "F&O", "Index");
+    println!("{:-<12}-|-{:-<4}-|-{:->8}-|-{:->8}-|-{:->8}-|-{:->8}-|-{:->8}-|-{:->8}-|-{:-<30}", "", "", "", "", "", "", "", "", "");
+
+    let (mut inserted, mut skipped, mut rejected, mut delivery, mut contracts, mut indices, mut failed) = (0, 0, 0, 0, 0, 0, 0);
+    for report in &reports {
+        let date = report.date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "-".to_string());
+        let exchange = report.exchange.map(|e| e.key()).unwrap_or("-");
+        println!("{:<12} | {:<4} | {:>8} | {:>8} | {:>8} | {:>8} | {:>8} | {:>8} | {}",
+                 date, exchange, report.inserted, report.skipped, report.rejected, report.delivery, report.contracts, report.indices, report.file);
+        if let Some(error) = &report.error {
+            println!("{:<12}   ✗ {}", "", error);
+            failed += 1;
+        }
+        for error in report.row_errors.iter().take(3) {
+            println!("{:<12}   rejected {}", "", error);
+        }
+        if report.row_errors.len() > 3 {
+            println!("{:<12}   ... and {} more rejected rows", "", report.row_errors.len() - 3);
+        }
+        inserted += report.inserted;
+        skipped += report.skipped;
+        rejected += report.rejected;
+        delivery += report.delivery;
+        contracts += report.contracts;
+        indices += report.indices;
+    }
+
+    println!("\n{} files: {} rows inserted, {} skipped, {} rejected, {} delivery positions, {} F&O contracts, {} index closes, {} files failed",
+             reports.len(), inserted, skipped, rejected, delivery, contracts, indices, failed);
+
+    for alert in indistocks_db::evaluate_alerts(&conn)? {
+        println!("Alert: {}", alert.message);
+    }
+
+    Ok(())
 }
 
-fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Option<String>, source: Option<String>, reset: bool) -> Result<(), Box<dyn std::error::Error>> {
     println!("=== INDISTOCKS TEST MODE ===");
     println!("Testing with symbol: {}\n", symbol);
 
+    let source = match source {
+        Some(url) => indistocks_db::data_source_from_url(&url)?,
+        None => indistocks_db::default_data_source()?,
+    };
+
     // Initialize database
     println!("1. Initializing database...");
     let conn = init_db()?;
     println!("   ✓ Database initialized\n");
 
-    // Clear existing bhavcopy data
-    println!("2. Clearing existing BhavCopy data...");
-    clear_bhavcopy_data(&conn)?;
-    println!("   ✓ Data cleared\n");
+    // Only wipe stored bhavcopy data when asked to
+    if reset {
+        println!("2. Clearing existing BhavCopy data...");
+        clear_bhavcopy_data(&conn)?;
+        println!("   ✓ Data cleared\n");
+    } else {
+        println!("2. Keeping existing BhavCopy data (pass --reset to clear it)\n");
+    }
 
     use std::sync::{Arc, Mutex, mpsc};
-    use indistocks_db::{BhavCopyMessage, download_bhavcopy_with_limit, download_bhavcopy_with_date_range};
+    use indistocks_db::{BhavCopyMessage, DownloadControl, download_bhavcopy_with_limit, download_bhavcopy_with_date_range};
     use chrono::NaiveDate;
 
     let conn_arc = Arc::new(Mutex::new(conn));
@@ -64,7 +413,7 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
         // Spawn download in a thread
         let conn_clone = conn_arc.clone();
         std::thread::spawn(move || {
-            let result = download_bhavcopy_with_date_range(&conn_clone, &tx, from, to, None);
+            let result = download_bhavcopy_with_date_range(&conn_clone, &tx, &DownloadControl::default(), &*source, from, to, None);
             let _ = tx.send(BhavCopyMessage::Done(result.map_err(|e| e.to_string())));
         });
     } else {
@@ -73,7 +422,7 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
         // Spawn download in a thread (same as GUI)
         let conn_clone = conn_arc.clone();
         std::thread::spawn(move || {
-            let result = download_bhavcopy_with_limit(&conn_clone, &tx, Some(days));
+            let result = download_bhavcopy_with_limit(&conn_clone, &tx, &DownloadControl::default(), &*source, Some(days));
             let _ = tx.send(BhavCopyMessage::Done(result.map_err(|e| e.to_string())));
         });
     }
@@ -81,9 +430,6 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
     // Process messages (same as GUI would)
     loop {
         match rx.recv() {
-            Ok(BhavCopyMessage::Progress(msg)) => {
-                println!("   {}", msg);
-            }
             Ok(BhavCopyMessage::DateRangeUpdated(_min, _max)) => {
                 // Date range updated, GUI would update display here
             }
@@ -94,6 +440,9 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
                 }
                 break;
             }
+            Ok(message) => {
+                println!("   {}", message.describe());
+            }
             Err(_) => {
                 println!("   ✗ Channel disconnected");
                 break;
@@ -107,7 +456,7 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
     // Count how many files were actually downloaded
     let conn = conn_arc.lock().unwrap();
     let downloaded_count: i64 = conn.query_row("SELECT COUNT(DISTINCT date) FROM bhavcopy_data", [], |row| row.get(0))?;
-    println!("   ✓ Downloaded {} days of data\n", downloaded_count);
+    println!("   ✓ {} days of data stored\n", downloaded_count);
 
     // Query data for the test symbol
     println!("4. Querying data for symbol '{}'...", symbol);
@@ -169,17 +518,97 @@ fn test_mode(symbol: &str, days: usize, from_date: Option<String>, to_date: Opti
     Ok(())
 }
 
+fn import_corporate_actions_mode(file: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    use indistocks_db::import_corporate_actions_csv;
+
+    let conn = init_db()?;
+    let report = import_corporate_actions_csv(&conn, file)?;
+
+    println!("Imported {} corporate actions from {}", report.inserted, file.display());
+    println!("  {} already present", report.skipped);
+    println!("  {} not price-affecting (ignored)", report.ignored);
+    println!("  {} rejected", report.rejected.len());
+    for error in report.rejected.iter().take(10) {
+        println!("    {}", error);
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    init_logging();
 
     match cli.command {
-        Some(Commands::Test { symbol, days, from, to }) => {
-            test_mode(&symbol, days, from, to)?;
+        Some(Commands::Test { symbol, days, from, to, source, reset }) => {
+            test_mode(&symbol, days, from, to, source, reset)?;
+            Ok(())
+        }
+        Some(Commands::Import { dir }) => {
+            import_mode(&dir)?;
+            Ok(())
+        }
+        Some(Commands::ImportCorporateActions { file }) => {
+            import_corporate_actions_mode(&file)?;
             Ok(())
         }
+        Some(Commands::Sync { initial_days, fill_gaps, delivery, derivatives, indices, source }) => {
+            cli::sync(source, initial_days, fill_gaps, delivery, derivatives, indices)
+        }
+        Some(Commands::Gaps { from, to }) => cli::gaps(from, to, cli.format.unwrap_or(OutputFormat::Table)),
+        Some(Commands::Holidays { command }) => match command {
+            HolidaysCommand::Import { file } => cli::holidays_import(&file),
+            HolidaysCommand::List { year } => cli::holidays_list(year, cli.format.unwrap_or(OutputFormat::Table)),
+        },
+        Some(Commands::Alerts { command }) => match command {
+            AlertsCommand::List => cli::alerts_list(cli.format.unwrap_or(OutputFormat::Table)),
+            AlertsCommand::Add { symbol, kind, value, period } => cli::alerts_add(&symbol, &kind, value, period),
+            AlertsCommand::Delete { id } => cli::alerts_delete(id),
+            AlertsCommand::Check => cli::alerts_check(),
+            AlertsCommand::Inbox { unread, mark_read } => cli::alerts_inbox(unread, mark_read, cli.format.unwrap_or(OutputFormat::Table)),
+        },
+        Some(Commands::Symbols { command: SymbolsCommand::Update { source } }) => cli::symbols_update(source),
+        Some(Commands::Quote { symbol, exchange }) => {
+            cli::quote(&symbol, exchange, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::History { symbol, exchange, from, to, adjusted }) => {
+            cli::history(&symbol, exchange, from, to, adjusted, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::Futures { symbol, days }) => cli::futures(&symbol, days, cli.format.unwrap_or(OutputFormat::Table)),
+        Some(Commands::OptionChain { symbol, expiry }) => {
+            cli::option_chain(&symbol, expiry, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::Compare { symbol, index, from, to, adjusted }) => {
+            cli::compare(&symbol, &index, from, to, adjusted, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::Screen { exchange, price_from, price_to, range_days, adjusted, expr, saved, sort, desc }) => {
+            let sort = StockSort { column: sort, descending: desc };
+            cli::screen(exchange, price_from, price_to, range_days, adjusted, expr, saved, sort, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::Screens { command }) => match command {
+            ScreensCommand::List => cli::screens_list(cli.format.unwrap_or(OutputFormat::Table)),
+            ScreensCommand::Save { name, expression } => cli::screens_save(&name, &expression),
+            ScreensCommand::Delete { name } => cli::screens_delete(&name),
+        },
+        Some(Commands::Export { from, to, exchange, symbol, output }) => {
+            cli::export(from, to, exchange, symbol, output, cli.format.unwrap_or(OutputFormat::Csv))
+        }
+        Some(Commands::TaxReport { year, schedule_112a, output }) => {
+            cli::tax_report(&year, schedule_112a, output, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::ImportTrades { file, broker, mappings, commit }) => {
+            cli::import_trades(&file, broker, &mappings, commit, cli.format.unwrap_or(OutputFormat::Table))
+        }
+        Some(Commands::Backtest(args)) => cli::backtest(args, cli.format.unwrap_or(OutputFormat::Table)),
         None => {
             // Initialize database
-            let conn = init_db().expect("Failed to initialize database");
+            let conn = match init_db() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::error!("Failed to initialize database: {}", e);
+                    std::process::exit(1);
+                }
+            };
 
             // Populate demo data (only if empty)
             let count: i64 = conn
@@ -0,0 +1,254 @@
+use crate::app::IndistocksApp;
+use indistocks_db::{
+    AlertKind, create_alert_rule, delete_alert_notification, delete_alert_rule, evaluate_alerts,
+    mark_all_alert_notifications_read, set_alert_notification_read, set_alert_rule_enabled,
+};
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.heading("Alerts");
+    ui.add_space(10.0);
+
+    render_rule_form(ui, app);
+
+    if !app.alerts_status.is_empty() {
+        ui.add_space(5.0);
+        ui.label(&app.alerts_status);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.strong(format!("Notifications ({} unread)", app.alerts_unread));
+                if ui.add_enabled(app.alerts_unread > 0, egui::Button::new("Mark all read")).clicked() {
+                    let result = mark_all_alert_notifications_read(&app.db_conn.lock().unwrap());
+                    if let Err(e) = result {
+                        app.alerts_status = format!("Error: {}", e);
+                    }
+                    app.refresh_alerts();
+                }
+            });
+            ui.add_space(5.0);
+            render_inbox(ui, app);
+
+            ui.add_space(20.0);
+            ui.strong("Rules");
+            ui.add_space(5.0);
+            render_rules(ui, app);
+        });
+}
+
+fn render_rule_form(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        ui.add_sized([110.0, 20.0], egui::TextEdit::singleline(&mut app.alert_symbol).hint_text("Symbol"));
+
+        egui::ComboBox::from_id_salt("alert_kind")
+            .selected_text(app.alert_kind.label())
+            .width(220.0)
+            .show_ui(ui, |ui| {
+                for kind in AlertKind::ALL {
+                    ui.selectable_value(&mut app.alert_kind, kind, kind.label());
+                }
+            });
+
+        if let Some(default) = app.alert_kind.default_period() {
+            ui.add_sized([70.0, 20.0], egui::TextEdit::singleline(&mut app.alert_period).hint_text(format!("Period ({})", default)));
+        }
+        if let Some(label) = app.alert_kind.value_label() {
+            ui.add_sized([90.0, 20.0], egui::TextEdit::singleline(&mut app.alert_value).hint_text(label));
+        }
+
+        if ui.add_enabled(!app.alert_symbol.trim().is_empty(), egui::Button::new("Add Alert")).clicked() {
+            add_rule(app);
+        }
+
+        if ui.button("Check Now").on_hover_text("Check the rules against the latest stored session").clicked() {
+            let result = evaluate_alerts(&app.db_conn.lock().unwrap());
+            app.alerts_status = match result {
+                Ok(alerts) if alerts.is_empty() => "No new alerts".to_string(),
+                Ok(alerts) => format!("{} new alerts", alerts.len()),
+                Err(e) => format!("Error: {}", e),
+            };
+            app.refresh_alerts();
+        }
+    });
+}
+
+fn add_rule(app: &mut IndistocksApp) {
+    let value = match app.alert_kind.value_label() {
+        Some(label) => match app.alert_value.trim().parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => {
+                app.alerts_status = format!("Error: {} must be a number", label);
+                return;
+            }
+        },
+        None => 0.0,
+    };
+    let period = match app.alert_period.trim() {
+        text if text.is_empty() || app.alert_kind.default_period().is_none() => None,
+        text => match text.parse::<usize>() {
+            Ok(period) => Some(period),
+            Err(_) => {
+                app.alerts_status = "Error: Period must be a whole number".to_string();
+                return;
+            }
+        },
+    };
+
+    let result = create_alert_rule(&app.db_conn.lock().unwrap(), &app.alert_symbol, app.alert_kind, value, period);
+    match result {
+        Ok(_) => {
+            app.alerts_status = format!("Added alert for {}", app.alert_symbol.trim().to_uppercase());
+            app.alert_value.clear();
+            app.alert_period.clear();
+            app.refresh_alerts();
+        }
+        Err(e) => app.alerts_status = format!("Error: {}", e),
+    }
+}
+
+fn render_inbox(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    if app.alerts_notifications.is_empty() {
+        ui.label("No notifications yet. Rules are checked after each BhavCopy download.");
+        return;
+    }
+
+    let mut open_symbol: Option<String> = None;
+    let mut toggle_read: Option<(i64, bool)> = None;
+    let mut to_delete: Option<i64> = None;
+
+    TableBuilder::new(ui)
+        .id_salt("alert_notifications")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(100.0).at_least(90.0))  // Date
+        .column(Column::initial(110.0).at_least(80.0))  // Symbol
+        .column(Column::initial(420.0).at_least(200.0)) // Message
+        .column(Column::remainder().at_least(120.0))    // Actions
+        .header(30.0, |mut header| {
+            for title in ["Date", "Symbol", "Message", ""] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for notification in &app.alerts_notifications {
+                body.row(25.0, |mut row| {
+                    // Unread entries are shown in bold
+                    let text = |s: String| {
+                        let text = egui::RichText::new(s);
+                        if notification.is_read { text } else { text.strong() }
+                    };
+                    row.col(|ui| {
+                        ui.label(text(notification.date.format("%d-%m-%Y").to_string()));
+                    });
+                    row.col(|ui| {
+                        if ui.link(text(notification.symbol.clone())).clicked() {
+                            open_symbol = Some(notification.symbol.clone());
+                            toggle_read = Some((notification.id, true));
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(text(notification.message.clone()));
+                    });
+                    row.col(|ui| {
+                        let label = if notification.is_read { "Mark unread" } else { "Mark read" };
+                        if ui.small_button(label).clicked() {
+                            toggle_read = Some((notification.id, !notification.is_read));
+                        }
+                        if ui.small_button("🗑").on_hover_text("Delete this notification").clicked() {
+                            to_delete = Some(notification.id);
+                        }
+                    });
+                });
+            }
+        });
+
+    let result = {
+        let conn = app.db_conn.lock().unwrap();
+        toggle_read.map(|(id, is_read)| set_alert_notification_read(&conn, id, is_read)).transpose()
+            .and(to_delete.map(|id| delete_alert_notification(&conn, id)).transpose())
+    };
+    if let Err(e) = result {
+        app.alerts_status = format!("Error: {}", e);
+    }
+    if toggle_read.is_some() || to_delete.is_some() {
+        app.refresh_alerts();
+    }
+    if let Some(symbol) = open_symbol {
+        app.load_plot_data(&symbol);
+    }
+}
+
+fn render_rules(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    if app.alerts_rules.is_empty() {
+        ui.label("No alert rules. Add one above.");
+        return;
+    }
+
+    let mut toggle: Option<(i64, bool)> = None;
+    let mut to_delete: Option<i64> = None;
+
+    TableBuilder::new(ui)
+        .id_salt("alert_rules")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(70.0).at_least(60.0))   // Enabled
+        .column(Column::initial(110.0).at_least(80.0))  // Symbol
+        .column(Column::initial(320.0).at_least(200.0)) // Condition
+        .column(Column::remainder().at_least(40.0))     // Delete
+        .header(30.0, |mut header| {
+            for title in ["Enabled", "Symbol", "Condition", ""] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for rule in &app.alerts_rules {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        let mut enabled = rule.enabled;
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            toggle = Some((rule.id, enabled));
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(&rule.symbol);
+                    });
+                    row.col(|ui| {
+                        ui.label(rule.describe());
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("🗑").on_hover_text("Double-click to delete this rule").double_clicked() {
+                            to_delete = Some(rule.id);
+                        }
+                    });
+                });
+            }
+        });
+
+    let result = {
+        let conn = app.db_conn.lock().unwrap();
+        toggle.map(|(id, enabled)| set_alert_rule_enabled(&conn, id, enabled)).transpose()
+            .and(to_delete.map(|id| delete_alert_rule(&conn, id)).transpose())
+    };
+    if let Err(e) = result {
+        app.alerts_status = format!("Error: {}", e);
+    }
+    if toggle.is_some() || to_delete.is_some() {
+        app.refresh_alerts();
+    }
+}
@@ -0,0 +1,327 @@
+use crate::app::IndistocksApp;
+use chrono::NaiveDate;
+use indistocks_db::{get_bhavcopy_date_range, get_price_bars_in_range, get_adjusted_price_bars_in_range, Exchange};
+use indistocks_db::backtest::{BacktestConfig, BacktestResult, CostModel, FillModel, PositionSizing, StrategySpec, run_backtest};
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.heading("Backtest");
+    ui.add_space(10.0);
+
+    render_form(ui, app);
+
+    if !app.backtest_status.is_empty() {
+        ui.add_space(5.0);
+        ui.colored_label(egui::Color32::YELLOW, &app.backtest_status);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let Some(result) = &app.backtest_result else {
+        ui.add_space(10.0);
+        ui.label("Choose a symbol and a strategy, then press Run. Each signal is evaluated after a bar closes.");
+        return;
+    };
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            ui.add_space(10.0);
+            render_summary(ui, result);
+
+            ui.add_space(10.0);
+            ui.strong("Equity curve");
+            render_equity_curve(ui, result);
+
+            ui.add_space(10.0);
+            egui::CollapsingHeader::new(format!("Trades ({})", result.trades.len()))
+                .default_open(true)
+                .show(ui, |ui| render_trades_table(ui, result));
+        });
+}
+
+fn render_form(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    egui::Grid::new("backtest_form")
+        .num_columns(2)
+        .spacing([10.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("Symbol:");
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut app.backtest_symbol).desired_width(120.0));
+                ui.label("From:");
+                ui.add(egui::TextEdit::singleline(&mut app.backtest_from).hint_text("YYYY-MM-DD").desired_width(100.0));
+                ui.label("To:");
+                ui.add(egui::TextEdit::singleline(&mut app.backtest_to).hint_text("YYYY-MM-DD").desired_width(100.0));
+                ui.label("(blank for all stored data)");
+            });
+            ui.end_row();
+
+            ui.label("Strategy:");
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("backtest_strategy")
+                    .selected_text(app.backtest_strategy.name())
+                    .show_ui(ui, |ui| {
+                        for spec in StrategySpec::defaults() {
+                            let selected = std::mem::discriminant(&spec) == std::mem::discriminant(&app.backtest_strategy);
+                            if ui.selectable_label(selected, spec.name()).clicked() && !selected {
+                                app.backtest_strategy = spec;
+                            }
+                        }
+                    });
+                render_strategy_params(ui, &mut app.backtest_strategy);
+            });
+            ui.end_row();
+
+            ui.label("Capital (₹):");
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut app.backtest_capital).desired_width(100.0));
+                ui.label("Position size:");
+                ui.add(egui::TextEdit::singleline(&mut app.backtest_size).desired_width(80.0))
+                    .on_hover_text("Percentage of equity (100%), rupees per entry (50000) or shares (25sh)");
+                ui.label("Fill at:");
+                egui::ComboBox::from_id_salt("backtest_fill")
+                    .selected_text(app.backtest_fill.label())
+                    .show_ui(ui, |ui| {
+                        for fill in FillModel::ALL {
+                            ui.selectable_value(&mut app.backtest_fill, fill, fill.label());
+                        }
+                    });
+            });
+            ui.end_row();
+
+            ui.label("");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut app.backtest_costs, "Include charges")
+                    .on_hover_text("Delivery brokerage (0.03%, max ₹20), STT, exchange and SEBI fees, stamp duty and GST");
+                ui.checkbox(&mut app.backtest_adjusted, "Adjusted prices")
+                    .on_hover_text("Adjust for splits, bonuses and dividends imported in Settings");
+                if ui.button("Run").clicked() {
+                    run(app);
+                }
+            });
+            ui.end_row();
+        });
+}
+
+fn render_strategy_params(ui: &mut egui::Ui, spec: &mut StrategySpec) {
+    match spec {
+        StrategySpec::SmaCrossover { fast, slow } => {
+            ui.label("Fast:");
+            ui.add(egui::DragValue::new(fast).range(1..=500));
+            ui.label("Slow:");
+            ui.add(egui::DragValue::new(slow).range(2..=500));
+        }
+        StrategySpec::RsiReversion { period, oversold, overbought } => {
+            ui.label("Period:");
+            ui.add(egui::DragValue::new(period).range(2..=100));
+            ui.label("Buy below:");
+            ui.add(egui::DragValue::new(oversold).range(0.0..=100.0));
+            ui.label("Sell above:");
+            ui.add(egui::DragValue::new(overbought).range(0.0..=100.0));
+        }
+        StrategySpec::Screen { entry, exit } => {
+            ui.label("Enter when:");
+            ui.add(egui::TextEdit::singleline(entry).desired_width(260.0))
+                .on_hover_text(indistocks_db::screener::SCREEN_HELP);
+            ui.label("Exit when:");
+            ui.add(egui::TextEdit::singleline(exit).desired_width(200.0))
+                .on_hover_text(indistocks_db::screener::SCREEN_HELP);
+        }
+        StrategySpec::BuyAndHold => {}
+    }
+}
+
+fn run(app: &mut IndistocksApp) {
+    app.backtest_status.clear();
+    match try_run(app) {
+        Ok(result) => app.backtest_result = Some(result),
+        Err(message) => {
+            app.backtest_status = message;
+            app.backtest_result = None;
+        }
+    }
+}
+
+fn try_run(app: &IndistocksApp) -> Result<BacktestResult, String> {
+    let symbol = app.backtest_symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Enter a symbol".to_string());
+    }
+    let initial_capital: f64 = app.backtest_capital.trim().replace(',', "").parse()
+        .ok()
+        .filter(|c: &f64| *c > 0.0)
+        .ok_or("Capital must be a positive amount")?;
+    let sizing = PositionSizing::parse(&app.backtest_size).ok_or("Position size must be like 100%, 50000 or 25sh")?;
+    let mut strategy = app.backtest_strategy.build().map_err(|e| e.to_string())?;
+    let config = BacktestConfig {
+        initial_capital,
+        fill: app.backtest_fill,
+        sizing,
+        costs: if app.backtest_costs { CostModel::default() } else { CostModel::zero() },
+    };
+
+    let conn = app.db_conn.lock().unwrap();
+    let (earliest, latest) = get_bhavcopy_date_range(&conn)
+        .map_err(|e| e.to_string())?
+        .ok_or("No BhavCopy data downloaded yet")?;
+    let parse_date = |value: &str, default: NaiveDate| {
+        let value = value.trim();
+        if value.is_empty() {
+            Ok(default)
+        } else {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("Invalid date {}, expected YYYY-MM-DD", value))
+        }
+    };
+    let from = parse_date(&app.backtest_from, earliest)?;
+    let to = parse_date(&app.backtest_to, latest)?;
+
+    let bars = if app.backtest_adjusted {
+        get_adjusted_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)
+    } else {
+        get_price_bars_in_range(&conn, Exchange::Nse, &symbol, from, to)
+    }.map_err(|e| e.to_string())?;
+    if bars.is_empty() {
+        return Err(format!("No EQ data for {} between {} and {}", symbol, from, to));
+    }
+
+    Ok(run_backtest(&bars, strategy.as_mut(), &config))
+}
+
+fn render_summary(ui: &mut egui::Ui, result: &BacktestResult) {
+    let summary = &result.summary;
+    let pct = |value: Option<f64>| value.map(|v| format!("{:.2}%", v)).unwrap_or_else(|| "-".to_string());
+    let color = |value: f64| if value >= 0.0 { egui::Color32::GREEN } else { egui::Color32::RED };
+
+    ui.horizontal(|ui| {
+        ui.label("Final equity:");
+        ui.strong(format!("₹{:.2}", summary.final_equity));
+        ui.add_space(20.0);
+        ui.label("Return:");
+        ui.colored_label(color(summary.total_return_pct), format!("{:+.2}%", summary.total_return_pct));
+        ui.add_space(20.0);
+        ui.label("Buy and hold:");
+        ui.colored_label(color(summary.buy_and_hold_pct), format!("{:+.2}%", summary.buy_and_hold_pct));
+        ui.add_space(20.0);
+        ui.label("CAGR:");
+        ui.strong(pct(summary.cagr_pct));
+        ui.add_space(20.0);
+        ui.label("Max drawdown:");
+        ui.colored_label(egui::Color32::RED, format!("{:.2}%", summary.max_drawdown_pct));
+        ui.add_space(20.0);
+        ui.label("Sharpe:");
+        ui.strong(summary.sharpe.map(|s| format!("{:.2}", s)).unwrap_or_else(|| "-".to_string()));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Trades:");
+        ui.strong(summary.trades.to_string());
+        ui.add_space(20.0);
+        ui.label("Win rate:");
+        ui.strong(pct(summary.win_rate_pct));
+        ui.add_space(20.0);
+        ui.label("Exposure:");
+        ui.strong(format!("{:.1}%", summary.exposure_pct));
+        ui.add_space(20.0);
+        let charges = &result.charges;
+        ui.label("Charges:");
+        ui.strong(format!("₹{:.2}", summary.total_charges)).on_hover_text(format!(
+            "Brokerage ₹{:.2}\nSTT ₹{:.2}\nExchange ₹{:.2}\nSEBI ₹{:.2}\nStamp duty ₹{:.2}\nGST ₹{:.2}",
+            charges.brokerage, charges.stt, charges.exchange, charges.sebi, charges.stamp_duty, charges.gst,
+        ));
+    });
+}
+
+fn render_equity_curve(ui: &mut egui::Ui, result: &BacktestResult) {
+    let to_x = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
+    let equity: egui_plot::PlotPoints = result.equity_curve.iter()
+        .map(|point| [to_x(point.date), point.equity])
+        .collect();
+    let initial = result.summary.initial_capital;
+
+    egui_plot::Plot::new("backtest_equity")
+        .height(250.0)
+        .allow_scroll(false)
+        .y_axis_min_width(70.0)
+        .x_axis_formatter(|mark, _range| {
+            chrono::DateTime::from_timestamp(mark.value as i64, 0)
+                .map(|dt| dt.format("%b %Y").to_string())
+                .unwrap_or_default()
+        })
+        .label_formatter(|_name, point| {
+            let date = chrono::DateTime::from_timestamp(point.x as i64, 0)
+                .map(|dt| dt.format("%d-%m-%Y").to_string())
+                .unwrap_or_default();
+            format!("{}\n₹{:.2}", date, point.y)
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.hline(egui_plot::HLine::new(initial).color(egui::Color32::GRAY).name("Starting capital"));
+            plot_ui.line(egui_plot::Line::new(equity).color(egui::Color32::LIGHT_BLUE).name("Equity"));
+        });
+}
+
+fn render_trades_table(ui: &mut egui::Ui, result: &BacktestResult) {
+    use egui_extras::{TableBuilder, Column};
+
+    if result.trades.is_empty() {
+        ui.label("The strategy made no trades in this period.");
+        return;
+    }
+
+    TableBuilder::new(ui)
+        .id_salt("backtest_trades")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(100.0).at_least(90.0))  // Entry date
+        .column(Column::initial(90.0).at_least(70.0))   // Entry price
+        .column(Column::initial(100.0).at_least(90.0))  // Exit date
+        .column(Column::initial(90.0).at_least(70.0))   // Exit price
+        .column(Column::initial(80.0).at_least(60.0))   // Qty
+        .column(Column::initial(60.0).at_least(50.0))   // Bars
+        .column(Column::initial(90.0).at_least(70.0))   // Charges
+        .column(Column::initial(110.0).at_least(80.0))  // P&L
+        .column(Column::remainder().at_least(80.0))     // Return
+        .header(30.0, |mut header| {
+            for title in ["Entry", "Entry Price", "Exit", "Exit Price", "Qty", "Bars", "Charges", "P&L", "Return"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for trade in &result.trades {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(trade.entry_date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", trade.entry_price));
+                    });
+                    row.col(|ui| {
+                        let response = ui.label(trade.exit_date.format("%d-%m-%Y").to_string());
+                        if trade.closed_at_end {
+                            response.on_hover_text("Still open at the end; valued at the last close");
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", trade.exit_price));
+                    });
+                    row.col(|ui| {
+                        ui.label(trade.quantity.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(trade.bars_held.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", trade.charges));
+                    });
+                    let color = if trade.pnl >= 0.0 { egui::Color32::GREEN } else { egui::Color32::RED };
+                    row.col(|ui| {
+                        ui.colored_label(color, format!("{:+.2}", trade.pnl));
+                    });
+                    row.col(|ui| {
+                        ui.colored_label(color, format!("{:+.2}%", trade.return_pct));
+                    });
+                });
+            }
+        });
+}
@@ -0,0 +1,303 @@
+use crate::app::IndistocksApp;
+use chrono::NaiveDate;
+use indistocks_db::{FuturesBasis, OptionChain, OptionQuote, get_futures_basis, get_option_chain};
+
+/// Sessions of near-month basis shown above the option chain
+const BASIS_SESSIONS: usize = 20;
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.heading("F&O");
+    ui.add_space(10.0);
+
+    render_form(ui, app);
+
+    if !app.fo_status.is_empty() {
+        ui.add_space(5.0);
+        ui.colored_label(egui::Color32::YELLOW, &app.fo_status);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    if app.fo_basis.is_empty() && app.fo_chain.is_none() {
+        ui.add_space(10.0);
+        ui.label("Enter a stock or index (e.g. NIFTY) and press Load. F&O BhavCopies are fetched from Settings.");
+        return;
+    }
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            if !app.fo_basis.is_empty() {
+                ui.add_space(10.0);
+                egui::CollapsingHeader::new("Futures basis (near month)")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        render_basis_plot(ui, &app.fo_basis);
+                        ui.add_space(5.0);
+                        render_basis_table(ui, &app.fo_basis);
+                    });
+            }
+
+            if let Some(chain) = &app.fo_chain {
+                ui.add_space(10.0);
+                egui::CollapsingHeader::new(format!("Option chain ({} expiry, {})", chain.expiry.format("%d-%m-%Y"), chain.date.format("%d-%m-%Y")))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        render_chain_summary(ui, chain);
+                        ui.add_space(5.0);
+                        render_chain_table(ui, chain);
+                    });
+            }
+        });
+}
+
+fn render_form(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        ui.label("Symbol:");
+        let response = ui.add(egui::TextEdit::singleline(&mut app.fo_symbol).desired_width(120.0));
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Load").clicked() || submitted {
+            app.fo_expiry = None;
+            load(app);
+        }
+
+        let expiries = app.fo_chain.as_ref().map(|chain| (chain.expiry, chain.expiries.clone()));
+        if let Some((current, expiries)) = expiries {
+            ui.add_space(20.0);
+            ui.label("Expiry:");
+            let mut selected = current;
+            egui::ComboBox::from_id_salt("fo_expiry")
+                .selected_text(current.format("%d-%m-%Y").to_string())
+                .show_ui(ui, |ui| {
+                    for expiry in expiries {
+                        ui.selectable_value(&mut selected, expiry, expiry.format("%d-%m-%Y").to_string());
+                    }
+                });
+            if selected != current {
+                app.fo_expiry = Some(selected);
+                load(app);
+            }
+        }
+    });
+}
+
+/// Load the futures basis and option chain for `fo_symbol`, keeping `fo_expiry` when it still trades
+pub fn load(app: &mut IndistocksApp) {
+    app.fo_status.clear();
+    app.fo_basis.clear();
+    app.fo_chain = None;
+
+    let symbol = app.fo_symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        app.fo_status = "Enter a symbol".to_string();
+        return;
+    }
+    app.fo_symbol = symbol.clone();
+
+    let conn = app.db_conn.lock().unwrap();
+    let result = get_futures_basis(&conn, &symbol, BASIS_SESSIONS)
+        .and_then(|basis| Ok((basis, get_option_chain(&conn, &symbol, app.fo_expiry)?)));
+    drop(conn);
+
+    match result {
+        Ok((basis, chain)) => {
+            if basis.is_empty() && chain.is_none() {
+                app.fo_status = format!("No F&O data for {}; use Fetch F&O Data in Settings", symbol);
+            }
+            app.fo_expiry = chain.as_ref().map(|c| c.expiry);
+            app.fo_basis = basis;
+            app.fo_chain = chain;
+        }
+        Err(e) => app.fo_status = format!("Error loading F&O data: {}", e),
+    }
+}
+
+fn price(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string())
+}
+
+fn count(value: Option<i64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+fn change_color(value: Option<i64>) -> egui::Color32 {
+    match value {
+        Some(v) if v > 0 => egui::Color32::GREEN,
+        Some(v) if v < 0 => egui::Color32::RED,
+        _ => egui::Color32::GRAY,
+    }
+}
+
+fn render_basis_plot(ui: &mut egui::Ui, basis: &[FuturesBasis]) {
+    let to_x = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
+    let points: egui_plot::PlotPoints = basis.iter()
+        .filter_map(|entry| Some([to_x(entry.date), entry.basis_percent()?]))
+        .collect();
+
+    egui_plot::Plot::new("fo_basis")
+        .height(180.0)
+        .allow_scroll(false)
+        .y_axis_min_width(50.0)
+        .x_axis_formatter(|mark, _range| {
+            chrono::DateTime::from_timestamp(mark.value as i64, 0)
+                .map(|dt| dt.format("%d %b").to_string())
+                .unwrap_or_default()
+        })
+        .label_formatter(|_name, point| {
+            let date = chrono::DateTime::from_timestamp(point.x as i64, 0)
+                .map(|dt| dt.format("%d-%m-%Y").to_string())
+                .unwrap_or_default();
+            format!("{}\nBasis {:.2}%", date, point.y)
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.hline(egui_plot::HLine::new(0.0).color(egui::Color32::GRAY));
+            plot_ui.line(egui_plot::Line::new(points).color(egui::Color32::LIGHT_BLUE).name("Basis %"));
+        });
+}
+
+fn render_basis_table(ui: &mut egui::Ui, basis: &[FuturesBasis]) {
+    use egui_extras::{TableBuilder, Column};
+
+    TableBuilder::new(ui)
+        .id_salt("fo_basis_table")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(100.0).at_least(90.0))  // Date
+        .column(Column::initial(100.0).at_least(90.0))  // Expiry
+        .column(Column::initial(90.0).at_least(70.0))   // Future
+        .column(Column::initial(90.0).at_least(70.0))   // Spot
+        .column(Column::initial(80.0).at_least(60.0))   // Basis
+        .column(Column::initial(80.0).at_least(60.0))   // Basis %
+        .column(Column::initial(100.0).at_least(80.0))  // OI
+        .column(Column::initial(100.0).at_least(80.0))  // Chg OI
+        .column(Column::remainder().at_least(80.0))     // Total OI
+        .header(30.0, |mut header| {
+            for title in ["Date", "Expiry", "Future", "Spot", "Basis", "Basis %", "OI", "Chg OI", "All expiries OI"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for entry in basis {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(entry.date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(entry.expiry.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(price(entry.future_price));
+                    });
+                    row.col(|ui| {
+                        ui.label(price(entry.spot));
+                    });
+                    let color = match entry.basis() {
+                        Some(b) if b < 0.0 => egui::Color32::RED,
+                        Some(_) => egui::Color32::GREEN,
+                        None => egui::Color32::GRAY,
+                    };
+                    row.col(|ui| {
+                        ui.colored_label(color, entry.basis().map(|b| format!("{:+.2}", b)).unwrap_or_else(|| "-".to_string()));
+                    });
+                    row.col(|ui| {
+                        ui.colored_label(color, entry.basis_percent().map(|b| format!("{:+.2}%", b)).unwrap_or_else(|| "-".to_string()));
+                    });
+                    row.col(|ui| {
+                        ui.label(count(entry.open_interest));
+                    });
+                    row.col(|ui| {
+                        ui.colored_label(change_color(entry.change_in_oi), count(entry.change_in_oi));
+                    });
+                    row.col(|ui| {
+                        ui.label(count(entry.total_open_interest));
+                    });
+                });
+            }
+        });
+}
+
+fn render_chain_summary(ui: &mut egui::Ui, chain: &OptionChain) {
+    ui.horizontal(|ui| {
+        ui.label("Spot:");
+        ui.strong(price(chain.spot));
+        ui.add_space(20.0);
+        ui.label("Call OI:");
+        ui.strong(chain.call_open_interest().to_string());
+        ui.add_space(20.0);
+        ui.label("Put OI:");
+        ui.strong(chain.put_open_interest().to_string());
+        ui.add_space(20.0);
+        ui.label("PCR:");
+        ui.strong(chain.put_call_ratio().map(|p| format!("{:.2}", p)).unwrap_or_else(|| "-".to_string()))
+            .on_hover_text("Put open interest divided by call open interest");
+    });
+}
+
+fn render_chain_table(ui: &mut egui::Ui, chain: &OptionChain) {
+    use egui_extras::{TableBuilder, Column};
+
+    let atm = chain.atm_strike();
+    // In-the-money options are shaded like on NSE's option chain page
+    let itm_fill = ui.visuals().faint_bg_color.gamma_multiply(2.0);
+    let quote_cells = |row: &mut egui_extras::TableRow, quote: Option<&OptionQuote>, itm: bool, calls: bool| {
+        let quote = quote.cloned().unwrap_or_default();
+        let mut cells = vec![
+            (count(quote.open_interest), None),
+            (count(quote.change_in_oi), Some(change_color(quote.change_in_oi))),
+            (count(quote.volume), None),
+            (price(quote.close), None),
+        ];
+        if !calls {
+            cells.reverse();
+        }
+        for (text, color) in cells {
+            row.col(|ui| {
+                if itm {
+                    ui.painter().rect_filled(ui.max_rect(), 0.0, itm_fill);
+                }
+                match color {
+                    Some(color) => ui.colored_label(color, text),
+                    None => ui.label(text),
+                };
+            });
+        }
+    };
+
+    TableBuilder::new(ui)
+        .id_salt("fo_chain_table")
+        .striped(false)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .columns(Column::initial(90.0).at_least(70.0), 4)  // Calls
+        .column(Column::initial(90.0).at_least(70.0))      // Strike
+        .columns(Column::initial(90.0).at_least(70.0), 4)  // Puts
+        .header(30.0, |mut header| {
+            for title in ["Call OI", "Chg OI", "Volume", "Close", "Strike", "Close", "Volume", "Chg OI", "Put OI"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for strike in &chain.rows {
+                let below_spot = chain.spot.is_some_and(|spot| strike.strike < spot);
+                let above_spot = chain.spot.is_some_and(|spot| strike.strike > spot);
+                body.row(25.0, |mut row| {
+                    quote_cells(&mut row, strike.call.as_ref(), below_spot, true);
+                    row.col(|ui| {
+                        let text = format!("{:.2}", strike.strike);
+                        if atm == Some(strike.strike) {
+                            ui.strong(text).on_hover_text("Nearest strike to spot");
+                        } else {
+                            ui.label(text);
+                        }
+                    });
+                    quote_cells(&mut row, strike.put.as_ref(), above_spot, false);
+                });
+            }
+        });
+}
@@ -0,0 +1,248 @@
+use crate::app::IndistocksApp;
+use crate::ui::main_content::{DOWN_COLOR, UP_COLOR, date_to_x, format_timestamp_to_date};
+use crate::ui::stocks::{format_turnover, format_volume};
+use indistocks_db::{BreadthPoint, MarketBreadth, Mover, get_breadth_history, get_market_breadth, reopen_db_connection};
+use std::sync::mpsc::{self, TryRecvError};
+
+/// Stocks in each top-movers list
+const TOP_MOVERS: usize = 10;
+
+/// Breadth history lengths offered, in sessions
+const HISTORY_SESSIONS: [(usize, &str); 4] = [(20, "1M"), (60, "3M"), (125, "6M"), (250, "1Y")];
+
+#[derive(Debug)]
+pub enum BreadthMessage {
+    Loaded(Result<(Option<MarketBreadth>, Vec<BreadthPoint>), String>),
+}
+
+#[derive(Clone, Copy)]
+enum MoverValue {
+    Change,
+    Volume,
+    Turnover,
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    poll(ui, app);
+
+    let Some(breadth) = app.home_breadth.clone() else {
+        render_welcome(ui, app);
+        return;
+    };
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.heading("Market Overview");
+            ui.label(format!("as of {}", breadth.summary.date.format("%d %b %Y")));
+            if app.home_breadth_receiver.is_some() {
+                ui.spinner();
+            }
+        });
+        ui.add_space(10.0);
+
+        render_summary(ui, &breadth);
+        ui.add_space(15.0);
+
+        let clicked = ui.columns(4, |columns| {
+            render_movers(&mut columns[0], "Top Gainers", &breadth.gainers, MoverValue::Change)
+                .or(render_movers(&mut columns[1], "Top Losers", &breadth.losers, MoverValue::Change))
+                .or(render_movers(&mut columns[2], "Most Active (Volume)", &breadth.most_active_by_volume, MoverValue::Volume))
+                .or(render_movers(&mut columns[3], "Most Active (Turnover)", &breadth.most_active_by_turnover, MoverValue::Turnover))
+        });
+        if let Some(symbol) = clicked {
+            app.load_plot_data(&symbol);
+            return;
+        }
+
+        ui.add_space(15.0);
+        render_history(ui, app);
+    });
+}
+
+/// Start a load when the data is stale, and pick up a finished one
+fn poll(ui: &egui::Ui, app: &mut IndistocksApp) {
+    if app.home_breadth_stale && app.home_breadth_receiver.is_none() {
+        app.home_breadth_stale = false;
+        start_loading(app);
+    }
+
+    let Some(rx) = &app.home_breadth_receiver else {
+        return;
+    };
+    match rx.try_recv() {
+        Ok(BreadthMessage::Loaded(result)) => {
+            app.home_breadth_receiver = None;
+            match result {
+                Ok((breadth, history)) => {
+                    app.home_breadth = breadth;
+                    app.home_breadth_history = history;
+                    app.home_status.clear();
+                }
+                Err(e) => app.home_status = format!("Failed to load market breadth: {}", e),
+            }
+        }
+        Err(TryRecvError::Empty) => {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        Err(TryRecvError::Disconnected) => {
+            app.home_breadth_receiver = None;
+            app.home_status = "Market breadth thread disconnected".to_string();
+        }
+    }
+}
+
+/// Breadth reads a year of history, so it runs on its own connection and thread
+fn start_loading(app: &mut IndistocksApp) {
+    let (tx, rx) = mpsc::channel();
+    app.home_breadth_receiver = Some(rx);
+
+    let reopened = reopen_db_connection(&app.db_conn.lock().unwrap());
+    let shared = app.db_conn.clone();
+    let sessions = app.home_breadth_sessions;
+    std::thread::spawn(move || {
+        let load = |conn: &indistocks_db::Connection| -> indistocks_db::Result<_> {
+            Ok((get_market_breadth(conn, TOP_MOVERS)?, get_breadth_history(conn, sessions)?))
+        };
+        let result = match reopened {
+            Some(Ok(conn)) => load(&conn),
+            _ => load(&shared.lock().unwrap()),
+        };
+        let _ = tx.send(BreadthMessage::Loaded(result.map_err(|e| e.to_string())));
+    });
+}
+
+fn render_welcome(ui: &mut egui::Ui, app: &IndistocksApp) {
+    ui.vertical_centered(|ui| {
+        ui.add_space(50.0);
+        ui.heading("Welcome to Indistocks");
+        ui.add_space(20.0);
+        ui.label("Select a stock from the sidebar or search above");
+        ui.add_space(10.0);
+        if app.home_breadth_receiver.is_some() {
+            ui.spinner();
+        } else if !app.home_status.is_empty() {
+            ui.colored_label(egui::Color32::YELLOW, &app.home_status);
+        } else {
+            ui.label("Download BhavCopy data in Settings to see the market overview here");
+        }
+    });
+}
+
+fn render_summary(ui: &mut egui::Ui, breadth: &MarketBreadth) {
+    let summary = &breadth.summary;
+    ui.horizontal_wrapped(|ui| {
+        stat(ui, "Advances", summary.advances.to_string(), Some(UP_COLOR));
+        stat(ui, "Declines", summary.declines.to_string(), Some(DOWN_COLOR));
+        stat(ui, "Unchanged", summary.unchanged.to_string(), None);
+        let ratio = summary.advance_decline_ratio()
+            .map(|r| format!("{:.2}", r))
+            .unwrap_or_else(|| "–".to_string());
+        stat(ui, "A/D Ratio", ratio, None);
+        stat(ui, "52W Highs", summary.new_highs.to_string(), Some(UP_COLOR));
+        stat(ui, "52W Lows", summary.new_lows.to_string(), Some(DOWN_COLOR));
+        stat(ui, "Volume", format_volume(breadth.total_volume), None);
+        stat(ui, "Turnover", format_turnover(breadth.total_turnover), None);
+    });
+
+    // Share of advancing, unchanged and declining stocks
+    let total = (summary.advances + summary.declines + summary.unchanged).max(1) as f32;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(800.0), 8.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let advance_end = rect.left() + rect.width() * summary.advances as f32 / total;
+    let unchanged_end = advance_end + rect.width() * summary.unchanged as f32 / total;
+    painter.rect_filled(egui::Rect::from_x_y_ranges(rect.left()..=advance_end, rect.y_range()), 2.0, UP_COLOR);
+    painter.rect_filled(egui::Rect::from_x_y_ranges(advance_end..=unchanged_end, rect.y_range()), 0.0, egui::Color32::GRAY);
+    painter.rect_filled(egui::Rect::from_x_y_ranges(unchanged_end..=rect.right(), rect.y_range()), 2.0, DOWN_COLOR);
+}
+
+fn stat(ui: &mut egui::Ui, label: &str, value: String, color: Option<egui::Color32>) {
+    ui.group(|ui| {
+        ui.set_min_width(90.0);
+        ui.vertical(|ui| {
+            ui.small(label);
+            let text = egui::RichText::new(value).heading();
+            match color {
+                Some(color) => ui.label(text.color(color)),
+                None => ui.label(text),
+            };
+        });
+    });
+}
+
+/// Returns the symbol that was clicked, if any
+fn render_movers(ui: &mut egui::Ui, title: &str, movers: &[Mover], value: MoverValue) -> Option<String> {
+    let mut clicked = None;
+    ui.strong(title);
+    ui.add_space(5.0);
+    egui::Grid::new(title).striped(true).num_columns(3).show(ui, |ui| {
+        for mover in movers {
+            let link = ui.link(&mover.symbol);
+            if link.clicked() {
+                clicked = Some(mover.symbol.clone());
+            }
+            if let Some(name) = &mover.name {
+                link.on_hover_text(name);
+            }
+            ui.label(format!("{:.2}", mover.close));
+            let change_color = if mover.change_percent >= 0.0 { UP_COLOR } else { DOWN_COLOR };
+            match value {
+                MoverValue::Change => ui.colored_label(change_color, format!("{:+.2}%", mover.change_percent)),
+                MoverValue::Volume => ui.label(format_volume(mover.volume)),
+                MoverValue::Turnover => ui.label(format_turnover(mover.turnover)),
+            };
+            ui.end_row();
+        }
+    });
+    clicked
+}
+
+fn render_history(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        ui.strong("Breadth History");
+        for (sessions, label) in HISTORY_SESSIONS {
+            if ui.selectable_label(app.home_breadth_sessions == sessions, label).clicked()
+                && app.home_breadth_sessions != sessions
+            {
+                app.home_breadth_sessions = sessions;
+                app.home_breadth_stale = true;
+            }
+        }
+    });
+
+    if app.home_breadth_history.len() < 2 {
+        ui.label("Breadth history needs at least two sessions of data");
+        return;
+    }
+
+    let history = &app.home_breadth_history;
+    let line = |name: &str, color: egui::Color32, value: fn(&BreadthPoint) -> usize| {
+        let points: egui_plot::PlotPoints = history.iter()
+            .map(|p| [date_to_x(p.date), value(p) as f64])
+            .collect();
+        egui_plot::Line::new(points).name(name).color(color)
+    };
+
+    egui_plot::Plot::new(format!("breadth_history_{}", app.home_breadth_sessions))
+        .height(260.0)
+        .legend(egui_plot::Legend::default())
+        .allow_zoom([true, false])
+        .allow_drag([true, false])
+        .allow_scroll([true, false])
+        .include_y(0.0)
+        .y_axis_min_width(50.0)
+        .x_axis_formatter(|mark, _range| format_timestamp_to_date(mark.value, "%d %b"))
+        .label_formatter(|name, value| {
+            let date = format_timestamp_to_date(value.x, "%d %b %Y");
+            if name.is_empty() {
+                date
+            } else {
+                format!("{}\n{}: {:.0}", date, name, value.y)
+            }
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.line(line("Advances", UP_COLOR, |p| p.advances));
+            plot_ui.line(line("Declines", DOWN_COLOR, |p| p.declines));
+            plot_ui.line(line("52W Highs", egui::Color32::LIGHT_BLUE, |p| p.new_highs));
+            plot_ui.line(line("52W Lows", egui::Color32::from_rgb(255, 165, 0), |p| p.new_lows));
+        });
+}
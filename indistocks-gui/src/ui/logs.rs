@@ -0,0 +1,181 @@
+use crate::app::IndistocksApp;
+use indistocks_db::logging::{LogEntry, LogTail, log_file_path};
+use log::{Level, LevelFilter};
+
+/// Entries kept in memory; older ones are still in the file
+const MAX_ENTRIES: usize = 20_000;
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    poll(app);
+    // Keep tailing while the page is open, even without input
+    ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+
+    ui.heading("Logs");
+    ui.add_space(10.0);
+
+    render_toolbar(ui, app);
+
+    if !app.logs_status.is_empty() {
+        ui.add_space(5.0);
+        ui.colored_label(egui::Color32::YELLOW, &app.logs_status);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let search = app.logs_search.trim().to_lowercase();
+    let visible: Vec<&LogEntry> = app.logs_entries.iter()
+        .filter(|entry| entry.level <= app.logs_level)
+        .filter(|entry| app.logs_module.as_ref().is_none_or(|module| &entry.module == module))
+        .filter(|entry| search.is_empty() || entry.message.to_lowercase().contains(&search))
+        .collect();
+
+    ui.label(format!("Showing {} of {} entries", visible.len(), app.logs_entries.len()));
+    ui.add_space(5.0);
+    render_table(ui, &visible, app.logs_follow);
+}
+
+/// Read whatever has been logged since the last frame
+fn poll(app: &mut IndistocksApp) {
+    let tail = app.logs_tail.get_or_insert_with(|| LogTail::new(log_file_path()));
+    match tail.poll() {
+        Ok(entries) => {
+            app.logs_entries.extend(entries);
+            if app.logs_entries.len() > MAX_ENTRIES {
+                let excess = app.logs_entries.len() - MAX_ENTRIES;
+                app.logs_entries.drain(..excess);
+            }
+        }
+        Err(e) => app.logs_status = format!("Failed to read {}: {}", tail.path().display(), e),
+    }
+}
+
+fn render_toolbar(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        ui.label("Level:");
+        egui::ComboBox::from_id_salt("logs_level")
+            .selected_text(level_label(app.logs_level))
+            .show_ui(ui, |ui| {
+                for level in [LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug, LevelFilter::Trace] {
+                    ui.selectable_value(&mut app.logs_level, level, level_label(level));
+                }
+            });
+
+        ui.add_space(10.0);
+
+        let mut modules: Vec<&str> = app.logs_entries.iter().map(|e| e.module.as_str()).collect();
+        modules.sort_unstable();
+        modules.dedup();
+        ui.label("Module:");
+        egui::ComboBox::from_id_salt("logs_module")
+            .selected_text(app.logs_module.as_deref().unwrap_or("All"))
+            .width(220.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.logs_module, None, "All");
+                for module in modules {
+                    ui.selectable_value(&mut app.logs_module, Some(module.to_string()), module);
+                }
+            });
+
+        ui.add_space(10.0);
+
+        ui.label("Search:");
+        ui.add(egui::TextEdit::singleline(&mut app.logs_search).hint_text("Message text").desired_width(200.0));
+
+        ui.add_space(10.0);
+
+        ui.checkbox(&mut app.logs_follow, "Follow")
+            .on_hover_text("Scroll to new entries as they are written");
+
+        if ui.button("Reload").on_hover_text("Read the log file again from the start").clicked() {
+            app.logs_tail = None;
+            app.logs_entries.clear();
+            app.logs_status.clear();
+        }
+
+        if ui.button("Open Folder").on_hover_text(log_file_path().display().to_string()).clicked() {
+            let dir = indistocks_db::get_logs_path();
+            if let Err(e) = open_in_file_manager(&dir) {
+                app.logs_status = format!("Could not open {}: {}", dir.display(), e);
+            }
+        }
+    });
+}
+
+fn level_label(level: LevelFilter) -> &'static str {
+    match level {
+        LevelFilter::Off | LevelFilter::Error => "Errors",
+        LevelFilter::Warn => "Warnings and up",
+        LevelFilter::Info => "Info and up",
+        LevelFilter::Debug => "Debug and up",
+        LevelFilter::Trace => "Everything",
+    }
+}
+
+fn level_color(level: Level) -> egui::Color32 {
+    match level {
+        Level::Error => egui::Color32::RED,
+        Level::Warn => egui::Color32::YELLOW,
+        Level::Info => egui::Color32::LIGHT_BLUE,
+        Level::Debug | Level::Trace => egui::Color32::GRAY,
+    }
+}
+
+fn render_table(ui: &mut egui::Ui, entries: &[&LogEntry], follow: bool) {
+    use egui_extras::{TableBuilder, Column};
+
+    if entries.is_empty() {
+        ui.label("No log entries match.");
+        return;
+    }
+
+    let available_height = ui.available_height();
+    TableBuilder::new(ui)
+        .id_salt("logs_table")
+        .striped(true)
+        .resizable(true)
+        .stick_to_bottom(follow)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .min_scrolled_height(0.0)
+        .max_scroll_height(available_height)
+        .column(Column::initial(150.0).at_least(130.0))  // Time
+        .column(Column::initial(60.0).at_least(50.0))    // Level
+        .column(Column::initial(220.0).at_least(100.0))  // Module
+        .column(Column::remainder().at_least(200.0))     // Message
+        .header(30.0, |mut header| {
+            for title in ["Time", "Level", "Module", "Message"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(22.0, entries.len(), |mut row| {
+                let entry = entries[row.index()];
+                row.col(|ui| {
+                    ui.label(entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string());
+                });
+                row.col(|ui| {
+                    ui.colored_label(level_color(entry.level), entry.level.as_str());
+                });
+                row.col(|ui| {
+                    ui.label(&entry.module);
+                });
+                row.col(|ui| {
+                    ui.label(&entry.message).on_hover_text(&entry.message);
+                });
+            });
+        });
+}
+
+/// Show a directory in the platform's file manager
+fn open_in_file_manager(path: &std::path::Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(path).spawn().map(|_| ())
+}
@@ -1,10 +1,23 @@
-use crate::app::{IndistocksApp, TimeRange};
+use crate::app::{ChartMode, IndistocksApp, TimeRange, View};
 use chrono::{Datelike, Duration, NaiveDate};
+use indistocks_db::{Exchange, PriceBar};
+use indistocks_db::indicators::{Indicator, IndicatorSeries};
+use crate::ui::stocks::format_volume;
+
+/// Width of a candle or volume bar, in seconds on the timestamp x-axis
+const BAR_WIDTH: f64 = 0.7 * 24.0 * 3600.0;
+
+pub const UP_COLOR: egui::Color32 = egui::Color32::from_rgb(38, 166, 91);
+pub const DOWN_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 53, 69);
+const INDEX_COLOR: egui::Color32 = egui::Color32::from_rgb(158, 158, 158);
 
 
 pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
     if let Some(symbol) = &app.selected_symbol.clone() {
-        ui.heading(format!("Historical Data for {}", symbol));
+        match &app.chart_name {
+            Some(name) => ui.heading(format!("Historical Data for {} ({}) – {}", symbol, app.chart_exchange, name)),
+            None => ui.heading(format!("Historical Data for {} ({})", symbol, app.chart_exchange)),
+        };
         ui.add_space(10.0);
 
         if app.plot_data.is_empty() {
@@ -12,57 +25,242 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
         } else {
             // Calculate date range
             let (min_date, max_date) = app.plot_data.iter().fold(
-                (app.plot_data[0].0, app.plot_data[0].0),
-                |(min, max), (date, _)| {
-                    (min.min(*date), max.max(*date))
+                (app.plot_data[0].date, app.plot_data[0].date),
+                |(min, max), bar| {
+                    (min.min(bar.date), max.max(bar.date))
                 }
             );
             let days_diff = (max_date - min_date).num_days();
 
             // Determine formatting based on date range
             let (x_fmt, should_filter_ticks) = get_date_format_and_filter(days_diff);
-            let x_fmt_clone = x_fmt.clone();
+            let volume_x_fmt = x_fmt.clone();
+            let osc_x_fmt = x_fmt.clone();
+
+            // Give up some of the price chart's height to oscillator panes
+            let oscillator_count = app.chart_indicators.iter().filter(|i| !i.is_overlay()).count();
+            let price_height = (450.0 - 80.0 * oscillator_count as f32).max(250.0);
+
+            // Price and volume plots share the x-axis and cursor so they pan, zoom and hover together
+            let link_group = egui::Id::new("price_volume_link");
 
             // Plot the data - use symbol and time range in ID to reset view when switching stocks or time ranges
             let mut plot = egui_plot::Plot::new(format!("price_plot_{}_{}", symbol, app.selected_time_range.label()))
-                .height(600.0)
+                .height(price_height)
                 .legend(egui_plot::Legend::default())
                 .allow_zoom([true, false])  // Allow horizontal zoom only
                 .allow_drag([true, false])  // Allow horizontal drag only
                 .allow_scroll([true, false])  // Allow horizontal scroll for zooming only
+                .link_axis(link_group, true, false)
+                .link_cursor(link_group, true, false)
+                .y_axis_min_width(60.0)
+                .show_x(false)
+                .show_y(false)
                 .x_axis_formatter(move |mark, _range| {
                     format_timestamp_to_date(mark.value, &x_fmt)
-                })
-                .label_formatter(move |_name, value| {
-                    format!("Date: {}\nPrice: {:.2}",
-                        format_timestamp_to_date(value.x, &x_fmt_clone),
-                        value.y)
                 });
 
+            let mut volume_plot = egui_plot::Plot::new(format!("volume_plot_{}_{}", symbol, app.selected_time_range.label()))
+                .height(120.0)
+                .allow_zoom([true, false])
+                .allow_drag([true, false])
+                .allow_scroll([true, false])
+                .link_axis(link_group, true, false)
+                .link_cursor(link_group, true, false)
+                .y_axis_min_width(60.0)
+                .include_y(0.0)
+                .show_x(false)
+                .show_y(false)
+                .x_axis_formatter(move |mark, _range| {
+                    format_timestamp_to_date(mark.value, &volume_x_fmt)
+                })
+                .y_axis_formatter(|mark, _range| format_volume(mark.value as i64));
+
             // Reset plot view if needed (when changing time range or loading new stock)
-            if app.plot_needs_reset {
+            let reset_plots = app.plot_needs_reset;
+            if reset_plots {
                 plot = plot.reset();
+                volume_plot = volume_plot.reset();
                 app.plot_needs_reset = false;
             }
 
-            let response = plot.show(ui, |plot_ui| {
-                let points: egui_plot::PlotPoints = app.plot_data
-                    .iter()
-                    .map(|(date, price)| {
-                        let x = date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
-                        [x, *price]
-                    })
+            // Indicators are recomputed over the loaded bars so they follow lazy loading of earlier data
+            let indicator_series: Vec<(Indicator, Vec<IndicatorSeries>)> = app.chart_indicators
+                .iter()
+                .map(|indicator| (*indicator, indicator.compute(&app.plot_data)))
+                .collect();
+
+            // The overlaid index is rebased to the stock's close on the first shared date so both start together
+            let index_line = app.chart_index.as_ref().and_then(|name| {
+                let scale = app.plot_data.iter()
+                    .find_map(|bar| Some(bar.close / app.chart_index_closes.get(&bar.date)?))?;
+                let points: egui_plot::PlotPoints = app.plot_data.iter()
+                    .filter_map(|bar| Some([date_to_x(bar.date), app.chart_index_closes.get(&bar.date)? * scale]))
                     .collect();
-                let line = egui_plot::Line::new(points)
-                    .name("Close Price");
-                plot_ui.line(line);
+                Some(egui_plot::Line::new(points).name(format!("{} (rebased)", name)).color(INDEX_COLOR).allow_hover(false))
+            });
+
+            let chart_mode = app.chart_mode;
+            let price_name = if app.plot_adjusted { "Adjusted Close" } else { "Close Price" };
+            let response = plot.show(ui, |plot_ui| {
+                match chart_mode {
+                    ChartMode::Line => {
+                        let points: egui_plot::PlotPoints = app.plot_data
+                            .iter()
+                            .map(|bar| [date_to_x(bar.date), bar.close])
+                            .collect();
+                        plot_ui.line(egui_plot::Line::new(points).name(price_name).allow_hover(false));
+                    }
+                    ChartMode::Candlestick => {
+                        let candles: Vec<egui_plot::BoxElem> = app.plot_data
+                            .iter()
+                            .map(|bar| {
+                                let color = bar_color(bar);
+                                let body_low = bar.open.min(bar.close);
+                                let body_high = bar.open.max(bar.close);
+                                // The "median" sits on the body edge so no extra line is drawn inside the candle
+                                egui_plot::BoxElem::new(
+                                    date_to_x(bar.date),
+                                    egui_plot::BoxSpread::new(bar.low, body_low, body_low, body_high, bar.high),
+                                )
+                                .box_width(BAR_WIDTH)
+                                .whisker_width(0.0)
+                                .fill(color)
+                                .stroke(egui::Stroke::new(1.0, color))
+                            })
+                            .collect();
+                        plot_ui.box_plot(egui_plot::BoxPlot::new(candles).name(price_name).allow_hover(false));
+                    }
+                    ChartMode::Ohlc => {
+                        // Each bar is a high-low line with the open tick on the left and the close tick on the right
+                        for bar in &app.plot_data {
+                            let x = date_to_x(bar.date);
+                            let tick = BAR_WIDTH / 2.0;
+                            let points: egui_plot::PlotPoints = vec![
+                                [x - tick, bar.open],
+                                [x, bar.open],
+                                [x, bar.low],
+                                [x, bar.high],
+                                [x, bar.close],
+                                [x + tick, bar.close],
+                            ].into();
+                            plot_ui.line(egui_plot::Line::new(points).color(bar_color(bar)).allow_hover(false));
+                        }
+                    }
+                }
+
+                let overlays = indicator_series.iter()
+                    .filter(|(indicator, _)| indicator.is_overlay())
+                    .flat_map(|(_, series)| series);
+                for (i, series) in overlays.enumerate() {
+                    plot_ui.line(indicator_line(&app.plot_data, series).color(indicator_color(i)));
+                }
+                if let Some(line) = index_line {
+                    plot_ui.line(line);
+                }
 
                 // Add custom X-axis ticks if needed
                 if should_filter_ticks {
                     add_custom_x_ticks(plot_ui, &app.plot_data, days_diff);
                 }
+
+                plot_ui.pointer_coordinate()
             });
 
+            let volume_response = volume_plot.show(ui, |plot_ui| {
+                let bars: Vec<egui_plot::Bar> = app.plot_data
+                    .iter()
+                    .map(|bar| {
+                        egui_plot::Bar::new(date_to_x(bar.date), bar.volume as f64)
+                            .width(BAR_WIDTH)
+                            .fill(bar_color(bar).gamma_multiply(0.6))
+                    })
+                    .collect();
+                plot_ui.bar_chart(egui_plot::BarChart::new(bars).name("Volume").allow_hover(false));
+
+                plot_ui.pointer_coordinate()
+            });
+
+            // Oscillators (RSI, MACD, ATR) each get their own pane under the volume
+            let mut hover_targets = vec![
+                (response.response.clone(), response.inner),
+                (volume_response.response.clone(), volume_response.inner),
+            ];
+            for (indicator, series) in indicator_series.iter().filter(|(indicator, _)| !indicator.is_overlay()) {
+                let osc_x_fmt = osc_x_fmt.clone();
+                let mut osc_plot = egui_plot::Plot::new(format!("indicator_plot_{}_{}_{}", indicator.name(), symbol, app.selected_time_range.label()))
+                    .height(120.0)
+                    .legend(egui_plot::Legend::default())
+                    .allow_zoom([true, false])
+                    .allow_drag([true, false])
+                    .allow_scroll([true, false])
+                    .link_axis(link_group, true, false)
+                    .link_cursor(link_group, true, false)
+                    .y_axis_min_width(60.0)
+                    .show_x(false)
+                    .show_y(false)
+                    .x_axis_formatter(move |mark, _range| {
+                        format_timestamp_to_date(mark.value, &osc_x_fmt)
+                    });
+                if let Indicator::Rsi { .. } = indicator {
+                    osc_plot = osc_plot.include_y(0.0).include_y(100.0);
+                }
+                if reset_plots {
+                    osc_plot = osc_plot.reset();
+                }
+
+                let osc_response = osc_plot.show(ui, |plot_ui| {
+                    if let Indicator::Rsi { .. } = indicator {
+                        for level in [30.0, 70.0] {
+                            plot_ui.hline(egui_plot::HLine::new(level).color(egui::Color32::GRAY).style(egui_plot::LineStyle::dashed_loose()));
+                        }
+                    }
+                    for (i, series) in series.iter().enumerate() {
+                        if series.histogram {
+                            let bars: Vec<egui_plot::Bar> = app.plot_data.iter().zip(&series.values)
+                                .filter_map(|(bar, value)| {
+                                    let value = (*value)?;
+                                    let color = if value >= 0.0 { UP_COLOR } else { DOWN_COLOR };
+                                    Some(egui_plot::Bar::new(date_to_x(bar.date), value).width(BAR_WIDTH).fill(color.gamma_multiply(0.6)))
+                                })
+                                .collect();
+                            plot_ui.bar_chart(egui_plot::BarChart::new(bars).name(&series.name).allow_hover(false));
+                        } else {
+                            plot_ui.line(indicator_line(&app.plot_data, series).color(indicator_color(i)));
+                        }
+                    }
+                    plot_ui.pointer_coordinate()
+                });
+                hover_targets.push((osc_response.response, osc_response.inner));
+            }
+
+            // Hovering any pane shows the full OHLCV values of the bar under the cursor, plus indicator values
+            for (hover_response, pointer) in hover_targets {
+                if let Some(idx) = pointer.and_then(|p| bar_index_at(&app.plot_data, p.x)) {
+                    let bar = &app.plot_data[idx];
+                    hover_response.on_hover_ui_at_pointer(|ui| {
+                        let mut text = format!(
+                            "Date: {}\nOpen: {:.2}\nHigh: {:.2}\nLow: {:.2}\nClose: {:.2}\nVolume: {}",
+                            bar.date.format("%d %b %Y"),
+                            bar.open, bar.high, bar.low, bar.close,
+                            format_volume(bar.volume),
+                        );
+                        if let Some(percent) = app.chart_delivery.get(&bar.date) {
+                            text.push_str(&format!("\nDelivery: {:.2}%", percent));
+                        }
+                        if let (Some(name), Some(close)) = (&app.chart_index, app.chart_index_closes.get(&bar.date)) {
+                            text.push_str(&format!("\n{}: {:.2}", name, close));
+                        }
+                        for series in indicator_series.iter().flat_map(|(_, series)| series) {
+                            if let Some(value) = series.values[idx] {
+                                text.push_str(&format!("\n{}: {:.2}", series.name, value));
+                            }
+                        }
+                        ui.label(text);
+                    });
+                }
+            }
+
             // Only check for loading more data if user is actively interacting with the plot
             // This prevents automatic cascading loads when the plot first renders
             if response.response.dragged() || (response.response.hovered() && ui.input(|i| i.raw_scroll_delta.x != 0.0)) {
@@ -74,11 +272,8 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                 let view_end_ts = plot_bounds_range.max()[0];
 
                 // Get the earliest and latest loaded data timestamps
-                if let (Some((earliest_date, _)), Some((latest_date, _))) =
-                    (app.plot_data.first(), app.plot_data.last()) {
-
-                    let earliest_ts = earliest_date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
-                    let _latest_ts = latest_date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64;
+                if let Some(earliest_date) = app.plot_data.first().map(|bar| bar.date) {
+                    let earliest_ts = date_to_x(earliest_date);
 
                     // Calculate visible range in days
                     let visible_range_days = (view_end_ts - view_start_ts) / (24.0 * 3600.0);
@@ -92,8 +287,8 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                     // 3. We haven't reached the earliest available data
                     if view_start_ts < (earliest_ts + threshold) && !app.plot_loading_in_progress {
                         if let Some(earliest_available) = app.plot_earliest_available {
-                            if earliest_date > &earliest_available {
-                                println!("Loading earlier data: view_start={}, earliest={}, threshold={}",
+                            if earliest_date > earliest_available {
+                                log::debug!("Loading earlier data: view_start={}, earliest={}, threshold={}",
                                     view_start_ts, earliest_ts, threshold);
                                 // Load 90 more days of data
                                 app.load_earlier_data(symbol, 90);
@@ -111,6 +306,55 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                 app.plot_data.clear();
             }
 
+            let backtest = ui.add_enabled(app.chart_exchange == Exchange::Nse, egui::Button::new("Backtest"))
+                .on_hover_text("Test a strategy on this symbol")
+                .on_disabled_hover_text("Backtests run on NSE prices");
+            if backtest.clicked() {
+                app.backtest_symbol = symbol.clone();
+                app.backtest_result = None;
+                app.selected_symbol = None;
+                app.plot_data.clear();
+                app.current_view = View::Backtest;
+            }
+
+            let derivatives = ui.add_enabled(app.chart_exchange == Exchange::Nse, egui::Button::new("F&O"))
+                .on_hover_text("Futures basis and option chain for this symbol")
+                .on_disabled_hover_text("F&O contracts trade on NSE");
+            if derivatives.clicked() {
+                app.fo_symbol = symbol.clone();
+                app.fo_expiry = None;
+                crate::ui::derivatives::load(app);
+                app.selected_symbol = None;
+                app.plot_data.clear();
+                app.current_view = View::Derivatives;
+            }
+
+            for mode in [ChartMode::Line, ChartMode::Candlestick, ChartMode::Ohlc] {
+                ui.selectable_value(&mut app.chart_mode, mode, mode.label());
+            }
+
+            let mut adjusted = app.plot_adjusted;
+            if ui.checkbox(&mut adjusted, "Adjusted prices")
+                .on_hover_text("Adjust for splits, bonuses and dividends imported in Settings")
+                .changed() {
+                app.set_plot_adjusted(adjusted);
+            }
+
+            // Listings of the same company are linked by ISIN
+            ui.separator();
+            let mut switch_to = None;
+            for exchange in Exchange::ALL {
+                let listed = app.chart_listings.iter().any(|(e, _)| *e == exchange);
+                let response = ui.add_enabled(listed, egui::SelectableLabel::new(app.chart_exchange == exchange, exchange.key()))
+                    .on_disabled_hover_text(format!("No {} listing with the same ISIN", exchange));
+                if response.clicked() {
+                    switch_to = Some(exchange);
+                }
+            }
+            if let Some(exchange) = switch_to {
+                app.switch_chart_exchange(exchange);
+            }
+
             // Add spacing to push time range buttons to the right
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 // Time range buttons (in reverse order because of right_to_left layout)
@@ -138,6 +382,9 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                 }
             });
         });
+
+        render_index_controls(ui, app);
+        render_indicator_controls(ui, app);
     } else if !app.search_query.is_empty() {
         // Show search results
         ui.heading("Search Results");
@@ -154,12 +401,111 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             app.load_plot_data(&symbol);
         }
     } else {
-        ui.vertical_centered(|ui| {
-            ui.add_space(50.0);
-            ui.heading("Welcome to Indistocks");
-            ui.add_space(20.0);
-            ui.label("Select a stock from the sidebar or search above");
+        crate::ui::home::render(ui, app);
+    }
+}
+
+/// Index picker for the overlay, with the stock's relative strength, beta and correlation over the loaded range
+fn render_index_controls(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        ui.label("Compare with:");
+        if app.chart_index_names.is_empty() {
+            ui.weak("no index data; use Fetch Index Data in Settings");
+            return;
+        }
+
+        let mut selected = app.chart_index.clone();
+        egui::ComboBox::from_id_salt("chart_index")
+            .selected_text(selected.clone().unwrap_or_else(|| "None".to_string()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "None");
+                for name in &app.chart_index_names {
+                    ui.selectable_value(&mut selected, Some(name.clone()), name);
+                }
+            });
+        if selected != app.chart_index {
+            app.set_chart_index(selected);
+        }
+
+        if app.chart_index.is_none() {
+            return;
+        }
+        match &app.chart_comparison {
+            Some(comparison) => {
+                let color = if comparison.relative_strength_pct >= 0.0 { UP_COLOR } else { DOWN_COLOR };
+                let optional = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string());
+                ui.separator();
+                ui.label("Relative strength:");
+                ui.colored_label(color, format!("{:+.2}%", comparison.relative_strength_pct))
+                    .on_hover_text(format!(
+                        "Stock {:+.2}% against index {:+.2}% from {} to {}",
+                        comparison.stock_return_pct, comparison.index_return_pct,
+                        comparison.from.format("%d %b %Y"), comparison.to.format("%d %b %Y"),
+                    ));
+                ui.separator();
+                ui.label("Beta:");
+                ui.strong(optional(comparison.beta))
+                    .on_hover_text("Sensitivity of the stock's daily returns to the index's");
+                ui.separator();
+                ui.label("Correlation:");
+                ui.strong(optional(comparison.correlation))
+                    .on_hover_text(format!("Correlation of daily returns over {} sessions", comparison.sessions));
+            }
+            None => {
+                ui.weak("fewer than two sessions in common over the loaded range");
+            }
+        }
+    });
+}
+
+/// Row of active indicators with their periods, and a menu to add more
+fn render_indicator_controls(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    let mut indicators = app.chart_indicators.clone();
+    let mut remove = None;
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Indicators:");
+        ui.menu_button("➕ Add", |ui| {
+            for indicator in Indicator::defaults() {
+                if ui.button(indicator.name()).clicked() {
+                    indicators.push(indicator);
+                    ui.close_menu();
+                }
+            }
         });
+
+        for (i, indicator) in indicators.iter_mut().enumerate() {
+            ui.separator();
+            ui.label(indicator.name());
+            match indicator {
+                Indicator::Sma { period }
+                | Indicator::Ema { period }
+                | Indicator::Rsi { period }
+                | Indicator::Atr { period }
+                | Indicator::Vwap { period } => {
+                    ui.add(egui::DragValue::new(period).range(1..=500));
+                }
+                Indicator::Bollinger { period, std_dev } => {
+                    ui.add(egui::DragValue::new(period).range(1..=500));
+                    ui.add(egui::DragValue::new(std_dev).range(0.5..=5.0).speed(0.1).suffix("σ"));
+                }
+                Indicator::Macd { fast, slow, signal } => {
+                    ui.add(egui::DragValue::new(fast).range(1..=500));
+                    ui.add(egui::DragValue::new(slow).range(1..=500));
+                    ui.add(egui::DragValue::new(signal).range(1..=500));
+                }
+            }
+            if ui.small_button("✖").on_hover_text("Remove indicator").clicked() {
+                remove = Some(i);
+            }
+        }
+    });
+
+    if let Some(i) = remove {
+        indicators.remove(i);
+    }
+    if indicators != app.chart_indicators {
+        app.set_chart_indicators(indicators);
     }
 }
 
@@ -178,7 +524,7 @@ fn get_date_format_and_filter(days_diff: i64) -> (String, bool) {
 }
 
 /// Format a Unix timestamp to a date string
-fn format_timestamp_to_date(timestamp: f64, format: &str) -> String {
+pub fn format_timestamp_to_date(timestamp: f64, format: &str) -> String {
     let dt = chrono::DateTime::from_timestamp(timestamp as i64, 0);
     if let Some(dt) = dt {
         dt.format(format).to_string()
@@ -187,14 +533,61 @@ fn format_timestamp_to_date(timestamp: f64, format: &str) -> String {
     }
 }
 
+/// X coordinate of a trading day on the plot (Unix timestamp at midnight UTC)
+pub fn date_to_x(date: NaiveDate) -> f64 {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64
+}
+
+/// Green for up days, red for down days
+fn bar_color(bar: &PriceBar) -> egui::Color32 {
+    if bar.close >= bar.open { UP_COLOR } else { DOWN_COLOR }
+}
+
+/// Index of the bar closest to an x coordinate; bars are sorted by date
+fn bar_index_at(bars: &[PriceBar], x: f64) -> Option<usize> {
+    if bars.is_empty() {
+        return None;
+    }
+    let idx = bars.partition_point(|bar| date_to_x(bar.date) < x);
+    if idx == 0 {
+        return Some(0);
+    }
+    if idx == bars.len() || x - date_to_x(bars[idx - 1].date) <= date_to_x(bars[idx].date) - x {
+        Some(idx - 1)
+    } else {
+        Some(idx)
+    }
+}
+
+/// Line for one indicator series, skipping its warm-up period
+fn indicator_line(bars: &[PriceBar], series: &IndicatorSeries) -> egui_plot::Line {
+    let points: egui_plot::PlotPoints = bars.iter().zip(&series.values)
+        .filter_map(|(bar, value)| Some([date_to_x(bar.date), (*value)?]))
+        .collect();
+    egui_plot::Line::new(points).name(&series.name).allow_hover(false)
+}
+
+/// Colours cycled through for indicator lines
+fn indicator_color(i: usize) -> egui::Color32 {
+    const PALETTE: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(255, 165, 0),
+        egui::Color32::from_rgb(30, 144, 255),
+        egui::Color32::from_rgb(186, 85, 211),
+        egui::Color32::from_rgb(0, 191, 165),
+        egui::Color32::from_rgb(240, 98, 146),
+        egui::Color32::from_rgb(205, 220, 57),
+    ];
+    PALETTE[i % PALETTE.len()]
+}
+
 /// Add custom X-axis tick marks based on the date range
-fn add_custom_x_ticks(_plot_ui: &mut egui_plot::PlotUi, data: &[(NaiveDate, f64)], days_diff: i64) {
+fn add_custom_x_ticks(_plot_ui: &mut egui_plot::PlotUi, data: &[PriceBar], days_diff: i64) {
     if data.is_empty() {
         return;
     }
 
-    let min_date = data.iter().map(|(d, _)| *d).min().unwrap();
-    let max_date = data.iter().map(|(d, _)| *d).max().unwrap();
+    let min_date = data.iter().map(|bar| bar.date).min().unwrap();
+    let max_date = data.iter().map(|bar| bar.date).max().unwrap();
 
     let mut tick_dates = Vec::new();
 
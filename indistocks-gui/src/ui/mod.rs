@@ -1,5 +1,11 @@
 pub mod top_nav;
 pub mod sidebar;
 pub mod main_content;
+pub mod home;
 pub mod settings;
 pub mod stocks;
+pub mod portfolio;
+pub mod backtest;
+pub mod derivatives;
+pub mod logs;
+pub mod alerts;
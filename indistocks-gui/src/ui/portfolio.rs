@@ -0,0 +1,708 @@
+use crate::app::IndistocksApp;
+use chrono::NaiveDate;
+use indistocks_db::{PortfolioTransaction, TradeSide, FinancialYear, GainTerm, add_transaction, delete_transaction, write_schedule_112a_csv};
+use indistocks_db::{Broker, TradeStatus, TradebookPreview, preview_tradebook, resolve_tradebook, commit_tradebook, save_broker_symbol_mapping};
+
+pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    if app.portfolio.is_none() {
+        app.refresh_portfolio();
+    }
+
+    ui.heading("Portfolio");
+    ui.add_space(10.0);
+
+    render_summary(ui, app);
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    render_transaction_form(ui, app);
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            ui.add_space(10.0);
+            egui::CollapsingHeader::new("Import Tradebook")
+                .default_open(app.tradebook_preview.is_some())
+                .show(ui, |ui| render_tradebook_import(ui, app));
+
+            ui.add_space(10.0);
+            ui.strong("Holdings");
+            ui.add_space(5.0);
+            render_holdings_table(ui, app);
+
+            ui.add_space(20.0);
+            egui::CollapsingHeader::new("Realized P&L")
+                .default_open(false)
+                .show(ui, |ui| render_realized_table(ui, app));
+
+            egui::CollapsingHeader::new("Capital Gains Tax")
+                .default_open(false)
+                .show(ui, |ui| render_tax_report(ui, app));
+
+            egui::CollapsingHeader::new("Transactions")
+                .default_open(true)
+                .show(ui, |ui| render_transactions_table(ui, app));
+        });
+}
+
+fn render_summary(ui: &mut egui::Ui, app: &IndistocksApp) {
+    let Some(portfolio) = &app.portfolio else {
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        ui.label("Invested:");
+        ui.strong(format!("₹{:.2}", portfolio.invested()));
+        ui.add_space(20.0);
+
+        ui.label("Current Value:");
+        ui.strong(format!("₹{:.2}", portfolio.market_value()));
+        ui.add_space(20.0);
+
+        ui.label("Unrealized P&L:");
+        pnl_label(ui, portfolio.unrealized_pnl());
+        ui.add_space(20.0);
+
+        ui.label("Realized P&L:");
+        pnl_label(ui, portfolio.realized_pnl());
+    });
+
+    for warning in &portfolio.warnings {
+        ui.colored_label(egui::Color32::YELLOW, warning);
+    }
+}
+
+fn render_transaction_form(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.strong("Add Transaction");
+    ui.add_space(5.0);
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("portfolio_side")
+            .selected_text(match app.portfolio_side {
+                TradeSide::Buy => "Buy",
+                TradeSide::Sell => "Sell",
+            })
+            .width(70.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.portfolio_side, TradeSide::Buy, "Buy");
+                ui.selectable_value(&mut app.portfolio_side, TradeSide::Sell, "Sell");
+            });
+
+        ui.add_sized([110.0, 20.0], egui::TextEdit::singleline(&mut app.portfolio_symbol).hint_text("Symbol"));
+        ui.add_sized([80.0, 20.0], egui::TextEdit::singleline(&mut app.portfolio_quantity).hint_text("Qty"));
+        ui.add_sized([90.0, 20.0], egui::TextEdit::singleline(&mut app.portfolio_price).hint_text("Price"));
+        ui.add_sized([90.0, 20.0], egui::TextEdit::singleline(&mut app.portfolio_charges).hint_text("Charges"));
+        ui.add_sized([100.0, 20.0], egui::TextEdit::singleline(&mut app.portfolio_date).hint_text("YYYY-MM-DD"));
+
+        if ui.button("Add").clicked() {
+            match parse_transaction_form(app) {
+                Ok(transaction) => {
+                    let result = add_transaction(&app.db_conn.lock().unwrap(), &transaction);
+                    match result {
+                        Ok(_) => {
+                            app.portfolio_status = format!(
+                                "Recorded {} of {} {}",
+                                transaction.side.as_str(), transaction.quantity, transaction.symbol.to_uppercase()
+                            );
+                            app.portfolio_quantity.clear();
+                            app.portfolio_price.clear();
+                            app.portfolio_charges.clear();
+                            app.portfolio = None;
+                        }
+                        Err(e) => app.portfolio_status = format!("Error: {}", e),
+                    }
+                }
+                Err(e) => app.portfolio_status = format!("Error: {}", e),
+            }
+        }
+    });
+
+    if !app.portfolio_status.is_empty() {
+        ui.label(&app.portfolio_status);
+    }
+}
+
+/// Broker tradebook import: preview first, map unmatched symbols, then add the ready trades
+fn render_tradebook_import(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("tradebook_broker")
+            .selected_text(app.tradebook_broker.map(|b| b.label()).unwrap_or("Detect broker"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.tradebook_broker, None, "Detect broker");
+                for broker in Broker::ALL {
+                    ui.selectable_value(&mut app.tradebook_broker, Some(broker), broker.label());
+                }
+            });
+        ui.add_sized([400.0, 20.0], egui::TextEdit::singleline(&mut app.tradebook_path).hint_text("/path/to/tradebook.csv"));
+
+        if ui.add_enabled(!app.tradebook_path.trim().is_empty(), egui::Button::new("Preview")).clicked() {
+            let path = std::path::PathBuf::from(app.tradebook_path.trim());
+            let result = preview_tradebook(&app.db_conn.lock().unwrap(), &path, app.tradebook_broker);
+            match result {
+                Ok(preview) => {
+                    app.tradebook_mapping_drafts.clear();
+                    app.tradebook_preview = Some(preview);
+                    app.portfolio_status.clear();
+                }
+                Err(e) => {
+                    app.tradebook_preview = None;
+                    app.portfolio_status = format!("Error: {}", e);
+                }
+            }
+        }
+    });
+
+    let Some(preview) = &app.tradebook_preview else {
+        return;
+    };
+
+    ui.add_space(5.0);
+    let ready = preview.count(TradeStatus::Ready);
+    ui.label(format!(
+        "{} tradebook: {} ready, {} already imported or repeated, {} unmatched, {} skipped",
+        preview.broker.label(),
+        ready,
+        preview.count(TradeStatus::Duplicate),
+        preview.count(TradeStatus::Unmatched),
+        preview.count(TradeStatus::Skipped),
+    ));
+    for line in &preview.rejected {
+        ui.colored_label(egui::Color32::YELLOW, line);
+    }
+
+    // Unmatched broker symbols can be mapped by hand; the mapping is kept for later imports
+    let mut mapping: Option<(String, String)> = None;
+    let unmatched = preview.unmatched_symbols();
+    if !unmatched.is_empty() {
+        ui.add_space(5.0);
+        ui.label("Map unmatched symbols to NSE symbols:");
+        for broker_symbol in unmatched {
+            ui.horizontal(|ui| {
+                ui.label(&broker_symbol);
+                let draft = app.tradebook_mapping_drafts.entry(broker_symbol.clone()).or_default();
+                ui.add_sized([120.0, 20.0], egui::TextEdit::singleline(draft).hint_text("NSE symbol"));
+                let symbol = draft.trim().to_uppercase();
+                if ui.add_enabled(!symbol.is_empty(), egui::Button::new("Map")).clicked() {
+                    mapping = Some((broker_symbol.clone(), symbol));
+                }
+            });
+        }
+    }
+
+    let mut import = false;
+    let mut cancel = false;
+    ui.add_space(5.0);
+    ui.horizontal(|ui| {
+        if ui.add_enabled(ready > 0, egui::Button::new(format!("Import {} trades", ready))).clicked() {
+            import = true;
+        }
+        if ui.button("Cancel").clicked() {
+            cancel = true;
+        }
+    });
+
+    render_tradebook_issues(ui, preview);
+
+    if let (Some((broker_symbol, symbol)), Some(preview)) = (mapping, app.tradebook_preview.as_mut()) {
+        let conn = app.db_conn.lock().unwrap();
+        let result = save_broker_symbol_mapping(&conn, preview.broker, &broker_symbol, &symbol)
+            .and_then(|_| resolve_tradebook(&conn, preview));
+        if let Err(e) = result {
+            app.portfolio_status = format!("Error: {}", e);
+        }
+    }
+
+    if import {
+        if let Some(preview) = app.tradebook_preview.take() {
+            let result = commit_tradebook(&app.db_conn.lock().unwrap(), &preview);
+            app.portfolio_status = match result {
+                Ok(report) if report.rejected.is_empty() => format!("Imported {} trades", report.inserted),
+                Ok(report) => format!(
+                    "Imported {} trades; {} rejected: {}",
+                    report.inserted,
+                    report.rejected.len(),
+                    report.rejected.join("; ")
+                ),
+                Err(e) => format!("Error: {}", e),
+            };
+            app.portfolio = None;
+        }
+    } else if cancel {
+        app.tradebook_preview = None;
+    }
+}
+
+/// Rows that will not be imported, with the reason
+fn render_tradebook_issues(ui: &mut egui::Ui, preview: &TradebookPreview) {
+    use egui_extras::{TableBuilder, Column};
+
+    let issues: Vec<_> = preview.rows.iter().filter(|r| r.status != TradeStatus::Ready).collect();
+    if issues.is_empty() {
+        return;
+    }
+
+    ui.add_space(5.0);
+    TableBuilder::new(ui)
+        .id_salt("tradebook_issues")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(50.0).at_least(40.0))   // Line
+        .column(Column::initial(90.0).at_least(80.0))   // Status
+        .column(Column::initial(130.0).at_least(80.0))  // Symbol
+        .column(Column::initial(100.0).at_least(90.0))  // Date
+        .column(Column::initial(60.0).at_least(50.0))   // Side
+        .column(Column::initial(80.0).at_least(60.0))   // Qty
+        .column(Column::initial(90.0).at_least(80.0))   // Price
+        .column(Column::remainder().at_least(150.0))    // Reason
+        .header(30.0, |mut header| {
+            for title in ["Line", "Status", "Symbol", "Date", "Side", "Qty", "Price", "Reason"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for row in issues {
+                body.row(25.0, |mut table_row| {
+                    table_row.col(|ui| {
+                        ui.label(row.line.to_string());
+                    });
+                    table_row.col(|ui| {
+                        let (text, color) = match row.status {
+                            TradeStatus::Duplicate => ("Duplicate", egui::Color32::GRAY),
+                            TradeStatus::Unmatched => ("Unmatched", egui::Color32::YELLOW),
+                            _ => ("Skipped", egui::Color32::GRAY),
+                        };
+                        ui.colored_label(color, text);
+                    });
+                    table_row.col(|ui| {
+                        let response = ui.label(&row.broker_symbol);
+                        if let Some(isin) = &row.isin {
+                            response.on_hover_text(isin);
+                        }
+                    });
+                    table_row.col(|ui| {
+                        ui.label(row.date.format("%d-%m-%Y").to_string());
+                    });
+                    table_row.col(|ui| {
+                        ui.label(match row.side {
+                            TradeSide::Buy => "Buy",
+                            TradeSide::Sell => "Sell",
+                        });
+                    });
+                    table_row.col(|ui| {
+                        ui.label(row.quantity.to_string());
+                    });
+                    table_row.col(|ui| {
+                        ui.label(format!("{:.2}", row.price));
+                    });
+                    table_row.col(|ui| {
+                        ui.label(row.reason.as_deref().unwrap_or(""));
+                    });
+                });
+            }
+        });
+}
+
+fn parse_transaction_form(app: &IndistocksApp) -> Result<PortfolioTransaction, String> {
+    let quantity = app.portfolio_quantity.trim().parse::<i64>()
+        .map_err(|_| "Quantity must be a whole number".to_string())?;
+    let price = app.portfolio_price.trim().parse::<f64>()
+        .map_err(|_| "Price must be a number".to_string())?;
+    let charges = if app.portfolio_charges.trim().is_empty() {
+        0.0
+    } else {
+        app.portfolio_charges.trim().parse::<f64>().map_err(|_| "Charges must be a number".to_string())?
+    };
+    let date = NaiveDate::parse_from_str(app.portfolio_date.trim(), "%Y-%m-%d")
+        .map_err(|_| "Date must be YYYY-MM-DD".to_string())?;
+
+    Ok(PortfolioTransaction {
+        id: 0,
+        symbol: app.portfolio_symbol.trim().to_uppercase(),
+        side: app.portfolio_side,
+        quantity,
+        price,
+        charges,
+        date,
+        note: None,
+    })
+}
+
+fn pnl_label(ui: &mut egui::Ui, value: f64) {
+    let color = if value > 0.0 {
+        egui::Color32::GREEN
+    } else if value < 0.0 {
+        egui::Color32::RED
+    } else {
+        ui.style().visuals.text_color()
+    };
+    ui.colored_label(color, format!("{:+.2}", value));
+}
+
+fn render_holdings_table(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    let holdings = app.portfolio.as_ref().map(|p| p.holdings.clone()).unwrap_or_default();
+    if holdings.is_empty() {
+        ui.label("No open positions. Add a buy transaction above.");
+        return;
+    }
+
+    let mut symbol_to_load: Option<String> = None;
+
+    TableBuilder::new(ui)
+        .id_salt("portfolio_holdings")
+        .striped(true)
+        .resizable(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(120.0).at_least(80.0))  // Symbol
+        .column(Column::initial(80.0).at_least(60.0))   // Qty
+        .column(Column::initial(100.0).at_least(80.0))  // Avg Cost
+        .column(Column::initial(110.0).at_least(90.0))  // Invested
+        .column(Column::initial(100.0).at_least(80.0))  // LTP
+        .column(Column::initial(110.0).at_least(90.0))  // Value
+        .column(Column::initial(110.0).at_least(90.0))  // Unrealized
+        .column(Column::initial(80.0).at_least(70.0))   // %
+        .column(Column::remainder().at_least(90.0))     // Realized
+        .header(30.0, |mut header| {
+            for title in ["Symbol", "Qty", "Avg Cost", "Invested", "LTP", "Value", "Unrealized P&L", "%", "Realized P&L"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for holding in &holdings {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        let response = ui.button(&holding.symbol);
+                        let response = match &holding.name {
+                            Some(name) => response.on_hover_text(name),
+                            None => response,
+                        };
+                        if response.clicked() {
+                            symbol_to_load = Some(holding.symbol.clone());
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(holding.quantity.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", holding.avg_cost));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", holding.invested));
+                    });
+                    row.col(|ui| {
+                        match (holding.last_close, holding.last_date) {
+                            (Some(close), Some(date)) => {
+                                ui.label(format!("{:.2}", close)).on_hover_text(format!("Close on {}", date));
+                            }
+                            _ => {
+                                ui.label("N/A").on_hover_text("No BhavCopy data for this symbol; valued at cost");
+                            }
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", holding.market_value));
+                    });
+                    row.col(|ui| {
+                        pnl_label(ui, holding.unrealized_pnl);
+                    });
+                    row.col(|ui| {
+                        pnl_label(ui, holding.unrealized_pct());
+                    });
+                    row.col(|ui| {
+                        pnl_label(ui, holding.realized_pnl);
+                    });
+                });
+            }
+        });
+
+    if let Some(symbol) = symbol_to_load {
+        app.load_plot_data(&symbol);
+    }
+}
+
+fn render_realized_table(ui: &mut egui::Ui, app: &IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    let realized = app.portfolio.as_ref().map(|p| p.realized.as_slice()).unwrap_or_default();
+    if realized.is_empty() {
+        ui.label("No closed positions yet.");
+        return;
+    }
+
+    TableBuilder::new(ui)
+        .id_salt("portfolio_realized")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(120.0).at_least(80.0))  // Symbol
+        .column(Column::initial(80.0).at_least(60.0))   // Qty
+        .column(Column::initial(100.0).at_least(90.0))  // Bought
+        .column(Column::initial(100.0).at_least(80.0))  // Buy price
+        .column(Column::initial(100.0).at_least(90.0))  // Sold
+        .column(Column::initial(100.0).at_least(80.0))  // Sell price
+        .column(Column::remainder().at_least(90.0))     // P&L
+        .header(30.0, |mut header| {
+            for title in ["Symbol", "Qty", "Bought", "Buy Price", "Sold", "Sell Price", "P&L"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for gain in realized {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(&gain.symbol);
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.quantity.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.buy_date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", gain.buy_price));
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.sell_date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", gain.sell_price));
+                    });
+                    row.col(|ui| {
+                        pnl_label(ui, gain.pnl());
+                    });
+                });
+            }
+        });
+}
+
+fn render_tax_report(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    // Financial years from the first transaction to the current one
+    let current = FinancialYear::containing(chrono::Local::now().date_naive());
+    let first = app.portfolio_transactions.first().map(|t| FinancialYear::containing(t.date)).unwrap_or(current);
+
+    ui.horizontal(|ui| {
+        ui.label("Financial year:");
+        let mut year = app.tax_year;
+        egui::ComboBox::from_id_salt("tax_year")
+            .selected_text(year.label())
+            .show_ui(ui, |ui| {
+                for start in (first.0.min(current.0)..=current.0).rev() {
+                    ui.selectable_value(&mut year, FinancialYear(start), FinancialYear(start).label());
+                }
+            });
+        if year != app.tax_year {
+            app.tax_year = year;
+            app.portfolio = None;
+        }
+    });
+
+    let Some(report) = app.tax_report.clone() else {
+        return;
+    };
+
+    ui.add_space(5.0);
+    ui.horizontal(|ui| {
+        ui.label("STCG:");
+        pnl_label(ui, report.stcg);
+        ui.add_space(20.0);
+        ui.label("LTCG:");
+        pnl_label(ui, report.ltcg);
+        ui.add_space(20.0);
+        ui.label("Exemption:");
+        ui.strong(format!("₹{:.2}", report.ltcg_exemption));
+        ui.add_space(20.0);
+        ui.label("Taxable STCG:");
+        ui.strong(format!("₹{:.2}", report.taxable_stcg));
+        ui.add_space(20.0);
+        ui.label("Taxable LTCG:");
+        ui.strong(format!("₹{:.2}", report.taxable_ltcg));
+    });
+    let (short_loss, long_loss) = report.carry_forward;
+    if report.stcl_set_off > 0.0 || short_loss > 0.0 || long_loss > 0.0 {
+        ui.label(format!(
+            "Short-term loss set off: ₹{:.2}   Carried forward: ₹{:.2} short-term, ₹{:.2} long-term",
+            report.stcl_set_off, short_loss, long_loss
+        ));
+    }
+    for warning in &report.warnings {
+        ui.colored_label(egui::Color32::YELLOW, warning);
+    }
+
+    ui.add_space(5.0);
+    ui.horizontal(|ui| {
+        ui.add_sized([300.0, 20.0], egui::TextEdit::singleline(&mut app.tax_export_path).hint_text("schedule-112a.csv"));
+        if ui.button("Export Schedule 112A").clicked() {
+            let path = if app.tax_export_path.trim().is_empty() {
+                format!("schedule-112a-{}.csv", report.year.label())
+            } else {
+                app.tax_export_path.trim().to_string()
+            };
+            let result = std::fs::File::create(&path)
+                .map_err(|e| e.into())
+                .and_then(|file| write_schedule_112a_csv(&report, file));
+            app.portfolio_status = match result {
+                Ok(()) => format!("Exported Schedule 112A for FY {} to {}", report.year.label(), path),
+                Err(e) => format!("Error: {}", e),
+            };
+        }
+    });
+
+    if report.gains.is_empty() {
+        ui.label("No sales in this financial year.");
+        return;
+    }
+
+    ui.add_space(5.0);
+    TableBuilder::new(ui)
+        .id_salt("portfolio_tax")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(120.0).at_least(80.0))  // Symbol
+        .column(Column::initial(60.0).at_least(50.0))   // Term
+        .column(Column::initial(70.0).at_least(60.0))   // Qty
+        .column(Column::initial(100.0).at_least(90.0))  // Bought
+        .column(Column::initial(100.0).at_least(90.0))  // Sold
+        .column(Column::initial(110.0).at_least(90.0))  // Sale value
+        .column(Column::initial(110.0).at_least(90.0))  // Cost
+        .column(Column::initial(90.0).at_least(80.0))   // FMV
+        .column(Column::initial(90.0).at_least(80.0))   // Expenses
+        .column(Column::remainder().at_least(90.0))     // Gain
+        .header(30.0, |mut header| {
+            for title in ["Symbol", "Term", "Qty", "Bought", "Sold", "Sale Value", "Cost", "FMV 31-Jan-18", "Expenses", "Gain"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for gain in &report.gains {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(&gain.symbol);
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.term.label());
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.quantity.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.buy_date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(gain.sell_date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", gain.full_value));
+                    });
+                    row.col(|ui| {
+                        let response = ui.label(format!("{:.2}", gain.cost_of_acquisition));
+                        if gain.cost_of_acquisition != gain.actual_cost {
+                            response.on_hover_text(format!("Grandfathered; actual cost {:.2}", gain.actual_cost));
+                        }
+                    });
+                    row.col(|ui| {
+                        match gain.fmv_per_share {
+                            Some(fmv) => ui.label(format!("{:.2}", fmv)),
+                            None if gain.term == GainTerm::Long && gain.is_grandfathered() => ui.label("N/A"),
+                            None => ui.label("-"),
+                        };
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", gain.transfer_expenses));
+                    });
+                    row.col(|ui| {
+                        pnl_label(ui, gain.gain);
+                    });
+                });
+            }
+        });
+}
+
+fn render_transactions_table(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    if app.portfolio_transactions.is_empty() {
+        ui.label("No transactions recorded.");
+        return;
+    }
+
+    let mut to_delete: Option<i64> = None;
+
+    TableBuilder::new(ui)
+        .id_salt("portfolio_transactions")
+        .striped(true)
+        .vscroll(false)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(100.0).at_least(90.0))  // Date
+        .column(Column::initial(120.0).at_least(80.0))  // Symbol
+        .column(Column::initial(60.0).at_least(50.0))   // Side
+        .column(Column::initial(80.0).at_least(60.0))   // Qty
+        .column(Column::initial(100.0).at_least(80.0))  // Price
+        .column(Column::initial(100.0).at_least(80.0))  // Charges
+        .column(Column::remainder().at_least(40.0))     // Delete
+        .header(30.0, |mut header| {
+            for title in ["Date", "Symbol", "Side", "Qty", "Price", "Charges", ""] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            // Newest first
+            for transaction in app.portfolio_transactions.iter().rev() {
+                body.row(25.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(transaction.date.format("%d-%m-%Y").to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(&transaction.symbol);
+                    });
+                    row.col(|ui| {
+                        ui.label(match transaction.side {
+                            TradeSide::Buy => "Buy",
+                            TradeSide::Sell => "Sell",
+                        });
+                    });
+                    row.col(|ui| {
+                        ui.label(transaction.quantity.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", transaction.price));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}", transaction.charges));
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("🗑").on_hover_text("Double-click to delete this transaction").double_clicked() {
+                            to_delete = Some(transaction.id);
+                        }
+                    });
+                });
+            }
+        });
+
+    if let Some(id) = to_delete {
+        if let Err(e) = delete_transaction(&app.db_conn.lock().unwrap(), id) {
+            app.portfolio_status = format!("Error: {}", e);
+        }
+        app.portfolio = None;
+    }
+}
@@ -1,5 +1,8 @@
 use crate::app::{IndistocksApp, View};
-use indistocks_db::{save_nse_symbols_with_names, download_bhavcopy, get_bhavcopy_date_range, BhavCopyMessage};
+use indistocks_db::{save_nse_symbols_with_names, download_nse_equity_list, default_data_source, get_bhavcopy_date_range, import_corporate_actions_csv, import_holidays_csv, get_market_holidays, find_missing_trading_days, find_bhavcopy_files, import_bhavcopy_file, evaluate_alerts, reopen_db_connection, AlertNotification, ImportReport, DownloadJob, DownloadJobKind, Exchange, JobStatus};
+use chrono::NaiveDate;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{self, TryRecvError};
 use std::thread;
 
@@ -10,37 +13,19 @@ pub enum NseListMessage {
     Done(Result<Vec<(String, String)>, String>),
 }
 
-fn download_nse_equity_list() -> Result<Vec<(String, String)>, String> {
-    let url = "https://nsearchives.nseindia.com/content/equities/EQUITY_L.csv";
-    let response = reqwest::blocking::get(url)
-        .map_err(|e| format!("Failed to download: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
-    }
-    
-    let content = response.text()
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-    
-    let mut rdr = csv::Reader::from_reader(content.as_bytes());
-    let mut symbols = Vec::new();
-    
-    for result in rdr.records() {
-        let record = result.map_err(|e| format!("CSV parse error: {}", e))?;
-        if let (Some(symbol), Some(name)) = (record.get(0), record.get(1)) {
-            if !symbol.trim().is_empty() && !name.trim().is_empty() {
-                symbols.push((symbol.trim().to_string(), name.trim().to_string()));
-            }
-        }
-    }
-    
-    Ok(symbols)
+/// Events from the BhavCopy folder import thread
+#[derive(Debug)]
+pub enum ImportMessage {
+    /// `done` of `total` files are imported; `reports` are those of the file just finished
+    Progress { done: usize, total: usize, reports: Vec<ImportReport> },
+    /// Every file is imported; carries the alerts that fired on the latest session
+    Done(Result<Vec<AlertNotification>, String>),
 }
 
 pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
     // Refresh BhavCopy date range only once when Settings view is opened (if not already set)
-    if app.bhavcopy_date_range.is_none() && !app.is_downloading_bhavcopy {
-        app.bhavcopy_date_range = get_bhavcopy_date_range(&*app.db_conn.lock().unwrap()).unwrap_or(None);
+    if app.bhavcopy_date_range.is_none() && !app.download_manager.is_busy() {
+        app.bhavcopy_date_range = get_bhavcopy_date_range(&app.db_conn.lock().unwrap()).unwrap_or(None);
     }
 
     egui::ScrollArea::vertical().show(ui, |ui| {
@@ -87,7 +72,7 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             app.nse_list_receiver = Some(rx);
 
             thread::spawn(move || {
-                let result = download_nse_equity_list();
+                let result = default_data_source().and_then(|source| download_nse_equity_list(&*source));
                 let _ = tx.send(NseListMessage::Done(result));
             });
         }
@@ -109,7 +94,7 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                             app.nse_list_receiver = None;
                             match result {
                                 Ok(symbols) => {
-                             let result = save_nse_symbols_with_names(&*app.db_conn.lock().unwrap(), symbols);
+                             let result = save_nse_symbols_with_names(&app.db_conn.lock().unwrap(), symbols);
                              match result {
                                  Ok((count, errors)) => {
                                      app.nse_list_status = format!("Downloaded and saved {} symbols successfully", count);
@@ -147,21 +132,50 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
         ui.heading("NSE Downloads");
         ui.add_space(10.0);
 
-        // Download BhavCopy button
-        if ui.button("Download BhavCopy").clicked() && !app.is_downloading_bhavcopy {
-            app.is_downloading_bhavcopy = true;
-            app.bhavcopy_progress = "Starting download...".to_string();
-            app.bhavcopy_status = String::new();
+        ui.horizontal(|ui| {
+            if ui.button("Download BhavCopy")
+                .on_hover_text("Fetch days after the latest stored date, then a year before the earliest")
+                .clicked()
+            {
+                enqueue(app, DownloadJobKind::Backfill);
+            }
+            if ui.button("Sync").on_hover_text("Fetch only the days after the latest stored date").clicked() {
+                enqueue(app, DownloadJobKind::Sync);
+            }
+            if ui.button("Fetch Delivery Data")
+                .on_hover_text("Fetch delivery positions for stored days that have none")
+                .clicked()
+            {
+                enqueue(app, DownloadJobKind::Delivery);
+            }
+            if ui.button("Fetch F&O Data")
+                .on_hover_text("Fetch F&O BhavCopies for the latest 60 stored days that have none")
+                .clicked()
+            {
+                enqueue(app, DownloadJobKind::Derivatives);
+            }
+            if ui.button("Fetch Index Data")
+                .on_hover_text("Fetch NSE index closes for stored days that have none")
+                .clicked()
+            {
+                enqueue(app, DownloadJobKind::Indices);
+            }
+        });
 
-            let (tx, rx) = mpsc::channel();
-            app.bhavcopy_receiver = Some(rx);
+        ui.add_space(5.0);
 
-            let db_conn = app.db_conn.clone();
-            thread::spawn(move || {
-                let result = download_bhavcopy(&db_conn, &tx);
-                let _ = tx.send(BhavCopyMessage::Done(result.map_err(|e| e.to_string())));
-            });
-        }
+        ui.horizontal(|ui| {
+            ui.label("From:");
+            ui.add(egui::TextEdit::singleline(&mut app.download_range_from).hint_text("YYYY-MM-DD").desired_width(90.0));
+            ui.label("To:");
+            ui.add(egui::TextEdit::singleline(&mut app.download_range_to).hint_text("YYYY-MM-DD").desired_width(90.0));
+            if ui.button("Queue Range").clicked() {
+                match parse_range(&app.download_range_from, &app.download_range_to) {
+                    Ok((from, to)) => enqueue(app, DownloadJobKind::Range { from, to }),
+                    Err(e) => app.download_status = e,
+                }
+            }
+        });
 
         ui.add_space(10.0);
 
@@ -176,59 +190,440 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             }
         }
 
-        // Check for bhavcopy messages - process all available messages
-        if let Some(ref rx) = app.bhavcopy_receiver {
-            loop {
-                match rx.try_recv() {
-                    Ok(message) => {
-                        match message {
-                            BhavCopyMessage::Progress(progress) => {
-                                app.bhavcopy_progress = progress;
-                            }
-                            BhavCopyMessage::DateRangeUpdated(min_date, max_date) => {
-                                app.bhavcopy_date_range = Some((min_date, max_date));
-                            }
-                            BhavCopyMessage::Done(result) => {
-                                app.is_downloading_bhavcopy = false;
-                                app.bhavcopy_receiver = None;
-                                match result {
-                                    Ok(()) => {
-                                        app.bhavcopy_status = "BhavCopy download completed successfully".to_string();
-                                        // Update date range
-                                        app.bhavcopy_date_range = get_bhavcopy_date_range(&*app.db_conn.lock().unwrap()).unwrap_or(None);
-                                    }
-                                    Err(e) => {
-                                        app.bhavcopy_status = format!("Error: {}", e);
-                                    }
-                                }
-                                break;
-                            }
-                        }
-                    }
-                    Err(TryRecvError::Empty) => {
-                        // No more messages
-                        break;
-                    }
-                    Err(TryRecvError::Disconnected) => {
-                        app.is_downloading_bhavcopy = false;
-                        app.bhavcopy_receiver = None;
-                        app.bhavcopy_status = "Download thread disconnected".to_string();
-                        break;
+        ui.add_space(10.0);
+
+        render_active_job(ui, app);
+        render_queue(ui, app);
+
+        if !app.download_status.is_empty() {
+            ui.label(&app.download_status);
+        }
+
+        ui.add_space(10.0);
+        ui.collapsing("Download History", |ui| render_job_history(ui, app));
+
+        ui.add_space(30.0);
+
+        render_trading_calendar(ui, app);
+
+        ui.add_space(30.0);
+
+        render_bhavcopy_import(ui, app);
+
+        ui.add_space(30.0);
+
+        // Corporate actions section
+        ui.heading("Corporate Actions");
+        ui.add_space(10.0);
+
+        ui.label("Import NSE's corporate actions CSV export to adjust prices for splits, bonuses and dividends:");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            ui.add_sized(
+                [400.0, 20.0],
+                egui::TextEdit::singleline(&mut app.corporate_actions_path)
+                    .hint_text("Path to CF-CA-equities CSV")
+            );
+            if ui.button("Import").clicked() && !app.corporate_actions_path.trim().is_empty() {
+                let path = std::path::PathBuf::from(app.corporate_actions_path.trim());
+                let result = import_corporate_actions_csv(&app.db_conn.lock().unwrap(), &path);
+                app.corporate_actions_status = match result {
+                    Ok(report) => {
+                        // Cached metrics and chart data were computed with the old actions
+                        app.stocks_cached_data.clear();
+                        format!(
+                            "Imported {} actions ({} already present, {} not price-affecting, {} rejected)",
+                            report.inserted, report.skipped, report.ignored, report.rejected.len()
+                        )
                     }
-                }
+                    Err(e) => format!("Error importing corporate actions: {}", e),
+                };
             }
+        });
+
+        if !app.corporate_actions_status.is_empty() {
+            ui.add_space(10.0);
+            ui.label(&app.corporate_actions_status);
         }
 
+        ui.add_space(20.0);
+    });
+}
+
+/// Import NSE or BSE BhavCopy files already on disk; BSE data is only available this way
+fn render_bhavcopy_import(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.heading("Import BhavCopy Files");
+    ui.add_space(10.0);
+
+    ui.label("Import NSE or BSE (EQ_ISINCODE or UDiFF) BhavCopy CSV/ZIP files, NSE F&O BhavCopies, NSE index close files, or NSE MTO delivery files, from a folder:");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.add_sized(
+            [400.0, 20.0],
+            egui::TextEdit::singleline(&mut app.bhavcopy_import_path)
+                .hint_text("Folder with BhavCopy files")
+        );
+        let importing = app.bhavcopy_import_receiver.is_some();
+        if ui.add_enabled(!importing, egui::Button::new("Import")).clicked() && !app.bhavcopy_import_path.trim().is_empty() {
+            start_bhavcopy_import(app, PathBuf::from(app.bhavcopy_import_path.trim()));
+        }
+    });
+
+    if let Some((done, total)) = app.bhavcopy_import_progress.filter(|_| app.bhavcopy_import_receiver.is_some()) {
+        ui.add_space(10.0);
+        let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+        ui.add(egui::ProgressBar::new(fraction).text(format!("{} of {} files", done, total)).desired_width(400.0));
+    }
+
+    if !app.bhavcopy_import_status.is_empty() {
         ui.add_space(10.0);
+        ui.label(&app.bhavcopy_import_status);
+    }
+}
+
+/// Import the files under `dir` on a worker thread, reporting each finished file to [`poll_bhavcopy_import`]
+fn start_bhavcopy_import(app: &mut IndistocksApp, dir: PathBuf) {
+    let (tx, rx) = mpsc::channel();
+    app.bhavcopy_import_receiver = Some(rx);
+    app.bhavcopy_import_progress = None;
+    app.bhavcopy_import_reports.clear();
+    app.bhavcopy_import_status = "Importing...".to_string();
+
+    // Like downloads, use a connection of our own so the UI never waits on a long import.
+    // An in-memory database can't be reopened, so lock the shared one a file at a time.
+    let conn = match reopen_db_connection(&app.db_conn.lock().unwrap()) {
+        Some(Ok(conn)) => Arc::new(Mutex::new(conn)),
+        _ => app.db_conn.clone(),
+    };
+    thread::spawn(move || {
+        let files = match find_bhavcopy_files(&dir) {
+            Ok(files) => files,
+            Err(e) => {
+                let _ = tx.send(ImportMessage::Done(Err(e.to_string())));
+                return;
+            }
+        };
 
-        // Progress and Status
-        if !app.bhavcopy_progress.is_empty() {
-            ui.label(&app.bhavcopy_progress);
+        let total = files.len();
+        let _ = tx.send(ImportMessage::Progress { done: 0, total, reports: Vec::new() });
+        for (i, path) in files.iter().enumerate() {
+            let reports = import_bhavcopy_file(&conn.lock().unwrap(), path);
+            let _ = tx.send(ImportMessage::Progress { done: i + 1, total, reports });
         }
-        if !app.bhavcopy_status.is_empty() {
-            ui.label(&app.bhavcopy_status);
+
+        let alerts = evaluate_alerts(&conn.lock().unwrap()).map_err(|e| e.to_string());
+        let _ = tx.send(ImportMessage::Done(alerts));
+    });
+}
+
+/// Handle events from the BhavCopy folder import thread, if one is running
+pub fn poll_bhavcopy_import(app: &mut IndistocksApp) {
+    let Some(rx) = app.bhavcopy_import_receiver.as_ref() else {
+        return;
+    };
+
+    let finished = loop {
+        match rx.try_recv() {
+            Ok(ImportMessage::Progress { done, total, reports }) => {
+                app.bhavcopy_import_progress = Some((done, total));
+                app.bhavcopy_import_reports.extend(reports);
+            }
+            Ok(ImportMessage::Done(result)) => break result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => break Err("Import thread stopped unexpectedly".to_string()),
         }
+    };
 
-        ui.add_space(20.0);
+    app.bhavcopy_import_receiver = None;
+    let reports = std::mem::take(&mut app.bhavcopy_import_reports);
+    app.bhavcopy_import_status = match finished {
+        Ok(alerts) => {
+            let count = |exchange| reports.iter().filter(|r| r.exchange == Some(exchange)).count();
+            let mut status = format!(
+                "Imported {} NSE and {} BSE files: {} rows inserted, {} already present, {} rejected, {} delivery positions, {} F&O contracts, {} index closes, {} files failed",
+                count(Exchange::Nse),
+                count(Exchange::Bse),
+                reports.iter().map(|r| r.inserted).sum::<usize>(),
+                reports.iter().map(|r| r.skipped).sum::<usize>(),
+                reports.iter().map(|r| r.rejected).sum::<usize>(),
+                reports.iter().map(|r| r.delivery).sum::<usize>(),
+                reports.iter().map(|r| r.contracts).sum::<usize>(),
+                reports.iter().map(|r| r.indices).sum::<usize>(),
+                reports.iter().filter(|r| r.error.is_some()).count(),
+            );
+            if !alerts.is_empty() {
+                status.push_str(&format!(", {} alerts triggered", alerts.len()));
+            }
+            status
+        }
+        Err(e) => format!("Error importing BhavCopy files: {}", e),
+    };
+
+    // Even a failed import may have stored some files before stopping
+    app.stocks_cached_data.clear();
+    app.home_breadth_stale = true;
+    app.calendar_missing_days = None;
+    app.bhavcopy_date_range = get_bhavcopy_date_range(&app.db_conn.lock().unwrap()).unwrap_or(None);
+    app.refresh_alerts();
+}
+
+fn enqueue(app: &mut IndistocksApp, kind: DownloadJobKind) {
+    match app.download_manager.enqueue(kind) {
+        Ok(_) => {
+            app.download_status.clear();
+            app.refresh_download_jobs();
+        }
+        Err(e) => app.download_status = format!("Error queueing download: {}", e),
+    }
+}
+
+fn parse_range(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let parse = |value: &str| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value.trim()));
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err("From date must not be after To date".to_string());
+    }
+    Ok((from, to))
+}
+
+/// Most missing days listed before "and N more"
+const MAX_LISTED_GAPS: usize = 200;
+
+fn render_trading_calendar(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    // Counted once per visit; a download finishing or a holiday import clears it
+    if app.calendar_missing_days.is_none() && !app.download_manager.is_busy() {
+        let conn = app.db_conn.lock().unwrap();
+        app.calendar_holiday_count = get_market_holidays(&conn).map(|h| h.len()).unwrap_or(0);
+        app.calendar_missing_days = Some(find_missing_trading_days(&conn, None, None).unwrap_or_default());
+    }
+
+    ui.heading("Trading Calendar");
+    ui.add_space(10.0);
+
+    ui.label("Weekends are always closed. Import the exchange holiday list so holidays are not treated as missing data:");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.add_sized(
+            [400.0, 20.0],
+            egui::TextEdit::singleline(&mut app.holidays_path)
+                .hint_text("Path to holiday list CSV")
+        );
+        if ui.button("Import").clicked() && !app.holidays_path.trim().is_empty() {
+            let path = std::path::PathBuf::from(app.holidays_path.trim());
+            let result = import_holidays_csv(&app.db_conn.lock().unwrap(), &path);
+            app.holidays_status = match result {
+                Ok(report) => {
+                    app.calendar_missing_days = None;
+                    format!(
+                        "Imported {} holidays ({} already present, {} on weekends, {} rejected)",
+                        report.inserted, report.updated, report.weekends, report.rejected.len()
+                    )
+                }
+                Err(e) => format!("Error importing holidays: {}", e),
+            };
+        }
     });
+
+    if !app.holidays_status.is_empty() {
+        ui.add_space(5.0);
+        ui.label(&app.holidays_status);
+    }
+
+    ui.add_space(10.0);
+    ui.label(format!("{} holidays in the calendar", app.calendar_holiday_count));
+
+    let Some(missing) = &app.calendar_missing_days else {
+        ui.label("Missing trading days: checking after the current download");
+        return;
+    };
+
+    let mut fill = false;
+    ui.horizontal(|ui| {
+        ui.label(format!("Missing trading days in the stored range: {}", missing.len()));
+        if !missing.is_empty() && ui.button("Fill Gaps").on_hover_text("Download only the missing days").clicked() {
+            fill = true;
+        }
+    });
+
+    if !missing.is_empty() {
+        ui.collapsing("Missing Days", |ui| {
+            for date in missing.iter().take(MAX_LISTED_GAPS) {
+                ui.label(date.format("%Y-%m-%d  %a").to_string());
+            }
+            if missing.len() > MAX_LISTED_GAPS {
+                ui.label(format!("… and {} more", missing.len() - MAX_LISTED_GAPS));
+            }
+        });
+    }
+
+    if fill {
+        enqueue(app, DownloadJobKind::FillGaps);
+    }
+}
+
+fn render_active_job(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    let (Some(kind), Some(progress)) = (app.download_manager.active_kind(), app.download_manager.active_progress()) else {
+        return;
+    };
+
+    let paused = app.download_manager.is_paused();
+    let cancelling = app.download_manager.is_cancelling();
+    let state = if cancelling {
+        "cancelling after the current day".to_string()
+    } else if paused {
+        "paused".to_string()
+    } else {
+        match progress.current_day {
+            Some(day) => format!("fetching {}", day),
+            None => "starting".to_string(),
+        }
+    };
+    ui.label(format!("{}: {}", kind.label(), state));
+
+    let mut text = format!("{} / {} days", progress.days_done, progress.days_total);
+    if let Some(eta) = progress.eta().filter(|_| !paused) {
+        text.push_str(&format!(" · ETA {}", format_duration(eta)));
+    }
+    ui.add(egui::ProgressBar::new(progress.fraction()).text(text).desired_width(400.0));
+    ui.label(format!("{} files, {} rows inserted", progress.files, progress.rows_inserted));
+
+    ui.horizontal(|ui| {
+        ui.add_enabled_ui(!cancelling, |ui| {
+            if paused {
+                if ui.button("Resume").clicked() {
+                    app.download_manager.resume();
+                    app.refresh_download_jobs();
+                }
+            } else if ui.button("Pause").clicked() {
+                app.download_manager.pause();
+                app.refresh_download_jobs();
+            }
+            if ui.button("Cancel").clicked() {
+                app.download_manager.cancel_active();
+            }
+        });
+    });
+
+    ui.add_space(10.0);
+}
+
+fn render_queue(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    let queued: Vec<(i64, DownloadJobKind)> = app.download_manager.queued().copied().collect();
+    if queued.is_empty() {
+        return;
+    }
+
+    ui.label("Queued:");
+    for (id, kind) in queued {
+        ui.horizontal(|ui| {
+            ui.label(format!("#{} {}", id, kind.label()));
+            if ui.small_button("✕").on_hover_text("Remove from queue").clicked() {
+                app.download_manager.cancel_queued(id);
+                app.refresh_download_jobs();
+            }
+        });
+    }
+    ui.add_space(10.0);
+}
+
+fn render_job_history(ui: &mut egui::Ui, app: &IndistocksApp) {
+    use egui_extras::{TableBuilder, Column};
+
+    if app.download_jobs.is_empty() {
+        ui.label("No download jobs yet");
+        return;
+    }
+
+    TableBuilder::new(ui)
+        .id_salt("download_jobs_table")
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::auto().at_least(40.0))   // Job
+        .column(Column::auto().at_least(160.0))  // Kind
+        .column(Column::auto().at_least(90.0))   // Status
+        .column(Column::auto().at_least(80.0))   // Days
+        .column(Column::auto().at_least(50.0))   // Files
+        .column(Column::auto().at_least(80.0))   // Rows
+        .column(Column::auto().at_least(130.0))  // Started
+        .column(Column::auto().at_least(70.0))   // Duration
+        .column(Column::remainder().at_least(100.0)) // Error
+        .header(24.0, |mut header| {
+            for title in ["Job", "Kind", "Status", "Days", "Files", "Rows", "Started", "Duration", "Note"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for job in &app.download_jobs {
+                body.row(22.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(format!("#{}", job.id));
+                    });
+                    row.col(|ui| {
+                        ui.label(job.kind.label());
+                    });
+                    row.col(|ui| {
+                        ui.colored_label(status_color(job.status), job.status.label());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{} / {}", job.days_done, job.days_total));
+                    });
+                    row.col(|ui| {
+                        ui.label(job.files.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(job.rows_inserted.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(job.started_at.map(format_timestamp).unwrap_or_default());
+                    });
+                    row.col(|ui| {
+                        ui.label(job_duration(job));
+                    });
+                    row.col(|ui| {
+                        if let Some(error) = &job.error_message {
+                            ui.label(error).on_hover_text(error);
+                        }
+                    });
+                });
+            }
+        });
+}
+
+fn status_color(status: JobStatus) -> egui::Color32 {
+    match status {
+        JobStatus::Completed => egui::Color32::GREEN,
+        JobStatus::Failed => egui::Color32::RED,
+        JobStatus::Cancelled | JobStatus::Interrupted => egui::Color32::YELLOW,
+        JobStatus::Queued | JobStatus::Running | JobStatus::Paused => egui::Color32::LIGHT_BLUE,
+    }
+}
+
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn job_duration(job: &DownloadJob) -> String {
+    match (job.started_at, job.finished_at) {
+        (Some(start), Some(end)) => format_duration(std::time::Duration::from_secs((end - start).max(0) as u64)),
+        _ => String::new(),
+    }
+}
+
+fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
 }
@@ -1,16 +1,20 @@
 use crate::app::IndistocksApp;
+use indistocks_db::{create_watchlist, delete_watchlist, add_to_watchlist, remove_from_watchlist, move_watchlist_item, set_watchlist_note};
 
 pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
-    ui.add_space(10.0);
-
-    ui.heading("Recently Viewed");
-
-    ui.add_space(10.0);
-    ui.separator();
-
     egui::ScrollArea::vertical()
         .auto_shrink([false; 2])
         .show(ui, |ui| {
+            ui.add_space(10.0);
+            render_watchlists(ui, app);
+
+            ui.add_space(20.0);
+
+            ui.heading("Recently Viewed");
+
+            ui.add_space(10.0);
+            ui.separator();
+
             let items: Vec<_> = app.recently_viewed.iter().map(|item| (item.symbol.clone(), item.name.clone())).collect();
             for (symbol, name) in items {
                 ui.add_space(5.0);
@@ -31,3 +35,158 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             }
         });
 }
+
+/// Changes to a watchlist, applied after rendering so the item list isn't borrowed while drawing
+enum WatchlistAction {
+    Create(String),
+    Delete(i64),
+    Add(String),
+    Remove(String),
+    Move(String, usize),
+    SetNote(String, String),
+}
+
+fn render_watchlists(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    ui.heading("Watchlists");
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let mut action = None;
+
+    // List selector and delete button
+    if !app.watchlists.is_empty() {
+        ui.horizontal(|ui| {
+            let selected_name = app.watchlists.iter()
+                .find(|w| Some(w.id) == app.active_watchlist)
+                .map(|w| w.name.clone())
+                .unwrap_or_default();
+            let mut selected = app.active_watchlist;
+            egui::ComboBox::from_id_salt("sidebar_watchlist")
+                .selected_text(selected_name)
+                .width(170.0)
+                .show_ui(ui, |ui| {
+                    for watchlist in &app.watchlists {
+                        ui.selectable_value(&mut selected, Some(watchlist.id), &watchlist.name);
+                    }
+                });
+            if selected != app.active_watchlist {
+                app.active_watchlist = selected;
+                app.refresh_watchlists();
+            }
+
+            if let Some(id) = app.active_watchlist {
+                if ui.small_button("🗑").on_hover_text("Double-click to delete this watchlist").double_clicked() {
+                    action = Some(WatchlistAction::Delete(id));
+                }
+            }
+        });
+    }
+
+    // New list
+    ui.horizontal(|ui| {
+        ui.add_sized(
+            [170.0, 20.0],
+            egui::TextEdit::singleline(&mut app.new_watchlist_name)
+                .hint_text("New watchlist")
+        );
+        let name = app.new_watchlist_name.trim().to_string();
+        if ui.add_enabled(!name.is_empty(), egui::Button::new("➕")).on_hover_text("Create watchlist").clicked() {
+            action = Some(WatchlistAction::Create(name));
+        }
+    });
+
+    // Add or remove the symbol on the chart
+    if let (Some(symbol), Some(_)) = (app.selected_symbol.clone(), app.active_watchlist) {
+        let in_list = app.watchlist_items.iter().any(|item| item.symbol == symbol);
+        if in_list {
+            if ui.button(format!("Remove {}", symbol)).clicked() {
+                action = Some(WatchlistAction::Remove(symbol));
+            }
+        } else if ui.button(format!("Add {}", symbol)).clicked() {
+            action = Some(WatchlistAction::Add(symbol));
+        }
+    }
+
+    ui.add_space(5.0);
+
+    let item_count = app.watchlist_items.len();
+    let mut symbol_to_load = None;
+    for (index, item) in app.watchlist_items.iter().enumerate() {
+        ui.add_space(5.0);
+
+        let response = ui.button(&item.symbol);
+        if response.clicked() {
+            symbol_to_load = Some(item.symbol.clone());
+        }
+        response.context_menu(|ui| {
+            if ui.add_enabled(index > 0, egui::Button::new("Move up")).clicked() {
+                action = Some(WatchlistAction::Move(item.symbol.clone(), index - 1));
+                ui.close_menu();
+            }
+            if ui.add_enabled(index + 1 < item_count, egui::Button::new("Move down")).clicked() {
+                action = Some(WatchlistAction::Move(item.symbol.clone(), index + 1));
+                ui.close_menu();
+            }
+            if ui.button("Remove").clicked() {
+                action = Some(WatchlistAction::Remove(item.symbol.clone()));
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.label("Note:");
+            ui.text_edit_singleline(&mut app.watchlist_note_draft);
+            if ui.button("Save note").clicked() {
+                action = Some(WatchlistAction::SetNote(item.symbol.clone(), app.watchlist_note_draft.clone()));
+                ui.close_menu();
+            }
+        });
+        if response.secondary_clicked() {
+            app.watchlist_note_draft = item.note.clone().unwrap_or_default();
+        }
+
+        let detail = item.note.as_ref().or(item.name.as_ref());
+        if let Some(detail) = detail {
+            ui.label(
+                egui::RichText::new(detail)
+                    .small()
+                    .color(egui::Color32::GRAY)
+            );
+        }
+    }
+
+    if app.active_watchlist.is_some() && app.watchlist_items.is_empty() {
+        ui.label(egui::RichText::new("Open a stock and add it here").small().color(egui::Color32::GRAY));
+    }
+
+    if let Some(symbol) = symbol_to_load {
+        app.load_plot_data(&symbol);
+    }
+
+    if let Some(action) = action {
+        apply_watchlist_action(app, action);
+    }
+}
+
+fn apply_watchlist_action(app: &mut IndistocksApp, action: WatchlistAction) {
+    let conn = app.db_conn.lock().unwrap();
+    let list = app.active_watchlist;
+
+    let result = match (&action, list) {
+        (WatchlistAction::Create(name), _) => create_watchlist(&conn, name).map(|id| {
+            app.active_watchlist = Some(id);
+            app.new_watchlist_name.clear();
+        }),
+        (WatchlistAction::Delete(id), _) => delete_watchlist(&conn, *id),
+        (WatchlistAction::Add(symbol), Some(id)) => add_to_watchlist(&conn, id, symbol).map(|_| ()),
+        (WatchlistAction::Remove(symbol), Some(id)) => remove_from_watchlist(&conn, id, symbol),
+        (WatchlistAction::Move(symbol, index), Some(id)) => move_watchlist_item(&conn, id, symbol, *index),
+        (WatchlistAction::SetNote(symbol, note), Some(id)) => set_watchlist_note(&conn, id, symbol, Some(note)),
+        _ => Ok(()),
+    };
+    drop(conn);
+
+    if let Err(e) = result {
+        log::error!("Failed to update watchlist: {}", e);
+    }
+    app.refresh_watchlists();
+}
@@ -1,5 +1,8 @@
 use crate::app::{IndistocksApp, RangeType};
-use indistocks_db::get_all_stocks_with_metrics;
+use indistocks_db::{get_all_stocks_with_metrics, get_all_stocks_with_metrics_adjusted, get_watchlist_items, retain_nse_symbols, Exchange, StockColumn, StockData, StockSort};
+use indistocks_db::{run_screen, save_screen, delete_saved_screen, get_saved_screens};
+use indistocks_db::screener::{Screen, SCREEN_HELP};
+use std::collections::HashSet;
 
 pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
     ui.heading("Stocks");
@@ -7,6 +10,18 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
 
     // Filters row
     ui.horizontal(|ui| {
+        ui.label("Exchange:");
+        egui::ComboBox::from_id_salt("stocks_exchange")
+            .selected_text(app.stocks_exchange.key())
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                for exchange in Exchange::ALL {
+                    ui.selectable_value(&mut app.stocks_exchange, exchange, exchange.key());
+                }
+            });
+
+        ui.add_space(20.0);
+
         ui.label("Price From:");
         ui.add_sized(
             [100.0, 20.0],
@@ -33,14 +48,78 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
                 ui.selectable_value(&mut app.stocks_range_type, RangeType::Last30Days, "Last 30 Days");
                 ui.selectable_value(&mut app.stocks_range_type, RangeType::Last52Weeks, "Last 52 Weeks");
             });
+
+        ui.add_space(20.0);
+
+        ui.checkbox(&mut app.stocks_adjusted, "Adjusted prices")
+            .on_hover_text("Adjust the low/high range for splits, bonuses and dividends");
+
+        ui.add_space(20.0);
+
+        ui.label("Watchlist:");
+        let selected_name = app.watchlists.iter()
+            .find(|w| Some(w.id) == app.stocks_watchlist)
+            .map(|w| w.name.as_str())
+            .unwrap_or("All stocks")
+            .to_string();
+        egui::ComboBox::from_id_salt("stocks_watchlist")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.stocks_watchlist, None, "All stocks");
+                for watchlist in &app.watchlists {
+                    ui.selectable_value(&mut app.stocks_watchlist, Some(watchlist.id), &watchlist.name);
+                }
+            });
+
+        ui.add_space(20.0);
+
+        ui.menu_button("Columns", |ui| {
+            let mut changed = false;
+            for column in StockColumn::ALL {
+                let mut visible = app.stocks_layout.columns.contains(&column);
+                // The symbol column opens the chart, so it always stays
+                let enabled = column != StockColumn::Symbol;
+                if ui.add_enabled(enabled, egui::Checkbox::new(&mut visible, column_title(column, app.stocks_range_type))).changed() {
+                    changed = true;
+                    if visible {
+                        app.stocks_layout.columns.push(column);
+                    } else {
+                        app.stocks_layout.columns.retain(|c| *c != column);
+                    }
+                }
+            }
+            ui.separator();
+            if ui.button("Reset to defaults").clicked() {
+                app.stocks_layout.columns = StockColumn::defaults();
+                changed = true;
+            }
+            if changed {
+                // Keep the chooser's order regardless of the order columns were ticked in
+                app.stocks_layout.columns.sort_by_key(|c| StockColumn::ALL.iter().position(|a| a == c));
+                app.save_stocks_layout();
+            }
+        });
     });
 
+    ui.add_space(5.0);
+    render_screen_controls(ui, app);
     ui.add_space(10.0);
 
+    // The screen only reruns when the expression or price adjustment changes
+    let screen_source = app.stocks_screen.as_ref().map(|s| s.source().to_string());
+    if screen_source != app.stocks_last_screen || app.stocks_adjusted != app.stocks_last_adjusted {
+        app.stocks_screen_matches = None;
+    }
+
     // Check if filters changed - only reload if they did
-    let filters_changed = app.stocks_price_from != app.stocks_last_price_from
+    let filters_changed = app.stocks_exchange != app.stocks_last_exchange
+        || app.stocks_price_from != app.stocks_last_price_from
         || app.stocks_price_to != app.stocks_last_price_to
-        || app.stocks_range_type != app.stocks_last_range_type;
+        || app.stocks_range_type != app.stocks_last_range_type
+        || app.stocks_adjusted != app.stocks_last_adjusted
+        || app.stocks_watchlist != app.stocks_last_watchlist
+        || app.stocks_layout.sort != app.stocks_last_sort
+        || (app.stocks_screen_matches.is_none() && app.stocks_screen.is_some());
 
     if filters_changed || app.stocks_cached_data.is_empty() {
         // Parse filters
@@ -52,15 +131,71 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             RangeType::Last52Weeks => 365,
         };
 
-        // Fetch data
+        // Fetch data, sorted by the database
+        let sort = app.stocks_layout.sort;
+        let exchange = app.stocks_exchange;
         let conn = app.db_conn.lock().unwrap();
-        app.stocks_cached_data = get_all_stocks_with_metrics(&*conn, price_from, price_to, range_days).unwrap_or_default();
+        app.stocks_cached_data = if app.stocks_adjusted {
+            get_all_stocks_with_metrics_adjusted(&conn, exchange, price_from, price_to, range_days, sort)
+        } else {
+            get_all_stocks_with_metrics(&conn, exchange, price_from, price_to, range_days, sort)
+        }.unwrap_or_default();
+
+        if let Some(watchlist_id) = app.stocks_watchlist {
+            let symbols: HashSet<String> = get_watchlist_items(&conn, watchlist_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|item| item.symbol)
+                .collect();
+            if let Err(e) = retain_nse_symbols(&conn, exchange, &mut app.stocks_cached_data, &symbols) {
+                log::error!("Failed to match watchlist symbols: {}", e);
+            }
+        }
+
+        if let Some(screen) = &app.stocks_screen {
+            if app.stocks_screen_matches.is_none() {
+                let matches = run_screen(&conn, screen, app.stocks_adjusted).unwrap_or_else(|e| {
+                    app.stocks_screen_error = format!("Screen failed: {}", e);
+                    Vec::new()
+                });
+                app.stocks_screen_matches = Some(matches.into_iter().map(|m| m.symbol).collect());
+            }
+            if let Some(matches) = &app.stocks_screen_matches {
+                if let Err(e) = retain_nse_symbols(&conn, exchange, &mut app.stocks_cached_data, matches) {
+                    app.stocks_screen_error = format!("Screen failed: {}", e);
+                }
+            }
+        }
         drop(conn);
 
         // Update last filter values
+        app.stocks_last_exchange = app.stocks_exchange;
         app.stocks_last_price_from = app.stocks_price_from.clone();
         app.stocks_last_price_to = app.stocks_price_to.clone();
         app.stocks_last_range_type = app.stocks_range_type;
+        app.stocks_last_adjusted = app.stocks_adjusted;
+        app.stocks_last_watchlist = app.stocks_watchlist;
+        app.stocks_last_sort = app.stocks_layout.sort;
+        app.stocks_last_screen = screen_source;
+    }
+
+    if app.stocks_cached_data.is_empty() && app.stocks_screen.is_some() {
+        ui.label("No stocks match this screen.");
+        return;
+    }
+
+    if app.stocks_screen.is_some() {
+        ui.label(format!("{} stocks match", app.stocks_cached_data.len()));
+    }
+
+    if app.stocks_cached_data.is_empty() && app.stocks_watchlist.is_some() {
+        ui.label("None of the stocks in this watchlist match the filters.");
+        return;
+    }
+
+    if app.stocks_cached_data.is_empty() && app.stocks_exchange == Exchange::Bse {
+        ui.label("No BSE data available. Import BSE BhavCopy files from Settings.");
+        return;
     }
 
     if app.stocks_cached_data.is_empty() {
@@ -72,6 +207,105 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
     render_virtual_table(ui, app);
 }
 
+/// Screener expression, saved screens and the actions on them
+fn render_screen_controls(ui: &mut egui::Ui, app: &mut IndistocksApp) {
+    let mut apply = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Screen:");
+        let response = ui.add_sized(
+            [520.0, 20.0],
+            egui::TextEdit::singleline(&mut app.stocks_screen_input)
+                .hint_text("e.g. close > sma(200) and volume > 2 * avg_volume(20)")
+        ).on_hover_text(SCREEN_HELP);
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            apply = true;
+        }
+        if ui.button("Run").clicked() {
+            apply = true;
+        }
+        if app.stocks_screen.is_some() && ui.button("Clear").clicked() {
+            app.stocks_screen = None;
+            app.stocks_screen_input.clear();
+            app.stocks_screen_error.clear();
+        }
+
+        ui.add_space(20.0);
+
+        if !app.saved_screens.is_empty() {
+            let selected_name = app.saved_screens.iter()
+                .find(|s| Some(s.expression.as_str()) == app.stocks_screen.as_ref().map(|s| s.source()))
+                .map(|s| s.name.clone());
+            let mut chosen = None;
+            egui::ComboBox::from_id_salt("saved_screens")
+                .selected_text(selected_name.as_deref().unwrap_or("Saved screens"))
+                .show_ui(ui, |ui| {
+                    for saved in &app.saved_screens {
+                        if ui.selectable_label(selected_name.as_deref() == Some(saved.name.as_str()), &saved.name)
+                            .on_hover_text(&saved.expression)
+                            .clicked()
+                        {
+                            chosen = Some(saved.expression.clone());
+                        }
+                    }
+                });
+            if let Some(expression) = chosen {
+                app.stocks_screen_input = expression;
+                apply = true;
+            }
+
+            let saved_id = app.saved_screens.iter()
+                .find(|s| Some(s.name.as_str()) == selected_name.as_deref())
+                .map(|s| s.id);
+            if let Some(id) = saved_id {
+                if ui.small_button("🗑").on_hover_text("Double-click to delete this saved screen").double_clicked() {
+                    let conn = app.db_conn.lock().unwrap();
+                    if let Err(e) = delete_saved_screen(&conn, id) {
+                        app.stocks_screen_error = format!("Error: {}", e);
+                    }
+                    app.saved_screens = get_saved_screens(&conn).unwrap_or_default();
+                }
+            }
+        }
+
+        ui.add_sized([140.0, 20.0], egui::TextEdit::singleline(&mut app.stocks_screen_name).hint_text("Screen name"));
+        let can_save = !app.stocks_screen_name.trim().is_empty() && !app.stocks_screen_input.trim().is_empty();
+        if ui.add_enabled(can_save, egui::Button::new("💾 Save")).clicked() {
+            let conn = app.db_conn.lock().unwrap();
+            match save_screen(&conn, &app.stocks_screen_name, &app.stocks_screen_input) {
+                Ok(_) => {
+                    app.stocks_screen_name.clear();
+                    app.stocks_screen_error.clear();
+                    apply = true;
+                }
+                Err(e) => app.stocks_screen_error = format!("Error: {}", e),
+            }
+            app.saved_screens = get_saved_screens(&conn).unwrap_or_default();
+        }
+    });
+
+    if apply {
+        if app.stocks_screen_input.trim().is_empty() {
+            app.stocks_screen = None;
+            app.stocks_screen_error.clear();
+        } else {
+            match Screen::parse(&app.stocks_screen_input) {
+                Ok(screen) => {
+                    app.stocks_screen = Some(screen);
+                    app.stocks_screen_error.clear();
+                    // Run again even if the expression is unchanged, e.g. after a download
+                    app.stocks_screen_matches = None;
+                }
+                Err(e) => app.stocks_screen_error = e.to_string(),
+            }
+        }
+    }
+
+    if !app.stocks_screen_error.is_empty() {
+        ui.colored_label(egui::Color32::RED, &app.stocks_screen_error);
+    }
+}
+
 fn range_type_label(range_type: RangeType) -> &'static str {
     match range_type {
         RangeType::Last5Days => "Last 5 Days",
@@ -80,6 +314,30 @@ fn range_type_label(range_type: RangeType) -> &'static str {
     }
 }
 
+/// Header text for a column; the range columns are named after the selected range
+fn column_title(column: StockColumn, range_type: RangeType) -> &'static str {
+    match (column, range_type) {
+        (StockColumn::RangeLow, RangeType::Last5Days) => "5D Low",
+        (StockColumn::RangeLow, RangeType::Last30Days) => "30D Low",
+        (StockColumn::RangeLow, RangeType::Last52Weeks) => "52W Low",
+        (StockColumn::RangeHigh, RangeType::Last5Days) => "5D High",
+        (StockColumn::RangeHigh, RangeType::Last30Days) => "30D High",
+        (StockColumn::RangeHigh, RangeType::Last52Weeks) => "52W High",
+        _ => column.label(),
+    }
+}
+
+fn column_width(column: StockColumn) -> (f32, f32) {
+    match column {
+        StockColumn::Symbol => (120.0, 80.0),
+        StockColumn::Volume | StockColumn::Turnover | StockColumn::DeliveryQty => (120.0, 100.0),
+        StockColumn::Isin => (130.0, 110.0),
+        StockColumn::Series => (70.0, 60.0),
+        StockColumn::ChangePercent | StockColumn::From52wHigh => (110.0, 90.0),
+        _ => (100.0, 80.0),
+    }
+}
+
 fn render_virtual_table(ui: &mut egui::Ui, app: &mut IndistocksApp) {
     use egui_extras::{TableBuilder, Column};
 
@@ -87,107 +345,170 @@ fn render_virtual_table(ui: &mut egui::Ui, app: &mut IndistocksApp) {
 
     // Clone the data to avoid borrow checker issues
     let stocks_data = app.stocks_cached_data.clone();
+    let columns = app.stocks_layout.columns.clone();
     let range_type = app.stocks_range_type;
+    let sort = app.stocks_layout.sort;
 
     let mut symbol_to_load: Option<String> = None;
-
-    // Dynamic column headers based on range type
-    let range_low_header = match range_type {
-        RangeType::Last5Days => "5D Low",
-        RangeType::Last30Days => "30D Low",
-        RangeType::Last52Weeks => "52W Low",
-    };
-    let range_high_header = match range_type {
-        RangeType::Last5Days => "5D High",
-        RangeType::Last30Days => "30D High",
-        RangeType::Last52Weeks => "52W High",
-    };
+    let mut new_sort: Option<StockSort> = None;
 
     let available_height = ui.available_height();
 
-    TableBuilder::new(ui)
+    let mut table = TableBuilder::new(ui)
         .striped(true)
         .resizable(true)
         .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
         .min_scrolled_height(0.0)
-        .max_scroll_height(available_height)
-        .column(Column::initial(120.0).at_least(80.0).resizable(true))   // Symbol
-        .column(Column::remainder().at_least(150.0))                      // Name (takes remaining space)
-        .column(Column::initial(100.0).at_least(80.0).resizable(true))   // LTP
-        .column(Column::initial(100.0).at_least(90.0).resizable(true))   // % Change
-        .column(Column::initial(120.0).at_least(100.0).resizable(true))  // Volume
-        .column(Column::initial(100.0).at_least(80.0).resizable(true))   // Range Low
-        .column(Column::initial(100.0).at_least(80.0).resizable(true))   // Range High
+        .max_scroll_height(available_height);
+
+    for column in &columns {
+        table = if *column == StockColumn::Name {
+            // Name takes the remaining space
+            table.column(Column::remainder().at_least(150.0))
+        } else {
+            let (initial, min) = column_width(*column);
+            table.column(Column::initial(initial).at_least(min).resizable(true))
+        };
+    }
+
+    table
         .header(30.0, |mut header| {
-            header.col(|ui| {
-                ui.strong("Symbol");
-            });
-            header.col(|ui| {
-                ui.strong("Name");
-            });
-            header.col(|ui| {
-                ui.strong("LTP");
-            });
-            header.col(|ui| {
-                ui.strong("% Change");
-            });
-            header.col(|ui| {
-                ui.strong("Volume");
-            });
-            header.col(|ui| {
-                ui.strong(range_low_header);
-            });
-            header.col(|ui| {
-                ui.strong(range_high_header);
-            });
+            for column in &columns {
+                header.col(|ui| {
+                    let title = column_title(*column, range_type);
+                    let text = if sort.column == *column {
+                        format!("{} {}", title, if sort.descending { "⏷" } else { "⏶" })
+                    } else {
+                        title.to_string()
+                    };
+                    let response = ui.add(egui::Label::new(egui::RichText::new(text).strong()).sense(egui::Sense::click()))
+                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                        .on_hover_text("Click to sort");
+                    if response.clicked() {
+                        new_sort = Some(if sort.column == *column {
+                            StockSort { column: *column, descending: !sort.descending }
+                        } else {
+                            StockSort { column: *column, descending: !column.is_text() }
+                        });
+                    }
+                });
+            }
         })
         .body(|body| {
             // Virtual scrolling: only render visible rows + buffer
             body.rows(25.0, total_rows, |mut row| {
                 let row_index = row.index();
                 if let Some(stock) = stocks_data.get(row_index) {
-                    let symbol = stock.symbol.clone();
-                    row.col(|ui| {
-                        if ui.button(&symbol).clicked() {
-                            symbol_to_load = Some(symbol.clone());
-                        }
-                    });
-                    row.col(|ui| {
-                        ui.label(stock.name.as_deref().unwrap_or("N/A"));
-                    });
-                    row.col(|ui| {
-                        ui.label(format!("{:.2}", stock.ltp));
-                    });
-                    row.col(|ui| {
-                        let color = if stock.change_percent > 0.0 {
-                            egui::Color32::GREEN
-                        } else if stock.change_percent < 0.0 {
-                            egui::Color32::RED
-                        } else {
-                            ui.style().visuals.text_color()
-                        };
-                        ui.colored_label(color, format!("{:+.2}%", stock.change_percent));
-                    });
-                    row.col(|ui| {
-                        ui.label(format_volume(stock.volume));
-                    });
-                    row.col(|ui| {
-                        ui.label(format!("{:.2}", stock.range_low));
-                    });
-                    row.col(|ui| {
-                        ui.label(format!("{:.2}", stock.range_high));
-                    });
+                    for column in &columns {
+                        row.col(|ui| {
+                            render_cell(ui, stock, *column, &mut symbol_to_load);
+                        });
+                    }
                 }
             });
         });
 
+    if let Some(sort) = new_sort {
+        app.stocks_layout.sort = sort;
+        app.save_stocks_layout();
+    }
+
     // Load plot data after table rendering to avoid borrow issues
     if let Some(symbol) = symbol_to_load {
-        app.load_plot_data(&symbol);
+        app.load_listing(app.stocks_exchange, &symbol);
+    }
+}
+
+fn render_cell(ui: &mut egui::Ui, stock: &StockData, column: StockColumn, symbol_to_load: &mut Option<String>) {
+    let price = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "N/A".to_string());
+
+    match column {
+        StockColumn::Symbol => {
+            if ui.button(&stock.symbol).clicked() {
+                *symbol_to_load = Some(stock.symbol.clone());
+            }
+        }
+        StockColumn::Name => {
+            ui.label(stock.name.as_deref().unwrap_or("N/A"));
+        }
+        StockColumn::Ltp => {
+            ui.label(format!("{:.2}", stock.ltp));
+        }
+        StockColumn::ChangePercent => {
+            change_label(ui, Some(stock.change_percent));
+        }
+        StockColumn::Volume => {
+            ui.label(format_volume(stock.volume));
+        }
+        StockColumn::RangeLow => {
+            ui.label(format!("{:.2}", stock.range_low));
+        }
+        StockColumn::RangeHigh => {
+            ui.label(format!("{:.2}", stock.range_high));
+        }
+        StockColumn::Open => {
+            ui.label(price(stock.open));
+        }
+        StockColumn::High => {
+            ui.label(price(stock.high));
+        }
+        StockColumn::Low => {
+            ui.label(price(stock.low));
+        }
+        StockColumn::PrevClose => {
+            ui.label(price(stock.prev_close));
+        }
+        StockColumn::Turnover => {
+            ui.label(stock.turnover.map(format_turnover).unwrap_or_else(|| "N/A".to_string()));
+        }
+        StockColumn::Trades => {
+            ui.label(stock.trades.map(|t| t.to_string()).unwrap_or_else(|| "N/A".to_string()));
+        }
+        StockColumn::Isin => {
+            ui.label(stock.isin.as_deref().unwrap_or("N/A"));
+        }
+        StockColumn::Series => {
+            ui.label(&stock.series);
+        }
+        StockColumn::From52wHigh => {
+            change_label(ui, stock.from_52w_high_pct());
+        }
+        StockColumn::DeliveryQty => {
+            ui.label(stock.deliverable_qty.map(format_volume).unwrap_or_else(|| "N/A".to_string()));
+        }
+        StockColumn::DeliveryPercent => {
+            ui.label(stock.delivery_percent.map(|p| format!("{:.2}%", p)).unwrap_or_else(|| "N/A".to_string()));
+        }
+    }
+}
+
+fn change_label(ui: &mut egui::Ui, value: Option<f64>) {
+    let Some(value) = value else {
+        ui.label("N/A");
+        return;
+    };
+    let color = if value > 0.0 {
+        egui::Color32::GREEN
+    } else if value < 0.0 {
+        egui::Color32::RED
+    } else {
+        ui.style().visuals.text_color()
+    };
+    ui.colored_label(color, format!("{:+.2}%", value));
+}
+
+/// Turnover in rupees as lakhs or crores
+pub fn format_turnover(turnover: f64) -> String {
+    if turnover >= 10_000_000.0 {
+        format!("₹{:.2} Cr", turnover / 10_000_000.0)
+    } else if turnover >= 100_000.0 {
+        format!("₹{:.2} L", turnover / 100_000.0)
+    } else {
+        format!("₹{:.0}", turnover)
     }
 }
 
-fn format_volume(volume: i64) -> String {
+pub fn format_volume(volume: i64) -> String {
     if volume >= 10_000_000 {
         format!("{:.1}M", volume as f64 / 1_000_000.0)
     } else if volume >= 100_000 {
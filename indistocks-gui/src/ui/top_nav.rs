@@ -33,12 +33,40 @@ pub fn render(ui: &mut egui::Ui, app: &mut IndistocksApp) {
             ui.add_space(5.0);
 
             // Notifications button
-            if ui.button("🔔").on_hover_text("Notifications").clicked() {
-                // Future: show notifications
+            let bell = if app.alerts_unread > 0 {
+                format!("🔔 {}", app.alerts_unread)
+            } else {
+                "🔔".to_string()
+            };
+            if ui.button(bell).on_hover_text("Alerts and notifications").clicked() {
+                app.current_view = View::Alerts;
+                app.refresh_alerts();
             }
 
             ui.add_space(10.0);
 
+            // Portfolio button
+            if ui.button("Portfolio").clicked() {
+                app.current_view = View::Portfolio;
+                app.portfolio = None; // Revalue at the latest closes
+            }
+
+            ui.add_space(5.0);
+
+            // F&O button
+            if ui.button("F&O").on_hover_text("Futures basis and option chain").clicked() {
+                app.current_view = View::Derivatives;
+            }
+
+            ui.add_space(5.0);
+
+            // Backtest button
+            if ui.button("Backtest").clicked() {
+                app.current_view = View::Backtest;
+            }
+
+            ui.add_space(5.0);
+
             // Stocks button
             if ui.button("Stocks").clicked() {
                 app.current_view = View::Stocks;